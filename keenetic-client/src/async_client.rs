@@ -0,0 +1,214 @@
+//! Talking to a router over HTTP with reqwest's async client, behind the
+//! `async` feature, for code that already runs on tokio and would rather
+//! not park a thread per request. It covers reading a router's state and
+//! changing client policies; the rest is on the blocking client.
+
+use crate::http::{base_url, header_value, ndm_error};
+use crate::protocol::{password_hash, rci};
+use crate::{
+    compat, fixture, parse_hotspot_hosts, parse_log, parse_mesh_nodes, parse_policies, RouterError,
+};
+use reqwest::Client;
+use router_model::{ClientInfo, LogEntry, MeshNode, PolicyInfo};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The async counterpart of `KeeneticRouter`: the same session handling and
+/// errors, with every request awaited.
+pub struct AsyncKeeneticRouter {
+    base_url: String,
+    username: String,
+    password: String,
+    name: String,
+    client: Client,
+}
+
+impl AsyncKeeneticRouter {
+    pub fn new(address: &str, username: &str, password: &str, name: &str) -> Self {
+        let client = Client::builder()
+            .cookie_store(true)
+            .build()
+            .expect("reqwest client");
+        Self {
+            base_url: base_url(address),
+            username: username.to_string(),
+            password: password.to_string(),
+            name: name.to_string(),
+            client,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Checks that the router answers HTTP at all, whatever the status.
+    pub async fn check_reachable(&self) -> Result<(), RouterError> {
+        self.client
+            .get(format!("{}/auth", self.base_url))
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    pub async fn login(&self) -> Result<(), RouterError> {
+        let auth_url = format!("{}/auth", self.base_url);
+        let initial = self.client.get(auth_url).send().await?;
+        if initial.status() == reqwest::StatusCode::UNAUTHORIZED {
+            let headers = initial.headers();
+            let realm = header_value(headers, "X-NDM-Realm")
+                .ok_or_else(|| RouterError::InvalidResponse("missing realm".into()))?;
+            let challenge = header_value(headers, "X-NDM-Challenge")
+                .ok_or_else(|| RouterError::InvalidResponse("missing challenge".into()))?;
+            let auth_data = serde_json::json!({
+                "login": self.username,
+                "password": password_hash(&self.username, &realm, &self.password, &challenge),
+            });
+            let auth_response = self
+                .client
+                .post(format!("{}/auth", self.base_url))
+                .json(&auth_data)
+                .send()
+                .await?;
+            if auth_response.status().is_success() {
+                Ok(())
+            } else {
+                Err(RouterError::AuthFailed)
+            }
+        } else if initial.status().is_success() {
+            Ok(())
+        } else {
+            Err(RouterError::InvalidResponse(format!(
+                "unexpected auth status: {}",
+                initial.status()
+            )))
+        }
+    }
+
+    async fn keen_request(
+        &self,
+        endpoint: &str,
+        data: Option<Value>,
+    ) -> Result<Value, RouterError> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let response = if let Some(payload) = &data {
+            log::debug!("{}: POST {}", self.name, endpoint);
+            self.client.post(url).json(payload).send().await?
+        } else {
+            log::debug!("{}: GET {}", self.name, endpoint);
+            self.client.get(url).send().await?
+        };
+        let status = response.status().as_u16();
+        if !response.status().is_success() {
+            log::warn!("{}: {} returned {}", self.name, endpoint, response.status());
+            fixture::record(&self.name, endpoint, data.as_ref(), status, &Value::Null);
+            return Err(RouterError::InvalidResponse(format!(
+                "status {}",
+                response.status()
+            )));
+        }
+        let json = response.json::<Value>().await?;
+        fixture::record(&self.name, endpoint, data.as_ref(), status, &json);
+        if let Some(message) = ndm_error(&json) {
+            log::warn!("{}: {} failed: {}", self.name, endpoint, message);
+            return Err(RouterError::InvalidResponse(message));
+        }
+        Ok(json)
+    }
+
+    pub async fn get_policies(&self) -> Result<HashMap<String, PolicyInfo>, RouterError> {
+        self.login().await?;
+        let data = self.keen_request(rci::POLICIES, None).await?;
+        Ok(parse_policies(&data))
+    }
+
+    pub async fn get_online_clients(&self) -> Result<Vec<ClientInfo>, RouterError> {
+        self.login().await?;
+        let data = self.keen_request(rci::HOTSPOT_HOSTS, None).await?;
+        Ok(parse_hotspot_hosts(&data))
+    }
+
+    pub async fn get_mesh_nodes(&self) -> Result<Vec<MeshNode>, RouterError> {
+        self.login().await?;
+        let data = self.keen_request(rci::MESH_MEMBERS, None).await?;
+        Ok(parse_mesh_nodes(&data))
+    }
+
+    /// Whether the router's own connectivity checker currently sees the
+    /// internet.
+    pub async fn check_internet(&self) -> Result<bool, RouterError> {
+        self.login().await?;
+        let data = self.keen_request(rci::INTERNET_STATUS, None).await?;
+        compat::flag(&data, "internet")
+            .ok_or_else(|| RouterError::InvalidResponse("missing internet status".into()))
+    }
+
+    /// The last `max_lines` lines of the system log, oldest first.
+    pub async fn get_log(&self, max_lines: u32) -> Result<Vec<LogEntry>, RouterError> {
+        self.login().await?;
+        let data = self.keen_request(&rci::log(max_lines), None).await?;
+        let log = data
+            .get("log")
+            .ok_or_else(|| RouterError::InvalidResponse("missing log".into()))?;
+        Ok(parse_log(log))
+    }
+
+    pub async fn apply_policy_to_client(
+        &self,
+        mac: &str,
+        policy: Option<&str>,
+    ) -> Result<(), RouterError> {
+        self.login().await?;
+        let policy_value = match policy {
+            Some(name) => Value::String(name.to_string()),
+            None => Value::Bool(false),
+        };
+        let payload = serde_json::json!({
+            "mac": mac,
+            "policy": policy_value,
+            "permit": true,
+            "schedule": false
+        });
+        self.keen_request(rci::HOTSPOT_HOST, Some(payload)).await?;
+        Ok(())
+    }
+
+    pub async fn apply_default_policy(&self, mac: &str) -> Result<(), RouterError> {
+        self.apply_policy_to_client(mac, None).await
+    }
+
+    pub async fn set_client_block(&self, mac: &str) -> Result<(), RouterError> {
+        self.login().await?;
+        let payload = serde_json::json!({
+            "mac": mac,
+            "schedule": false,
+            "deny": true
+        });
+        self.keen_request(rci::HOTSPOT_HOST, Some(payload)).await?;
+        Ok(())
+    }
+
+    /// Registers the host on the router, or renames it if already registered.
+    pub async fn register_device(&self, mac: &str, name: &str) -> Result<(), RouterError> {
+        self.login().await?;
+        let payload = serde_json::json!({ "mac": mac, "name": name });
+        self.keen_request(rci::KNOWN_HOSTS, Some(payload)).await?;
+        Ok(())
+    }
+
+    /// Has the router send a Wake-on-LAN packet to `mac` on its network.
+    pub async fn wake(&self, mac: &str) -> Result<(), RouterError> {
+        self.login().await?;
+        let payload = serde_json::json!({ "mac": mac });
+        self.keen_request(rci::HOTSPOT_WAKE, Some(payload)).await?;
+        Ok(())
+    }
+
+    /// Restarts the router. The connection may drop before it answers.
+    pub async fn reboot(&self) -> Result<(), RouterError> {
+        self.login().await?;
+        self.keen_request(rci::REBOOT, Some(serde_json::json!({})))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,536 @@
+//! Talking to a router over HTTP with a blocking client, behind the
+//! `blocking` feature.
+
+use crate::http::{base_url, header_value, ndm_error};
+use crate::protocol::{password_hash, rci};
+use crate::{
+    client_is_online, client_is_registered, compat, fixture, parse_access_list, parse_guest_wifi,
+    parse_hotspot_hosts, parse_log, parse_mesh_nodes, parse_policies, AccessList, RouterError,
+};
+use reqwest::blocking::Client;
+use router_model::{
+    ClientInfo, DeviceInfo, DiscoveredRouter, GuestWifi, LogEntry, MeshNode, PolicyInfo, SpeedTest,
+    Topology, TopologyClient, TrafficCounters, WifiBand,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+
+/// A large file on a CDN, for `speed_test`.
+pub const SPEED_TEST_URL: &str = "https://speed.cloudflare.com/__down?bytes=25000000";
+
+pub struct KeeneticRouter {
+    base_url: String,
+    username: String,
+    password: String,
+    name: String,
+    client: Client,
+}
+
+impl KeeneticRouter {
+    pub fn new(address: &str, username: &str, password: &str, name: &str) -> Self {
+        let client = Client::builder()
+            .cookie_store(true)
+            .build()
+            .expect("reqwest client");
+        Self {
+            base_url: base_url(address),
+            username: username.to_string(),
+            password: password.to_string(),
+            name: name.to_string(),
+            client,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Checks that the router answers HTTP at all, whatever the status.
+    pub fn check_reachable(&self) -> Result<(), RouterError> {
+        self.client.get(format!("{}/auth", self.base_url)).send()?;
+        Ok(())
+    }
+
+    pub fn login(&self) -> Result<(), RouterError> {
+        let auth_url = format!("{}/auth", self.base_url);
+        let initial = self.client.get(auth_url).send()?;
+        if initial.status() == reqwest::StatusCode::UNAUTHORIZED {
+            let headers = initial.headers();
+            let realm = header_value(headers, "X-NDM-Realm")
+                .ok_or_else(|| RouterError::InvalidResponse("missing realm".into()))?;
+            let challenge = header_value(headers, "X-NDM-Challenge")
+                .ok_or_else(|| RouterError::InvalidResponse("missing challenge".into()))?;
+            let auth_data = serde_json::json!({
+                "login": self.username,
+                "password": password_hash(&self.username, &realm, &self.password, &challenge),
+            });
+            let auth_response = self
+                .client
+                .post(format!("{}/auth", self.base_url))
+                .json(&auth_data)
+                .send()?;
+            if auth_response.status().is_success() {
+                Ok(())
+            } else {
+                Err(RouterError::AuthFailed)
+            }
+        } else if initial.status().is_success() {
+            Ok(())
+        } else {
+            Err(RouterError::InvalidResponse(format!(
+                "unexpected auth status: {}",
+                initial.status()
+            )))
+        }
+    }
+
+    fn keen_request(&self, endpoint: &str, data: Option<Value>) -> Result<Value, RouterError> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        let response = if let Some(payload) = &data {
+            log::debug!("{}: POST {}", self.name, endpoint);
+            self.client.post(url).json(payload).send()?
+        } else {
+            log::debug!("{}: GET {}", self.name, endpoint);
+            self.client.get(url).send()?
+        };
+        let status = response.status().as_u16();
+        if !response.status().is_success() {
+            log::warn!("{}: {} returned {}", self.name, endpoint, response.status());
+            fixture::record(&self.name, endpoint, data.as_ref(), status, &Value::Null);
+            return Err(RouterError::InvalidResponse(format!(
+                "status {}",
+                response.status()
+            )));
+        }
+        let json = response.json::<Value>()?;
+        fixture::record(&self.name, endpoint, data.as_ref(), status, &json);
+        if let Some(message) = ndm_error(&json) {
+            log::warn!("{}: {} failed: {}", self.name, endpoint, message);
+            return Err(RouterError::InvalidResponse(message));
+        }
+        Ok(json)
+    }
+
+    pub fn get_keendns_urls(&self) -> Result<Vec<String>, RouterError> {
+        self.login()?;
+        let data = self.keen_request(rci::ACME_CERTIFICATES, None)?;
+        Ok(compat::items(&data, "certificate")
+            .into_iter()
+            .filter_map(|item| compat::text(item, "domain"))
+            .collect())
+    }
+
+    pub fn get_network_ip(&self) -> Result<Option<String>, RouterError> {
+        self.login()?;
+        let data = self.keen_request(rci::BRIDGE_ADDRESS, None)?;
+        Ok(compat::text(&data, "address"))
+    }
+
+    pub fn get_policies(&self) -> Result<HashMap<String, PolicyInfo>, RouterError> {
+        self.login()?;
+        let data = self.keen_request(rci::POLICIES, None)?;
+        Ok(parse_policies(&data))
+    }
+
+    pub fn get_online_clients(&self) -> Result<Vec<ClientInfo>, RouterError> {
+        self.login()?;
+        let data = self.keen_request(rci::HOTSPOT_HOSTS, None)?;
+        Ok(parse_hotspot_hosts(&data))
+    }
+
+    pub fn get_devices(&self) -> Result<Vec<DeviceInfo>, RouterError> {
+        let clients = self.get_online_clients()?;
+        let known = self.keen_request(rci::KNOWN_HOSTS, None)?;
+        let mut devices: Vec<DeviceInfo> = clients
+            .iter()
+            .map(|client| DeviceInfo {
+                name: client.name.clone(),
+                mac: client.mac.clone(),
+                ip: client.ip.clone(),
+                registered: client_is_registered(client),
+                online: client_is_online(client),
+                policy: client.policy.clone(),
+                deny: client.deny,
+                last_seen: compat::number(&client.raw, "last-seen"),
+                vendor: compat::text(&client.raw, "vendor"),
+            })
+            .collect();
+        // Registered hosts the router hasn't seen lately are missing from the
+        // hotspot list but are exactly what cleanup is about.
+        for item in compat::items(&known, "host") {
+            let Some(mac) = compat::text(item, "mac") else {
+                continue;
+            };
+            let mac = mac.to_lowercase();
+            if devices.iter().any(|d| d.mac == mac) {
+                continue;
+            }
+            devices.push(DeviceInfo {
+                name: compat::text(item, "name"),
+                mac,
+                ip: None,
+                registered: true,
+                online: false,
+                policy: None,
+                deny: false,
+                last_seen: None,
+                vendor: None,
+            });
+        }
+        devices.sort_by_key(|d| d.name.clone().unwrap_or_default().to_lowercase());
+        Ok(devices)
+    }
+
+    /// Registers the host on the router, or renames it if already registered.
+    pub fn register_device(&self, mac: &str, name: &str) -> Result<(), RouterError> {
+        self.login()?;
+        let payload = serde_json::json!({ "mac": mac, "name": name });
+        self.keen_request(rci::KNOWN_HOSTS, Some(payload))?;
+        Ok(())
+    }
+
+    pub fn forget_device(&self, mac: &str) -> Result<(), RouterError> {
+        self.login()?;
+        let payload = serde_json::json!({ "mac": mac, "no": true });
+        self.keen_request(rci::KNOWN_HOSTS, Some(payload.clone()))?;
+        self.keen_request(rci::HOTSPOT_HOST, Some(payload))?;
+        Ok(())
+    }
+
+    pub fn get_mesh_nodes(&self) -> Result<Vec<MeshNode>, RouterError> {
+        self.login()?;
+        let data = self.keen_request(rci::MESH_MEMBERS, None)?;
+        Ok(parse_mesh_nodes(&data))
+    }
+
+    /// Whether the router's own connectivity checker currently sees the
+    /// internet.
+    pub fn check_internet(&self) -> Result<bool, RouterError> {
+        self.login()?;
+        let data = self.keen_request(rci::INTERNET_STATUS, None)?;
+        compat::flag(&data, "internet")
+            .ok_or_else(|| RouterError::InvalidResponse("missing internet status".into()))
+    }
+
+    /// Byte counters of the interface carrying the default route.
+    pub fn get_wan_traffic(&self) -> Result<TrafficCounters, RouterError> {
+        self.login()?;
+        let routes = self.keen_request(rci::ROUTES, None)?;
+        let iface = compat::items(&routes, "route")
+            .into_iter()
+            .find(|route| compat::text(route, "destination").as_deref() == Some("0.0.0.0/0"))
+            .and_then(|route| compat::text(route, "interface"))
+            .ok_or_else(|| RouterError::InvalidResponse("no default route".into()))?;
+        let stat = self.keen_request(&rci::interface_stat(&iface), None)?;
+        let counter = |key: &str| {
+            compat::number(&stat, key)
+                .ok_or_else(|| RouterError::InvalidResponse(format!("missing {key}")))
+        };
+        Ok(TrafficCounters {
+            rx_bytes: counter("rxbytes")?,
+            tx_bytes: counter("txbytes")?,
+        })
+    }
+
+    /// The last `max_lines` lines of the system log, oldest first. The
+    /// router keeps only a small buffer in memory.
+    pub fn get_log(&self, max_lines: u32) -> Result<Vec<LogEntry>, RouterError> {
+        self.login()?;
+        let data = self.keen_request(&rci::log(max_lines), None)?;
+        let log = data
+            .get("log")
+            .ok_or_else(|| RouterError::InvalidResponse("missing log".into()))?;
+        Ok(parse_log(log))
+    }
+
+    pub fn get_topology(&self) -> Result<Topology, RouterError> {
+        let nodes = self.get_mesh_nodes()?;
+        let clients = self.get_online_clients()?;
+        let mut out: Vec<TopologyClient> = clients.iter().map(topology_client).collect();
+        out.sort_by_key(|c| c.name.to_lowercase());
+        Ok(Topology {
+            nodes,
+            clients: out,
+        })
+    }
+
+    pub fn apply_policy_to_client(
+        &self,
+        mac: &str,
+        policy: Option<&str>,
+    ) -> Result<(), RouterError> {
+        self.login()?;
+        let policy_value = match policy {
+            Some(name) => serde_json::Value::String(name.to_string()),
+            None => serde_json::Value::Bool(false),
+        };
+        let payload = serde_json::json!({
+            "mac": mac,
+            "policy": policy_value,
+            "permit": true,
+            "schedule": false
+        });
+        self.keen_request(rci::HOTSPOT_HOST, Some(payload))?;
+        Ok(())
+    }
+
+    pub fn apply_default_policy(&self, mac: &str) -> Result<(), RouterError> {
+        self.apply_policy_to_client(mac, None)
+    }
+
+    pub fn set_client_block(&self, mac: &str) -> Result<(), RouterError> {
+        self.login()?;
+        let payload = serde_json::json!({
+            "mac": mac,
+            "schedule": false,
+            "deny": true
+        });
+        self.keen_request(rci::HOTSPOT_HOST, Some(payload))?;
+        Ok(())
+    }
+
+    /// Adds `interface` to the connections `policy` routes through, after
+    /// the ones it has.
+    pub fn add_policy_connection(&self, policy: &str, interface: &str) -> Result<(), RouterError> {
+        self.set_policy_connection(policy, interface, false)
+    }
+
+    pub fn remove_policy_connection(
+        &self,
+        policy: &str,
+        interface: &str,
+    ) -> Result<(), RouterError> {
+        self.set_policy_connection(policy, interface, true)
+    }
+
+    fn set_policy_connection(
+        &self,
+        policy: &str,
+        interface: &str,
+        remove: bool,
+    ) -> Result<(), RouterError> {
+        self.login()?;
+        let mut permit = serde_json::json!({ "auto": true, "interface": interface });
+        if remove {
+            permit["no"] = Value::Bool(true);
+        }
+        let payload = serde_json::json!({ "name": policy, "permit": permit });
+        self.keen_request(rci::POLICY, Some(payload))?;
+        Ok(())
+    }
+
+    /// The band each client is held to. The router has no such setting of
+    /// its own, so a client denied on one band's access point counts as
+    /// held to the other.
+    pub fn get_band_locks(&self) -> Result<HashMap<String, WifiBand>, RouterError> {
+        self.login()?;
+        let on_2_4 = self.access_list(WifiBand::Ghz2_4)?;
+        let on_5 = self.access_list(WifiBand::Ghz5)?;
+        let mut locks = HashMap::new();
+        for mac in on_2_4.macs.iter().chain(&on_5.macs) {
+            // Denied on both is off Wi-Fi altogether, not a preference.
+            match (on_2_4.denies(mac), on_5.denies(mac)) {
+                (true, false) => locks.insert(mac.clone(), WifiBand::Ghz5),
+                (false, true) => locks.insert(mac.clone(), WifiBand::Ghz2_4),
+                _ => None,
+            };
+        }
+        Ok(locks)
+    }
+
+    /// Holds `mac` to `band` by denying it on the other band's access
+    /// point, or with `None` lets it on both again.
+    pub fn set_band_lock(&self, mac: &str, band: Option<WifiBand>) -> Result<(), RouterError> {
+        self.login()?;
+        let mac = mac.to_lowercase();
+        let bands = [WifiBand::Ghz2_4, WifiBand::Ghz5];
+        let lists = bands
+            .iter()
+            .map(|band| self.access_list(*band))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Checked on both before changing either, so a refusal leaves
+        // things as they were.
+        let keep_off = |on: WifiBand| band == Some(on.other());
+        for (on, list) in bands.iter().zip(&lists) {
+            if keep_off(*on) && list.kind.as_deref() == Some("permit") {
+                return Err(RouterError::InvalidResponse(format!(
+                    "{} only admits the clients on its list",
+                    rci::access_point(*on)
+                )));
+            }
+        }
+        for (on, list) in bands.iter().zip(&lists) {
+            let access_point = rci::access_point(*on);
+            if keep_off(*on) && !list.denies(&mac) {
+                if list.kind.is_none() {
+                    let payload = serde_json::json!({ "type": "deny" });
+                    self.keen_request(&rci::access_list(access_point), Some(payload))?;
+                }
+                let payload = serde_json::json!({ "address": mac });
+                self.keen_request(&rci::access_list_address(access_point), Some(payload))?;
+            } else if !keep_off(*on) && list.denies(&mac) {
+                let payload = serde_json::json!({ "address": mac, "no": true });
+                self.keen_request(&rci::access_list_address(access_point), Some(payload))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The guest network, read from its 2.4 GHz access point.
+    pub fn get_guest_wifi(&self) -> Result<GuestWifi, RouterError> {
+        self.login()?;
+        let access_point = rci::guest_access_point(WifiBand::Ghz2_4);
+        let data = self.keen_request(&rci::interface_config(access_point), None)?;
+        Ok(parse_guest_wifi(&data))
+    }
+
+    /// Brings the guest network up or down on both bands, first switching it
+    /// to WPA2 with `passphrase` if one is given. Routers with a single band
+    /// only have the 2.4 GHz access point.
+    pub fn set_guest_wifi(
+        &self,
+        enabled: bool,
+        passphrase: Option<&str>,
+    ) -> Result<(), RouterError> {
+        self.login()?;
+        let mut payload = if enabled {
+            serde_json::json!({ "up": true })
+        } else {
+            serde_json::json!({ "down": true })
+        };
+        if let Some(passphrase) = passphrase {
+            payload["authentication"] = serde_json::json!({ "wpa-psk": { "psk": passphrase } });
+            payload["encryption"] = serde_json::json!({ "enable": true, "wpa2": true });
+        }
+        let access_point = rci::guest_access_point(WifiBand::Ghz2_4);
+        self.keen_request(&rci::interface(access_point), Some(payload.clone()))?;
+        let access_point = rci::guest_access_point(WifiBand::Ghz5);
+        if let Err(err) = self.keen_request(&rci::interface(access_point), Some(payload)) {
+            log::debug!("{}: no 5 GHz guest network: {err}", self.name);
+        }
+        Ok(())
+    }
+
+    fn access_list(&self, band: WifiBand) -> Result<AccessList, RouterError> {
+        let data = self.keen_request(&rci::access_list_config(rci::access_point(band)), None)?;
+        Ok(parse_access_list(&data))
+    }
+
+    /// Has the router send a Wake-on-LAN packet to `mac` on its network.
+    pub fn wake(&self, mac: &str) -> Result<(), RouterError> {
+        self.login()?;
+        let payload = serde_json::json!({ "mac": mac });
+        self.keen_request(rci::HOTSPOT_WAKE, Some(payload))?;
+        Ok(())
+    }
+
+    /// Restarts the router. The connection may drop before it answers.
+    pub fn reboot(&self) -> Result<(), RouterError> {
+        self.login()?;
+        self.keen_request(rci::REBOOT, Some(serde_json::json!({})))?;
+        Ok(())
+    }
+
+    /// The running configuration as the router's CLI prints it, one
+    /// command per line, which is what restoring it takes.
+    pub fn get_running_config(&self) -> Result<String, RouterError> {
+        self.login()?;
+        let data = self.keen_request(rci::RUNNING_CONFIG, None)?;
+        let lines = data
+            .get("message")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| RouterError::InvalidResponse("missing configuration".into()))?;
+        let mut config: String = lines
+            .iter()
+            .filter_map(|line| line.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        config.push('\n');
+        Ok(config)
+    }
+
+    /// Downloads `url` through the router and times it. KeeneticOS has no
+    /// speed test of its own in RCI, so the router's part is its WAN
+    /// counters, read before and after.
+    pub fn speed_test(&self, url: &str) -> Result<SpeedTest, RouterError> {
+        let before = self.get_wan_traffic().ok();
+        let download = Client::builder().timeout(Duration::from_secs(60)).build()?;
+        let started = Instant::now();
+        let mut response = download.get(url).send()?.error_for_status()?;
+        let bytes = std::io::copy(&mut response, &mut std::io::sink())
+            .map_err(|err| RouterError::InvalidResponse(format!("download failed: {err}")))?;
+        let seconds = started.elapsed().as_secs_f64();
+        let after = self.get_wan_traffic().ok();
+        let mbps = |bytes: u64| bytes as f64 * 8.0 / seconds.max(f64::EPSILON) / 1_000_000.0;
+        Ok(SpeedTest {
+            bytes,
+            seconds,
+            download_mbps: mbps(bytes),
+            wan_mbps: before
+                .zip(after)
+                .map(|(before, after)| mbps(after.rx_bytes.saturating_sub(before.rx_bytes))),
+        })
+    }
+}
+
+fn topology_client(client: &ClientInfo) -> TopologyClient {
+    let raw = &client.raw;
+    TopologyClient {
+        name: client.name.clone().unwrap_or_else(|| client.mac.clone()),
+        mac: client.mac.clone(),
+        ip: client.ip.clone(),
+        parent: raw.get("mws").and_then(|mws| compat::text(mws, "cid")),
+        wireless: raw.get("ssid").is_some() || raw.get("ap").is_some(),
+        port: compat::text(raw, "port"),
+        online: client_is_online(client),
+        deny: client.deny,
+    }
+}
+
+/// Probes the first host of every local IPv4 segment (where the gateway
+/// usually sits) and returns the ones that answer like a Keenetic router,
+/// i.e. with an `X-NDM-Realm` header on `/auth`.
+pub fn discover_routers(networks: &[ipnetwork::IpNetwork]) -> Vec<DiscoveredRouter> {
+    let client = match Client::builder().timeout(Duration::from_secs(2)).build() {
+        Ok(client) => client,
+        Err(_) => return Vec::new(),
+    };
+    let mut candidates: Vec<(Ipv4Addr, String)> = Vec::new();
+    for network in networks {
+        let ipnetwork::IpNetwork::V4(v4) = network else {
+            continue;
+        };
+        if v4.ip().is_loopback() || v4.ip().is_link_local() || !(16..=30).contains(&v4.prefix()) {
+            continue;
+        }
+        let gateway = Ipv4Addr::from(u32::from(v4.network()) + 1);
+        let cidr = format!("{}/{}", v4.network(), v4.prefix());
+        if !candidates.iter().any(|(ip, _)| *ip == gateway) {
+            candidates.push((gateway, cidr));
+        }
+    }
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = candidates
+            .into_iter()
+            .map(|(ip, network)| {
+                let client = &client;
+                scope.spawn(move || {
+                    let response = client.get(format!("http://{}/auth", ip)).send().ok()?;
+                    let headers = response.headers();
+                    headers.get("X-NDM-Realm")?;
+                    Some(DiscoveredRouter {
+                        address: ip.to_string(),
+                        realm: header_value(headers, "X-NDM-Realm"),
+                        network,
+                    })
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().ok().flatten())
+            .collect()
+    })
+}
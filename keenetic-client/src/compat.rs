@@ -0,0 +1,68 @@
+//! Reading RCI answers across firmware versions, which disagree on shapes:
+//! lists come bare, wrapped in an object, or keyed by their index; flags
+//! are booleans or "yes"/"up"; counters are numbers or strings. A field
+//! that doesn't fit reads as missing instead of failing the answer.
+
+use serde_json::Value;
+
+/// The entries of a list, however it came: an array, an object holding it
+/// under `wrapper`, or an object keyed by the entries' numbers.
+pub(crate) fn items<'a>(data: &'a Value, wrapper: &str) -> Vec<&'a Value> {
+    match data {
+        Value::Array(list) => list.iter().collect(),
+        Value::Object(map) => {
+            if let Some(inner) = map.get(wrapper) {
+                return items(inner, wrapper);
+            }
+            let mut numbered: Vec<(u64, &Value)> = map
+                .iter()
+                .filter_map(|(key, value)| Some((key.parse().ok()?, value)))
+                .collect();
+            if numbered.len() != map.len() {
+                return Vec::new();
+            }
+            numbered.sort_by_key(|(index, _)| *index);
+            numbered.into_iter().map(|(_, value)| value).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+pub(crate) fn text(item: &Value, key: &str) -> Option<String> {
+    match item.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+pub(crate) fn flag(item: &Value, key: &str) -> Option<bool> {
+    match item.get(key)? {
+        Value::Bool(b) => Some(*b),
+        Value::Number(n) => n.as_u64().map(|n| n != 0),
+        Value::String(s) => match s.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "up" | "1" => Some(true),
+            "false" | "no" | "off" | "down" | "0" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub(crate) fn number(item: &Value, key: &str) -> Option<u64> {
+    match item.get(key)? {
+        Value::Number(n) => n
+            .as_u64()
+            .or_else(|| n.as_f64().filter(|f| *f >= 0.0).map(|f| f as u64)),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+pub(crate) fn signed(item: &Value, key: &str) -> Option<i64> {
+    match item.get(key)? {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,234 @@
+//! Recording RCI exchanges with a real router as fixtures, so a parsing
+//! problem on someone's firmware can be replayed with keenetic-sim:
+//!
+//! ```sh
+//! router-cli --record ./capture clients
+//! cargo run -p keenetic-sim -- --replay ./capture
+//! ```
+//!
+//! Each request goes to its own numbered file with the router's answer.
+//! Before anything is written, MACs, host and network names, KeenDNS
+//! domains and public addresses are swapped for stand-ins, the same one
+//! every time the original appears, and secrets in the running
+//! configuration are blanked.
+
+use crate::protocol::rci;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+
+/// Configuration lines with any of these in them are cut after the command.
+const SECRET_WORDS: [&str; 6] = ["password", "psk", "key", "secret", "community", "token"];
+
+struct Recorder {
+    dir: PathBuf,
+    count: usize,
+    sanitizer: Sanitizer,
+}
+
+/// Starts saving every RCI request this process makes, and the answer, to
+/// `dir`.
+pub fn record_fixtures(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut recorder = RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+    *recorder = Some(Recorder {
+        dir: dir.to_path_buf(),
+        count: 0,
+        sanitizer: Sanitizer::default(),
+    });
+    Ok(())
+}
+
+pub(crate) fn record(
+    router: &str,
+    endpoint: &str,
+    request: Option<&Value>,
+    status: u16,
+    response: &Value,
+) {
+    let mut guard = RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(recorder) = guard.as_mut() else {
+        return;
+    };
+    recorder.count += 1;
+    let sanitizer = &mut recorder.sanitizer;
+    let mut request = request.cloned().unwrap_or(Value::Null);
+    sanitizer.value(None, &mut request);
+    let mut response = response.clone();
+    if endpoint == rci::RUNNING_CONFIG {
+        sanitizer.config(&mut response);
+    }
+    sanitizer.value(None, &mut response);
+    let fixture = json!({
+        "router": router,
+        "method": if request.is_null() { "GET" } else { "POST" },
+        "endpoint": endpoint,
+        "request": request,
+        "status": status,
+        "response": response,
+    });
+    let slug: String = endpoint
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let path = recorder
+        .dir
+        .join(format!("{:04}-{}.json", recorder.count, slug));
+    let written = serde_json::to_string_pretty(&fixture)
+        .map_err(std::io::Error::from)
+        .and_then(|text| fs::write(&path, text));
+    if let Err(err) = written {
+        log::warn!("failed to write fixture {}: {}", path.display(), err);
+    }
+}
+
+#[derive(Default)]
+struct Sanitizer {
+    macs: HashMap<String, String>,
+    names: HashMap<String, String>,
+    addresses: HashMap<Ipv4Addr, Ipv4Addr>,
+}
+
+impl Sanitizer {
+    fn value(&mut self, key: Option<&str>, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.text(key, text),
+            Value::Array(items) => {
+                for item in items {
+                    self.value(key, item);
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map.iter_mut() {
+                    self.value(Some(key), item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, key: Option<&str>, text: &str) -> String {
+        match key {
+            Some("name" | "known-host" | "hostname") => self.pseudonym("host", text),
+            Some("ssid") => self.pseudonym("network", text),
+            Some("domain") => format!("{}.example", self.pseudonym("router", text)),
+            _ => match text.parse::<Ipv4Addr>() {
+                Ok(ip) if is_public(ip) => self.address(ip).to_string(),
+                _ => self.macs_in(text),
+            },
+        }
+    }
+
+    fn pseudonym(&mut self, kind: &str, text: &str) -> String {
+        if text.is_empty() {
+            return String::new();
+        }
+        let prefix = format!("{kind}:");
+        let next = self.names.keys().filter(|k| k.starts_with(&prefix)).count() + 1;
+        self.names
+            .entry(format!("{kind}:{text}"))
+            .or_insert_with(|| format!("{kind}-{next}"))
+            .clone()
+    }
+
+    fn address(&mut self, ip: Ipv4Addr) -> Ipv4Addr {
+        // TEST-NET-3, reserved for documentation.
+        let next = (self.addresses.len() % 254) as u8 + 1;
+        *self
+            .addresses
+            .entry(ip)
+            .or_insert(Ipv4Addr::new(203, 0, 113, next))
+    }
+
+    /// Replaces anything shaped like a MAC, keeping the separator, with a
+    /// locally administered one.
+    fn macs_in(&mut self, text: &str) -> String {
+        let bytes = text.as_bytes();
+        let mut out = String::with_capacity(text.len());
+        let (mut start, mut i) = (0, 0);
+        while i + 17 <= bytes.len() {
+            if is_mac(&bytes[i..i + 17]) {
+                out.push_str(&text[start..i]);
+                let mac = text[i..i + 17].to_lowercase().replace('-', ":");
+                let next = self.macs.len() + 1;
+                let fake = self
+                    .macs
+                    .entry(mac)
+                    .or_insert_with(|| {
+                        format!(
+                            "02:00:00:{:02x}:{:02x}:{:02x}",
+                            next >> 16 & 0xff,
+                            next >> 8 & 0xff,
+                            next & 0xff
+                        )
+                    })
+                    .replace(':', &(bytes[i + 2] as char).to_string());
+                out.push_str(&fake);
+                i += 17;
+                start = i;
+            } else {
+                i += 1;
+            }
+        }
+        out.push_str(&text[start..]);
+        out
+    }
+
+    fn config(&mut self, response: &mut Value) {
+        let Some(lines) = response.get_mut("message").and_then(|v| v.as_array_mut()) else {
+            return;
+        };
+        for line in lines {
+            let Some(text) = line.as_str() else {
+                continue;
+            };
+            let lower = text.to_lowercase();
+            if SECRET_WORDS.iter().any(|word| lower.contains(word)) {
+                let indent = text.len() - text.trim_start().len();
+                let command = text.split_whitespace().next().unwrap_or_default();
+                *line = Value::String(format!("{}{command} [removed]", &text[..indent]));
+                continue;
+            }
+            // Host names, SSIDs and descriptions are quoted.
+            let quoted: Vec<String> = text
+                .split('"')
+                .enumerate()
+                .map(|(i, part)| {
+                    if i % 2 == 1 {
+                        self.pseudonym("text", part)
+                    } else {
+                        part.to_string()
+                    }
+                })
+                .collect();
+            *line = Value::String(quoted.join("\""));
+        }
+    }
+}
+
+fn is_mac(bytes: &[u8]) -> bool {
+    let separator = bytes[2];
+    (separator == b':' || separator == b'-')
+        && bytes.iter().enumerate().all(|(i, c)| {
+            if i % 3 == 2 {
+                *c == separator
+            } else {
+                c.is_ascii_hexdigit()
+            }
+        })
+}
+
+fn is_public(ip: Ipv4Addr) -> bool {
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.octets()[0] == 0
+        // Multicast, reserved, and netmasks.
+        || ip.octets()[0] >= 224)
+}
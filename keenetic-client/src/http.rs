@@ -0,0 +1,42 @@
+//! What the blocking and async clients share about talking HTTP to a
+//! router.
+
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+
+/// The router's address as given by the user, with a scheme and without a
+/// trailing slash.
+pub(crate) fn base_url(address: &str) -> String {
+    let mut base = address.trim().to_string();
+    if !base.starts_with("http") {
+        if base.ends_with('/') {
+            base.pop();
+        }
+        base = format!("http://{}", base);
+    }
+    base
+}
+
+/// A command the router refused still comes back with 200, and a status
+/// list such as `{"status": [{"status": "error", "message": "..."}]}`.
+pub(crate) fn ndm_error(data: &Value) -> Option<String> {
+    let failed = data
+        .get("status")?
+        .as_array()?
+        .iter()
+        .find(|status| status.get("status").and_then(|v| v.as_str()) == Some("error"))?;
+    Some(
+        failed
+            .get("message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("command failed")
+            .to_string(),
+    )
+}
+
+pub(crate) fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+}
@@ -0,0 +1,299 @@
+//! Talking to Keenetic routers over RCI, their JSON API: logging in,
+//! reading clients, policies, the mesh and the log, and changing them.
+//! With default features off it's only the protocol and the parsers of the
+//! router's answers, which build for `wasm32-unknown-unknown`.
+
+use router_model::{
+    ClientInfo, GuestWifi, InterfaceInfo, LogEntry, MeshNode, PolicyConnection, PolicyInfo,
+    TrafficCounters, WifiLink,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[cfg(feature = "async")]
+mod async_client;
+#[cfg(feature = "blocking")]
+mod client;
+mod compat;
+#[cfg(any(feature = "blocking", feature = "async"))]
+mod fixture;
+#[cfg(any(feature = "blocking", feature = "async"))]
+mod http;
+pub mod protocol;
+
+#[cfg(feature = "async")]
+pub use async_client::AsyncKeeneticRouter;
+#[cfg(feature = "blocking")]
+pub use client::{discover_routers, KeeneticRouter, SPEED_TEST_URL};
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub use fixture::record_fixtures;
+pub use protocol::password_hash;
+
+#[derive(Debug, Error)]
+pub enum RouterError {
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+    #[error("authentication failed")]
+    AuthFailed,
+}
+
+/// The clients in a `show ip hotspot host` answer, one per MAC. The router
+/// lists a host once per interface it was seen on.
+pub fn parse_hotspot_hosts(data: &Value) -> Vec<ClientInfo> {
+    let mut map: HashMap<String, ClientInfo> = HashMap::new();
+    for item in compat::items(data, "host") {
+        if !item.is_object() {
+            continue;
+        }
+        let mac = compat::text(item, "mac").unwrap_or_default().to_lowercase();
+        if mac.is_empty() {
+            continue;
+        }
+        let entry = map.entry(mac.clone()).or_insert(ClientInfo {
+            name: None,
+            ip: None,
+            mac: mac.clone(),
+            policy: None,
+            deny: false,
+            raw: Value::Null,
+        });
+        if entry.name.is_none() {
+            entry.name = compat::text(item, "name");
+        }
+        if entry.ip.is_none() {
+            entry.ip = compat::text(item, "ip");
+        }
+        if entry.policy.is_none() {
+            // `false` stands for the default policy.
+            entry.policy = item
+                .get("policy")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+        }
+        if let Some(deny) = compat::flag(item, "deny") {
+            entry.deny = deny;
+        }
+        entry.raw = item.clone();
+    }
+    map.into_values().collect()
+}
+
+/// Policies by name. Newer firmware lists them as an object keyed by name,
+/// some as a list of objects carrying their `name`.
+pub fn parse_policies(data: &Value) -> HashMap<String, PolicyInfo> {
+    let data = data.get("policy").unwrap_or(data);
+    let entries: Vec<(String, &Value)> = match data {
+        Value::Object(map) => map
+            .iter()
+            .map(|(name, info)| (name.clone(), info))
+            .collect(),
+        Value::Array(list) => list
+            .iter()
+            .filter_map(|info| Some((compat::text(info, "name")?, info)))
+            .collect(),
+        _ => Vec::new(),
+    };
+    entries
+        .into_iter()
+        .map(|(name, info)| {
+            let connections = info
+                .get("permit")
+                .map(|permit| compat::items(permit, "permit"))
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|entry| {
+                    Some(PolicyConnection {
+                        interface: compat::text(entry, "interface")?,
+                        enabled: compat::flag(entry, "enabled")
+                            .unwrap_or(compat::flag(entry, "no") != Some(true)),
+                    })
+                })
+                .collect();
+            let description = compat::text(info, "description");
+            (
+                name,
+                PolicyInfo {
+                    description,
+                    connections,
+                },
+            )
+        })
+        .collect()
+}
+
+/// The mesh extenders in a `show mws member` answer; ones without an id
+/// are left out.
+pub fn parse_mesh_nodes(data: &Value) -> Vec<MeshNode> {
+    compat::items(data, "member")
+        .into_iter()
+        .filter_map(|item| {
+            Some(MeshNode {
+                id: compat::text(item, "cid")?,
+                name: compat::text(item, "known-host"),
+                model: compat::text(item, "model"),
+                mac: compat::text(item, "mac").map(|mac| mac.to_lowercase()),
+                ip: compat::text(item, "ip"),
+                online: compat::flag(item, "internet-available")
+                    .or_else(|| compat::flag(item, "connected"))
+                    .unwrap_or(true),
+            })
+        })
+        .collect()
+}
+
+/// The lines of a `show log` answer's `log`, oldest first. Older firmware
+/// lists them in an object keyed by their number.
+pub fn parse_log(log: &Value) -> Vec<LogEntry> {
+    compat::items(log, "log")
+        .into_iter()
+        .filter_map(|item| {
+            let (level, message) = match item.get("message")? {
+                Value::String(text) => (None, text.clone()),
+                other => (
+                    compat::text(other, "level"),
+                    compat::text(other, "message")?,
+                ),
+            };
+            Some(LogEntry {
+                timestamp: compat::text(item, "timestamp").unwrap_or_default(),
+                ident: compat::text(item, "ident").unwrap_or_default(),
+                level,
+                message,
+            })
+        })
+        .collect()
+}
+
+/// An access point's MAC list: whether it denies or only permits the
+/// clients on it, and their MACs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccessList {
+    /// `"deny"` or `"permit"`; a list that isn't in use has none.
+    pub kind: Option<String>,
+    pub macs: Vec<String>,
+}
+
+impl AccessList {
+    pub fn denies(&self, mac: &str) -> bool {
+        self.kind.as_deref() == Some("deny") && self.macs.iter().any(|m| m == mac)
+    }
+}
+
+pub fn parse_access_list(data: &Value) -> AccessList {
+    let macs = data
+        .get("address")
+        .map(|addresses| compat::items(addresses, "address"))
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Value::String(mac) => Some(mac.clone()),
+            _ => compat::text(entry, "address").or_else(|| compat::text(entry, "mac")),
+        })
+        .map(|mac| mac.to_lowercase())
+        .collect();
+    AccessList {
+        kind: compat::text(data, "type").filter(|kind| kind != "none"),
+        macs,
+    }
+}
+
+/// The `sc interface` answer for a guest access point.
+pub fn parse_guest_wifi(data: &Value) -> GuestWifi {
+    GuestWifi {
+        ssid: compat::text(data, "ssid").filter(|ssid| !ssid.is_empty()),
+        enabled: compat::flag(data, "up").unwrap_or(false),
+    }
+}
+
+pub fn client_interfaces(clients: &[ClientInfo]) -> Vec<InterfaceInfo> {
+    let mut out: Vec<InterfaceInfo> = clients
+        .iter()
+        .map(|client| {
+            let iface_type = if client.raw.get("ssid").is_some() {
+                "Wi-Fi"
+            } else {
+                "Unknown"
+            };
+            InterfaceInfo {
+                name: client.mac.clone(),
+                display_name: client.name.clone().unwrap_or_else(|| client.mac.clone()),
+                mac: client.mac.clone(),
+                ip: client.ip.clone().unwrap_or_else(|| "N/A".to_string()),
+                iface_type: iface_type.to_string(),
+                online: client_is_online(client),
+                policy: client.policy.clone(),
+                deny: client.deny,
+                wifi: client_wifi(client),
+            }
+        })
+        .collect();
+    out.sort_by_key(|iface| iface.display_name.to_lowercase());
+    out
+}
+
+/// Cumulative byte counters per client MAC, as seen by the router.
+pub fn client_traffic(clients: &[ClientInfo]) -> HashMap<String, TrafficCounters> {
+    clients
+        .iter()
+        .filter_map(|client| {
+            let rx_bytes = compat::number(&client.raw, "rxbytes")?;
+            let tx_bytes = compat::number(&client.raw, "txbytes")?;
+            Some((client.mac.clone(), TrafficCounters { rx_bytes, tx_bytes }))
+        })
+        .collect()
+}
+
+/// Wi-Fi details of a client, or `None` for wired ones. Mesh clients carry
+/// them under `mws`; the band follows from the radio the access point
+/// belongs to (`WifiMaster0` is 2.4 GHz, `WifiMaster1` is 5 GHz).
+pub fn client_wifi(client: &ClientInfo) -> Option<WifiLink> {
+    let sources = [Some(&client.raw), client.raw.get("mws")];
+    let field = |key: &str| {
+        sources
+            .iter()
+            .flatten()
+            .find_map(|raw| compat::text(raw, key))
+    };
+    let ssid = field("ssid");
+    let ap = field("ap");
+    if ssid.is_none() && ap.is_none() {
+        return None;
+    }
+    let band = ap.and_then(|ap| {
+        if ap.starts_with("WifiMaster0") {
+            Some("2.4 GHz".to_string())
+        } else if ap.starts_with("WifiMaster1") {
+            Some("5 GHz".to_string())
+        } else {
+            None
+        }
+    });
+    Some(WifiLink {
+        ssid,
+        band,
+        rssi: sources
+            .iter()
+            .flatten()
+            .find_map(|raw| compat::signed(raw, "rssi")),
+    })
+}
+
+/// Whether the host is on the router's list of known (registered) hosts.
+pub fn client_is_registered(client: &ClientInfo) -> bool {
+    compat::flag(&client.raw, "registered").unwrap_or(false)
+}
+
+pub fn client_is_online(client: &ClientInfo) -> bool {
+    if compat::flag(&client.raw, "link") == Some(true) {
+        return true;
+    }
+    client
+        .raw
+        .get("mws")
+        .and_then(|mws| compat::flag(mws, "link"))
+        == Some(true)
+}
@@ -0,0 +1,90 @@
+//! The parts of the RCI protocol that don't need a transport: the login
+//! hash and the command paths. Everything here, like the models and
+//! parsing, builds for `wasm32-unknown-unknown` with default features off,
+//! so a frontend can share it with whatever does the HTTP.
+
+use sha2::{Digest, Sha256};
+
+/// What `/auth` expects as the password after the router answered with a
+/// realm and challenge: the `login:realm:password` MD5, appended to the
+/// challenge and hashed again with SHA-256.
+pub fn password_hash(login: &str, realm: &str, password: &str, challenge: &str) -> String {
+    let md5_hex = format!("{:x}", md5::compute(format!("{login}:{realm}:{password}")));
+    hex::encode(Sha256::digest(format!("{challenge}{md5_hex}")))
+}
+
+/// RCI command paths, relative to the router's address.
+pub mod rci {
+    use router_model::WifiBand;
+
+    pub const ACME_CERTIFICATES: &str = "rci/ip/http/ssl/acme/list/certificate";
+    pub const BRIDGE_ADDRESS: &str = "rci/sc/interface/Bridge0/ip/address";
+    pub const POLICIES: &str = "rci/show/rc/ip/policy";
+    /// Posting here changes a policy's connections.
+    pub const POLICY: &str = "rci/ip/policy";
+    pub const HOTSPOT_HOSTS: &str = "rci/show/ip/hotspot/host";
+    /// Registered hosts; posting here names or forgets one.
+    pub const KNOWN_HOSTS: &str = "rci/known/host";
+    /// Posting here sets a host's policy, access or schedule.
+    pub const HOTSPOT_HOST: &str = "rci/ip/hotspot/host";
+    pub const HOTSPOT_WAKE: &str = "rci/ip/hotspot/wake";
+    pub const MESH_MEMBERS: &str = "rci/show/mws/member";
+    pub const INTERNET_STATUS: &str = "rci/show/internet/status";
+    pub const ROUTES: &str = "rci/show/ip/route";
+    pub const REBOOT: &str = "rci/system/reboot";
+    pub const RUNNING_CONFIG: &str = "rci/show/running-config";
+
+    /// The home segment's access point on each band.
+    pub const ACCESS_POINT_2_4: &str = "WifiMaster0/AccessPoint0";
+    pub const ACCESS_POINT_5: &str = "WifiMaster1/AccessPoint0";
+
+    pub fn access_point(band: WifiBand) -> &'static str {
+        match band {
+            WifiBand::Ghz2_4 => ACCESS_POINT_2_4,
+            WifiBand::Ghz5 => ACCESS_POINT_5,
+        }
+    }
+
+    /// The guest segment's access point on each band.
+    pub const GUEST_ACCESS_POINT_2_4: &str = "WifiMaster0/AccessPoint1";
+    pub const GUEST_ACCESS_POINT_5: &str = "WifiMaster1/AccessPoint1";
+
+    pub fn guest_access_point(band: WifiBand) -> &'static str {
+        match band {
+            WifiBand::Ghz2_4 => GUEST_ACCESS_POINT_2_4,
+            WifiBand::Ghz5 => GUEST_ACCESS_POINT_5,
+        }
+    }
+
+    /// Posting here changes an interface, or brings it up or down.
+    pub fn interface(name: &str) -> String {
+        format!("rci/interface/{name}")
+    }
+
+    pub fn interface_config(name: &str) -> String {
+        format!("rci/sc/interface/{name}")
+    }
+
+    /// The MAC list of an access point; posting here sets whether it
+    /// allows or denies the listed clients.
+    pub fn access_list(access_point: &str) -> String {
+        format!("rci/interface/{access_point}/mac/access-list")
+    }
+
+    /// Posting here adds a MAC to the list, or with `"no": true` removes it.
+    pub fn access_list_address(access_point: &str) -> String {
+        format!("rci/interface/{access_point}/mac/access-list/address")
+    }
+
+    pub fn access_list_config(access_point: &str) -> String {
+        format!("rci/sc/interface/{access_point}/mac/access-list")
+    }
+
+    pub fn interface_stat(name: &str) -> String {
+        format!("rci/show/interface/stat?name={name}")
+    }
+
+    pub fn log(max_lines: u32) -> String {
+        format!("rci/show/log?max-lines={max_lines}")
+    }
+}
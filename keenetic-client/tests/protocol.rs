@@ -0,0 +1,23 @@
+//! The transport-free protocol layer, which has to hold without `blocking`.
+
+use keenetic_client::password_hash;
+use keenetic_client::protocol::rci;
+
+#[test]
+fn password_hash_matches_router() {
+    // The same vector the login test in rci.rs expects on the wire.
+    let hash = password_hash("admin", "Keenetic Giga", "hunter2", "NXQOBLJWZSYHGTCKPVFI");
+    assert_eq!(
+        hash,
+        "1965f42d8a570f714f5460f32475d58c65fe9d440dec0f1d26934ef1aeeba3ed"
+    );
+}
+
+#[test]
+fn rci_paths_with_arguments() {
+    assert_eq!(
+        rci::interface_stat("ISP"),
+        "rci/show/interface/stat?name=ISP"
+    );
+    assert_eq!(rci::log(50), "rci/show/log?max-lines=50");
+}
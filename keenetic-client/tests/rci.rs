@@ -0,0 +1,314 @@
+//! `KeeneticRouter` against a mock router: the login handshake, how RCI
+//! answers are parsed, and how failures surface.
+
+use httpmock::prelude::*;
+use keenetic_client::{KeeneticRouter, RouterError};
+use router_model::WifiBand;
+use serde_json::json;
+
+/// A router whose session is already open, so commands go straight through.
+fn logged_in(server: &MockServer) -> KeeneticRouter {
+    server.mock(|when, then| {
+        when.method(GET).path("/auth");
+        then.status(200);
+    });
+    KeeneticRouter::new(&server.base_url(), "admin", "hunter2", "Test")
+}
+
+fn challenge(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET).path("/auth");
+        then.status(401)
+            .header("X-NDM-Realm", "Keenetic Giga")
+            .header("X-NDM-Challenge", "NXQOBLJWZSYHGTCKPVFI");
+    });
+}
+
+#[test]
+fn login_answers_the_challenge() {
+    let server = MockServer::start();
+    challenge(&server);
+    // sha256(challenge + md5("admin:Keenetic Giga:hunter2")), worked out
+    // independently of the code under test.
+    let answer = server.mock(|when, then| {
+        when.method(POST).path("/auth").json_body(json!({
+            "login": "admin",
+            "password": "1965f42d8a570f714f5460f32475d58c65fe9d440dec0f1d26934ef1aeeba3ed",
+        }));
+        then.status(200);
+    });
+    let router = KeeneticRouter::new(&server.base_url(), "admin", "hunter2", "Test");
+    router.login().unwrap();
+    answer.assert();
+}
+
+#[test]
+fn refused_login_is_auth_failed() {
+    let server = MockServer::start();
+    challenge(&server);
+    server.mock(|when, then| {
+        when.method(POST).path("/auth");
+        then.status(401);
+    });
+    let router = KeeneticRouter::new(&server.base_url(), "admin", "wrong", "Test");
+    assert!(matches!(router.login(), Err(RouterError::AuthFailed)));
+}
+
+#[test]
+fn open_session_skips_the_handshake() {
+    let server = MockServer::start();
+    let router = logged_in(&server);
+    let answer = server.mock(|when, then| {
+        when.method(POST).path("/auth");
+        then.status(200);
+    });
+    router.login().unwrap();
+    answer.assert_hits(0);
+}
+
+#[test]
+fn challenge_without_realm_is_invalid() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method(GET).path("/auth");
+        then.status(401)
+            .header("X-NDM-Challenge", "NXQOBLJWZSYHGTCKPVFI");
+    });
+    let router = KeeneticRouter::new(&server.base_url(), "admin", "hunter2", "Test");
+    assert!(matches!(
+        router.login(),
+        Err(RouterError::InvalidResponse(message)) if message == "missing realm"
+    ));
+}
+
+#[test]
+fn policies_are_keyed_by_name() {
+    let server = MockServer::start();
+    let router = logged_in(&server);
+    server.mock(|when, then| {
+        when.method(GET).path("/rci/show/rc/ip/policy");
+        then.status(200).json_body(json!({
+            "Policy0": { "description": "VPN", "permit": [] },
+            "Policy1": {},
+        }));
+    });
+    let policies = router.get_policies().unwrap();
+    assert_eq!(policies.len(), 2);
+    assert_eq!(policies["Policy0"].description.as_deref(), Some("VPN"));
+    assert_eq!(policies["Policy1"].description, None);
+}
+
+#[test]
+fn hotspot_hosts_are_merged_by_mac() {
+    let server = MockServer::start();
+    let router = logged_in(&server);
+    // The same host once per interface it was seen on, in either case.
+    server.mock(|when, then| {
+        when.method(GET).path("/rci/show/ip/hotspot/host");
+        then.status(200).json_body(json!([
+            { "mac": "AA:BB:CC:00:00:01", "ip": "192.168.1.20", "deny": false },
+            { "mac": "aa:bb:cc:00:00:01", "name": "Laptop", "ip": "192.168.1.99",
+              "policy": "Policy0", "deny": true, "link": "up" },
+            { "mac": "aa:bb:cc:00:00:02", "name": "Phone" },
+            { "name": "No MAC" },
+        ]));
+    });
+    let mut clients = router.get_online_clients().unwrap();
+    clients.sort_by(|a, b| a.mac.cmp(&b.mac));
+    assert_eq!(clients.len(), 2);
+
+    let laptop = &clients[0];
+    assert_eq!(laptop.mac, "aa:bb:cc:00:00:01");
+    assert_eq!(laptop.name.as_deref(), Some("Laptop"));
+    // The first answer wins for the address, the last for the block.
+    assert_eq!(laptop.ip.as_deref(), Some("192.168.1.20"));
+    assert_eq!(laptop.policy.as_deref(), Some("Policy0"));
+    assert!(laptop.deny);
+    assert!(keenetic_client::client_is_online(laptop));
+
+    let phone = &clients[1];
+    assert_eq!(phone.name.as_deref(), Some("Phone"));
+    assert_eq!(phone.policy, None);
+    assert!(!phone.deny);
+    assert!(!keenetic_client::client_is_online(phone));
+}
+
+#[test]
+fn error_status_is_invalid_response() {
+    let server = MockServer::start();
+    let router = logged_in(&server);
+    server.mock(|when, then| {
+        when.method(GET).path("/rci/show/rc/ip/policy");
+        then.status(500);
+    });
+    assert!(matches!(
+        router.get_policies(),
+        Err(RouterError::InvalidResponse(message)) if message.starts_with("status 500")
+    ));
+}
+
+#[test]
+fn ndm_error_payload_fails_the_command() {
+    let server = MockServer::start();
+    let router = logged_in(&server);
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/rci/ip/hotspot/host")
+            .json_body(json!({
+                "mac": "aa:bb:cc:00:00:01",
+                "policy": "Policy9",
+                "permit": true,
+                "schedule": false,
+            }));
+        then.status(200).json_body(json!({
+            "status": [{
+                "status": "error",
+                "code": "7405600",
+                "ident": "Command::Base",
+                "message": "no such policy: Policy9",
+            }],
+        }));
+    });
+    assert!(matches!(
+        router.apply_policy_to_client("aa:bb:cc:00:00:01", Some("Policy9")),
+        Err(RouterError::InvalidResponse(message)) if message == "no such policy: Policy9"
+    ));
+}
+
+#[test]
+fn ndm_messages_are_not_errors() {
+    let server = MockServer::start();
+    let router = logged_in(&server);
+    server.mock(|when, then| {
+        when.method(POST).path("/rci/ip/hotspot/host");
+        then.status(200).json_body(json!({
+            "status": [{ "status": "message", "message": "policy applied" }],
+        }));
+    });
+    router.apply_default_policy("aa:bb:cc:00:00:01").unwrap();
+}
+
+#[test]
+fn unreachable_router_is_a_request_error() {
+    // Nothing listens on the discard port.
+    let router = KeeneticRouter::new("http://127.0.0.1:9", "admin", "hunter2", "Test");
+    assert!(matches!(router.login(), Err(RouterError::Request(_))));
+}
+
+#[test]
+fn preferring_5ghz_denies_the_client_on_2_4() {
+    let server = MockServer::start();
+    let router = logged_in(&server);
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/rci/sc/interface/WifiMaster0/AccessPoint0/mac/access-list");
+        then.status(200).json_body(json!({}));
+    });
+    server.mock(|when, then| {
+        when.method(GET)
+            .path("/rci/sc/interface/WifiMaster1/AccessPoint0/mac/access-list");
+        then.status(200).json_body(json!({
+            "type": "deny",
+            "address": [{ "address": "AA:BB:CC:00:00:01" }],
+        }));
+    });
+    let list_type = server.mock(|when, then| {
+        when.method(POST)
+            .path("/rci/interface/WifiMaster0/AccessPoint0/mac/access-list")
+            .json_body(json!({ "type": "deny" }));
+        then.status(200).json_body(json!({}));
+    });
+    let denied = server.mock(|when, then| {
+        when.method(POST)
+            .path("/rci/interface/WifiMaster0/AccessPoint0/mac/access-list/address")
+            .json_body(json!({ "address": "aa:bb:cc:00:00:01" }));
+        then.status(200).json_body(json!({}));
+    });
+    // Held to 2.4 GHz before, so it comes off the 5 GHz list.
+    let released = server.mock(|when, then| {
+        when.method(POST)
+            .path("/rci/interface/WifiMaster1/AccessPoint0/mac/access-list/address")
+            .json_body(json!({ "address": "aa:bb:cc:00:00:01", "no": true }));
+        then.status(200).json_body(json!({}));
+    });
+
+    let locks = router.get_band_locks().unwrap();
+    assert_eq!(locks.get("aa:bb:cc:00:00:01"), Some(&WifiBand::Ghz2_4));
+    router
+        .set_band_lock("AA:BB:CC:00:00:01", Some(WifiBand::Ghz5))
+        .unwrap();
+    list_type.assert();
+    denied.assert();
+    released.assert();
+}
+
+#[test]
+fn guest_wifi_without_5ghz_still_comes_up() {
+    let server = MockServer::start();
+    let router = logged_in(&server);
+    let up = server.mock(|when, then| {
+        when.method(POST)
+            .path("/rci/interface/WifiMaster0/AccessPoint1")
+            .json_body(json!({
+                "up": true,
+                "authentication": { "wpa-psk": { "psk": "correct-horse" } },
+                "encryption": { "enable": true, "wpa2": true },
+            }));
+        then.status(200).json_body(json!({}));
+    });
+    server.mock(|when, then| {
+        when.method(POST)
+            .path("/rci/interface/WifiMaster1/AccessPoint1");
+        then.status(404);
+    });
+
+    router.set_guest_wifi(true, Some("correct-horse")).unwrap();
+    up.assert();
+}
+
+#[test]
+fn speed_test_times_the_download_and_reads_the_wan_counters() {
+    let server = MockServer::start();
+    let router = logged_in(&server);
+    server.mock(|when, then| {
+        when.method(GET).path("/rci/show/ip/route");
+        then.status(200)
+            .json_body(json!([{ "destination": "0.0.0.0/0", "interface": "ISP" }]));
+    });
+    let stat = server.mock(|when, then| {
+        when.method(GET)
+            .path("/rci/show/interface/stat")
+            .query_param("name", "ISP");
+        then.status(200)
+            .json_body(json!({ "rxbytes": 1000, "txbytes": 500 }));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/download");
+        then.status(200).body(vec![0u8; 64 * 1024]);
+    });
+
+    let test = router.speed_test(&server.url("/download")).unwrap();
+    assert_eq!(test.bytes, 64 * 1024);
+    assert!(test.download_mbps > 0.0);
+    // The counters didn't move between the two reads.
+    assert_eq!(test.wan_mbps, Some(0.0));
+    stat.assert_hits(2);
+}
+
+#[test]
+fn speed_test_without_a_default_route_has_no_wan_rate() {
+    let server = MockServer::start();
+    let router = logged_in(&server);
+    server.mock(|when, then| {
+        when.method(GET).path("/rci/show/ip/route");
+        then.status(200).json_body(json!([]));
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/download");
+        then.status(200).body("0123456789");
+    });
+
+    let test = router.speed_test(&server.url("/download")).unwrap();
+    assert_eq!(test.bytes, 10);
+    assert_eq!(test.wan_mbps, None);
+}
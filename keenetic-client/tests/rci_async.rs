@@ -0,0 +1,56 @@
+//! `AsyncKeeneticRouter` against a mock router: the same handshake and
+//! failures as the blocking client, awaited.
+
+use httpmock::prelude::*;
+use keenetic_client::{AsyncKeeneticRouter, RouterError};
+use serde_json::json;
+
+#[tokio::test]
+async fn login_answers_the_challenge() {
+    let server = MockServer::start_async().await;
+    server.mock(|when, then| {
+        when.method(GET).path("/auth");
+        then.status(401)
+            .header("X-NDM-Realm", "Keenetic Giga")
+            .header("X-NDM-Challenge", "NXQOBLJWZSYHGTCKPVFI");
+    });
+    let answer = server.mock(|when, then| {
+        when.method(POST).path("/auth").json_body(json!({
+            "login": "admin",
+            "password": "1965f42d8a570f714f5460f32475d58c65fe9d440dec0f1d26934ef1aeeba3ed",
+        }));
+        then.status(200);
+    });
+    let router = AsyncKeeneticRouter::new(&server.base_url(), "admin", "hunter2", "Test");
+    router.login().await.unwrap();
+    answer.assert();
+}
+
+#[tokio::test]
+async fn policies_and_refusals() {
+    let server = MockServer::start_async().await;
+    server.mock(|when, then| {
+        when.method(GET).path("/auth");
+        then.status(200);
+    });
+    server.mock(|when, then| {
+        when.method(GET).path("/rci/show/rc/ip/policy");
+        then.status(200).json_body(json!({
+            "Policy0": { "description": "VPN", "permit": [] },
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(POST).path("/rci/ip/hotspot/host");
+        then.status(200).json_body(json!({
+            "status": [{ "status": "error", "message": "no such policy: Policy9" }],
+        }));
+    });
+    let router = AsyncKeeneticRouter::new(&server.base_url(), "admin", "hunter2", "Test");
+
+    let policies = router.get_policies().await.unwrap();
+    assert_eq!(policies["Policy0"].description.as_deref(), Some("VPN"));
+    assert!(matches!(
+        router.apply_policy_to_client("aa:bb:cc:00:00:01", Some("Policy9")).await,
+        Err(RouterError::InvalidResponse(message)) if message == "no such policy: Policy9"
+    ));
+}
@@ -0,0 +1,175 @@
+//! Answers from firmware that shapes things differently, or from a router
+//! having a bad day: parsing keeps what it can read and never panics.
+
+use keenetic_client::{
+    client_interfaces, client_is_online, client_traffic, client_wifi, parse_hotspot_hosts,
+    parse_log, parse_mesh_nodes, parse_policies,
+};
+use proptest::prelude::*;
+use serde_json::{json, Map, Value};
+
+fn any_json() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        any::<f64>().prop_map(Value::from),
+        ".{0,12}".prop_map(Value::String),
+    ];
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(Value::Array),
+            prop::collection::btree_map("[a-z-]{1,10}|mac|name|ip|link|mws|0|1", inner, 0..8)
+                .prop_map(|map| Value::Object(map.into_iter().collect::<Map<_, _>>())),
+        ]
+    })
+}
+
+fn host() -> Value {
+    json!({
+        "mac": "AA:BB:CC:00:00:01",
+        "name": "Laptop",
+        "ip": "192.168.1.20",
+        "policy": "Policy0",
+        "deny": false,
+        "link": "up",
+        "ssid": "Home",
+        "ap": "WifiMaster1/AccessPoint0",
+        "rssi": -50,
+        "rxbytes": 1000,
+        "txbytes": 100,
+        "mws": { "cid": "node", "link": "up" },
+    })
+}
+
+proptest! {
+    #[test]
+    fn nothing_panics(data in any_json()) {
+        let clients = parse_hotspot_hosts(&data);
+        client_interfaces(&clients);
+        client_traffic(&clients);
+        for client in &clients {
+            client_is_online(client);
+            client_wifi(client);
+        }
+        parse_policies(&data);
+        parse_mesh_nodes(&data);
+        parse_log(&data);
+    }
+
+    /// Whatever becomes of the other fields, a host with a MAC is kept.
+    #[test]
+    fn a_host_survives_any_field(
+        key in "name|ip|policy|deny|link|ssid|ap|rssi|rxbytes|txbytes|mws|unknown",
+        value in any_json(),
+    ) {
+        let mut item = host();
+        item[key.as_str()] = value;
+        let clients = parse_hotspot_hosts(&json!([item]));
+        prop_assert_eq!(clients.len(), 1);
+        prop_assert_eq!(&clients[0].mac, "aa:bb:cc:00:00:01");
+        client_interfaces(&clients);
+        client_traffic(&clients);
+    }
+
+    #[test]
+    fn a_host_survives_missing_fields(keep in prop::collection::vec(any::<bool>(), 12)) {
+        let mut item = host();
+        let map = item.as_object_mut().unwrap();
+        let keys: Vec<String> = map.keys().filter(|k| *k != "mac").cloned().collect();
+        for (key, keep) in keys.iter().zip(keep) {
+            if !keep {
+                map.remove(key);
+            }
+        }
+        let clients = parse_hotspot_hosts(&json!([item]));
+        prop_assert_eq!(clients.len(), 1);
+    }
+}
+
+#[test]
+fn lists_come_wrapped_or_numbered() {
+    for data in [
+        json!([host()]),
+        json!({ "host": [host()] }),
+        json!({ "0": host() }),
+    ] {
+        let clients = parse_hotspot_hosts(&data);
+        assert_eq!(clients.len(), 1, "{data}");
+        assert!(client_is_online(&clients[0]));
+    }
+}
+
+#[test]
+fn flags_and_counters_come_as_text() {
+    let data = json!([{
+        "mac": "aa:bb:cc:00:00:01",
+        "deny": "yes",
+        "link": "down",
+        "mws": { "link": "up" },
+        "rxbytes": "1000",
+        "txbytes": 100.0,
+        "rssi": "-61",
+        "ssid": "Home",
+    }]);
+    let clients = parse_hotspot_hosts(&data);
+    let client = &clients[0];
+    assert!(client.deny);
+    assert!(client_is_online(client));
+    let traffic = &client_traffic(&clients)["aa:bb:cc:00:00:01"];
+    assert_eq!((traffic.rx_bytes, traffic.tx_bytes), (1000, 100));
+    assert_eq!(client_wifi(client).unwrap().rssi, Some(-61));
+}
+
+#[test]
+fn policies_come_keyed_or_listed() {
+    let keyed = parse_policies(&json!({ "Policy0": { "description": "VPN" } }));
+    let listed = parse_policies(&json!([{ "name": "Policy0", "description": "VPN" }]));
+    for policies in [keyed, listed] {
+        assert_eq!(policies["Policy0"].description.as_deref(), Some("VPN"));
+    }
+}
+
+#[test]
+fn policy_connections_keep_their_order() {
+    let policies = parse_policies(&json!({ "Policy0": { "permit": [
+        { "interface": "Wireguard0", "enabled": true },
+        { "interface": "GigabitEthernet1", "enabled": "no" },
+        { "enabled": true },
+        { "interface": "PPPoE0", "no": true },
+    ]}}));
+    let connections: Vec<(&str, bool)> = policies["Policy0"]
+        .connections
+        .iter()
+        .map(|c| (c.interface.as_str(), c.enabled))
+        .collect();
+    assert_eq!(
+        connections,
+        [
+            ("Wireguard0", true),
+            ("GigabitEthernet1", false),
+            ("PPPoE0", false)
+        ]
+    );
+}
+
+#[test]
+fn mesh_nodes_without_an_id_are_skipped() {
+    let nodes = parse_mesh_nodes(&json!({ "member": [
+        { "cid": "node", "connected": "no" },
+        { "model": "Buddy" },
+    ]}));
+    assert_eq!(nodes.len(), 1);
+    assert!(!nodes[0].online);
+}
+
+#[test]
+fn log_lines_keep_their_order() {
+    let lines = parse_log(&json!({
+        "10": { "message": "second" },
+        "9": { "message": { "level": "warning", "message": "first" } },
+    }));
+    let messages: Vec<&str> = lines.iter().map(|line| line.message.as_str()).collect();
+    assert_eq!(messages, ["first", "second"]);
+    assert_eq!(lines[0].level.as_deref(), Some("warning"));
+}
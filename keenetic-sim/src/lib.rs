@@ -0,0 +1,185 @@
+//! The simulator as a library, so the tray's demo mode can run one
+//! in-process. See the binary for running it on its own.
+
+mod replay;
+mod router;
+
+pub use replay::Replay;
+pub use router::Router;
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+const REALM: &str = "Keenetic Simulator";
+const COOKIE: &str = "sim_session";
+
+/// The login the simulated router accepts.
+#[derive(Clone)]
+pub struct Config {
+    pub login: String,
+    pub password: String,
+    /// Prints every request to stdout.
+    pub verbose: bool,
+}
+
+/// Login state per session cookie.
+#[derive(Default)]
+struct Sessions {
+    challenges: HashMap<String, String>,
+    authorized: HashSet<String>,
+}
+
+/// Answers requests on `server` until it is closed, from `router`, or from
+/// `replay` when given.
+pub fn serve(server: &Server, config: &Config, mut router: Router, mut replay: Option<Replay>) {
+    let mut sessions = Sessions::default();
+    for request in server.incoming_requests() {
+        let response = handle(config, &mut sessions, &mut router, replay.as_mut(), request);
+        if let Err(err) = response {
+            eprintln!("keenetic-sim: {err}");
+        }
+    }
+}
+
+/// Serves `router` on a free port of 127.0.0.1 from a background thread,
+/// returning its address as `http://127.0.0.1:PORT`.
+pub fn spawn(config: Config, router: Router) -> std::io::Result<String> {
+    let server = Server::http("127.0.0.1:0").map_err(std::io::Error::other)?;
+    let port = server
+        .server_addr()
+        .to_ip()
+        .map(|addr| addr.port())
+        .ok_or_else(|| std::io::Error::other("not listening on an IP address"))?;
+    std::thread::spawn(move || serve(&server, &config, router, None));
+    Ok(format!("http://127.0.0.1:{port}"))
+}
+
+fn handle(
+    config: &Config,
+    sessions: &mut Sessions,
+    router: &mut Router,
+    replay: Option<&mut Replay>,
+    mut request: Request,
+) -> std::io::Result<()> {
+    let method = request.method().clone();
+    let url = request.url().trim_start_matches('/').to_string();
+    let session = cookie(&request);
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body)?;
+    let payload: Option<Value> = serde_json::from_str(&body).ok();
+    if config.verbose {
+        println!("{method} /{url}");
+    }
+
+    if url == "auth" {
+        return match (method, session) {
+            (Method::Get, Some(id)) if sessions.authorized.contains(&id) => {
+                request.respond(json_response(200, &serde_json::json!({})))
+            }
+            (Method::Post, Some(id)) => {
+                let expected = sessions
+                    .challenges
+                    .get(&id)
+                    .map(|challenge| password_hash(&config.login, &config.password, challenge));
+                let given = payload.as_ref().and_then(|p| p.get("password"));
+                let login = payload.as_ref().and_then(|p| p.get("login"));
+                if expected.is_some()
+                    && given.and_then(Value::as_str) == expected.as_deref()
+                    && login.and_then(Value::as_str) == Some(config.login.as_str())
+                {
+                    sessions.challenges.remove(&id);
+                    sessions.authorized.insert(id);
+                    request.respond(json_response(200, &serde_json::json!({})))
+                } else {
+                    request.respond(Response::empty(401))
+                }
+            }
+            _ => {
+                let id = token();
+                let challenge = token();
+                sessions.challenges.insert(id.clone(), challenge.clone());
+                let response = Response::empty(401)
+                    .with_header(header("X-NDM-Realm", REALM))
+                    .with_header(header("X-NDM-Challenge", &challenge))
+                    .with_header(header("Set-Cookie", &format!("{COOKIE}={id}; Path=/")));
+                request.respond(response)
+            }
+        };
+    }
+
+    let Some(endpoint) = url.strip_prefix("rci/") else {
+        return request.respond(Response::empty(404));
+    };
+    if !session.is_some_and(|id| sessions.authorized.contains(&id)) {
+        return request.respond(Response::empty(401));
+    }
+    if let Some(replay) = replay {
+        return match replay.answer(method.as_str(), endpoint) {
+            Some((status, value)) => request.respond(json_response(status, &value)),
+            None => request.respond(Response::empty(404)),
+        };
+    }
+    let (path, query) = endpoint.split_once('?').unwrap_or((endpoint, ""));
+    let query: HashMap<&str, &str> = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect();
+    let answer = match method {
+        Method::Get => router.get(path, &query),
+        Method::Post => router.post(path, payload.unwrap_or(Value::Null)),
+        _ => None,
+    };
+    if path == "system/reboot" {
+        // Everyone has to log in again, as after a real reboot.
+        sessions.authorized.clear();
+    }
+    match answer {
+        Some(value) => request.respond(json_response(200, &value)),
+        None => request.respond(json_response(
+            404,
+            &serde_json::json!({ "error": format!("no such command: {path}") }),
+        )),
+    }
+}
+
+/// What router-core sends as the password: the challenge and the
+/// `login:realm:password` MD5, hashed again with SHA-256.
+fn password_hash(login: &str, password: &str, challenge: &str) -> String {
+    let md5_hex = format!("{:x}", md5::compute(format!("{login}:{REALM}:{password}")));
+    hex::encode(Sha256::digest(format!("{challenge}{md5_hex}")))
+}
+
+fn cookie(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .filter(|h| h.field.equiv("Cookie"))
+        .flat_map(|h| h.value.as_str().split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(&format!("{COOKIE}=")))
+        .map(str::to_string)
+}
+
+/// Unique enough for sessions on a development machine.
+fn token() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    hex::encode(&Sha256::digest(format!("{nanos}:{count}"))[..16])
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+}
+
+fn json_response(status: u16, value: &Value) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+}
@@ -0,0 +1,65 @@
+//! A pretend Keenetic router for developing and demoing the tray without
+//! one. It speaks the `/auth` challenge login and answers the RCI calls
+//! router-core makes from a small home network that changes as policies are
+//! applied, devices are renamed or forgotten, and a guest phone comes and
+//! goes every minute.
+//!
+//! ```sh
+//! cargo run -p keenetic-sim -- --port 8080
+//! ```
+//!
+//! Then add a router at `http://127.0.0.1:8080` with login `admin` and
+//! password `admin`. State lives in memory and resets on restart.
+//!
+//! With `--replay DIR` it serves answers recorded from a real router
+//! instead, to reproduce a problem with someone's firmware.
+
+use clap::Parser;
+use keenetic_sim::{Config, Replay, Router};
+use std::path::PathBuf;
+use tiny_http::Server;
+
+#[derive(Parser)]
+#[command(name = "keenetic-sim", about = "Serves a simulated Keenetic router")]
+struct Args {
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+    #[arg(long, default_value = "admin")]
+    login: String,
+    #[arg(long, default_value = "admin")]
+    password: String,
+    /// Serves the fixtures recorded into this directory.
+    #[arg(long, value_name = "DIR")]
+    replay: Option<PathBuf>,
+}
+
+fn main() {
+    let args = Args::parse();
+    let replay = match args.replay.as_deref().map(Replay::load).transpose() {
+        Ok(replay) => replay,
+        Err(err) => {
+            eprintln!("keenetic-sim: {err}");
+            std::process::exit(1);
+        }
+    };
+    let address = format!("{}:{}", args.host, args.port);
+    let server = match Server::http(&address) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("keenetic-sim: failed to listen on {address}: {err}");
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "Simulating a Keenetic at http://{address} (login {}, password {})",
+        args.login, args.password
+    );
+    let config = Config {
+        login: args.login,
+        password: args.password,
+        verbose: true,
+    };
+    keenetic_sim::serve(&server, &config, Router::new(), replay);
+}
@@ -0,0 +1,60 @@
+//! Answers recorded from a real router with `router-cli --record` or the
+//! tray's `ROUTER_RECORD_DIR`, served back in the order they were recorded.
+
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+pub struct Replay {
+    /// Status and body by method and endpoint, query included.
+    answers: HashMap<(String, String), VecDeque<(u16, Value)>>,
+}
+
+impl Replay {
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .map_err(|err| format!("failed to read {}: {err}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        // Numbered, so this is the order they were recorded in.
+        paths.sort();
+        let mut answers: HashMap<_, VecDeque<_>> = HashMap::new();
+        for path in &paths {
+            let fixture: Value = fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| serde_json::from_str(&text).map_err(|err| err.to_string()))
+                .map_err(|err| format!("{}: {err}", path.display()))?;
+            let text = |key: &str| fixture.get(key).and_then(Value::as_str);
+            let (Some(method), Some(endpoint)) = (text("method"), text("endpoint")) else {
+                return Err(format!("{}: not a fixture", path.display()));
+            };
+            let endpoint = endpoint.trim_start_matches("rci/").to_string();
+            let status = fixture.get("status").and_then(Value::as_u64).unwrap_or(200);
+            let response = fixture.get("response").cloned().unwrap_or(Value::Null);
+            answers
+                .entry((method.to_string(), endpoint))
+                .or_default()
+                .push_back((status as u16, response));
+        }
+        if answers.is_empty() {
+            return Err(format!("no fixtures in {}", dir.display()));
+        }
+        println!("Replaying {} answers from {}", paths.len(), dir.display());
+        Ok(Replay { answers })
+    }
+
+    /// The next recorded answer to this request; the last one repeats once
+    /// they run out, as for a tray that keeps polling.
+    pub fn answer(&mut self, method: &str, endpoint: &str) -> Option<(u16, Value)> {
+        let queue = self
+            .answers
+            .get_mut(&(method.to_string(), endpoint.to_string()))?;
+        if queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        }
+    }
+}
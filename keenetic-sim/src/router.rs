@@ -0,0 +1,492 @@
+//! The simulated network and the RCI commands that read and change it.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// How often the guest phone joins or leaves.
+const GUEST_PERIOD_SECS: u64 = 60;
+const GUEST_MAC: &str = "ae:12:34:56:78:9a";
+/// Kept low to show the log's scrolling.
+const LOG_CAPACITY: usize = 200;
+const ACCESS_POINTS: [&str; 2] = ["WifiMaster0/AccessPoint0", "WifiMaster1/AccessPoint0"];
+const GUEST_ACCESS_POINTS: [&str; 2] = ["WifiMaster0/AccessPoint1", "WifiMaster1/AccessPoint1"];
+
+struct Host {
+    mac: String,
+    name: Option<String>,
+    ip: String,
+    vendor: &'static str,
+    policy: Option<String>,
+    deny: bool,
+    /// The SSID for Wi-Fi hosts, otherwise the switch port.
+    ssid: Option<&'static str>,
+    port: u64,
+    /// Connected through the mesh extender rather than the router itself.
+    mesh: bool,
+    /// Bytes per second while online, to make the traffic counters move.
+    rate: u64,
+}
+
+pub struct Router {
+    started: Instant,
+    hosts: Vec<Host>,
+    /// Registered hosts by MAC.
+    known: Vec<(String, String)>,
+    policies: Vec<(&'static str, &'static str)>,
+    /// The connections of each policy, by name.
+    permits: HashMap<&'static str, Vec<String>>,
+    /// The MAC list type and entries of each access point.
+    access_lists: HashMap<&'static str, (String, Vec<String>)>,
+    /// Whether the guest network is up, on both bands alike.
+    guest_up: bool,
+    log: Vec<Value>,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Router {
+    pub fn new() -> Self {
+        let host = |mac: &str, name: &str, ip: &str, vendor| Host {
+            mac: mac.into(),
+            name: Some(name.into()),
+            ip: ip.into(),
+            vendor,
+            policy: None,
+            deny: false,
+            ssid: Some("Home"),
+            port: 0,
+            mesh: false,
+            rate: 2_000,
+        };
+        let hosts = vec![
+            Host {
+                ssid: None,
+                port: 1,
+                rate: 150_000,
+                ..host("50:eb:f6:10:20:30", "Desktop", "192.168.1.20", "ASUSTek")
+            },
+            Host {
+                policy: Some("Policy0".into()),
+                rate: 40_000,
+                ..host("f0:18:98:aa:bb:01", "MacBook", "192.168.1.21", "Apple")
+            },
+            host("3c:22:fb:aa:bb:02", "iPhone", "192.168.1.22", "Apple"),
+            Host {
+                policy: Some("Policy1".into()),
+                mesh: true,
+                rate: 600_000,
+                ..host("a8:23:fe:cc:dd:03", "Living room TV", "192.168.1.30", "LG")
+            },
+            Host {
+                deny: true,
+                mesh: true,
+                ..host(
+                    "d8:f1:5b:cc:dd:04",
+                    "Smart plug",
+                    "192.168.1.31",
+                    "Espressif",
+                )
+            },
+            Host {
+                name: None,
+                ssid: Some("Guest"),
+                ..host(GUEST_MAC, "", "192.168.1.40", "Samsung")
+            },
+        ];
+        let known = hosts
+            .iter()
+            .filter_map(|host| Some((host.mac.clone(), host.name.clone()?)))
+            .collect();
+        let mut router = Router {
+            started: Instant::now(),
+            hosts,
+            known,
+            policies: vec![
+                ("Policy0", "VPN"),
+                ("Policy1", "Streaming"),
+                ("Policy2", "Kids"),
+            ],
+            permits: [
+                ("Policy0", "Wireguard0"),
+                ("Policy1", "GigabitEthernet1"),
+                ("Policy2", "GigabitEthernet1"),
+            ]
+            .map(|(name, interface)| (name, vec![interface.to_string()]))
+            .into(),
+            access_lists: ACCESS_POINTS
+                .map(|ap| (ap, ("none".to_string(), Vec::new())))
+                .into(),
+            guest_up: false,
+            log: Vec::new(),
+        };
+        router.log("ndm", "info", "Core::System::Started: system ready.");
+        router
+    }
+
+    /// Adds a wired host, registered as `name`, e.g. for the machine the
+    /// simulator is used from.
+    pub fn add_host(&mut self, mac: &str, ip: &str, name: &str) {
+        self.hosts.push(Host {
+            mac: mac.to_lowercase(),
+            name: Some(name.into()),
+            ip: ip.into(),
+            vendor: "",
+            policy: None,
+            deny: false,
+            ssid: None,
+            port: 2,
+            mesh: false,
+            rate: 80_000,
+        });
+        self.known.push((mac.to_lowercase(), name.into()));
+    }
+
+    pub fn get(&mut self, path: &str, query: &HashMap<&str, &str>) -> Option<Value> {
+        if let Some(ap) = access_point(path, "sc/interface/", "/mac/access-list") {
+            let (kind, macs) = self.access_lists.get(ap)?;
+            let address: Vec<Value> = macs.iter().map(|mac| json!({ "address": mac })).collect();
+            return Some(json!({ "type": kind, "address": address }));
+        }
+        if let Some(ap) = path.strip_prefix("sc/interface/") {
+            if GUEST_ACCESS_POINTS.contains(&ap) {
+                return Some(json!({ "ssid": "Guest", "up": self.guest_up }));
+            }
+        }
+        Some(match path {
+            "show/ip/hotspot/host" => Value::Array(self.hotspot()),
+            "known/host" => self
+                .known
+                .iter()
+                .map(|(mac, name)| json!({ "mac": mac, "name": name }))
+                .collect(),
+            "show/rc/ip/policy" => self
+                .policies
+                .iter()
+                .map(|(name, description)| {
+                    let permit: Vec<Value> = self.permits[name]
+                        .iter()
+                        .map(|interface| json!({ "interface": interface, "enabled": true }))
+                        .collect();
+                    let policy = json!({ "description": description, "permit": permit });
+                    (name.to_string(), policy)
+                })
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+            // Without an address of its own the tray keeps to the one it was
+            // given, port and all.
+            "sc/interface/Bridge0/ip/address" => json!({}),
+            "ip/http/ssl/acme/list/certificate" => json!([{ "domain": "sim.keenetic.pro" }]),
+            "show/mws/member" => json!([{
+                "cid": "b2f1c7e0-sim-extender",
+                "known-host": "Extender",
+                "model": "Buddy 6",
+                "mac": "50:ff:20:00:00:01",
+                "ip": "192.168.1.2",
+                "internet-available": true,
+            }]),
+            "show/internet/status" => json!({ "internet": true }),
+            "show/ip/route" => json!([
+                { "destination": "192.168.1.0/24", "interface": "Bridge0" },
+                { "destination": "0.0.0.0/0", "interface": "GigabitEthernet1" },
+            ]),
+            "show/interface/stat" => {
+                let uptime = self.uptime();
+                match query.get("name").copied() {
+                    Some("GigabitEthernet1") => json!({
+                        "rxbytes": uptime * 900_000,
+                        "txbytes": uptime * 120_000,
+                    }),
+                    Some("Bridge0") => json!({
+                        "rxbytes": uptime * 120_000,
+                        "txbytes": uptime * 900_000,
+                    }),
+                    _ => return None,
+                }
+            }
+            "show/log" => {
+                let max = query
+                    .get("max-lines")
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(LOG_CAPACITY);
+                let skip = self.log.len().saturating_sub(max);
+                json!({ "log": &self.log[skip..] })
+            }
+            "show/running-config" => json!({ "message": self.running_config() }),
+            _ => return None,
+        })
+    }
+
+    pub fn post(&mut self, path: &str, payload: Value) -> Option<Value> {
+        let mac = payload
+            .get("mac")
+            .and_then(Value::as_str)
+            .map(str::to_lowercase);
+        let forget = payload.get("no").and_then(Value::as_bool) == Some(true);
+        if let Some(ap) = access_point(path, "interface/", "/mac/access-list") {
+            let kind = payload.get("type").and_then(Value::as_str)?.to_string();
+            self.access_lists.get_mut(ap)?.0 = kind;
+            return Some(json!({}));
+        }
+        if let Some(ap) = access_point(path, "interface/", "/mac/access-list/address") {
+            let mac = payload
+                .get("address")
+                .and_then(Value::as_str)?
+                .to_lowercase();
+            let macs = &mut self.access_lists.get_mut(ap)?.1;
+            macs.retain(|listed| *listed != mac);
+            if !forget {
+                macs.push(mac.clone());
+            }
+            let verb = if forget { "removed" } else { "added" };
+            self.log(
+                "ndm",
+                "info",
+                &format!("{ap}: {verb} {mac} on the MAC list."),
+            );
+            return Some(json!({}));
+        }
+        if let Some(ap) = path.strip_prefix("interface/") {
+            if GUEST_ACCESS_POINTS.contains(&ap) {
+                if payload.get("up").and_then(Value::as_bool) == Some(true) {
+                    self.guest_up = true;
+                } else if payload.get("down").and_then(Value::as_bool) == Some(true) {
+                    self.guest_up = false;
+                }
+                if payload.get("authentication").is_some() {
+                    self.log("ndm", "info", &format!("{ap}: set a new WPA2 passphrase."));
+                }
+                let state = if self.guest_up { "up" } else { "down" };
+                self.log("ndm", "info", &format!("{ap}: interface is {state}."));
+                return Some(json!({}));
+            }
+        }
+        match path {
+            "known/host" => {
+                let mac = mac?;
+                self.known.retain(|(known, _)| *known != mac);
+                if forget {
+                    self.log("ndm", "info", &format!("Hotspot: unregistered {mac}."));
+                } else {
+                    let name = payload.get("name").and_then(Value::as_str)?.to_string();
+                    self.log(
+                        "ndm",
+                        "info",
+                        &format!("Hotspot: registered {mac} as \"{name}\"."),
+                    );
+                    if let Some(host) = self.host(&mac) {
+                        host.name = Some(name.clone());
+                    }
+                    self.known.push((mac, name));
+                }
+            }
+            "ip/hotspot/host" => {
+                let mac = mac?;
+                if forget {
+                    // The guest comes back, now as a stranger.
+                    self.hosts
+                        .retain(|host| host.mac != mac || host.mac == GUEST_MAC);
+                    if let Some(guest) = self.host(&mac) {
+                        guest.name = None;
+                        guest.policy = None;
+                        guest.deny = false;
+                    }
+                    return Some(json!({}));
+                }
+                if !self.policy_exists(&payload) {
+                    return None;
+                }
+                let host = self.host(&mac)?;
+                if payload.get("deny").and_then(Value::as_bool) == Some(true) {
+                    host.deny = true;
+                } else if payload.get("permit").and_then(Value::as_bool) == Some(true) {
+                    host.deny = false;
+                    host.policy = payload
+                        .get("policy")
+                        .and_then(Value::as_str)
+                        .map(str::to_string);
+                }
+                let line = format!(
+                    "Hotspot: {mac} {}.",
+                    match (host.deny, &host.policy) {
+                        (true, _) => "denied access".to_string(),
+                        (false, Some(policy)) => format!("uses {policy}"),
+                        (false, None) => "uses the default policy".to_string(),
+                    }
+                );
+                self.log("ndm", "info", &line);
+            }
+            "ip/policy" => {
+                let name = payload.get("name").and_then(Value::as_str)?;
+                let permit = payload.get("permit")?;
+                let interface = permit.get("interface").and_then(Value::as_str)?.to_string();
+                let remove = permit.get("no").and_then(Value::as_bool) == Some(true);
+                let permits = self
+                    .permits
+                    .iter_mut()
+                    .find(|(policy, _)| **policy == name)
+                    .map(|(_, permits)| permits)?;
+                permits.retain(|listed| *listed != interface);
+                if !remove {
+                    permits.push(interface.clone());
+                }
+                let verb = if remove { "no longer uses" } else { "uses" };
+                self.log(
+                    "ndm",
+                    "info",
+                    &format!("Policy: {name} {verb} {interface}."),
+                );
+            }
+            "ip/hotspot/wake" => {
+                let mac = mac?;
+                self.log(
+                    "ndm",
+                    "info",
+                    &format!("Hotspot: sent a wake-on-LAN packet to {mac}."),
+                );
+            }
+            "system/reboot" => {
+                self.log("ndm", "warning", "Core::System: rebooting on request.");
+                self.started = Instant::now();
+                self.log("ndm", "info", "Core::System::Started: system ready.");
+            }
+            _ => return None,
+        }
+        Some(json!({}))
+    }
+
+    fn hotspot(&self) -> Vec<Value> {
+        let uptime = self.uptime();
+        self.hosts
+            .iter()
+            .map(|host| {
+                let online =
+                    host.mac != GUEST_MAC || (uptime / GUEST_PERIOD_SECS).is_multiple_of(2);
+                let link = if online { "up" } else { "down" };
+                let mut value = json!({
+                    "mac": host.mac,
+                    "ip": host.ip,
+                    "name": host.name,
+                    "vendor": host.vendor,
+                    "registered": self.known.iter().any(|(mac, _)| *mac == host.mac),
+                    "deny": host.deny,
+                    // Seconds ago.
+                    "last-seen": if online { 0 } else { uptime % GUEST_PERIOD_SECS },
+                    "rxbytes": uptime * host.rate,
+                    "txbytes": uptime * host.rate / 8,
+                });
+                if let Some(policy) = &host.policy {
+                    value["policy"] = json!(policy);
+                }
+                if host.mesh {
+                    value["mws"] = json!({ "cid": "b2f1c7e0-sim-extender", "link": link });
+                } else {
+                    value["link"] = json!(link);
+                }
+                match host.ssid {
+                    Some(ssid) => {
+                        value["ssid"] = json!(ssid);
+                        value["ap"] = json!(if ssid == "Guest" {
+                            "WifiMaster0/AccessPoint1"
+                        } else if self.denied(ACCESS_POINTS[0], &host.mac) {
+                            ACCESS_POINTS[1]
+                        } else {
+                            ACCESS_POINTS[0]
+                        });
+                        value["rssi"] = json!(-48 - (host.ip.len() as i64 % 7) * 4);
+                    }
+                    None => value["port"] = json!(host.port),
+                }
+                value
+            })
+            .collect()
+    }
+
+    fn running_config(&self) -> Vec<String> {
+        let mut lines = vec![
+            "! $$$ Model: Keenetic Simulator".to_string(),
+            "system".to_string(),
+            "    hostname Keenetic-Sim".to_string(),
+            "!".to_string(),
+        ];
+        for (name, description) in &self.policies {
+            lines.push(format!("ip policy {name}"));
+            lines.push(format!("    description {description}"));
+            for interface in &self.permits[name] {
+                lines.push(format!("    permit auto {interface}"));
+            }
+            lines.push("!".to_string());
+        }
+        for (mac, name) in &self.known {
+            lines.push(format!("known host \"{name}\" {mac}"));
+        }
+        for host in &self.hosts {
+            if let Some(policy) = &host.policy {
+                lines.push(format!("ip hotspot host {} policy {policy}", host.mac));
+            }
+            if host.deny {
+                lines.push(format!("ip hotspot host {} deny", host.mac));
+            }
+        }
+        lines.push("!".to_string());
+        lines
+    }
+
+    /// A missing or `false` policy means the default one.
+    fn policy_exists(&self, payload: &Value) -> bool {
+        match payload.get("policy").and_then(Value::as_str) {
+            Some(policy) => self.policies.iter().any(|(name, _)| *name == policy),
+            None => true,
+        }
+    }
+
+    /// Whether `ap` keeps `mac` off, so it joins the other band.
+    fn denied(&self, ap: &str, mac: &str) -> bool {
+        self.access_lists
+            .get(ap)
+            .is_some_and(|(kind, macs)| kind == "deny" && macs.iter().any(|m| m == mac))
+    }
+
+    fn host(&mut self, mac: &str) -> Option<&mut Host> {
+        self.hosts.iter_mut().find(|host| host.mac == mac)
+    }
+
+    fn uptime(&self) -> u64 {
+        self.started.elapsed().as_secs()
+    }
+
+    fn log(&mut self, ident: &str, level: &str, message: &str) {
+        let timestamp = clock();
+        self.log.push(json!({
+            "timestamp": timestamp,
+            "ident": ident,
+            "message": { "level": level, "message": message },
+        }));
+        if self.log.len() > LOG_CAPACITY {
+            self.log.remove(0);
+        }
+    }
+}
+
+/// The access point named in `path` between `prefix` and `suffix`.
+fn access_point<'a>(path: &'a str, prefix: &str, suffix: &str) -> Option<&'a str> {
+    let ap = path.strip_prefix(prefix)?.strip_suffix(suffix)?;
+    ACCESS_POINTS.contains(&ap).then_some(ap)
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// `HH:MM:SS` in UTC, which is all the log view shows.
+fn clock() -> String {
+    let secs = unix_time() % 86_400;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
@@ -0,0 +1,102 @@
+//! How router-cli fails, for scripts to branch on. Exit codes and kinds are
+//! stable:
+//!
+//! | code | kind          | when                                                   |
+//! |------|---------------|--------------------------------------------------------|
+//! | 0    |               | success                                                |
+//! | 1    | `error`       | anything else, e.g. an unreadable config file          |
+//! | 2    | `usage`       | bad arguments                                          |
+//! | 3    | `not_found`   | no such router, device or policy, or an ambiguous name |
+//! | 4    | `unreachable` | a router can't be reached                              |
+//! | 5    | `auth_failed` | a login was refused, or a password is missing or wrong |
+//! | 6    | `partial`     | some routers failed; the others' results were printed  |
+//!
+//! With `--format json` the error goes to stderr as one line of JSON, leaving
+//! stdout to the results:
+//!
+//! ```json
+//! {"error":{"kind":"unreachable","exit_code":4,"message":"Home: request failed: ..."}}
+//! ```
+
+use router_core::RouterError;
+use serde_json::json;
+use std::fmt;
+use std::process::ExitCode;
+
+#[derive(Debug)]
+pub enum Failure {
+    NotFound(String),
+    Unreachable(String),
+    AuthFailed(String),
+    Partial(String),
+}
+
+impl Failure {
+    /// `err` from talking to `router`, sorted by what it means for a script.
+    pub fn from_router(router: &str, err: RouterError) -> anyhow::Error {
+        let message = format!("{router}: {err}");
+        match err {
+            RouterError::AuthFailed => Failure::AuthFailed(message).into(),
+            RouterError::Request(_) => Failure::Unreachable(message).into(),
+            RouterError::InvalidResponse(_) => anyhow::anyhow!(message),
+        }
+    }
+
+    /// Combines what went wrong with some of the routers. Fails with the
+    /// first error as is when none of them worked.
+    pub fn combine(mut errors: Vec<anyhow::Error>, any_worked: bool) -> anyhow::Result<()> {
+        if errors.is_empty() {
+            return Ok(());
+        }
+        if !any_worked {
+            return Err(errors.remove(0));
+        }
+        let messages: Vec<String> = errors.iter().map(|err| format!("{err:#}")).collect();
+        Err(Failure::Partial(messages.join("; ")).into())
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::NotFound(message)
+            | Failure::Unreachable(message)
+            | Failure::AuthFailed(message)
+            | Failure::Partial(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Prints `err` and picks the exit code for it.
+pub fn report(err: &anyhow::Error, json: bool) -> ExitCode {
+    let (kind, code) = match err.downcast_ref::<Failure>() {
+        Some(Failure::NotFound(_)) => ("not_found", 3),
+        Some(Failure::Unreachable(_)) => ("unreachable", 4),
+        Some(Failure::AuthFailed(_)) => ("auth_failed", 5),
+        Some(Failure::Partial(_)) => ("partial", 6),
+        None => ("error", 1),
+    };
+    emit(kind, code, &format!("{err:#}"), json)
+}
+
+/// Prints a bad-arguments error; clap prints its own in text mode.
+pub fn usage(err: clap::Error, json: bool) -> ExitCode {
+    if !json || !err.use_stderr() {
+        err.exit();
+    }
+    let rendered = err.render().to_string();
+    let message = rendered.lines().next().unwrap_or_default();
+    emit("usage", 2, message.trim_start_matches("error: "), json)
+}
+
+fn emit(kind: &str, code: u8, message: &str, json: bool) -> ExitCode {
+    if json {
+        let error = json!({ "error": { "kind": kind, "exit_code": code, "message": message } });
+        eprintln!("{error}");
+    } else {
+        eprintln!("router-cli: {message}");
+    }
+    ExitCode::from(code)
+}
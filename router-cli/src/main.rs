@@ -0,0 +1,680 @@
+//! The tray's router controls for SSH sessions and scripts. It reads the
+//! routers saved by the tray and their passwords from the same secret store,
+//! so nothing has to be set up twice. With a master password, it is taken
+//! from `ROUTER_MASTER_PASSWORD` or asked for.
+//!
+//! Where there is no secret service, as in CI jobs and containers, router
+//! passwords can come from `ROUTER_PASSWORD_<NAME>` variables, a JSON file
+//! named by `ROUTER_CREDENTIALS_FILE`, or `--credentials-stdin`:
+//!
+//! ```sh
+//! echo '{"Home": "secret"}' | router-cli --credentials-stdin clients
+//! ```
+//!
+//! `--format json` prints the same keys as the tray's REST API, for Raycast
+//! script commands, Alfred workflows and the like: `status`, `clients` and
+//! `policies` print an array, the commands that change a client print the
+//! client, and `wol`, `reboot`, `speedtest` and `backup` print an object
+//! naming the router and what was done. Failures have stable exit codes, listed in
+//! `failure.rs`.
+
+mod failure;
+mod routers;
+mod service;
+mod watch;
+
+use anyhow::{anyhow, Context, Result};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use directories::ProjectDirs;
+use failure::Failure;
+use router_core::{
+    client_is_online, local_networks, remote_address, router_address, KeeneticRouter,
+    PolicyConnection, RouterInfo, SPEED_TEST_URL,
+};
+use router_secrets as secrets;
+use secrets::SecretStore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const MASTER_PASSWORD_VAR: &str = "ROUTER_MASTER_PASSWORD";
+
+#[derive(Parser)]
+#[command(
+    name = "router-cli",
+    version,
+    about = "Controls the routers saved in the tray"
+)]
+struct Cli {
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = Format::Text, global = true)]
+    format: Format,
+    /// Only use this saved router.
+    #[arg(long, global = true)]
+    router: Option<String>,
+    /// Reads router passwords from stdin as a JSON object of name to password.
+    #[arg(long, global = true)]
+    credentials_stdin: bool,
+    /// Saves the router's answers, with personal details replaced, as
+    /// fixtures for keenetic-sim --replay.
+    #[arg(long, global = true, value_name = "DIR")]
+    record: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Shows whether each router is reachable and has internet.
+    Status,
+    /// Lists the clients the routers know.
+    Clients {
+        /// Only clients connected right now.
+        #[arg(long)]
+        online: bool,
+    },
+    /// Lists the access policies of the routers.
+    Policies,
+    /// Puts a client under a policy, or `default` for the router's default.
+    SetPolicy { device: String, policy: String },
+    /// Blocks a client's internet access.
+    Block { device: String },
+    /// Lifts a block, keeping the client's policy.
+    Unblock { device: String },
+    /// Wakes a client with a Wake-on-LAN packet sent by its router.
+    Wol { device: String },
+    /// Restarts a router.
+    Reboot {
+        /// Doesn't ask first.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Times a download through a router, with the router's WAN rate over
+    /// the same time.
+    Speedtest {
+        /// File to download.
+        #[arg(long, default_value = SPEED_TEST_URL)]
+        url: String,
+    },
+    /// Saves a router's running configuration.
+    Backup {
+        /// File to write; printed when not given.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Prints clients joining and leaving and policy changes as they happen.
+    Watch {
+        /// Seconds between polls of the routers.
+        #[arg(long, default_value_t = 10)]
+        interval: u64,
+    },
+    /// Prints a completion script, e.g. `router-cli completions bash > /etc/bash_completion.d/router-cli`.
+    Completions { shell: clap_complete::Shell },
+    /// Prints the man page, e.g. `router-cli man > /usr/local/share/man/man1/router-cli.1`.
+    Man,
+    /// Adds, lists, removes and tests the saved routers.
+    Router {
+        #[command(subcommand)]
+        action: routers::RouterAction,
+    },
+    /// Runs the tray's daemon mode as a systemd service.
+    Service {
+        #[command(subcommand)]
+        action: service::ServiceAction,
+    },
+}
+
+/// The part of the tray's settings.json needed here.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Settings {
+    secret_store: SecretStore,
+}
+
+#[derive(Serialize)]
+struct Status {
+    router: String,
+    address: Option<String>,
+    reachable: bool,
+    internet: Option<bool>,
+    online_clients: Option<usize>,
+    error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct Client {
+    router: String,
+    mac: String,
+    name: String,
+    ip: String,
+    online: bool,
+    policy: Option<String>,
+    blocked: bool,
+}
+
+#[derive(Serialize)]
+struct Policy {
+    router: String,
+    name: String,
+    description: Option<String>,
+    connections: Vec<PolicyConnection>,
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) => return failure::usage(err, json_requested()),
+    };
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => failure::report(&err, cli.format == Format::Json),
+    }
+}
+
+/// Whether `--format json` was given, for arguments clap couldn't parse.
+fn json_requested() -> bool {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter().any(|arg| arg == "--format=json")
+        || args
+            .windows(2)
+            .any(|pair| pair[0] == "--format" && pair[1] == "json")
+}
+
+fn run(cli: &Cli) -> Result<()> {
+    secrets::load_overrides()?;
+    if cli.credentials_stdin {
+        let passwords: HashMap<String, String> = serde_json::from_reader(std::io::stdin())
+            .map_err(|err| anyhow!("failed to read credentials from stdin: {err}"))?;
+        for (name, password) in passwords {
+            secrets::set_override(&name, &password);
+        }
+    }
+    if let Some(dir) = &cli.record {
+        router_core::record_fixtures(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    // None of these needs saved routers; packaging runs some without any.
+    match &cli.command {
+        Command::Completions { shell } => {
+            let mut command = Cli::command();
+            clap_complete::generate(*shell, &mut command, "router-cli", &mut std::io::stdout());
+            return Ok(());
+        }
+        Command::Man => {
+            clap_mangen::Man::new(Cli::command()).render(&mut std::io::stdout())?;
+            return Ok(());
+        }
+        Command::Service { action } => return service::run(action),
+        Command::Router { action } => return routers::run(action, cli.format),
+        _ => {}
+    }
+    let routers = selected_routers(cli.router.as_deref())?;
+    unlock_secrets()?;
+    match &cli.command {
+        Command::Status => {
+            let (statuses, errors): (Vec<Status>, Vec<_>) = routers.iter().map(status).unzip();
+            let errors: Vec<anyhow::Error> = errors.into_iter().flatten().collect();
+            let any_worked = errors.len() < statuses.len();
+            print(cli.format, &statuses, |status| {
+                vec![
+                    status.router.clone(),
+                    status.address.clone().unwrap_or_else(|| "-".into()),
+                    match (status.reachable, status.internet) {
+                        (false, _) => "unreachable".into(),
+                        (true, Some(false)) => "no internet".into(),
+                        (true, _) => "online".into(),
+                    },
+                    status
+                        .online_clients
+                        .map(|count| format!("{count} clients"))
+                        .or_else(|| status.error.clone())
+                        .unwrap_or_default(),
+                ]
+            })?;
+            Failure::combine(errors, any_worked)
+        }
+        Command::Clients { online } => {
+            let (mut clients, errors) = all_clients(&routers);
+            let any_worked = errors.len() < routers.len();
+            clients.retain(|client| client.online || !online);
+            print(cli.format, &clients, |client| {
+                vec![
+                    client.router.clone(),
+                    client.mac.clone(),
+                    client.name.clone(),
+                    client.ip.clone(),
+                    if client.online { "online" } else { "offline" }.into(),
+                    policy_label(client),
+                ]
+            })?;
+            Failure::combine(errors, any_worked)
+        }
+        Command::Policies => {
+            let mut policies = Vec::new();
+            let mut errors = Vec::new();
+            for router in &routers {
+                let found = connect(router).and_then(|connection| {
+                    connection
+                        .get_policies()
+                        .map_err(|err| router_failure(router, err))
+                });
+                let mut found: Vec<Policy> = match found {
+                    Ok(found) => found
+                        .into_iter()
+                        .map(|(name, info)| Policy {
+                            router: router.name.clone(),
+                            name,
+                            description: info.description,
+                            connections: info.connections,
+                        })
+                        .collect(),
+                    Err(err) => {
+                        errors.push(err);
+                        continue;
+                    }
+                };
+                found.sort_by(|a, b| a.name.cmp(&b.name));
+                policies.extend(found);
+            }
+            let any_worked = errors.len() < routers.len();
+            print(cli.format, &policies, |policy| {
+                vec![
+                    policy.router.clone(),
+                    policy.name.clone(),
+                    policy.description.clone().unwrap_or_default(),
+                    policy
+                        .connections
+                        .iter()
+                        .map(|c| {
+                            if c.enabled {
+                                c.interface.clone()
+                            } else {
+                                format!("{} (off)", c.interface)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ]
+            })?;
+            Failure::combine(errors, any_worked)
+        }
+        Command::SetPolicy { device, policy } => {
+            let (router, mut client) = find_client(&routers, device)?;
+            let connection = connect(router)?;
+            let policy = (policy != "default").then(|| policy.clone());
+            if let Some(name) = &policy {
+                let known = connection
+                    .get_policies()
+                    .map_err(|err| router_failure(router, err))?;
+                if !known.contains_key(name) {
+                    return Err(Failure::NotFound(format!(
+                        "{} has no policy \"{name}\"",
+                        router.name
+                    ))
+                    .into());
+                }
+            }
+            connection
+                .apply_policy_to_client(&client.mac, policy.as_deref())
+                .map_err(|err| router_failure(router, err))?;
+            client.policy = policy;
+            client.blocked = false;
+            print_client(cli.format, &client)
+        }
+        Command::Block { device } => {
+            let (router, mut client) = find_client(&routers, device)?;
+            connect(router)?
+                .set_client_block(&client.mac)
+                .map_err(|err| router_failure(router, err))?;
+            client.blocked = true;
+            print_client(cli.format, &client)
+        }
+        Command::Unblock { device } => {
+            let (router, mut client) = find_client(&routers, device)?;
+            connect(router)?
+                .apply_policy_to_client(&client.mac, client.policy.as_deref())
+                .map_err(|err| router_failure(router, err))?;
+            client.blocked = false;
+            print_client(cli.format, &client)
+        }
+        Command::Wol { device } => {
+            let (router, client) = find_client(&routers, device)?;
+            connect(router)?
+                .wake(&client.mac)
+                .map_err(|err| router_failure(router, err))?;
+            print_done(
+                cli.format,
+                json!({ "router": client.router, "mac": client.mac, "name": client.name, "woken": true }),
+                &format!("Sent a wake-up packet to {} ({})", client.name, client.mac),
+            )
+        }
+        Command::Reboot { yes } => {
+            let router = single_router(&routers)?;
+            let connection = connect(router)?;
+            confirm(&format!("Reboot {}?", router.name), *yes)?;
+            connection
+                .reboot()
+                .map_err(|err| router_failure(router, err))?;
+            print_done(
+                cli.format,
+                json!({ "router": router.name, "rebooting": true }),
+                &format!("{} is rebooting", router.name),
+            )
+        }
+        Command::Speedtest { url } => {
+            let router = single_router(&routers)?;
+            let test = connect(router)?
+                .speed_test(url)
+                .map_err(|err| router_failure(router, err))?;
+            let wan = test
+                .wan_mbps
+                .map(|mbps| format!(", {mbps:.1} Mbit/s on the WAN link"))
+                .unwrap_or_default();
+            print_done(
+                cli.format,
+                json!({ "router": router.name, "speedtest": test }),
+                &format!(
+                    "{}: {:.1} Mbit/s down ({:.1} MB in {:.1} s){wan}",
+                    router.name,
+                    test.download_mbps,
+                    test.bytes as f64 / 1_000_000.0,
+                    test.seconds
+                ),
+            )
+        }
+        Command::Backup { output } => {
+            let router = single_router(&routers)?;
+            let config = connect(router)?
+                .get_running_config()
+                .map_err(|err| router_failure(router, err))?;
+            match output {
+                Some(path) => {
+                    secrets::write_private(path, &config)?;
+                    print_done(
+                        cli.format,
+                        json!({ "router": router.name, "path": path }),
+                        &format!(
+                            "Saved the configuration of {} to {}",
+                            router.name,
+                            path.display()
+                        ),
+                    )
+                }
+                None => print_done(
+                    cli.format,
+                    json!({ "router": router.name, "config": config }),
+                    config.trim_end(),
+                ),
+            }
+        }
+        Command::Watch { interval } => watch::run(&routers, cli.format, *interval),
+        Command::Completions { .. }
+        | Command::Man
+        | Command::Service { .. }
+        | Command::Router { .. } => Ok(()),
+    }
+}
+
+fn config_path(file: &str) -> PathBuf {
+    let dir = ProjectDirs::from("ru", "toxblh", "KeeneticTray")
+        .map(|d| d.config_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."));
+    // A machine provisioned from the command line may not have it yet.
+    let _ = fs::create_dir_all(&dir);
+    dir.join(file)
+}
+
+fn read_config<T: serde::de::DeserializeOwned + Default>(file: &str) -> Result<T> {
+    let path = config_path(file);
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn selected_routers(name: Option<&str>) -> Result<Vec<RouterInfo>> {
+    let mut routers: Vec<RouterInfo> = read_config("routers.json")?;
+    if routers.is_empty() {
+        return Err(Failure::NotFound("no routers saved; add one in the tray".into()).into());
+    }
+    if let Some(name) = name {
+        routers.retain(|router| router.name == name);
+        if routers.is_empty() {
+            return Err(Failure::NotFound(format!("no saved router named \"{name}\"")).into());
+        }
+    }
+    Ok(routers)
+}
+
+fn unlock_secrets() -> Result<()> {
+    let settings: Settings = read_config("settings.json")?;
+    secrets::set_store(settings.secret_store);
+    if secrets::is_locked() {
+        let password = match std::env::var(MASTER_PASSWORD_VAR) {
+            Ok(password) => password,
+            Err(_) if std::io::stdin().is_terminal() => {
+                rpassword::prompt_password("Master password: ")?
+            }
+            // Nobody to ask; passwords handed in may be all it takes.
+            Err(_) => return Ok(()),
+        };
+        secrets::unlock(&password)
+            .map_err(|_| Failure::AuthFailed("wrong master password".into()))?;
+    }
+    Ok(())
+}
+
+/// The one router a command acts on: the only one saved, or `--router`.
+fn single_router(routers: &[RouterInfo]) -> Result<&RouterInfo> {
+    match routers {
+        [router] => Ok(router),
+        _ => Err(anyhow!("several routers are saved; pick one with --router")),
+    }
+}
+
+/// Configurations hold password hashes and keys, so only the owner can read
+/// the file.
+/// Asks before something that can't be undone, unless `yes` was given.
+/// Without a terminal to ask on, it takes `--yes`.
+fn confirm(question: &str, yes: bool) -> Result<()> {
+    if yes {
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow!("not confirmed; pass --yes"));
+    }
+    eprint!("{question} [y/N] ");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if matches!(answer.trim(), "y" | "Y" | "yes") {
+        Ok(())
+    } else {
+        Err(anyhow!("cancelled"))
+    }
+}
+
+fn connect(router: &RouterInfo) -> Result<KeeneticRouter> {
+    let address = router_address(router, &local_networks())
+        .or_else(|| remote_address(router))
+        .ok_or_else(|| {
+            Failure::Unreachable(format!("{} is not reachable from here", router.name))
+        })?;
+    let password = secrets::get(&router.name)
+        .ok_or_else(|| Failure::AuthFailed(format!("no password saved for {}", router.name)))?;
+    Ok(KeeneticRouter::new(
+        &address,
+        &router.login,
+        &password,
+        &router.name,
+    ))
+}
+
+fn router_failure(router: &RouterInfo, err: router_core::RouterError) -> anyhow::Error {
+    Failure::from_router(&router.name, err)
+}
+
+/// The router's status, and what kept it from answering.
+fn status(router: &RouterInfo) -> (Status, Option<anyhow::Error>) {
+    let address = router_address(router, &local_networks()).or_else(|| remote_address(router));
+    let mut status = Status {
+        router: router.name.clone(),
+        address,
+        reachable: false,
+        internet: None,
+        online_clients: None,
+        error: None,
+    };
+    let clients = connect(router).and_then(|connection| {
+        let clients = connection
+            .get_online_clients()
+            .map_err(|err| router_failure(router, err))?;
+        status.internet = connection.check_internet().ok();
+        Ok(clients)
+    });
+    match clients {
+        Ok(clients) => {
+            status.reachable = true;
+            status.online_clients = Some(clients.iter().filter(|c| client_is_online(c)).count());
+            (status, None)
+        }
+        Err(err) => {
+            status.error = Some(err.to_string());
+            (status, Some(err))
+        }
+    }
+}
+
+fn clients(router: &RouterInfo) -> Result<Vec<Client>> {
+    let mut clients: Vec<Client> = connect(router)?
+        .get_online_clients()
+        .map_err(|err| router_failure(router, err))?
+        .into_iter()
+        .map(|client| Client {
+            router: router.name.clone(),
+            online: client_is_online(&client),
+            name: client.name.unwrap_or_default(),
+            ip: client.ip.unwrap_or_default(),
+            mac: client.mac,
+            policy: client.policy,
+            blocked: client.deny,
+        })
+        .collect();
+    clients.sort_by_key(|client| client.name.to_lowercase());
+    Ok(clients)
+}
+
+/// Clients of every router that answers, and what went wrong with the rest.
+fn all_clients(routers: &[RouterInfo]) -> (Vec<Client>, Vec<anyhow::Error>) {
+    let mut all = Vec::new();
+    let mut errors = Vec::new();
+    for router in routers {
+        match clients(router) {
+            Ok(found) => all.extend(found),
+            Err(err) => errors.push(err),
+        }
+    }
+    (all, errors)
+}
+
+/// The client with `device` as its MAC or name, case-insensitively.
+fn find_client<'a>(routers: &'a [RouterInfo], device: &str) -> Result<(&'a RouterInfo, Client)> {
+    let (clients, mut errors) = all_clients(routers);
+    let mut matches: Vec<Client> = clients
+        .into_iter()
+        .filter(|client| {
+            client.mac.eq_ignore_ascii_case(device) || client.name.eq_ignore_ascii_case(device)
+        })
+        .collect();
+    let client = match matches.len() {
+        // It may be on a router that didn't answer.
+        0 if !errors.is_empty() => return Err(errors.remove(0)),
+        0 => return Err(Failure::NotFound(format!("no client \"{device}\"")).into()),
+        1 => matches.remove(0),
+        _ => {
+            let names: Vec<String> = matches
+                .iter()
+                .map(|client| format!("{} on {}", client.mac, client.router))
+                .collect();
+            return Err(Failure::NotFound(format!(
+                "\"{device}\" matches several clients ({}); use the MAC or --router",
+                names.join(", ")
+            ))
+            .into());
+        }
+    };
+    let router = routers
+        .iter()
+        .find(|router| router.name == client.router)
+        .ok_or_else(|| anyhow!("router of {} went missing", client.mac))?;
+    Ok((router, client))
+}
+
+fn policy_label(client: &Client) -> String {
+    if client.blocked {
+        "blocked".into()
+    } else {
+        client.policy.clone().unwrap_or_else(|| "default".into())
+    }
+}
+
+fn print_client(format: Format, client: &Client) -> Result<()> {
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(client)?),
+        Format::Text => println!(
+            "{} ({}) on {}: {}",
+            client.name,
+            client.mac,
+            client.router,
+            policy_label(client)
+        ),
+    }
+    Ok(())
+}
+
+/// Prints what a command did: `value` as JSON, or `message`.
+fn print_done(format: Format, value: serde_json::Value, message: &str) -> Result<()> {
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&value)?),
+        Format::Text => println!("{message}"),
+    }
+    Ok(())
+}
+
+/// Prints `items` as a JSON array or as a table of the given columns.
+fn print<T: Serialize>(
+    format: Format,
+    items: &[T],
+    columns: impl Fn(&T) -> Vec<String>,
+) -> Result<()> {
+    if format == Format::Json {
+        println!("{}", serde_json::to_string_pretty(items)?);
+        return Ok(());
+    }
+    let rows: Vec<Vec<String>> = items.iter().map(columns).collect();
+    let mut widths = Vec::new();
+    for row in &rows {
+        widths.resize(widths.len().max(row.len()), 0);
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+    Ok(())
+}
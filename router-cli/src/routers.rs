@@ -0,0 +1,221 @@
+//! `router-cli router`: the saved routers, kept in the tray's routers.json
+//! and secret store, so machines can be provisioned by a script:
+//!
+//! ```sh
+//! echo "$PASSWORD" | router-cli router add Home http://192.168.1.1 --password-stdin --primary
+//! ```
+//!
+//! A running tray notices the change and picks it up.
+
+use crate::{
+    config_path, confirm, connect, print, read_config, router_failure, secrets, unlock_secrets,
+    Failure, Format,
+};
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use router_core::{KeeneticRouter, RouterInfo};
+use serde::Serialize;
+use std::fs;
+use std::io::BufRead;
+
+/// The tray's shortest refresh interval; routers can't be polled faster.
+const MIN_REFRESH_SECS: u64 = 5;
+
+#[derive(Subcommand)]
+pub enum RouterAction {
+    /// Lists the saved routers.
+    List,
+    /// Checks the login and saves a router with its password.
+    Add {
+        name: String,
+        /// Address on the local network, e.g. http://192.168.1.1.
+        address: String,
+        #[arg(long, default_value = "admin")]
+        login: String,
+        /// Reads the password from the first line of stdin instead of asking.
+        #[arg(long)]
+        password_stdin: bool,
+        /// Also use the router's KeenDNS address away from home.
+        #[arg(long)]
+        remote: bool,
+        /// Lists it first when several routers are reachable.
+        #[arg(long)]
+        primary: bool,
+        /// Seconds between polls instead of the app's default.
+        #[arg(long)]
+        interval: Option<u64>,
+    },
+    /// Removes a saved router and its password.
+    Remove {
+        name: String,
+        /// Doesn't ask first.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Goes through connecting to a saved router step by step.
+    Test { name: String },
+}
+
+#[derive(Serialize)]
+struct Step {
+    step: &'static str,
+    ok: bool,
+    detail: Option<String>,
+}
+
+pub fn run(action: &RouterAction, format: Format) -> Result<()> {
+    let mut routers: Vec<RouterInfo> = read_config("routers.json")?;
+    if !matches!(action, RouterAction::List) {
+        unlock_secrets()?;
+    }
+    match action {
+        RouterAction::List => print(format, &routers, |router| {
+            vec![
+                router.name.clone(),
+                router.address.clone(),
+                router.login.clone(),
+                if router.primary { "primary" } else { "" }.into(),
+                if router.allow_remote { "remote" } else { "" }.into(),
+            ]
+        }),
+        RouterAction::Add {
+            name,
+            address,
+            login,
+            password_stdin,
+            remote,
+            primary,
+            interval,
+        } => {
+            if routers.iter().any(|router| &router.name == name) {
+                return Err(anyhow!("a router named \"{name}\" is already saved"));
+            }
+            let password = if *password_stdin {
+                let mut line = String::new();
+                std::io::stdin().lock().read_line(&mut line)?;
+                line.trim_end_matches(['\r', '\n']).to_string()
+            } else {
+                rpassword::prompt_password(format!("Password for {login}@{name}: "))?
+            };
+            let address = address.trim_end_matches('/').to_string();
+            let client = KeeneticRouter::new(&address, login, &password, name);
+            client
+                .login()
+                .map_err(|err| Failure::from_router(name, err))?;
+            let router = RouterInfo {
+                name: name.clone(),
+                address,
+                login: login.clone(),
+                network_ip: client.get_network_ip().ok().flatten(),
+                keendns_urls: client.get_keendns_urls().ok(),
+                allow_remote: *remote,
+                refresh_interval_secs: interval.map(|secs| secs.max(MIN_REFRESH_SECS)),
+                primary: *primary,
+            };
+            secrets::set(name, &password)?;
+            // Only one router can be preferred.
+            if *primary {
+                for other in routers.iter_mut() {
+                    other.primary = false;
+                }
+            }
+            routers.push(router);
+            save(&routers)?;
+            println!("Saved {name}");
+            Ok(())
+        }
+        RouterAction::Remove { name, yes } => {
+            if !routers.iter().any(|router| &router.name == name) {
+                return Err(Failure::NotFound(format!("no saved router named \"{name}\"")).into());
+            }
+            confirm(&format!("Remove {name} and its password?"), *yes)?;
+            routers.retain(|router| &router.name != name);
+            secrets::delete(name);
+            save(&routers)?;
+            println!("Removed {name}");
+            Ok(())
+        }
+        RouterAction::Test { name } => {
+            let router = routers
+                .iter()
+                .find(|router| &router.name == name)
+                .ok_or_else(|| Failure::NotFound(format!("no saved router named \"{name}\"")))?;
+            let (steps, failure) = test(router);
+            print(format, &steps, |step| {
+                vec![
+                    step.step.into(),
+                    if step.ok { "ok" } else { "failed" }.into(),
+                    step.detail.clone().unwrap_or_default(),
+                ]
+            })?;
+            failure.map_or(Ok(()), Err)
+        }
+    }
+}
+
+/// Stops at the first step that fails, like the tray's connection test,
+/// and returns why it failed.
+fn test(router: &RouterInfo) -> (Vec<Step>, Option<anyhow::Error>) {
+    let mut steps = Vec::new();
+    let mut failure = None;
+    let mut step = |name: &'static str, result: Result<Option<String>>| match result {
+        Ok(detail) => {
+            steps.push(Step {
+                step: name,
+                ok: true,
+                detail,
+            });
+            true
+        }
+        Err(err) => {
+            steps.push(Step {
+                step: name,
+                ok: false,
+                detail: Some(err.to_string()),
+            });
+            failure = Some(err);
+            false
+        }
+    };
+    let client = match connect(router) {
+        Ok(client) => client,
+        Err(err) => {
+            step("address", Err(err));
+            return (steps, failure);
+        }
+    };
+    let _ = step(
+        "reachable",
+        client
+            .check_reachable()
+            .map(|_| None)
+            .map_err(|err| router_failure(router, err)),
+    ) && step(
+        "auth",
+        client
+            .login()
+            .map(|_| None)
+            .map_err(|err| router_failure(router, err)),
+    ) && step(
+        "policies",
+        client
+            .get_policies()
+            .map(|policies| Some(format!("{} found", policies.len())))
+            .map_err(|err| router_failure(router, err)),
+    ) && step(
+        "clients",
+        client
+            .get_online_clients()
+            .map(|clients| Some(format!("{} found", clients.len())))
+            .map_err(|err| router_failure(router, err)),
+    );
+    (steps, failure)
+}
+
+fn save(routers: &[RouterInfo]) -> Result<()> {
+    fs::write(
+        config_path("routers.json"),
+        serde_json::to_string_pretty(routers)?,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,148 @@
+//! `router-cli service`: runs the tray's `--daemon` mode as a systemd
+//! service. A system service runs as the user installing it, with sudo, so
+//! it finds the same routers; without a desktop session their passwords
+//! come from the encrypted file store rather than the keyring.
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Subcommand};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const UNIT_NAME: &str = "router-managerd.service";
+/// The daemon pings every few seconds; this leaves room for a refresh stuck
+/// on a slow router.
+const WATCHDOG_SECS: u32 = 120;
+
+#[derive(Subcommand)]
+pub enum ServiceAction {
+    /// Prints the unit file.
+    Print(ServiceOptions),
+    /// Writes the unit file and enables and starts the service.
+    Install(ServiceOptions),
+    /// Stops and disables the service and removes the unit file.
+    Uninstall {
+        /// The user service instead of the system one.
+        #[arg(long)]
+        user: bool,
+    },
+}
+
+#[derive(Args)]
+pub struct ServiceOptions {
+    /// A user service instead of a system one.
+    #[arg(long)]
+    user: bool,
+    /// The tray binary; by default the router-tray next to router-cli.
+    #[arg(long)]
+    binary: Option<PathBuf>,
+    /// Runs it under xvfb-run, for machines without a display.
+    #[arg(long)]
+    xvfb: bool,
+}
+
+pub fn run(action: &ServiceAction) -> Result<()> {
+    match action {
+        ServiceAction::Print(options) => {
+            print!("{}", unit(options)?);
+            Ok(())
+        }
+        ServiceAction::Install(options) => {
+            let path = unit_path(options.user)?;
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, unit(options)?)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            println!("Wrote {}", path.display());
+            systemctl(options.user, &["daemon-reload"])?;
+            systemctl(options.user, &["enable", "--now", UNIT_NAME])
+        }
+        ServiceAction::Uninstall { user } => {
+            let path = unit_path(*user)?;
+            // Disabling fails when it was never enabled; the file goes anyway.
+            let _ = systemctl(*user, &["disable", "--now", UNIT_NAME]);
+            if path.exists() {
+                fs::remove_file(&path)
+                    .with_context(|| format!("failed to remove {}", path.display()))?;
+                println!("Removed {}", path.display());
+            }
+            systemctl(*user, &["daemon-reload"])
+        }
+    }
+}
+
+fn unit(options: &ServiceOptions) -> Result<String> {
+    let binary = match &options.binary {
+        Some(binary) => binary.clone(),
+        None => std::env::current_exe()?
+            .with_file_name("router-tray")
+            .canonicalize()
+            .map_err(|_| anyhow!("router-tray is not next to router-cli; pass --binary"))?,
+    };
+    let mut command = format!("\"{}\" --daemon", binary.display());
+    if options.xvfb {
+        command = format!("/usr/bin/xvfb-run -a {command}");
+    }
+    let mut service = String::new();
+    if !options.user {
+        // sudo keeps the name of who ran it.
+        let user = std::env::var("SUDO_USER")
+            .or_else(|_| std::env::var("USER"))
+            .map_err(|_| anyhow!("can't tell which user the service should run as"))?;
+        service.push_str(&format!("User={user}\n"));
+    }
+    let wanted_by = if options.user {
+        "default.target"
+    } else {
+        "multi-user.target"
+    };
+    Ok(format!(
+        "[Unit]
+Description=Keenetic router manager
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+Type=notify
+# The notification may come from a child of xvfb-run.
+NotifyAccess=all
+{service}ExecStart={command}
+Restart=on-failure
+RestartSec=10
+WatchdogSec={WATCHDOG_SECS}
+# With a master password, add it with `systemctl edit`:
+# Environment=ROUTER_MASTER_PASSWORD=...
+
+[Install]
+WantedBy={wanted_by}
+"
+    ))
+}
+
+fn unit_path(user: bool) -> Result<PathBuf> {
+    if !user {
+        return Ok(PathBuf::from("/etc/systemd/system").join(UNIT_NAME));
+    }
+    let config = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME").ok_or_else(|| anyhow!("HOME is not set"))?)
+            .join(".config"),
+    };
+    Ok(config.join("systemd/user").join(UNIT_NAME))
+}
+
+fn systemctl(user: bool, args: &[&str]) -> Result<()> {
+    let mut command = Command::new("systemctl");
+    if user {
+        command.arg("--user");
+    }
+    let status = command
+        .args(args)
+        .status()
+        .context("failed to run systemctl")?;
+    if !status.success() {
+        return Err(anyhow!("systemctl {} failed", args.join(" ")));
+    }
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! `router-cli watch`: polls the routers and prints what changed since the
+//! last poll. With `--format json` every event is one line shaped like the
+//! tray's webhooks, so the output can be piped into `jq` or a script:
+//!
+//! ```json
+//! {"event":"device_joined","router":"Home","time":"2024-05-01T20:15:00+03:00","data":{"mac":"aa:bb:cc:dd:ee:ff","name":"TV","ip":"192.168.1.20"}}
+//! ```
+//!
+//! Events are `device_joined`, `device_left` and `policy_changed`, with the
+//! same `data` as the webhooks. The first poll only takes a snapshot.
+
+use crate::{clients, Client, Format};
+use anyhow::Result;
+use chrono::Local;
+use router_core::RouterInfo;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub fn run(routers: &[RouterInfo], format: Format, interval: u64) -> Result<()> {
+    let interval = Duration::from_secs(interval.max(1));
+    let mut known: HashMap<String, HashMap<String, Client>> = HashMap::new();
+    loop {
+        for router in routers {
+            let current: HashMap<String, Client> = match clients(router) {
+                Ok(found) => found
+                    .into_iter()
+                    .map(|client| (client.mac.clone(), client))
+                    .collect(),
+                Err(err) => {
+                    // Kept until the router answers again.
+                    eprintln!("router-cli: {err:#}");
+                    continue;
+                }
+            };
+            if let Some(previous) = known.get(&router.name) {
+                for (kind, data) in changes(previous, &current) {
+                    emit(format, kind, &router.name, data);
+                }
+            }
+            known.insert(router.name.clone(), current);
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+fn changes(
+    previous: &HashMap<String, Client>,
+    current: &HashMap<String, Client>,
+) -> Vec<(&'static str, Value)> {
+    let mut events = Vec::new();
+    for (mac, client) in current {
+        let before = previous.get(mac);
+        let was_online = before.is_some_and(|before| before.online);
+        if client.online && !was_online {
+            events.push(("device_joined", presence(client)));
+        } else if !client.online && was_online {
+            events.push(("device_left", presence(client)));
+        }
+        if let Some(before) = before {
+            if before.policy != client.policy || before.blocked != client.blocked {
+                events.push((
+                    "policy_changed",
+                    json!({
+                        "mac": client.mac,
+                        "name": client.name,
+                        "policy": client.policy,
+                        "blocked": client.blocked,
+                    }),
+                ));
+            }
+        }
+    }
+    // Clients the router forgot about have left as well.
+    for (mac, client) in previous {
+        if client.online && !current.contains_key(mac) {
+            events.push(("device_left", presence(client)));
+        }
+    }
+    events
+}
+
+fn presence(client: &Client) -> Value {
+    json!({ "mac": client.mac, "name": client.name, "ip": client.ip })
+}
+
+fn emit(format: Format, kind: &str, router: &str, data: Value) {
+    let time = Local::now();
+    match format {
+        Format::Json => println!(
+            "{}",
+            json!({
+                "event": kind,
+                "router": router,
+                "time": time.to_rfc3339(),
+                "data": data,
+            })
+        ),
+        Format::Text => {
+            let field = |key: &str| data.get(key).and_then(Value::as_str).unwrap_or_default();
+            let mut line = format!(
+                "{}  {router}  {kind}  {} ({})",
+                time.format("%Y-%m-%d %H:%M:%S"),
+                field("name"),
+                field("mac"),
+            );
+            if kind == "policy_changed" {
+                let blocked = data.get("blocked").and_then(Value::as_bool) == Some(true);
+                let policy = if blocked { "blocked" } else { field("policy") };
+                line.push_str(&format!(
+                    " -> {}",
+                    if policy.is_empty() { "default" } else { policy }
+                ));
+            } else if !field("ip").is_empty() {
+                line.push_str(&format!(" {}", field("ip")));
+            }
+            println!("{line}");
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! What a tray refresh does with the router's answers, for a large network:
+//! `cargo bench -p router-core`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use router_core::{client_interfaces, client_traffic, local_interfaces, parse_hotspot_hosts};
+use serde_json::{json, Value};
+
+const HOSTS: usize = 500;
+
+/// A `show ip hotspot host` answer with every host listed twice, as the
+/// router does for one seen on two interfaces.
+fn hotspot_answer() -> Value {
+    let mut list = Vec::new();
+    for i in 0..HOSTS {
+        let mac = format!("02:00:00:00:{:02x}:{:02x}", i / 256, i % 256);
+        let mut host = json!({
+            "mac": mac,
+            "ip": format!("10.0.{}.{}", i / 250, i % 250 + 2),
+            "name": format!("Host {i}"),
+            "registered": true,
+            "deny": i % 50 == 0,
+            "link": if i % 3 == 0 { "down" } else { "up" },
+            "rxbytes": i as u64 * 1_000_000,
+            "txbytes": i as u64 * 100_000,
+            "last-seen": 0,
+            "vendor": "Example",
+        });
+        if i % 4 == 0 {
+            host["policy"] = json!(format!("Policy{}", i % 3));
+        }
+        if i % 2 == 0 {
+            host["ssid"] = json!("Home");
+            host["ap"] = json!("WifiMaster1/AccessPoint0");
+            host["rssi"] = json!(-40 - (i % 40) as i64);
+        } else {
+            host["port"] = json!(i % 4 + 1);
+        }
+        list.push(json!({ "mac": mac.to_uppercase(), "ip": null }));
+        list.push(host);
+    }
+    Value::Array(list)
+}
+
+fn benches(c: &mut Criterion) {
+    let answer = hotspot_answer();
+    c.bench_function("parse_hotspot_hosts/500", |b| {
+        b.iter(|| parse_hotspot_hosts(black_box(&answer)))
+    });
+
+    let clients = parse_hotspot_hosts(&answer);
+    // Building the state of a router reached remotely, and at home.
+    c.bench_function("client_interfaces/500", |b| {
+        b.iter(|| client_interfaces(black_box(&clients)))
+    });
+    c.bench_function("local_interfaces/500", |b| {
+        b.iter(|| local_interfaces(black_box(&clients)))
+    });
+    c.bench_function("client_traffic/500", |b| {
+        b.iter(|| client_traffic(black_box(&clients)))
+    });
+}
+
+criterion_group!(parsing, benches);
+criterion_main!(parsing);
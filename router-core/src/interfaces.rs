@@ -0,0 +1,136 @@
+//! This machine's network interfaces, behind the `local-discovery`
+//! feature, which brings in pnet.
+
+use crate::{client_is_online, client_wifi, interface_type, ClientInfo, InterfaceInfo};
+use pnet::datalink;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub fn local_interfaces(clients: &[ClientInfo]) -> Vec<InterfaceInfo> {
+    let mut out = Vec::new();
+    let mut by_mac: HashMap<String, &ClientInfo> = HashMap::new();
+    for client in clients {
+        by_mac.insert(client.mac.to_lowercase(), client);
+    }
+
+    for iface in snapshot().interfaces.iter() {
+        if iface.is_loopback() {
+            continue;
+        }
+        let mac = match iface.mac {
+            Some(mac) => mac.to_string().to_lowercase(),
+            None => continue,
+        };
+        if !by_mac.is_empty() && !by_mac.contains_key(&mac) {
+            continue;
+        }
+        let ip = iface
+            .ips
+            .iter()
+            .find_map(|ip| match ip.ip() {
+                IpAddr::V4(v4) => Some(v4.to_string()),
+                _ => None,
+            })
+            .unwrap_or_else(|| "N/A".to_string());
+        let name = iface.name.clone();
+        let mut display_name = name.clone();
+        let iface_type = interface_type(&name);
+        let mut policy = None;
+        let mut deny = false;
+        let mut online = false;
+        let mut wifi = None;
+        if let Some(client) = by_mac.get(&mac) {
+            if let Some(name) = &client.name {
+                display_name = name.clone();
+            }
+            policy = client.policy.clone();
+            deny = client.deny;
+            online = client_is_online(client);
+            wifi = client_wifi(client);
+        }
+        out.push(InterfaceInfo {
+            name,
+            display_name,
+            mac,
+            ip,
+            iface_type,
+            online,
+            policy,
+            deny,
+            wifi,
+        });
+    }
+    out
+}
+
+pub fn local_networks() -> Vec<ipnetwork::IpNetwork> {
+    let mut out = Vec::new();
+    for iface in snapshot().interfaces.iter() {
+        for ip in &iface.ips {
+            if let ipnetwork::IpNetwork::V4(v4) = ip {
+                out.push(ipnetwork::IpNetwork::V4(*v4));
+            }
+        }
+    }
+    out
+}
+
+/// A refresh looks at the local interfaces several times; within this long
+/// the previous enumeration is reused.
+const SNAPSHOT_TTL: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+struct InterfaceSnapshot {
+    taken: Instant,
+    fingerprint: u64,
+    interfaces: Arc<Vec<datalink::NetworkInterface>>,
+}
+
+static SNAPSHOT: Mutex<Option<InterfaceSnapshot>> = Mutex::new(None);
+
+fn snapshot() -> InterfaceSnapshot {
+    let mut cached = SNAPSHOT.lock().unwrap();
+    if let Some(snapshot) = cached.as_ref().filter(|s| s.taken.elapsed() < SNAPSHOT_TTL) {
+        return snapshot.clone();
+    }
+    let interfaces = datalink::interfaces();
+    let fingerprint = fingerprint(&interfaces);
+    let snapshot = match cached.as_ref() {
+        // Nothing changed: keep the old list so its users can tell.
+        Some(previous) if previous.fingerprint == fingerprint => InterfaceSnapshot {
+            taken: Instant::now(),
+            ..previous.clone()
+        },
+        _ => InterfaceSnapshot {
+            taken: Instant::now(),
+            fingerprint,
+            interfaces: Arc::new(interfaces),
+        },
+    };
+    *cached = Some(snapshot.clone());
+    snapshot
+}
+
+/// Interface count plus every MAC and address, which is all the tray uses.
+fn fingerprint(interfaces: &[datalink::NetworkInterface]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    interfaces.len().hash(&mut hasher);
+    for iface in interfaces {
+        iface.name.hash(&mut hasher);
+        iface.mac.map(|mac| mac.octets()).hash(&mut hasher);
+        for ip in &iface.ips {
+            ip.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Changes whenever local interfaces or their addresses do, e.g. after
+/// joining another network.
+pub fn local_interfaces_fingerprint() -> u64 {
+    snapshot().fingerprint
+}
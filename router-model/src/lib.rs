@@ -0,0 +1,183 @@
+//! The routers, clients and network state the tray and CLI show, without
+//! anything tied to talking to a particular vendor's router.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RouterInfo {
+    pub name: String,
+    pub address: String,
+    pub login: String,
+    #[serde(default)]
+    pub network_ip: Option<String>,
+    #[serde(default)]
+    pub keendns_urls: Option<Vec<String>>,
+    #[serde(default)]
+    pub allow_remote: bool,
+    /// How often the background poller queries this router; `None` uses the
+    /// app's default.
+    #[serde(default)]
+    pub refresh_interval_secs: Option<u64>,
+    /// Listed first when several routers are reachable at once.
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PolicyInfo {
+    pub description: Option<String>,
+    /// The connections the policy routes through, most preferred first.
+    #[serde(default)]
+    pub connections: Vec<PolicyConnection>,
+}
+
+/// A connection bound to a policy.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyConnection {
+    /// The router's interface name, e.g. `Wireguard0` or `GigabitEthernet1`.
+    pub interface: String,
+    /// Off when it stays in the policy but isn't used.
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct ClientInfo {
+    pub name: Option<String>,
+    pub ip: Option<String>,
+    pub mac: String,
+    pub policy: Option<String>,
+    pub deny: bool,
+    pub raw: Value,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub display_name: String,
+    pub mac: String,
+    pub ip: String,
+    pub iface_type: String,
+    pub online: bool,
+    pub policy: Option<String>,
+    pub deny: bool,
+    #[serde(default)]
+    pub wifi: Option<WifiLink>,
+}
+
+/// The wireless association of a client as reported by the router.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WifiLink {
+    pub ssid: Option<String>,
+    pub band: Option<String>,
+    /// Signal strength in dBm.
+    pub rssi: Option<i64>,
+}
+
+/// A Wi-Fi band a client can be held to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WifiBand {
+    #[serde(rename = "2.4")]
+    Ghz2_4,
+    #[serde(rename = "5")]
+    Ghz5,
+}
+
+impl WifiBand {
+    pub fn other(self) -> Self {
+        match self {
+            WifiBand::Ghz2_4 => WifiBand::Ghz5,
+            WifiBand::Ghz5 => WifiBand::Ghz2_4,
+        }
+    }
+}
+
+/// The guest network as configured on the router.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GuestWifi {
+    pub ssid: Option<String>,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DiscoveredRouter {
+    pub address: String,
+    pub realm: Option<String>,
+    pub network: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    pub name: Option<String>,
+    pub mac: String,
+    pub ip: Option<String>,
+    pub registered: bool,
+    pub online: bool,
+    pub policy: Option<String>,
+    pub deny: bool,
+    pub last_seen: Option<u64>,
+    pub vendor: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MeshNode {
+    pub id: String,
+    pub name: Option<String>,
+    pub model: Option<String>,
+    pub mac: Option<String>,
+    pub ip: Option<String>,
+    pub online: bool,
+}
+
+/// A client and where it hangs off the network: `parent` is the mesh node id,
+/// or `None` when attached to the main router.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TopologyClient {
+    pub name: String,
+    pub mac: String,
+    pub ip: Option<String>,
+    pub parent: Option<String>,
+    pub wireless: bool,
+    pub port: Option<String>,
+    pub online: bool,
+    pub deny: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Topology {
+    pub nodes: Vec<MeshNode>,
+    pub clients: Vec<TopologyClient>,
+}
+
+/// A line of the router's system log.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// As the router prints it, e.g. "Mar 4 21:03:12".
+    pub timestamp: String,
+    /// The process that logged it, e.g. "ndm".
+    pub ident: String,
+    /// "Info", "Warning", "Error", ...
+    pub level: Option<String>,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct TrafficCounters {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// A test download through a router, timed here and on the router's WAN
+/// counters.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct SpeedTest {
+    /// Bytes of the test file that arrived.
+    pub bytes: u64,
+    pub seconds: f64,
+    /// The test download's rate, in megabits per second.
+    pub download_mbps: f64,
+    /// Everything the router received on its WAN link meanwhile, other
+    /// clients' traffic included, in megabits per second. `None` when the
+    /// router wouldn't give its counters.
+    pub wan_mbps: Option<f64>,
+}
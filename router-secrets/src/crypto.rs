@@ -0,0 +1,99 @@
+use anyhow::{anyhow, Result};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// Passphrase-encrypted payload: Argon2id key derivation + XChaCha20-Poly1305.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sealed {
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// A key derived from a passphrase once, for sealing and opening many times
+/// without running Argon2 again. Everything it seals shares its salt.
+pub struct DerivedKey {
+    salt: Vec<u8>,
+    key: Zeroizing<[u8; 32]>,
+}
+
+impl DerivedKey {
+    /// Derives a key with a fresh salt.
+    pub fn new(passphrase: &str) -> Result<Self> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(passphrase, salt.to_vec())
+    }
+
+    /// Derives the key `sealed` was sealed with.
+    pub fn for_sealed(passphrase: &str, sealed: &Sealed) -> Result<Self> {
+        Self::derive(passphrase, STANDARD.decode(&sealed.salt)?)
+    }
+
+    fn derive(passphrase: &str, salt: Vec<u8>) -> Result<Self> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut *key)
+            .map_err(|e| anyhow!("key derivation failed: {e}"))?;
+        Ok(Self { salt, key })
+    }
+
+    /// Whether `sealed` was sealed with a key derived with this salt.
+    pub fn matches(&self, sealed: &Sealed) -> bool {
+        STANDARD
+            .decode(&sealed.salt)
+            .is_ok_and(|salt| salt == self.salt)
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Sealed> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("encryption failed"))?;
+        Ok(Sealed {
+            salt: STANDARD.encode(&self.salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    pub fn open(&self, sealed: &Sealed) -> Result<Vec<u8>> {
+        if !self.matches(sealed) {
+            return Err(anyhow!("sealed with another key"));
+        }
+        let nonce = STANDARD.decode(&sealed.nonce)?;
+        let ciphertext = STANDARD.decode(&sealed.ciphertext)?;
+        if nonce.len() != 24 {
+            return Err(anyhow!("invalid nonce"));
+        }
+        self.cipher()
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| anyhow!("wrong passphrase or corrupted data"))
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(Key::from_slice(&*self.key))
+    }
+}
+
+/// Random key material for secrets that are not protected by a user passphrase.
+pub fn random_key() -> String {
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    STANDARD.encode(key)
+}
+
+pub fn seal(passphrase: &str, plaintext: &[u8]) -> Result<Sealed> {
+    DerivedKey::new(passphrase)?.seal(plaintext)
+}
+
+pub fn open(passphrase: &str, sealed: &Sealed) -> Result<Vec<u8>> {
+    DerivedKey::for_sealed(passphrase, sealed)?.open(sealed)
+}
@@ -0,0 +1,428 @@
+//! Router passwords and tokens, shared by the tray and router-cli so both
+//! read the same store: the OS keyring, an encrypted file in the
+//! configuration directory, or a vault behind a master password.
+
+pub mod crypto;
+
+use anyhow::{anyhow, Result};
+use directories::ProjectDirs;
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use zeroize::Zeroizing;
+
+const SERVICE: &str = "router_manager";
+/// Keyring entry for the key of the file store, under its own service so a
+/// router can't be named after it.
+const FILE_KEY_SERVICE: &str = "router_manager_file_key";
+const FILE_KEY_ENTRY: &str = "secrets.json";
+/// Where the file store's key goes without a keyring. Beside the data it
+/// only keeps the passwords from being read at a glance.
+const KEY_FILE: &str = "secrets.key";
+const SECRETS_FILE: &str = "secrets.json";
+const VAULT_FILE: &str = "vault.json";
+/// `ROUTER_PASSWORD_HOME=...` gives the password of the router named Home.
+const PASSWORD_VAR_PREFIX: &str = "ROUTER_PASSWORD_";
+/// A JSON file of `{"router name": "password"}`, for many at once.
+const CREDENTIALS_FILE_VAR: &str = "ROUTER_CREDENTIALS_FILE";
+
+/// Where router passwords are kept. `Auto` prefers the OS keyring and falls
+/// back to the encrypted file when no secret service is available.
+/// `MasterPassword` keeps them in a vault keyed by a password entered once
+/// per session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretStore {
+    #[default]
+    Auto,
+    Keyring,
+    File,
+    MasterPassword,
+}
+
+static STORE: RwLock<SecretStore> = RwLock::new(SecretStore::Auto);
+/// Set by [`isolate`]: the directory used instead of the configuration
+/// directory, with the keyring left alone.
+static ISOLATED: RwLock<Option<PathBuf>> = RwLock::new(None);
+/// The vault key for this session, derived from the master password once.
+static SESSION: RwLock<Option<crypto::DerivedKey>> = RwLock::new(None);
+/// Passwords already read this session, and `None` for names the store
+/// doesn't have. Keychains on macOS may prompt on every read, and refreshes
+/// read them all the time.
+static CACHE: RwLock<BTreeMap<String, Option<Zeroizing<String>>>> = RwLock::new(BTreeMap::new());
+/// Passwords handed in for this run by a CI job or container, by
+/// [`override_key`]. They win over the store and are never written to it.
+static OVERRIDES: RwLock<BTreeMap<String, Zeroizing<String>>> = RwLock::new(BTreeMap::new());
+
+/// Keeps everything in `dir` and out of the OS keyring, for a throwaway
+/// configuration like the tray's demo.
+pub fn isolate(dir: PathBuf) {
+    *ISOLATED.write().unwrap() = Some(dir);
+    set_store(SecretStore::File);
+}
+
+fn isolated() -> bool {
+    ISOLATED.read().unwrap().is_some()
+}
+
+/// The tray's configuration directory, which router-cli shares.
+fn config_path(file: &str) -> PathBuf {
+    let dir = ISOLATED.read().unwrap().clone().unwrap_or_else(|| {
+        ProjectDirs::from("ru", "toxblh", "KeeneticTray")
+            .map(|d| d.config_dir().to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."))
+    });
+    let _ = fs::create_dir_all(&dir);
+    dir.join(file)
+}
+
+pub fn set_store(store: SecretStore) {
+    let mut current = STORE.write().unwrap();
+    if *current != store {
+        clear_cache();
+    }
+    *current = store;
+}
+
+/// Forgets the cached passwords, wiping them from memory.
+pub fn clear_cache() {
+    CACHE.write().unwrap().clear();
+}
+
+pub fn store() -> SecretStore {
+    *STORE.read().unwrap()
+}
+
+pub fn is_locked() -> bool {
+    store() == SecretStore::MasterPassword && SESSION.read().unwrap().is_none()
+}
+
+pub fn has_vault() -> bool {
+    config_path(VAULT_FILE).exists()
+}
+
+/// Checks the master password against the vault and keeps the key derived
+/// from it for the session.
+pub fn unlock(password: &str) -> Result<()> {
+    let sealed = read_sealed_file(VAULT_FILE)?
+        .ok_or_else(|| anyhow!("there is no master password vault to unlock"))?;
+    let key = crypto::DerivedKey::for_sealed(password, &sealed)?;
+    key.open(&sealed)?;
+    *SESSION.write().unwrap() = Some(key);
+    clear_cache();
+    Ok(())
+}
+
+/// Moves every known password into a vault encrypted with `password`, which
+/// has to be typed twice.
+pub fn enable_master_password(password: &str, confirmation: &str, names: &[String]) -> Result<()> {
+    check_confirmation(password, confirmation)?;
+    let secrets: HashMap<String, String> = names
+        .iter()
+        .filter_map(|name| get(name).map(|p| (name.clone(), p.to_string())))
+        .collect();
+    let key = crypto::DerivedKey::new(password)?;
+    write_vault(&key, &secrets)?;
+    for name in names {
+        delete_unprotected(name);
+    }
+    set_store(SecretStore::MasterPassword);
+    *SESSION.write().unwrap() = Some(key);
+    Ok(())
+}
+
+pub fn change_master_password(password: &str, confirmation: &str) -> Result<()> {
+    check_confirmation(password, confirmation)?;
+    let secrets = vault()?;
+    let key = crypto::DerivedKey::new(password)?;
+    write_vault(&key, &secrets)?;
+    *SESSION.write().unwrap() = Some(key);
+    Ok(())
+}
+
+fn check_confirmation(password: &str, confirmation: &str) -> Result<()> {
+    if password != confirmation {
+        return Err(anyhow!("the master passwords don't match"));
+    }
+    Ok(())
+}
+
+/// Moves the vault contents back into `fallback` and removes the vault.
+pub fn disable_master_password(fallback: SecretStore) -> Result<()> {
+    let secrets = vault()?;
+    set_store(fallback);
+    for (name, password) in &secrets {
+        set(name, password)?;
+    }
+    let _ = fs::remove_file(config_path(VAULT_FILE));
+    *SESSION.write().unwrap() = None;
+    Ok(())
+}
+
+fn vault() -> Result<HashMap<String, String>> {
+    let session = SESSION.read().unwrap();
+    let key = session
+        .as_ref()
+        .ok_or_else(|| anyhow!("credentials are locked"))?;
+    let Some(sealed) = read_sealed_file(VAULT_FILE)? else {
+        return Ok(HashMap::new());
+    };
+    if !key.matches(&sealed) {
+        // Resealed with another password, by the CLI or another instance.
+        drop(session);
+        *SESSION.write().unwrap() = None;
+        return Err(anyhow!("the vault changed; unlock it again"));
+    }
+    Ok(serde_json::from_slice(&key.open(&sealed)?)?)
+}
+
+/// Writes the vault with the session key.
+fn write_session_vault(secrets: &HashMap<String, String>) -> Result<()> {
+    let session = SESSION.read().unwrap();
+    let key = session
+        .as_ref()
+        .ok_or_else(|| anyhow!("credentials are locked"))?;
+    write_vault(key, secrets)
+}
+
+fn write_vault(key: &crypto::DerivedKey, secrets: &HashMap<String, String>) -> Result<()> {
+    let sealed = key.seal(&serde_json::to_vec(secrets)?)?;
+    write_private(
+        &config_path(VAULT_FILE),
+        &serde_json::to_string_pretty(&sealed)?,
+    )
+}
+
+/// Takes passwords from `ROUTER_PASSWORD_*` variables and the file named by
+/// `ROUTER_CREDENTIALS_FILE`, for machines without a secret service. The
+/// variables are removed so script hooks don't inherit them.
+pub fn load_overrides() -> Result<()> {
+    if let Some(path) = std::env::var_os(CREDENTIALS_FILE_VAR) {
+        let data = fs::read_to_string(&path)
+            .map_err(|err| anyhow!("failed to read {}: {err}", path.to_string_lossy()))?;
+        let passwords: HashMap<String, String> = serde_json::from_str(&data)?;
+        for (name, password) in passwords {
+            set_override(&name, &password);
+        }
+    }
+    for (var, password) in std::env::vars() {
+        if let Some(name) = var.strip_prefix(PASSWORD_VAR_PREFIX) {
+            set_override(name, &password);
+            std::env::remove_var(&var);
+        }
+    }
+    Ok(())
+}
+
+pub fn set_override(name: &str, password: &str) {
+    OVERRIDES
+        .write()
+        .unwrap()
+        .insert(override_key(name), Zeroizing::new(password.to_string()));
+}
+
+/// Names as they can be spelled in a variable: `Home Office` becomes
+/// `HOME_OFFICE`.
+fn override_key(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+pub fn get(name: &str) -> Option<Zeroizing<String>> {
+    if let Some(password) = OVERRIDES.read().unwrap().get(&override_key(name)) {
+        return Some(password.clone());
+    }
+    if let Some(cached) = CACHE.read().unwrap().get(name) {
+        return cached.clone();
+    }
+    // A store that can't be read now, like a locked vault, isn't a miss.
+    let password = read(name).ok()?.map(Zeroizing::new);
+    CACHE
+        .write()
+        .unwrap()
+        .insert(name.to_string(), password.clone());
+    password
+}
+
+fn read(name: &str) -> Result<Option<String>> {
+    match store() {
+        SecretStore::Keyring => keyring_get(name),
+        SecretStore::File => file_get(name),
+        SecretStore::MasterPassword => Ok(vault()?.remove(name)),
+        SecretStore::Auto => match keyring_get(name) {
+            Ok(Some(password)) => Ok(Some(password)),
+            Ok(None) | Err(_) => file_get(name),
+        },
+    }
+}
+
+pub fn set(name: &str, password: &str) -> Result<()> {
+    CACHE.write().unwrap().remove(name);
+    match store() {
+        SecretStore::Keyring => keyring_set(name, password),
+        SecretStore::File => file_set(name, password),
+        SecretStore::MasterPassword => {
+            let mut secrets = vault()?;
+            secrets.insert(name.to_string(), password.to_string());
+            write_session_vault(&secrets)
+        }
+        SecretStore::Auto => {
+            if keyring_set(name, password).is_ok() {
+                return Ok(());
+            }
+            file_set(name, password)
+        }
+    }
+}
+
+pub fn delete(name: &str) {
+    CACHE.write().unwrap().remove(name);
+    delete_unprotected(name);
+    if let Ok(mut secrets) = vault() {
+        if secrets.remove(name).is_some() {
+            let _ = write_session_vault(&secrets);
+        }
+    }
+}
+
+fn delete_unprotected(name: &str) {
+    if let Ok(entry) = keyring_entry(SERVICE, name) {
+        let _ = entry.delete_password();
+    }
+    if let Ok(mut secrets) = read_file_secrets() {
+        if secrets.remove(name).is_some() {
+            let _ = write_file_secrets(&secrets);
+        }
+    }
+}
+
+/// The keyring entry for `name`, unless the store is isolated from it.
+fn keyring_entry(service: &str, name: &str) -> keyring::Result<Entry> {
+    if isolated() {
+        return Err(keyring::Error::NoStorageAccess(
+            "isolated from the keyring".into(),
+        ));
+    }
+    Entry::new(service, name)
+}
+
+fn keyring_get(name: &str) -> Result<Option<String>> {
+    match keyring_entry(SERVICE, name)?.get_password() {
+        Ok(password) => Ok(Some(password)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn keyring_set(name: &str, password: &str) -> Result<()> {
+    keyring_entry(SERVICE, name)?.set_password(password)?;
+    Ok(())
+}
+
+fn file_get(name: &str) -> Result<Option<String>> {
+    Ok(read_file_secrets()?.remove(name))
+}
+
+fn file_set(name: &str, password: &str) -> Result<()> {
+    let mut secrets = read_file_secrets()?;
+    secrets.insert(name.to_string(), password.to_string());
+    write_file_secrets(&secrets)
+}
+
+/// Writes `data` to a file only this user can read. The file is created
+/// that way, so the contents are never readable by others, and a file that
+/// already existed is narrowed down first.
+pub fn write_private(path: &Path, data: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(data.as_bytes())?;
+    Ok(())
+}
+
+/// The key of `secrets.json`, kept in the OS keyring when there is one. A
+/// key written to `secrets.key` while there was none moves into the keyring
+/// once it can be reached.
+fn file_key() -> Result<String> {
+    let entry = keyring_entry(FILE_KEY_SERVICE, FILE_KEY_ENTRY);
+    let path = config_path(KEY_FILE);
+    if path.exists() {
+        let key = fs::read_to_string(&path)?.trim().to_string();
+        if entry.and_then(|e| e.set_password(&key)).is_ok() {
+            let _ = fs::remove_file(&path);
+        }
+        return Ok(key);
+    }
+    match entry.as_ref().map(Entry::get_password) {
+        Ok(Ok(key)) => return Ok(key),
+        Ok(Err(keyring::Error::NoEntry)) => {}
+        // A new key would lock out the passwords sealed with the old one.
+        _ if config_path(SECRETS_FILE).exists() => {
+            return Err(anyhow!(
+                "the key for {SECRETS_FILE} is in a keyring that can't be reached"
+            ));
+        }
+        _ => {}
+    }
+    let key = crypto::random_key();
+    if entry.and_then(|e| e.set_password(&key)).is_err() {
+        write_private(&path, &key)?;
+    }
+    Ok(key)
+}
+
+/// Whether the file store's key sits beside it, so the file is obfuscated
+/// rather than encrypted.
+pub fn file_key_exposed() -> bool {
+    config_path(KEY_FILE).exists()
+}
+
+fn read_sealed_file(file: &str) -> Result<Option<crypto::Sealed>> {
+    let path = config_path(file);
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+fn read_sealed(file: &str, key: &str) -> Result<HashMap<String, String>> {
+    let Some(sealed) = read_sealed_file(file)? else {
+        return Ok(HashMap::new());
+    };
+    let plain = crypto::open(key, &sealed)?;
+    Ok(serde_json::from_slice(&plain)?)
+}
+
+fn write_sealed(file: &str, key: &str, secrets: &HashMap<String, String>) -> Result<()> {
+    let sealed = crypto::seal(key, &serde_json::to_vec(secrets)?)?;
+    write_private(&config_path(file), &serde_json::to_string_pretty(&sealed)?)
+}
+
+fn read_file_secrets() -> Result<HashMap<String, String>> {
+    if !config_path(SECRETS_FILE).exists() {
+        return Ok(HashMap::new());
+    }
+    read_sealed(SECRETS_FILE, &file_key()?)
+}
+
+fn write_file_secrets(secrets: &HashMap<String, String>) -> Result<()> {
+    write_sealed(SECRETS_FILE, &file_key()?, secrets)
+}
@@ -0,0 +1,26 @@
+//! Sealing and opening with a master password, as the vault and the export
+//! bundles do it.
+
+use router_secrets::crypto::{self, DerivedKey};
+
+#[test]
+fn sealed_data_opens_with_the_same_password() {
+    let sealed = crypto::seal("correct horse", b"hunter2").unwrap();
+    assert_eq!(crypto::open("correct horse", &sealed).unwrap(), b"hunter2");
+}
+
+#[test]
+fn wrong_password_does_not_open() {
+    let sealed = crypto::seal("correct horse", b"hunter2").unwrap();
+    assert!(crypto::open("battery staple", &sealed).is_err());
+}
+
+#[test]
+fn derived_key_recognises_its_own_salt() {
+    let key = DerivedKey::new("correct horse").unwrap();
+    let sealed = key.seal(b"hunter2").unwrap();
+    assert!(key.matches(&sealed));
+    let again = DerivedKey::for_sealed("correct horse", &sealed).unwrap();
+    assert_eq!(again.open(&sealed).unwrap(), b"hunter2");
+    assert!(!DerivedKey::new("correct horse").unwrap().matches(&sealed));
+}
@@ -0,0 +1,20 @@
+//! Files holding secrets are only readable by their owner.
+
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+#[test]
+fn private_files_are_narrowed_down() {
+    let path = std::env::temp_dir().join(format!("router-secrets-{}.json", std::process::id()));
+    fs::write(&path, "{}").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+    router_secrets::write_private(&path, "{\"psk\": \"hunter2\"}").unwrap();
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    let contents = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(contents, "{\"psk\": \"hunter2\"}");
+}
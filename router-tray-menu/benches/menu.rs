@@ -0,0 +1,94 @@
+//! Building the tray menu for a large network, which happens on every
+//! refresh: `cargo bench -p router-tray-menu`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use router_model::{InterfaceInfo, PolicyInfo, RouterInfo, WifiLink};
+use router_tray_menu::{build_tray_menu, ActiveState, MenuLayout, TrayMenuModel};
+use std::collections::HashMap;
+
+const CLIENTS: usize = 500;
+
+fn router(name: &str) -> RouterInfo {
+    serde_json::from_value(serde_json::json!({
+        "name": name,
+        "address": "http://192.168.1.1",
+        "login": "admin",
+    }))
+    .unwrap()
+}
+
+/// A router with `CLIENTS` clients, half of them on Wi-Fi and a third
+/// offline, and a handful of policies.
+fn active_state(name: &str) -> ActiveState {
+    let interfaces: Vec<InterfaceInfo> = (0..CLIENTS)
+        .map(|i| InterfaceInfo {
+            name: format!("GigabitEthernet0/{}", i % 4),
+            display_name: format!("Host {i}"),
+            mac: format!("02:00:00:00:{:02x}:{:02x}", i / 256, i % 256),
+            ip: format!("10.0.{}.{}", i / 250, i % 250 + 2),
+            iface_type: "Ethernet".to_string(),
+            online: i % 3 != 0,
+            policy: (i % 4 == 0).then(|| format!("Policy{}", i % 3)),
+            deny: i % 50 == 0,
+            wifi: (i % 2 == 0).then(|| WifiLink {
+                ssid: Some("Home".to_string()),
+                band: Some("5 GHz".to_string()),
+                rssi: Some(-40 - (i % 40) as i64),
+            }),
+        })
+        .collect();
+    let policies = (0..3)
+        .map(|i| {
+            let info = PolicyInfo {
+                description: Some(format!("Policy {i}")),
+                connections: Vec::new(),
+            };
+            (format!("Policy{i}"), info)
+        })
+        .collect();
+    ActiveState {
+        router: router(name),
+        online_clients: interfaces.iter().filter(|i| i.online).count(),
+        active_iface: interfaces.get(1).cloned(),
+        interfaces,
+        policies,
+        active_address: "http://192.168.1.1".to_string(),
+        remote: false,
+        segment: None,
+        band_locks: HashMap::new(),
+    }
+}
+
+fn benches(c: &mut Criterion) {
+    let notes = HashMap::new();
+    let one = [active_state("Home")];
+    let two = [active_state("Home"), active_state("Office")];
+    let cut = MenuLayout::default();
+    let everything = MenuLayout {
+        max_devices: CLIENTS,
+        ..MenuLayout::default()
+    };
+    let model = |states, layout| TrayMenuModel {
+        states,
+        layout,
+        device_notes: &notes,
+        has_routers: true,
+        last_error: None,
+        updated: Some("Updated 12:00:00"),
+        update: None,
+        crash_report: false,
+    };
+
+    c.bench_function("build_tray_menu/500", |b| {
+        b.iter(|| build_tray_menu(black_box(&model(&one, &cut))))
+    });
+    c.bench_function("build_tray_menu/500/all_devices", |b| {
+        b.iter(|| build_tray_menu(black_box(&model(&one, &everything))))
+    });
+    c.bench_function("build_tray_menu/2x500", |b| {
+        b.iter(|| build_tray_menu(black_box(&model(&two, &cut))))
+    });
+}
+
+criterion_group!(menu, benches);
+criterion_main!(menu);
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+const EN: &str = include_str!("../locales/en.json");
+const RU: &str = include_str!("../locales/ru.json");
+
+pub const LANGUAGES: &[&str] = &["en", "ru"];
+
+struct Catalog {
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+fn parse(source: &str) -> HashMap<String, String> {
+    serde_json::from_str(source).unwrap_or_default()
+}
+
+fn source(language: &str) -> &'static str {
+    match language {
+        "ru" => RU,
+        _ => EN,
+    }
+}
+
+fn catalog() -> &'static RwLock<Catalog> {
+    static CATALOG: OnceLock<RwLock<Catalog>> = OnceLock::new();
+    CATALOG.get_or_init(|| {
+        RwLock::new(Catalog {
+            strings: parse(EN),
+            fallback: parse(EN),
+        })
+    })
+}
+
+pub fn detect_language() -> String {
+    let locale = sys_locale::get_locale().unwrap_or_default().to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|lang| locale.starts_with(*lang))
+        .unwrap_or(&"en")
+        .to_string()
+}
+
+/// Switches the active catalog; an empty or "auto" setting follows the OS locale.
+pub fn set_language(setting: &str) {
+    let language = if setting.is_empty() || setting == "auto" || !LANGUAGES.contains(&setting) {
+        detect_language()
+    } else {
+        setting.to_string()
+    };
+    let mut catalog = catalog().write().unwrap();
+    catalog.strings = parse(source(&language));
+}
+
+pub fn t(key: &str) -> String {
+    let catalog = catalog().read().unwrap();
+    catalog
+        .strings
+        .get(key)
+        .or_else(|| catalog.fallback.get(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+pub fn tf(key: &str, args: &[(&str, &str)]) -> String {
+    let mut out = t(key);
+    for (name, value) in args {
+        out = out.replace(&format!("{{{name}}}"), value);
+    }
+    out
+}
+
+pub fn translations() -> HashMap<String, String> {
+    let catalog = catalog().read().unwrap();
+    let mut out = catalog.fallback.clone();
+    out.extend(catalog.strings.clone());
+    out
+}
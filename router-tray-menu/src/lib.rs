@@ -0,0 +1,667 @@
+//! What the tray menu shows for a given state of the routers, worked out
+//! from plain data so it can be checked without a running app. Nothing here
+//! needs GTK or a window system; the tray turns the result into a native
+//! menu.
+
+pub mod i18n;
+mod menu;
+
+use chrono::{DateTime, Local};
+use i18n::{t, tf};
+pub use menu::{Entry, TrayItem, TrayMenu};
+use router_model::{InterfaceInfo, PolicyInfo, RouterInfo, WifiBand, WifiLink};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A router as of its last refresh.
+#[derive(Clone, Debug, Serialize)]
+pub struct ActiveState {
+    pub router: RouterInfo,
+    pub interfaces: Vec<InterfaceInfo>,
+    pub policies: HashMap<String, PolicyInfo>,
+    pub active_iface: Option<InterfaceInfo>,
+    pub active_address: String,
+    pub remote: bool,
+    pub segment: Option<String>,
+    /// Clients of the router that are currently online.
+    pub online_clients: usize,
+    /// Wi-Fi clients held to one band, by MAC.
+    pub band_locks: HashMap<String, WifiBand>,
+}
+
+/// Which rows the tray menu shows.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MenuLayout {
+    pub show_interface: bool,
+    pub show_wifi: bool,
+    pub show_note: bool,
+    pub show_ip: bool,
+    pub show_mac: bool,
+    pub show_type: bool,
+    pub show_state: bool,
+    /// List the active interface in the top menu rather than a submenu.
+    pub active_inline: bool,
+    /// Router name and last update time.
+    pub show_router_info: bool,
+    /// Devices listed per router; the rest are left to the devices page.
+    pub max_devices: usize,
+}
+
+impl Default for MenuLayout {
+    fn default() -> Self {
+        Self {
+            show_interface: true,
+            show_wifi: true,
+            show_note: true,
+            show_ip: true,
+            show_mac: true,
+            show_type: true,
+            show_state: true,
+            active_inline: true,
+            show_router_info: true,
+            max_devices: 25,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ErrorEntry {
+    pub time: DateTime<Local>,
+    pub message: String,
+    /// Set for a router's refresh failure, which stops being current once
+    /// that router refreshes again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub router: Option<String>,
+}
+
+pub fn encode_mac(mac: &str) -> String {
+    mac.replace(':', "")
+}
+
+pub fn policy_label(
+    policy: Option<&str>,
+    deny: bool,
+    policies: &HashMap<String, PolicyInfo>,
+) -> String {
+    if deny {
+        return t("policy.blocked");
+    }
+    if policy.is_none() {
+        return t("policy.default");
+    }
+    let key = policy.unwrap_or_default();
+    if let Some(info) = policies.get(key) {
+        if let Some(desc) = &info.description {
+            return desc.clone();
+        }
+    }
+    key.to_string()
+}
+
+/// Everything the menu depends on, gathered once per refresh.
+#[derive(Clone, Copy)]
+pub struct TrayMenuModel<'a> {
+    pub states: &'a [ActiveState],
+    pub layout: &'a MenuLayout,
+    /// The user's notes on devices, by MAC.
+    pub device_notes: &'a HashMap<String, String>,
+    pub has_routers: bool,
+    pub last_error: Option<&'a ErrorEntry>,
+    /// "Updated 12:00:00", when the routers have been read at all.
+    pub updated: Option<&'a str>,
+    /// The version of a newer release, when there is one.
+    pub update: Option<&'a Version>,
+    pub crash_report: bool,
+}
+
+fn info_item(id: &str, title: &str) -> TrayItem {
+    TrayItem::new(id, title).disabled()
+}
+
+/// "Wi-Fi: Home (5 GHz, -54 dBm)", leaving out whatever the router didn't
+/// report.
+fn wifi_label(link: &WifiLink) -> String {
+    let ssid = link.ssid.clone().unwrap_or_else(|| "?".to_string());
+    let details: Vec<String> = link
+        .band
+        .clone()
+        .into_iter()
+        .chain(link.rssi.map(|rssi| format!("{rssi} dBm")))
+        .collect();
+    let network = if details.is_empty() {
+        ssid
+    } else {
+        format!("{ssid} ({})", details.join(", "))
+    };
+    tf("iface.wifi", &[("network", &network)])
+}
+
+fn append_interface_section(
+    mut menu: TrayMenu,
+    iface: &InterfaceInfo,
+    active: &ActiveState,
+    prefix: &str,
+    with_header: bool,
+    model: &TrayMenuModel,
+) -> TrayMenu {
+    let layout = model.layout;
+    let policies = &active.policies;
+    let router = active.router.name.as_str();
+    if with_header {
+        menu = menu.add_item(info_item(&format!("{prefix}:header"), &iface.display_name));
+    }
+    let mut rows = TrayMenu::new();
+    if layout.show_interface {
+        rows = rows.add_item(info_item(
+            &format!("{prefix}:iface"),
+            &tf("iface.interface", &[("name", &iface.name)]),
+        ));
+    }
+    if let Some(link) = iface.wifi.as_ref().filter(|_| layout.show_wifi) {
+        rows = rows.add_item(info_item(&format!("{prefix}:wifi"), &wifi_label(link)));
+    }
+    if let Some(note) = model
+        .device_notes
+        .get(&iface.mac)
+        .filter(|_| layout.show_note)
+    {
+        rows = rows.add_item(info_item(
+            &format!("{prefix}:note"),
+            &tf("iface.note", &[("note", note)]),
+        ));
+    }
+    let mac_encoded = encode_mac(&iface.mac);
+    if layout.show_ip {
+        let label = tf("iface.ip", &[("ip", &iface.ip)]);
+        rows = rows.add_item(if iface.ip == "N/A" {
+            info_item(&format!("{prefix}:ip"), &label)
+        } else {
+            TrayItem::new(format!("copy|{}|ip|{}", mac_encoded, iface.ip), label)
+        });
+    }
+    if layout.show_mac {
+        rows = rows.add_item(TrayItem::new(
+            format!("copy|{}|mac", mac_encoded),
+            tf("iface.mac", &[("mac", &iface.mac)]),
+        ));
+    }
+    if layout.show_type {
+        rows = rows.add_item(info_item(
+            &format!("{prefix}:type"),
+            &tf("iface.type", &[("type", &iface.iface_type)]),
+        ));
+    }
+    if layout.show_state {
+        let state = if iface.online {
+            t("iface.online")
+        } else {
+            t("iface.offline")
+        };
+        rows = rows.add_item(info_item(
+            &format!("{prefix}:state"),
+            &tf("iface.state", &[("state", &state)]),
+        ));
+    }
+    if !rows.entries().is_empty() {
+        menu = menu.append(rows).add_separator();
+    }
+
+    let current_label = policy_label(iface.policy.as_deref(), iface.deny, policies);
+    let default_name = t("policy.default");
+    let blocked_name = t("policy.blocked");
+    let default_label = if current_label == default_name {
+        format!("• {}", default_name)
+    } else {
+        default_name
+    };
+    let blocked_label = if current_label == blocked_name {
+        format!("• {}", blocked_name)
+    } else {
+        blocked_name
+    };
+
+    menu = menu.add_item(TrayItem::new(
+        format!("policy|{}|default||{}", mac_encoded, router),
+        default_label,
+    ));
+    menu = menu.add_item(TrayItem::new(
+        format!("policy|{}|blocked||{}", mac_encoded, router),
+        blocked_label,
+    ));
+
+    // By name, so the menu keeps its layout between refreshes.
+    let mut policies: Vec<_> = policies.iter().collect();
+    policies.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, info) in policies {
+        let label = info.description.as_deref().unwrap_or(name);
+        let title = if label == current_label {
+            format!("• {}", label)
+        } else {
+            label.to_string()
+        };
+        menu = menu.add_item(TrayItem::new(
+            format!("policy|{}|set|{}|{}", mac_encoded, name, router),
+            title,
+        ));
+    }
+
+    if iface.wifi.is_some() {
+        let prefer_5 = t("iface.prefer_5ghz");
+        let title = if active.band_locks.get(&iface.mac) == Some(&WifiBand::Ghz5) {
+            format!("• {}", prefer_5)
+        } else {
+            prefer_5
+        };
+        menu = menu.add_separator().add_item(TrayItem::new(
+            format!("band|{}|5|{}", mac_encoded, router),
+            title,
+        ));
+    }
+
+    menu
+}
+
+fn append_router_section(
+    mut menu: TrayMenu,
+    active: &ActiveState,
+    index: usize,
+    model: &TrayMenuModel,
+) -> TrayMenu {
+    if let Some(active_iface) = &active.active_iface {
+        let prefix = format!("r{}iface{}", index, encode_mac(&active_iface.mac));
+        if model.layout.active_inline {
+            menu = append_interface_section(menu, active_iface, active, &prefix, true, model);
+        } else {
+            let sub = append_interface_section(
+                TrayMenu::new(),
+                active_iface,
+                active,
+                &prefix,
+                false,
+                model,
+            );
+            menu = menu.add_submenu(active_iface.display_name.clone(), sub);
+        }
+        menu = menu.add_separator();
+    }
+
+    let mut others: Vec<&InterfaceInfo> = active
+        .interfaces
+        .iter()
+        .filter(|iface| {
+            active
+                .active_iface
+                .as_ref()
+                .is_none_or(|active_iface| iface.mac != active_iface.mac)
+        })
+        .collect();
+    let max_devices = model.layout.max_devices.max(1);
+    let overflow = others.len() > max_devices;
+    if overflow {
+        // Long menus are hard to scan and some platforms cut them off, so
+        // only online devices the user has named make the cut first.
+        others.sort_by_key(|iface| (!iface.online, !model.device_notes.contains_key(&iface.mac)));
+        others.truncate(max_devices);
+    }
+    for iface in others {
+        let prefix = format!("r{}iface{}", index, encode_mac(&iface.mac));
+        let sub = append_interface_section(TrayMenu::new(), iface, active, &prefix, false, model);
+        menu = menu.add_submenu(iface.display_name.clone(), sub);
+    }
+    if overflow {
+        menu = menu.add_item(TrayItem::new(
+            format!("r{index}:all_devices"),
+            tf(
+                "tray.show_all_devices",
+                &[("count", &active.interfaces.len().to_string())],
+            ),
+        ));
+    }
+    menu
+}
+
+fn router_title(active: &ActiveState) -> String {
+    if active.remote {
+        tf("tray.router_remote", &[("name", &active.router.name)])
+    } else if let Some(segment) = &active.segment {
+        tf(
+            "tray.router_segment",
+            &[("name", &active.router.name), ("segment", segment)],
+        )
+    } else {
+        tf("tray.router", &[("name", &active.router.name)])
+    }
+}
+
+fn append_error_section(mut menu: TrayMenu, last_error: Option<&ErrorEntry>) -> TrayMenu {
+    let Some(entry) = last_error else {
+        return menu;
+    };
+    let time = entry.time.format("%H:%M").to_string();
+    menu = menu.add_item(info_item(
+        "error:last",
+        &tf(
+            "tray.last_error",
+            &[("message", &entry.message), ("time", &time)],
+        ),
+    ));
+    menu.add_item(TrayItem::new("error_details", t("tray.error_details")))
+}
+
+pub fn build_tray_menu(model: &TrayMenuModel) -> TrayMenu {
+    let TrayMenuModel {
+        states,
+        layout,
+        has_routers,
+        last_error,
+        updated,
+        update,
+        crash_report,
+        ..
+    } = *model;
+    let update_item = update.map(|version| {
+        TrayItem::new(
+            "update",
+            tf(
+                "tray.update_available",
+                &[("version", &version.to_string())],
+            ),
+        )
+    });
+    let mut menu = TrayMenu::new();
+    if !has_routers {
+        menu = menu.add_item(info_item("info:no_routers", &t("tray.no_routers")));
+        menu = menu.add_separator();
+        menu = menu.add_item(TrayItem::new("add_router", t("tray.add_router")));
+        menu = menu.add_item(TrayItem::new("quit", t("tray.quit")));
+        return menu;
+    }
+
+    match states {
+        [] => {
+            menu = menu.add_item(info_item("info:none", &t("tray.no_available")));
+            menu = append_error_section(menu, last_error);
+            menu = menu.add_separator();
+            if let Some(item) = update_item {
+                menu = menu.add_item(item);
+            }
+            if crash_report {
+                menu = menu.add_item(TrayItem::new("crash_report", t("tray.crash_report")));
+            }
+            menu = menu.add_item(TrayItem::new("add_router", t("tray.add_router")));
+            menu = menu.add_item(TrayItem::new("settings", t("tray.settings")));
+            menu = menu.add_item(TrayItem::new("refresh", t("tray.refresh")));
+            menu = menu.add_item(TrayItem::new("quit", t("tray.quit")));
+            return menu;
+        }
+        [active] => {
+            menu = append_router_section(menu, active, 0, model);
+            if layout.show_router_info {
+                menu = menu.add_separator();
+                menu = menu.add_item(info_item("router:name", &router_title(active)));
+            }
+        }
+        _ => {
+            for (index, active) in states.iter().enumerate() {
+                let sub = append_router_section(TrayMenu::new(), active, index, model);
+                menu = menu.add_submenu(router_title(active), sub);
+            }
+        }
+    }
+
+    if let Some(updated) = updated.filter(|_| layout.show_router_info) {
+        menu = menu.add_item(info_item("info:updated", updated));
+    }
+    if last_error.is_some() {
+        menu = menu.add_separator();
+        menu = append_error_section(menu, last_error);
+    }
+    menu = menu.add_separator();
+    if let Some(item) = update_item {
+        menu = menu.add_item(item);
+    }
+    if crash_report {
+        menu = menu.add_item(TrayItem::new("crash_report", t("tray.crash_report")));
+    }
+    menu = menu.add_item(TrayItem::new("topology", t("tray.topology")));
+    menu = menu.add_item(TrayItem::new("traffic", t("tray.traffic")));
+    menu = menu.add_item(TrayItem::new("family", t("tray.family")));
+    menu = menu.add_item(TrayItem::new("guest", t("tray.guest")));
+    menu = menu.add_item(TrayItem::new("settings", t("tray.settings")));
+    menu = menu.add_item(TrayItem::new("log_folder", t("tray.log_folder")));
+    menu = menu.add_item(TrayItem::new("refresh", t("tray.refresh")));
+    menu = menu.add_item(TrayItem::new("quit", t("tray.quit")));
+    menu
+}
+
+pub fn build_locked_menu() -> TrayMenu {
+    TrayMenu::new()
+        .add_item(info_item("info:locked", &t("tray.locked")))
+        .add_separator()
+        .add_item(TrayItem::new("unlock", t("tray.unlock")))
+        .add_item(TrayItem::new("settings", t("tray.settings")))
+        .add_item(TrayItem::new("quit", t("tray.quit")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One line per entry: the title and id, with disabled items in
+    /// parentheses and submenu entries indented under `> title`.
+    fn outline(menu: &TrayMenu, nested: bool) -> Vec<String> {
+        let mut lines = Vec::new();
+        for entry in menu.entries() {
+            match entry {
+                Entry::Item(item) if item.enabled => {
+                    lines.push(format!("{} <{}>", item.title, item.id))
+                }
+                Entry::Item(item) => lines.push(format!("({}) <{}>", item.title, item.id)),
+                Entry::Separator => lines.push("---".to_string()),
+                Entry::Submenu(title, sub) => {
+                    lines.push(format!("> {title}"));
+                    if nested {
+                        lines.extend(outline(sub, true).into_iter().map(|l| format!("  {l}")));
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    fn router() -> RouterInfo {
+        serde_json::from_value(serde_json::json!({
+            "name": "Home",
+            "address": "http://192.168.1.1",
+            "login": "admin",
+        }))
+        .unwrap()
+    }
+
+    fn iface(display_name: &str, mac: &str, ip: &str) -> InterfaceInfo {
+        InterfaceInfo {
+            name: display_name.to_string(),
+            display_name: display_name.to_string(),
+            mac: mac.to_string(),
+            ip: ip.to_string(),
+            iface_type: "Ethernet".to_string(),
+            online: true,
+            policy: None,
+            deny: false,
+            wifi: None,
+        }
+    }
+
+    fn active_state(
+        interfaces: Vec<InterfaceInfo>,
+        active_iface: Option<InterfaceInfo>,
+    ) -> ActiveState {
+        let policies = [("Policy1", "Streaming"), ("Policy0", "VPN")]
+            .into_iter()
+            .map(|(name, description)| {
+                let info = PolicyInfo {
+                    description: Some(description.to_string()),
+                    connections: Vec::new(),
+                };
+                (name.to_string(), info)
+            })
+            .collect();
+        ActiveState {
+            router: router(),
+            online_clients: interfaces.iter().filter(|i| i.online).count(),
+            interfaces,
+            policies,
+            active_iface,
+            active_address: "http://192.168.1.1".to_string(),
+            remote: false,
+            segment: None,
+            band_locks: HashMap::new(),
+        }
+    }
+
+    fn model<'a>(
+        states: &'a [ActiveState],
+        layout: &'a MenuLayout,
+        device_notes: &'a HashMap<String, String>,
+    ) -> TrayMenuModel<'a> {
+        TrayMenuModel {
+            states,
+            layout,
+            device_notes,
+            has_routers: true,
+            last_error: None,
+            updated: None,
+            update: None,
+            crash_report: false,
+        }
+    }
+
+    #[test]
+    fn no_routers() {
+        let (layout, notes) = (MenuLayout::default(), HashMap::new());
+        let menu = build_tray_menu(&TrayMenuModel {
+            has_routers: false,
+            ..model(&[], &layout, &notes)
+        });
+        assert_eq!(
+            outline(&menu, true),
+            [
+                "(No routers configured.) <info:no_routers>",
+                "---",
+                "Add Router... <add_router>",
+                "Quit <quit>",
+            ]
+        );
+    }
+
+    #[test]
+    fn active_interface_blocked() {
+        let (layout, notes) = (MenuLayout::default(), HashMap::new());
+        let laptop = InterfaceInfo {
+            name: "en0".to_string(),
+            deny: true,
+            ..iface("MacBook", "aa:bb:cc:dd:ee:01", "192.168.1.20")
+        };
+        let phone = InterfaceInfo {
+            name: "WifiMaster0/AccessPoint0".to_string(),
+            iface_type: "Wi-Fi".to_string(),
+            policy: Some("Policy0".to_string()),
+            wifi: Some(WifiLink {
+                ssid: Some("Home".to_string()),
+                band: Some("5 GHz".to_string()),
+                rssi: Some(-54),
+            }),
+            ..iface("Phone", "aa:bb:cc:dd:ee:02", "192.168.1.30")
+        };
+        let states = [active_state(vec![laptop.clone(), phone], Some(laptop))];
+        let menu = build_tray_menu(&TrayMenuModel {
+            updated: Some("Updated 12:00:00"),
+            ..model(&states, &layout, &notes)
+        });
+        assert_eq!(
+            outline(&menu, true),
+            [
+                "(MacBook) <r0ifaceaabbccddee01:header>",
+                "(Interface: en0) <r0ifaceaabbccddee01:iface>",
+                "IP: 192.168.1.20 <copy|aabbccddee01|ip|192.168.1.20>",
+                "MAC: aa:bb:cc:dd:ee:01 <copy|aabbccddee01|mac>",
+                "(Type: Ethernet) <r0ifaceaabbccddee01:type>",
+                "(State: Online) <r0ifaceaabbccddee01:state>",
+                "---",
+                "Default <policy|aabbccddee01|default||Home>",
+                "• Blocked <policy|aabbccddee01|blocked||Home>",
+                "VPN <policy|aabbccddee01|set|Policy0|Home>",
+                "Streaming <policy|aabbccddee01|set|Policy1|Home>",
+                "---",
+                "> Phone",
+                "  (Interface: WifiMaster0/AccessPoint0) <r0ifaceaabbccddee02:iface>",
+                "  (Wi-Fi: Home (5 GHz, -54 dBm)) <r0ifaceaabbccddee02:wifi>",
+                "  IP: 192.168.1.30 <copy|aabbccddee02|ip|192.168.1.30>",
+                "  MAC: aa:bb:cc:dd:ee:02 <copy|aabbccddee02|mac>",
+                "  (Type: Wi-Fi) <r0ifaceaabbccddee02:type>",
+                "  (State: Online) <r0ifaceaabbccddee02:state>",
+                "  ---",
+                "  Default <policy|aabbccddee02|default||Home>",
+                "  Blocked <policy|aabbccddee02|blocked||Home>",
+                "  • VPN <policy|aabbccddee02|set|Policy0|Home>",
+                "  Streaming <policy|aabbccddee02|set|Policy1|Home>",
+                "  ---",
+                "  Prefer 5 GHz <band|aabbccddee02|5|Home>",
+                "---",
+                "(Router: Home) <router:name>",
+                "(Updated 12:00:00) <info:updated>",
+                "---",
+                "Network Map... <topology>",
+                "Bandwidth... <traffic>",
+                "Family... <family>",
+                "Guest Access... <guest>",
+                "Settings... <settings>",
+                "Open Log Folder <log_folder>",
+                "Refresh <refresh>",
+                "Quit <quit>",
+            ]
+        );
+    }
+
+    #[test]
+    fn fifty_clients_are_cut_to_online_ones() {
+        let (layout, notes) = (MenuLayout::default(), HashMap::new());
+        let devices = (0..50)
+            .map(|i| InterfaceInfo {
+                online: i % 2 == 0,
+                ..iface(
+                    &format!("Device {i:02}"),
+                    &format!("aa:bb:cc:dd:ee:{i:02x}"),
+                    &format!("192.168.1.{}", i + 100),
+                )
+            })
+            .collect();
+        let states = [active_state(devices, None)];
+        let menu = build_tray_menu(&model(&states, &layout, &notes));
+
+        let mut expected: Vec<String> = (0..50)
+            .step_by(2)
+            .map(|i| format!("> Device {i:02}"))
+            .collect();
+        expected.extend(
+            [
+                "Show all devices (50)… <r0:all_devices>",
+                "---",
+                "(Router: Home) <router:name>",
+                "---",
+                "Network Map... <topology>",
+                "Bandwidth... <traffic>",
+                "Family... <family>",
+                "Guest Access... <guest>",
+                "Settings... <settings>",
+                "Open Log Folder <log_folder>",
+                "Refresh <refresh>",
+                "Quit <quit>",
+            ]
+            .map(String::from),
+        );
+        assert_eq!(outline(&menu, false), expected);
+    }
+}
@@ -0,0 +1,95 @@
+//! Tray menu contents as plain data. Native menu items need an app handle to
+//! be created, so the menu is put together here first and turned into a
+//! native menu in one place, by the tray.
+
+#[derive(Clone)]
+pub struct TrayItem {
+    pub id: String,
+    pub title: String,
+    pub enabled: bool,
+}
+
+impl TrayItem {
+    pub fn new(id: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            enabled: true,
+        }
+    }
+
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+}
+
+#[derive(Clone)]
+pub enum Entry {
+    Item(TrayItem),
+    Separator,
+    Submenu(String, TrayMenu),
+}
+
+#[derive(Clone, Default)]
+pub struct TrayMenu {
+    entries: Vec<Entry>,
+}
+
+impl TrayMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_item(mut self, item: TrayItem) -> Self {
+        self.entries.push(Entry::Item(item));
+        self
+    }
+
+    pub fn add_separator(mut self) -> Self {
+        self.entries.push(Entry::Separator);
+        self
+    }
+
+    /// Adds every entry of `other` at the end.
+    pub fn append(mut self, other: TrayMenu) -> Self {
+        self.entries.extend(other.entries);
+        self
+    }
+
+    pub fn add_submenu(mut self, title: impl Into<String>, menu: TrayMenu) -> Self {
+        self.entries.push(Entry::Submenu(title.into(), menu));
+        self
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Renames the item with `id`, looking into submenus too.
+    pub fn set_title(&mut self, id: &str, title: &str) {
+        for entry in &mut self.entries {
+            match entry {
+                Entry::Item(item) if item.id == id => item.title = title.to_string(),
+                Entry::Submenu(_, menu) => menu.set_title(id, title),
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether `other` has the same items with the same ids in the same
+    /// places, so the native menu can be updated instead of rebuilt.
+    pub fn same_layout(&self, other: &TrayMenu) -> bool {
+        self.entries.len() == other.entries.len()
+            && self
+                .entries
+                .iter()
+                .zip(&other.entries)
+                .all(|pair| match pair {
+                    (Entry::Item(a), Entry::Item(b)) => a.id == b.id,
+                    (Entry::Separator, Entry::Separator) => true,
+                    (Entry::Submenu(_, a), Entry::Submenu(_, b)) => a.same_layout(b),
+                    _ => false,
+                })
+    }
+}
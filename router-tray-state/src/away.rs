@@ -0,0 +1,146 @@
+//! Away mode: once the user's own devices have been gone from the router for
+//! a while, chosen devices get other policies (block the TV, give the
+//! cameras a priority policy), and they get their old ones back on return.
+
+use crate::PolicyOverride;
+use router_model::ClientInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AwaySettings {
+    pub enabled: bool,
+    pub router: String,
+    /// MACs whose absence means nobody is home; empty uses this computer,
+    /// which is away when the router can only be reached remotely.
+    pub devices: Vec<String>,
+    /// How long the devices have to be gone before the changes apply.
+    pub after_minutes: u64,
+    pub changes: Vec<AwayChange>,
+}
+
+impl Default for AwaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            router: String::new(),
+            devices: Vec::new(),
+            after_minutes: 10,
+            changes: Vec::new(),
+        }
+    }
+}
+
+impl AwaySettings {
+    /// How long the devices have to be gone.
+    pub fn delay(&self) -> Duration {
+        Duration::from_secs(self.after_minutes * 60)
+    }
+
+    /// Whether nobody is home now. With devices set, that is when none of
+    /// them is online in `online`, the away router's clients by lowercase
+    /// MAC. Without, it is when this computer only reaches the router
+    /// remotely, as `remote` says. `None` when the router hasn't been seen.
+    pub fn is_away(
+        &self,
+        remote: Option<bool>,
+        online: Option<&HashMap<String, bool>>,
+    ) -> Option<bool> {
+        if !self.enabled {
+            return Some(false);
+        }
+        if self.devices.is_empty() {
+            return remote;
+        }
+        let online = online?;
+        Some(
+            !self
+                .devices
+                .iter()
+                .any(|mac| online.get(&mac.to_lowercase()).copied().unwrap_or(false)),
+        )
+    }
+
+    /// The policy each changed device gets, with what it has now in
+    /// `clients` to put back on return.
+    pub fn plan(&self, clients: &[ClientInfo]) -> Vec<(PolicyOverride, Saved)> {
+        self.changes
+            .iter()
+            .map(|change| {
+                let mac = change.mac.to_lowercase();
+                let current = clients.iter().find(|c| c.mac == mac);
+                let target = PolicyOverride {
+                    policy: change.policy.clone(),
+                    deny: change.block,
+                };
+                let saved = Saved {
+                    router: self.router.clone(),
+                    mac,
+                    policy: current.and_then(|c| c.policy.clone()),
+                    deny: current.is_some_and(|c| c.deny),
+                };
+                (target, saved)
+            })
+            .collect()
+    }
+}
+
+/// What one device gets while away; `None` is the router default policy.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AwayChange {
+    pub mac: String,
+    #[serde(default)]
+    pub policy: Option<String>,
+    #[serde(default)]
+    pub block: bool,
+}
+
+/// A device changed by away mode, with the policy to put back.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Saved {
+    pub router: String,
+    pub mac: String,
+    pub policy: Option<String>,
+    pub deny: bool,
+}
+
+impl Saved {
+    /// What the device gets back on return.
+    pub fn restore(&self) -> PolicyOverride {
+        PolicyOverride {
+            policy: self.policy.clone(),
+            deny: self.deny,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct AwayTracker {
+    leaving_since: Option<Instant>,
+}
+
+impl AwayTracker {
+    /// Takes whether away mode is in effect and whether the devices are gone
+    /// now, and returns the mode to switch to. Leaving has to last `delay`;
+    /// coming back switches at once.
+    pub fn update(
+        &mut self,
+        active: bool,
+        away: bool,
+        delay: Duration,
+        now: Instant,
+    ) -> Option<bool> {
+        if active == away || !away {
+            self.leaving_since = None;
+            return (active != away).then_some(away);
+        }
+        let since = *self.leaving_since.get_or_insert(now);
+        if now.duration_since(since) < delay {
+            return None;
+        }
+        self.leaving_since = None;
+        Some(true)
+    }
+}
@@ -0,0 +1,78 @@
+//! Guest access vouchers: the guest Wi-Fi comes up with a fresh passphrase,
+//! goes down again after a few hours, and is shared as a QR code phones can
+//! join from.
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use chrono::{DateTime, Local};
+use qrcodegen::{QrCode, QrCodeEcc};
+use serde::{Deserialize, Serialize};
+
+/// Letters and digits that can't be mistaken for one another when read out.
+const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const PASSPHRASE_LEN: usize = 12;
+/// Light modules around the code, as the QR spec asks for.
+const QUIET_ZONE: i32 = 4;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Voucher {
+    pub router: String,
+    pub ssid: String,
+    pub passphrase: String,
+    pub expires: DateTime<Local>,
+}
+
+impl Voucher {
+    /// The `WIFI:` payload phone cameras offer to join.
+    pub fn wifi_uri(&self) -> String {
+        format!(
+            "WIFI:T:WPA;S:{};P:{};;",
+            escape(&self.ssid),
+            escape(&self.passphrase)
+        )
+    }
+}
+
+/// The vouchers in `vouchers` that have run out by `now`.
+pub fn expired(vouchers: &[Voucher], now: DateTime<Local>) -> Vec<Voucher> {
+    vouchers
+        .iter()
+        .filter(|voucher| voucher.expires <= now)
+        .cloned()
+        .collect()
+}
+
+pub fn random_passphrase() -> String {
+    (0..PASSPHRASE_LEN)
+        .map(|_| ALPHABET[OsRng.next_u32() as usize % ALPHABET.len()] as char)
+        .collect()
+}
+
+/// `text` as a QR code in SVG.
+pub fn qr_svg(text: &str) -> Option<String> {
+    let qr = QrCode::encode_text(text, QrCodeEcc::Medium).ok()?;
+    let size = qr.size() + QUIET_ZONE * 2;
+    let mut path = String::new();
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if qr.get_module(x, y) {
+                path.push_str(&format!("M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE));
+            }
+        }
+    }
+    Some(format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {size} {size}" shape-rendering="crispEdges"><rect width="{size}" height="{size}" fill="#fff"/><path d="{path}" fill="#000"/></svg>"##
+    ))
+}
+
+/// Backslashes the characters that delimit fields in a `WIFI:` payload.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,10 @@
+//! The tray's state changes that need neither a window nor a router:
+//! pausing every client, away mode and guest vouchers. They live outside
+//! the GTK-linked tray so they can be tested on their own; the tray does
+//! the router calls and keeps the results.
+
+pub mod away;
+pub mod guest;
+pub mod pause;
+
+pub use pause::PolicyOverride;
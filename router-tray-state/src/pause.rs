@@ -0,0 +1,108 @@
+//! Pausing the internet for every client at once, and putting each one's
+//! policy back afterwards.
+
+use router_model::InterfaceInfo;
+use router_tray_menu::i18n::tf;
+use router_tray_menu::ActiveState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// A policy and block state for a client; `None` is the router default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PolicyOverride {
+    pub policy: Option<String>,
+    pub deny: bool,
+}
+
+impl PolicyOverride {
+    /// What a paused client gets.
+    pub fn paused() -> Self {
+        Self {
+            policy: None,
+            deny: true,
+        }
+    }
+
+    /// What `iface` gets back when it is resumed: its policy, unblocked.
+    pub fn resumed(iface: &InterfaceInfo) -> Self {
+        Self {
+            policy: iface.policy.clone(),
+            deny: false,
+        }
+    }
+}
+
+/// The clients pausing blocks: every one that isn't blocked already, except
+/// the interface this computer is connected through.
+pub fn to_pause(states: &[ActiveState]) -> Vec<(&ActiveState, &InterfaceInfo)> {
+    let mut clients = Vec::new();
+    for active in states {
+        let own = active.active_iface.as_ref().map(|iface| iface.mac.as_str());
+        for iface in &active.interfaces {
+            if !iface.deny && Some(iface.mac.as_str()) != own {
+                clients.push((active, iface));
+            }
+        }
+    }
+    clients
+}
+
+/// The paused clients that can be put back now, by MAC, each with the router
+/// that has it. Clients on routers that aren't reachable stay paused.
+pub fn to_resume<'a>(
+    paused: &'a HashMap<String, PolicyOverride>,
+    states: &'a [ActiveState],
+) -> Vec<(&'a ActiveState, &'a str, &'a PolicyOverride)> {
+    let mut clients: Vec<_> = paused
+        .iter()
+        .filter_map(|(mac, previous)| {
+            let active = states
+                .iter()
+                .find(|active| active.interfaces.iter().any(|iface| &iface.mac == mac))?;
+            Some((active, mac.as_str(), previous))
+        })
+        .collect();
+    clients.sort_by_key(|(_, mac, _)| *mac);
+    clients
+}
+
+/// What pausing or resuming did: how many clients it changed, and the ones
+/// it couldn't with the reason. One failure doesn't stop the rest.
+#[derive(Debug, Default)]
+pub struct BulkOutcome {
+    pub done: usize,
+    pub failed: Vec<String>,
+}
+
+impl BulkOutcome {
+    /// Counts the `result` of changing the client called `name`, and
+    /// returns whether it worked.
+    pub fn record<E: Display>(&mut self, name: &str, result: Result<(), E>) -> bool {
+        match result {
+            Ok(()) => {
+                self.done += 1;
+                true
+            }
+            Err(err) => {
+                log::warn!("{name}: {err:#}");
+                self.failed.push(format!("{name} ({err})"));
+                false
+            }
+        }
+    }
+
+    /// `done_key` when every client was changed, otherwise an error saying
+    /// how many were and which were not.
+    pub fn report(self, done_key: &str, partial_key: &str) -> Result<String, String> {
+        let count = self.done.to_string();
+        if self.failed.is_empty() {
+            Ok(tf(done_key, &[("count", &count)]))
+        } else {
+            Err(tf(
+                partial_key,
+                &[("count", &count), ("failed", &self.failed.join(", "))],
+            ))
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! When away mode switches, and what it changes and puts back.
+
+use router_model::ClientInfo;
+use router_tray_state::away::{AwayChange, AwaySettings, AwayTracker};
+use router_tray_state::PolicyOverride;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const DELAY: Duration = Duration::from_secs(600);
+
+fn settings(devices: &[&str]) -> AwaySettings {
+    AwaySettings {
+        enabled: true,
+        router: "Home".into(),
+        devices: devices.iter().map(|mac| mac.to_string()).collect(),
+        ..AwaySettings::default()
+    }
+}
+
+#[test]
+fn leaving_has_to_last_the_delay() {
+    let mut tracker = AwayTracker::default();
+    let start = Instant::now();
+    assert_eq!(tracker.update(false, true, DELAY, start), None);
+    assert_eq!(tracker.update(false, true, DELAY, start + DELAY / 2), None);
+    assert_eq!(
+        tracker.update(false, true, DELAY, start + DELAY),
+        Some(true)
+    );
+}
+
+#[test]
+fn coming_back_switches_at_once() {
+    let mut tracker = AwayTracker::default();
+    assert_eq!(
+        tracker.update(true, false, DELAY, Instant::now()),
+        Some(false)
+    );
+}
+
+#[test]
+fn a_short_absence_starts_the_wait_over() {
+    let mut tracker = AwayTracker::default();
+    let start = Instant::now();
+    tracker.update(false, true, DELAY, start);
+    // Back before the delay ran out.
+    assert_eq!(tracker.update(false, false, DELAY, start + DELAY / 2), None);
+    assert_eq!(tracker.update(false, true, DELAY, start + DELAY), None);
+    assert_eq!(
+        tracker.update(false, true, DELAY, start + DELAY * 2),
+        Some(true)
+    );
+}
+
+#[test]
+fn away_when_none_of_the_devices_is_online() {
+    let settings = settings(&["AA:00:00:00:00:01", "aa:00:00:00:00:02"]);
+    let mut online = HashMap::from([
+        ("aa:00:00:00:00:01".to_string(), false),
+        ("aa:00:00:00:00:02".to_string(), false),
+    ]);
+    assert_eq!(settings.is_away(None, Some(&online)), Some(true));
+    online.insert("aa:00:00:00:00:01".into(), true);
+    assert_eq!(settings.is_away(None, Some(&online)), Some(false));
+    // The router wasn't reached.
+    assert_eq!(settings.is_away(None, None), None);
+}
+
+#[test]
+fn without_devices_this_computer_being_remote_is_away() {
+    let settings = settings(&[]);
+    assert_eq!(settings.is_away(Some(true), None), Some(true));
+    assert_eq!(settings.is_away(Some(false), None), Some(false));
+    assert_eq!(settings.is_away(None, None), None);
+}
+
+#[test]
+fn disabled_is_never_away() {
+    let settings = AwaySettings::default();
+    assert_eq!(settings.is_away(Some(true), None), Some(false));
+}
+
+#[test]
+fn plan_saves_what_each_device_had() {
+    let mut settings = settings(&[]);
+    settings.changes = vec![
+        AwayChange {
+            mac: "AA:00:00:00:00:01".into(),
+            policy: None,
+            block: true,
+        },
+        AwayChange {
+            mac: "aa:00:00:00:00:02".into(),
+            policy: Some("Cameras".into()),
+            block: false,
+        },
+    ];
+    let clients = [ClientInfo {
+        name: Some("TV".into()),
+        ip: None,
+        mac: "aa:00:00:00:00:01".into(),
+        policy: Some("VPN".into()),
+        deny: false,
+        raw: serde_json::Value::Null,
+    }];
+    let plan = settings.plan(&clients);
+    assert_eq!(plan.len(), 2);
+
+    let (target, saved) = &plan[0];
+    assert_eq!(target, &PolicyOverride::paused());
+    assert_eq!(saved.router, "Home");
+    assert_eq!(saved.mac, "aa:00:00:00:00:01");
+    assert_eq!(
+        saved.restore(),
+        PolicyOverride {
+            policy: Some("VPN".into()),
+            deny: false,
+        }
+    );
+
+    // Not on the router now: it goes back to the default on return.
+    let (target, saved) = &plan[1];
+    assert_eq!(target.policy.as_deref(), Some("Cameras"));
+    assert_eq!(
+        saved.restore(),
+        PolicyOverride {
+            policy: None,
+            deny: false,
+        }
+    );
+}
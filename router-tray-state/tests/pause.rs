@@ -0,0 +1,137 @@
+//! Which clients pausing blocks and resuming puts back.
+
+use router_model::{InterfaceInfo, RouterInfo};
+use router_tray_menu::ActiveState;
+use router_tray_state::pause::{to_pause, to_resume, BulkOutcome};
+use router_tray_state::PolicyOverride;
+use std::collections::HashMap;
+
+fn iface(mac: &str, policy: Option<&str>, deny: bool) -> InterfaceInfo {
+    InterfaceInfo {
+        name: mac.into(),
+        display_name: mac.into(),
+        mac: mac.into(),
+        ip: String::new(),
+        iface_type: "wifi".into(),
+        online: true,
+        policy: policy.map(str::to_string),
+        deny,
+        wifi: None,
+    }
+}
+
+fn router(name: &str, interfaces: Vec<InterfaceInfo>, own: Option<&str>) -> ActiveState {
+    ActiveState {
+        router: RouterInfo {
+            name: name.into(),
+            address: "192.168.1.1".into(),
+            login: "admin".into(),
+            network_ip: None,
+            keendns_urls: None,
+            allow_remote: false,
+            refresh_interval_secs: None,
+            primary: false,
+        },
+        active_iface: own.and_then(|mac| interfaces.iter().find(|i| i.mac == mac).cloned()),
+        interfaces,
+        policies: HashMap::new(),
+        active_address: "192.168.1.1".into(),
+        remote: false,
+        segment: None,
+        online_clients: 0,
+        band_locks: HashMap::new(),
+    }
+}
+
+#[test]
+fn pausing_skips_this_computer_and_blocked_clients() {
+    let states = [router(
+        "Home",
+        vec![
+            iface("aa:00:00:00:00:01", Some("VPN"), false),
+            iface("aa:00:00:00:00:02", None, true),
+            iface("aa:00:00:00:00:03", None, false),
+        ],
+        Some("aa:00:00:00:00:03"),
+    )];
+    let macs: Vec<&str> = to_pause(&states)
+        .into_iter()
+        .map(|(_, iface)| iface.mac.as_str())
+        .collect();
+    assert_eq!(macs, ["aa:00:00:00:00:01"]);
+}
+
+#[test]
+fn pause_and_resume_round_trip_the_policy() {
+    let tv = iface("aa:00:00:00:00:01", Some("VPN"), false);
+    let previous = PolicyOverride::resumed(&tv);
+    assert_eq!(
+        previous,
+        PolicyOverride {
+            policy: Some("VPN".into()),
+            deny: false,
+        }
+    );
+    assert!(PolicyOverride::paused().deny);
+
+    let mut blocked = tv.clone();
+    blocked.deny = true;
+    let states = [router("Home", vec![blocked], None)];
+    let paused = HashMap::from([(tv.mac.clone(), previous.clone())]);
+    let resume = to_resume(&paused, &states);
+    assert_eq!(resume.len(), 1);
+    assert_eq!(resume[0].0.router.name, "Home");
+    assert_eq!(resume[0].1, tv.mac);
+    assert_eq!(resume[0].2, &previous);
+}
+
+#[test]
+fn clients_on_unreachable_routers_stay_paused() {
+    let states = [router(
+        "Home",
+        vec![iface("aa:00:00:00:00:01", None, true)],
+        None,
+    )];
+    let paused = HashMap::from([
+        (
+            "aa:00:00:00:00:01".to_string(),
+            PolicyOverride {
+                policy: None,
+                deny: false,
+            },
+        ),
+        (
+            "bb:00:00:00:00:01".to_string(),
+            PolicyOverride {
+                policy: Some("Kids".into()),
+                deny: false,
+            },
+        ),
+    ]);
+    let macs: Vec<&str> = to_resume(&paused, &states)
+        .into_iter()
+        .map(|(_, mac, _)| mac)
+        .collect();
+    assert_eq!(macs, ["aa:00:00:00:00:01"]);
+}
+
+#[test]
+fn failures_are_counted_and_reported_with_the_rest() {
+    router_tray_menu::i18n::set_language("en");
+    let mut outcome = BulkOutcome::default();
+    assert!(outcome.record::<String>("TV", Ok(())));
+    assert!(!outcome.record("Laptop", Err("timed out")));
+    assert!(outcome.record::<String>("Phone", Ok(())));
+    assert_eq!(outcome.done, 2);
+    assert_eq!(
+        outcome.report("automation.paused_all", "automation.paused_some"),
+        Err("Paused 2 clients; failed: Laptop (timed out)".to_string())
+    );
+
+    let mut outcome = BulkOutcome::default();
+    outcome.record::<String>("TV", Ok(()));
+    assert_eq!(
+        outcome.report("automation.paused_all", "automation.paused_some"),
+        Ok("Paused 1 clients".to_string())
+    );
+}
@@ -0,0 +1,45 @@
+//! Guest vouchers running out, and the Wi-Fi payload they are shared as.
+
+use chrono::{Duration, Local};
+use router_tray_state::guest::{expired, Voucher};
+
+fn voucher(router: &str, hours: i64) -> Voucher {
+    Voucher {
+        router: router.into(),
+        ssid: "Guests".into(),
+        passphrase: "abc".into(),
+        expires: Local::now() + Duration::hours(hours),
+    }
+}
+
+#[test]
+fn only_vouchers_past_their_time_expire() {
+    let vouchers = [voucher("Home", -1), voucher("Office", 2)];
+    let now = Local::now();
+    let gone: Vec<String> = expired(&vouchers, now)
+        .into_iter()
+        .map(|voucher| voucher.router)
+        .collect();
+    assert_eq!(gone, ["Home"]);
+    assert_eq!(expired(&vouchers, now + Duration::hours(3)).len(), 2);
+}
+
+#[test]
+fn a_voucher_expires_at_its_exact_time() {
+    let voucher = voucher("Home", 1);
+    assert_eq!(
+        expired(std::slice::from_ref(&voucher), voucher.expires).len(),
+        1
+    );
+}
+
+#[test]
+fn wifi_uri_escapes_delimiters() {
+    let voucher = Voucher {
+        router: "Home".into(),
+        ssid: "Cafe;Guests".into(),
+        passphrase: "a:b\\c".into(),
+        expires: Local::now(),
+    };
+    assert_eq!(voucher.wifi_uri(), r"WIFI:T:WPA;S:Cafe\;Guests;P:a\:b\\c;;");
+}
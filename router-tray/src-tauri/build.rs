@@ -1,3 +1,12 @@
 fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        // A bundled protoc, so building with gRPC needs nothing installed.
+        std::env::set_var(
+            "PROTOC",
+            protoc_bin_vendored::protoc_bin_path().expect("no bundled protoc for this platform"),
+        );
+        tonic_build::compile_protos("proto/router.proto").expect("failed to compile router.proto");
+    }
     tauri_build::build()
 }
@@ -0,0 +1,76 @@
+use crate::traffic::TrafficDelta;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Fires when a device (or, without a MAC, all clients together) stays above
+/// `mbps` for `minutes`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BandwidthAlert {
+    #[serde(default)]
+    pub mac: Option<String>,
+    pub mbps: f64,
+    pub minutes: u64,
+}
+
+pub struct Triggered {
+    pub alert: BandwidthAlert,
+    /// MAC of the device responsible: the alert's own, or the biggest
+    /// consumer for a total alert.
+    pub offender: Option<String>,
+    pub bps: f64,
+}
+
+/// Tracks how long each alert has been over its threshold. An alert fires
+/// once per excursion and re-arms after the rate drops back.
+#[derive(Default)]
+pub struct AlertTracker {
+    over_since: HashMap<usize, Instant>,
+    fired: HashSet<usize>,
+}
+
+impl AlertTracker {
+    pub fn check(&mut self, alerts: &[BandwidthAlert], deltas: &[TrafficDelta]) -> Vec<Triggered> {
+        let now = Instant::now();
+        let mut rates: HashMap<&str, f64> = HashMap::new();
+        for delta in deltas {
+            *rates.entry(delta.mac.as_str()).or_default() += delta.bps();
+        }
+        let top = rates
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(mac, _)| mac.to_string());
+        let mut out = Vec::new();
+        for (index, alert) in alerts.iter().enumerate() {
+            let (bps, offender) = match &alert.mac {
+                Some(mac) => (
+                    rates.get(mac.as_str()).copied().unwrap_or(0.0),
+                    Some(mac.clone()),
+                ),
+                None => (rates.values().sum(), top.clone()),
+            };
+            if bps <= alert.mbps * 1_000_000.0 {
+                self.over_since.remove(&index);
+                self.fired.remove(&index);
+                continue;
+            }
+            let since = *self.over_since.entry(index).or_insert(now);
+            let sustained = now.duration_since(since) >= Duration::from_secs(alert.minutes * 60);
+            if sustained && self.fired.insert(index) {
+                out.push(Triggered {
+                    alert: alert.clone(),
+                    offender,
+                    bps,
+                });
+            }
+        }
+        out
+    }
+
+    /// Forgets progress, e.g. after the alert list was edited and indices
+    /// no longer line up.
+    pub fn reset(&mut self) {
+        self.over_since.clear();
+        self.fired.clear();
+    }
+}
@@ -0,0 +1,176 @@
+//! Local REST API for scripts and other tools. It is off by default, listens
+//! on localhost unless told otherwise, and every request needs the bearer
+//! token shown in settings.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Name of the token in the secret store.
+pub const TOKEN_KEY: &str = "api:token";
+/// Largest request body read, which is plenty for a policy change.
+const MAX_BODY: u64 = 16 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Listen on every interface instead of only localhost.
+    pub listen_all: bool,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9878,
+            listen_all: false,
+        }
+    }
+}
+
+/// A client as listed by `GET /clients`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Client {
+    pub router: String,
+    pub mac: String,
+    pub name: String,
+    pub ip: String,
+    pub online: bool,
+    pub policy: Option<String>,
+    pub blocked: bool,
+}
+
+/// Body of `POST /clients/{mac}/policy`.
+#[derive(Debug, Deserialize)]
+pub struct PolicyRequest {
+    /// Needed only when the MAC is known to several routers.
+    #[serde(default)]
+    pub router: Option<String>,
+    #[serde(default)]
+    pub policy: Option<String>,
+    #[serde(default)]
+    pub blocked: bool,
+}
+
+pub enum Route {
+    State,
+    Clients,
+    SetPolicy { mac: String, request: PolicyRequest },
+}
+
+/// An error response: status code and message.
+pub type ApiError = (u16, String);
+
+struct Running {
+    settings: ApiSettings,
+    token: String,
+    server: Arc<Server>,
+}
+
+static SERVER: Mutex<Option<Running>> = Mutex::new(None);
+
+/// Serves the API with `settings`, replacing the running server. Does
+/// nothing when it already runs with the same settings and token.
+pub fn start(
+    settings: &ApiSettings,
+    token: &str,
+    handle: impl Fn(Route) -> Result<Value, ApiError> + Send + 'static,
+) {
+    let mut running = SERVER.lock().unwrap();
+    if running
+        .as_ref()
+        .is_some_and(|current| &current.settings == settings && current.token == token)
+    {
+        return;
+    }
+    if let Some(old) = running.take() {
+        old.server.unblock();
+    }
+    if !settings.enabled || token.is_empty() {
+        return;
+    }
+    let host = if settings.listen_all {
+        "0.0.0.0"
+    } else {
+        "127.0.0.1"
+    };
+    let Ok(content_type) = Header::from_bytes("Content-Type", "application/json") else {
+        return;
+    };
+    let server = match Server::http((host, settings.port)) {
+        Ok(server) => Arc::new(server),
+        Err(err) => {
+            log::warn!("Failed to start the REST API: {err}");
+            return;
+        }
+    };
+    *running = Some(Running {
+        settings: settings.clone(),
+        token: token.to_string(),
+        server: server.clone(),
+    });
+    let token = token.to_string();
+    std::thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let result = if authorized(&request, &token) {
+                route(&mut request).and_then(&handle)
+            } else {
+                Err((401, "missing or wrong bearer token".to_string()))
+            };
+            let (status, body) = match result {
+                Ok(body) => (200, body),
+                Err((status, message)) => (status, json!({ "error": message })),
+            };
+            let response = Response::from_string(body.to_string())
+                .with_status_code(status)
+                .with_header(content_type.clone());
+            let _ = request.respond(response);
+        }
+    });
+}
+
+fn authorized(request: &Request, token: &str) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|given| constant_time_eq(given.trim().as_bytes(), token.as_bytes()))
+}
+
+/// Compares without stopping at the first difference, so response times
+/// don't give the token away.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn route(request: &mut Request) -> Result<Route, ApiError> {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (request.method(), segments.as_slice()) {
+        (Method::Get, ["state"]) => Ok(Route::State),
+        (Method::Get, ["clients"]) => Ok(Route::Clients),
+        (Method::Post, ["clients", mac, "policy"]) => {
+            // Accept aa-bb-… and URL-encoded colons too.
+            let mac = mac.to_lowercase().replace("%3a", ":").replace('-', ":");
+            let mut body = String::new();
+            request
+                .as_reader()
+                .take(MAX_BODY)
+                .read_to_string(&mut body)
+                .map_err(|err| (400, err.to_string()))?;
+            let request = serde_json::from_str(&body).map_err(|err| (400, err.to_string()))?;
+            Ok(Route::SetPolicy { mac, request })
+        }
+        _ => Err((404, "not found".to_string())),
+    }
+}
@@ -0,0 +1,66 @@
+//! "Arrived home / left home" alerts for devices the user picked. A device
+//! has to stay on its new side for the debounce time before it counts, so a
+//! phone dropping off Wi-Fi for a minute isn't reported as gone.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WatchedDevice {
+    pub mac: String,
+    /// Used in the alert instead of the name the router knows it by.
+    #[serde(default)]
+    pub label: String,
+}
+
+struct Seen {
+    online: bool,
+    /// When the device was first seen on the other side.
+    changed_at: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct ArrivalTracker {
+    seen: HashMap<String, Seen>,
+}
+
+impl ArrivalTracker {
+    /// Takes whether each watched device is online now and returns the ones
+    /// whose change has held for `debounce`, with their new state. The first
+    /// sample of a device only records where it is.
+    pub fn update(
+        &mut self,
+        devices: &[(String, bool)],
+        debounce: Duration,
+        now: Instant,
+    ) -> Vec<(String, bool)> {
+        let mut changed = Vec::new();
+        for (mac, online) in devices {
+            let Some(seen) = self.seen.get_mut(mac) else {
+                self.seen.insert(
+                    mac.clone(),
+                    Seen {
+                        online: *online,
+                        changed_at: None,
+                    },
+                );
+                continue;
+            };
+            if seen.online == *online {
+                seen.changed_at = None;
+                continue;
+            }
+            let since = *seen.changed_at.get_or_insert(now);
+            if now.duration_since(since) >= debounce {
+                seen.online = *online;
+                seen.changed_at = None;
+                changed.push((mac.clone(), *online));
+            }
+        }
+        // Devices no longer watched start over if they're added back.
+        self.seen
+            .retain(|mac, _| devices.iter().any(|(watched, _)| watched == mac));
+        changed
+    }
+}
@@ -0,0 +1,194 @@
+//! `routertray://` links, so bookmarks, Stream Deck "open URL" buttons,
+//! Shortcuts ("Open X-Callback URL") and AppleScript (`open location`) can
+//! drive the tray:
+//!
+//! ```text
+//! routertray://status
+//! routertray://block?device=TV
+//! routertray://unblock?mac=aa:bb:cc:dd:ee:ff
+//! routertray://policy?mac=aa:bb:cc:dd:ee:ff&set=VPN
+//! routertray://pause-all
+//! routertray://resume-all
+//! routertray://x-callback-url/status?x-success=shortcuts://x-callback-url/...
+//! ```
+//!
+//! `device` (or `mac`) is a client's name or MAC, `router` picks one when
+//! several know it and a missing `set` means the router's default policy.
+//! Results go to `x-success` as `result`, failures to `x-error` as
+//! `errorMessage`.
+
+use tauri::Url;
+
+pub const SCHEME: &str = "routertray";
+
+pub enum Action {
+    Status,
+    Policy {
+        device: String,
+        router: Option<String>,
+        policy: Option<String>,
+    },
+    Block {
+        device: String,
+        router: Option<String>,
+        blocked: bool,
+    },
+    PauseAll,
+    ResumeAll,
+}
+
+pub struct Request {
+    pub action: Action,
+    success: Option<Url>,
+    error: Option<Url>,
+}
+
+impl Request {
+    pub fn parse(url: &Url) -> Result<Self, String> {
+        if url.scheme() != SCHEME {
+            return Err(format!("unsupported link: {url}"));
+        }
+        let param = |names: &[&str]| {
+            url.query_pairs()
+                .find(|(key, _)| names.contains(&key.as_ref()))
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        // Both routertray://status and routertray://x-callback-url/status.
+        let name = match url.host_str() {
+            Some("x-callback-url") => url.path().trim_matches('/').to_string(),
+            host => host.unwrap_or_default().to_string(),
+        };
+        let device = || param(&["device", "mac"]).ok_or_else(|| "missing device".to_string());
+        let action = match name.to_lowercase().as_str() {
+            "status" => Action::Status,
+            "policy" => Action::Policy {
+                device: device()?,
+                router: param(&["router"]),
+                policy: param(&["set", "policy"]),
+            },
+            "block" | "unblock" => Action::Block {
+                device: device()?,
+                router: param(&["router"]),
+                blocked: name.eq_ignore_ascii_case("block"),
+            },
+            "pause-all" => Action::PauseAll,
+            "resume-all" => Action::ResumeAll,
+            other => return Err(format!("unknown action: {other}")),
+        };
+        Ok(Self {
+            action,
+            success: param(&["x-success"]).and_then(|url| Url::parse(&url).ok()),
+            error: param(&["x-error"]).and_then(|url| Url::parse(&url).ok()),
+        })
+    }
+
+    /// The callback to open with the outcome, if the caller asked for one.
+    pub fn callback(&self, outcome: &Result<String, String>) -> Option<Url> {
+        let (url, key, value) = match outcome {
+            Ok(result) => (self.success.as_ref()?, "result", result),
+            Err(err) => (self.error.as_ref()?, "errorMessage", err),
+        };
+        let mut url = url.clone();
+        url.query_pairs_mut().append_pair(key, value);
+        Some(url)
+    }
+}
+
+/// The links among command-line arguments. Windows and Linux start the app
+/// with the link as an argument, which the single-instance plugin then
+/// hands to the running tray.
+pub fn links_in(args: &[String]) -> Vec<Url> {
+    args.iter()
+        .filter(|arg| arg.starts_with(&format!("{SCHEME}:")))
+        .filter_map(|arg| Url::parse(arg).ok())
+        .collect()
+}
+
+/// Makes the app the handler for `routertray://` links. macOS reads the
+/// scheme from Info.plist instead.
+pub fn register() {
+    if let Err(err) = platform::register() {
+        log::warn!("Failed to register the {SCHEME}:// handler: {err}");
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    pub fn register() -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::SCHEME;
+    use std::io;
+    use std::process::Command;
+
+    pub fn register() -> io::Result<()> {
+        let exe = std::env::current_exe()?;
+        let key = format!(r"HKCU\Software\Classes\{SCHEME}");
+        let command = format!("\"{}\" \"%1\"", exe.display());
+        let entries = [
+            (key.clone(), None, format!("URL:{SCHEME}")),
+            (key.clone(), Some("URL Protocol"), String::new()),
+            (format!(r"{key}\shell\open\command"), None, command),
+        ];
+        for (key, name, value) in entries {
+            let mut reg = Command::new("reg");
+            reg.args(["add", &key, "/f", "/d", &value]);
+            match name {
+                Some(name) => reg.args(["/v", name]),
+                None => reg.arg("/ve"),
+            };
+            let status = reg.output()?.status;
+            if !status.success() {
+                return Err(io::Error::other(format!("reg add {key} failed: {status}")));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::SCHEME;
+    use std::io;
+    use std::process::Command;
+
+    const DESKTOP_FILE: &str = "routertray-handler.desktop";
+
+    pub fn register() -> io::Result<()> {
+        let exe = std::env::current_exe()?;
+        let dirs =
+            directories::BaseDirs::new().ok_or_else(|| io::Error::other("no home directory"))?;
+        let applications = dirs.data_dir().join("applications");
+        std::fs::create_dir_all(&applications)?;
+        let entry = format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name=Keenetic Tray\n\
+             Exec=\"{}\" %u\n\
+             NoDisplay=true\n\
+             MimeType=x-scheme-handler/{SCHEME};\n",
+            exe.display()
+        );
+        let path = applications.join(DESKTOP_FILE);
+        if std::fs::read_to_string(&path).ok().as_deref() == Some(entry.as_str()) {
+            return Ok(());
+        }
+        std::fs::write(&path, entry)?;
+        let status = Command::new("xdg-mime")
+            .args([
+                "default",
+                DESKTOP_FILE,
+                &format!("x-scheme-handler/{SCHEME}"),
+            ])
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("xdg-mime failed: {status}")));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, TimeZone};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Samples older than this are dropped.
+const RETENTION_DAYS: i64 = 30;
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Availability {
+    /// Share of samples in the window where the router answered, 0–100.
+    pub uptime_percent: Option<f64>,
+    pub last_down: Option<DateTime<Local>>,
+    pub samples: u64,
+}
+
+/// Periodic reachability samples per router, kept in a local SQLite database.
+pub struct AvailabilityStore {
+    conn: Connection,
+}
+
+impl AvailabilityStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS samples (
+                router TEXT NOT NULL,
+                time INTEGER NOT NULL,
+                up INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS samples_router_time ON samples (router, time);",
+        )?;
+        Ok(Self { conn })
+    }
+
+    pub fn record(&self, router: &str, up: bool) -> Result<()> {
+        let now = Local::now();
+        self.conn.execute(
+            "INSERT INTO samples (router, time, up) VALUES (?1, ?2, ?3)",
+            params![router, now.timestamp(), up],
+        )?;
+        self.conn.execute(
+            "DELETE FROM samples WHERE time < ?1",
+            params![(now - Duration::days(RETENTION_DAYS)).timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Uptime over the last `days` days and the most recent failed sample
+    /// ever kept, per router.
+    pub fn stats(&self, days: i64) -> Result<HashMap<String, Availability>> {
+        let since = (Local::now() - Duration::days(days)).timestamp();
+        let mut stmt = self.conn.prepare(
+            "SELECT router,
+                    AVG(CASE WHEN time >= ?1 THEN up END) * 100.0,
+                    SUM(time >= ?1),
+                    MAX(CASE WHEN up = 0 THEN time END)
+             FROM samples GROUP BY router",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            let last_down: Option<i64> = row.get(3)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                Availability {
+                    uptime_percent: row.get(1)?,
+                    samples: row.get(2)?,
+                    last_down: last_down.and_then(|t| Local.timestamp_opt(t, 0).single()),
+                },
+            ))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE samples SET router = ?2 WHERE router = ?1",
+            params![from, to],
+        )?;
+        Ok(())
+    }
+}
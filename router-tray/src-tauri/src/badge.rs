@@ -0,0 +1,131 @@
+use std::sync::OnceLock;
+
+const ICON_PNG: &[u8] = include_bytes!("../icons/icon.png");
+const BADGE_COLOR: [u8; 4] = [0xe5, 0x48, 0x4d, 0xff];
+const TEXT_COLOR: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
+/// 3x5 bitmaps for the characters a count can contain, one row per entry
+/// with the three low bits as pixels.
+const GLYPHS: [(char, [u8; 5]); 11] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+];
+
+struct Image {
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl Image {
+    fn put(&mut self, x: i64, y: i64, color: [u8; 4]) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let offset = ((y as u32 * self.width + x as u32) * 4) as usize;
+        self.rgba[offset..offset + 4].copy_from_slice(&color);
+    }
+
+    fn into_icon(self) -> tauri::image::Image<'static> {
+        tauri::image::Image::new_owned(self.rgba, self.width, self.height)
+    }
+}
+
+fn base_image() -> Option<&'static Image> {
+    static BASE: OnceLock<Option<Image>> = OnceLock::new();
+    BASE.get_or_init(|| {
+        let mut decoder = png::Decoder::new(ICON_PNG);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().ok()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).ok()?;
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            return None;
+        }
+        buf.truncate(info.buffer_size());
+        Some(Image {
+            rgba: buf,
+            width: info.width,
+            height: info.height,
+        })
+    })
+    .as_ref()
+}
+
+/// The plain tray icon, without a badge.
+pub fn plain() -> Option<tauri::image::Image<'static>> {
+    let base = base_image()?;
+    Some(
+        Image {
+            rgba: base.rgba.clone(),
+            width: base.width,
+            height: base.height,
+        }
+        .into_icon(),
+    )
+}
+
+/// The tray icon with `count` drawn in a badge in the bottom-right corner.
+pub fn with_count(count: usize) -> Option<tauri::image::Image<'static>> {
+    let base = base_image()?;
+    let mut image = Image {
+        rgba: base.rgba.clone(),
+        width: base.width,
+        height: base.height,
+    };
+    let size = image.width.min(image.height) as i64;
+    let radius = size * 3 / 10;
+    let (cx, cy) = (size - radius, size - radius);
+    for y in cy - radius..=cy + radius {
+        for x in cx - radius..=cx + radius {
+            if (x - cx).pow(2) + (y - cy).pow(2) <= radius.pow(2) {
+                image.put(x, y, BADGE_COLOR);
+            }
+        }
+    }
+
+    let text = if count > 99 {
+        "99+".to_string()
+    } else {
+        count.to_string()
+    };
+    let glyphs: Vec<&[u8; 5]> = text
+        .chars()
+        .filter_map(|c| GLYPHS.iter().find(|(g, _)| *g == c).map(|(_, rows)| rows))
+        .collect();
+    // Each glyph is 3 pixels wide plus one of spacing; fit the text inside
+    // the circle.
+    let columns = glyphs.len() as i64 * 4 - 1;
+    let scale = (radius * 3 / 2 / columns).min(radius / 4).max(1);
+    let left = cx - columns * scale / 2;
+    let top = cy - 5 * scale / 2;
+    for (index, rows) in glyphs.iter().enumerate() {
+        let glyph_left = left + index as i64 * 4 * scale;
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        image.put(
+                            glyph_left + column * scale + dx,
+                            top + row as i64 * scale + dy,
+                            TEXT_COLOR,
+                        );
+                    }
+                }
+            }
+        }
+    }
+    Some(image.into_icon())
+}
@@ -0,0 +1,139 @@
+//! Opt-in crash reports: a panic hook writes the panic message, a backtrace
+//! and a short configuration summary to the config dir, and the next start
+//! offers to open the report or sends it to a user-configured endpoint.
+
+use anyhow::Result;
+use chrono::Local;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+/// Reports not yet shown to the user end with this instead of `.txt`.
+const NEW_SUFFIX: &str = ".new.txt";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SUMMARY: RwLock<String> = RwLock::new(String::new());
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Installs the panic hook. Panics always go to the log; reports are only
+/// written while enabled. The previous hook still runs, so panics keep
+/// printing to stderr.
+pub fn install(dir: PathBuf) {
+    let _ = DIR.set(dir);
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let location = info
+            .location()
+            .map(|l| format!(" at {}:{}", l.file(), l.line()))
+            .unwrap_or_default();
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let panic = format!("{message}{location}");
+        log::error!("Panic: {panic}");
+        if ENABLED.load(Ordering::Relaxed) {
+            if let Err(err) = write_report(&panic) {
+                log::error!("Failed to write crash report: {err}");
+            }
+        }
+        previous(info);
+    }));
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Configuration details to include in reports. Callers leave out
+/// addresses, names and credentials.
+pub fn set_summary(summary: String) {
+    *SUMMARY.write().unwrap() = summary;
+}
+
+fn write_report(panic: &str) -> Result<()> {
+    let Some(dir) = DIR.get() else {
+        return Ok(());
+    };
+    fs::create_dir_all(dir)?;
+    let now = Local::now();
+    let thread = std::thread::current();
+    let report = format!(
+        "Keenetic Tray crash report\n\
+         Version: {}\n\
+         Time: {}\n\
+         OS: {} {}\n\
+         Thread: {}\n\
+         Panic: {}\n\n\
+         Configuration:\n{}\n\n\
+         Backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        now.to_rfc3339(),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        thread.name().unwrap_or("unnamed"),
+        panic,
+        SUMMARY.read().map(|s| s.clone()).unwrap_or_default(),
+        Backtrace::force_capture(),
+    );
+    let file = dir.join(format!("crash-{}{NEW_SUFFIX}", now.format("%Y%m%d-%H%M%S")));
+    fs::write(file, report)?;
+    Ok(())
+}
+
+/// The newest report the user hasn't been shown yet.
+pub fn pending() -> Option<PathBuf> {
+    let dir = DIR.get()?;
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.ends_with(NEW_SUFFIX))
+        })
+        .max()
+}
+
+/// Marks every pending report as seen, returning the new path of `report`.
+pub fn mark_seen(report: &Path) -> PathBuf {
+    let mut renamed = report.to_path_buf();
+    let Some(dir) = DIR.get() else {
+        return renamed;
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return renamed;
+    };
+    for path in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(stem) = name.strip_suffix(NEW_SUFFIX) else {
+            continue;
+        };
+        let target = path.with_file_name(format!("{stem}.txt"));
+        if fs::rename(&path, &target).is_ok() && path == report {
+            renamed = target;
+        }
+    }
+    renamed
+}
+
+/// Posts the report as plain text to the user's endpoint.
+pub fn submit(url: &str, report: &Path) -> Result<()> {
+    let body = fs::read_to_string(report)?;
+    reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()?
+        .post(url)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(body)
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! `ru.toxblh.RouterManager` on the session bus, so GNOME extensions, KDE
+//! widgets and shell scripts can list clients and change policies without
+//! scraping the tray:
+//!
+//! ```sh
+//! gdbus call --session --dest ru.toxblh.RouterManager \
+//!     --object-path /ru/toxblh/RouterManager \
+//!     --method ru.toxblh.RouterManager.ListClients
+//! ```
+
+use crate::{AppState, PolicyOverride};
+use std::sync::{Arc, OnceLock};
+use tauri::{AppHandle, Manager};
+use zbus::fdo;
+use zbus::object_server::SignalEmitter;
+use zbus::Connection;
+
+const NAME: &str = "ru.toxblh.RouterManager";
+const PATH: &str = "/ru/toxblh/RouterManager";
+
+/// Router, MAC, name, IP, online, policy ("" for the default), blocked.
+type Client = (String, String, String, String, bool, String, bool);
+
+static CONNECTION: OnceLock<Connection> = OnceLock::new();
+
+struct Service {
+    app: AppHandle,
+}
+
+impl Service {
+    fn state(&self) -> fdo::Result<tauri::State<'_, Arc<AppState>>> {
+        self.app
+            .try_state::<Arc<AppState>>()
+            .ok_or_else(|| fdo::Error::Failed("not ready".into()))
+    }
+}
+
+#[zbus::interface(name = "ru.toxblh.RouterManager")]
+impl Service {
+    fn list_clients(&self) -> fdo::Result<Vec<Client>> {
+        let state = self.state()?;
+        Ok(crate::client_list(&state)
+            .into_iter()
+            .map(|client| {
+                (
+                    client.router,
+                    client.mac,
+                    client.name,
+                    client.ip,
+                    client.online,
+                    client.policy.unwrap_or_default(),
+                    client.blocked,
+                )
+            })
+            .collect())
+    }
+
+    /// An empty policy means the router's default.
+    async fn set_policy(
+        &self,
+        router: String,
+        mac: String,
+        policy: String,
+        blocked: bool,
+    ) -> fdo::Result<()> {
+        let app = self.app.clone();
+        let target = PolicyOverride {
+            policy: (!policy.is_empty()).then_some(policy),
+            deny: blocked,
+        };
+        // Waiting for the router would hold up the bus connection's executor.
+        tauri::async_runtime::spawn_blocking(move || {
+            crate::apply_external_policy(&app, router, mac.to_lowercase(), target)
+        })
+        .await
+        .map_err(|err| fdo::Error::Failed(err.to_string()))?
+        .map_err(fdo::Error::Failed)
+    }
+
+    /// The tray's active router states as JSON, the same as the REST API's
+    /// `GET /state`.
+    fn get_active_state(&self) -> fdo::Result<String> {
+        let states = self.state()?.active_states.lock().unwrap().clone();
+        serde_json::to_string(&states).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Sent after every refresh with what `GetActiveState` would return.
+    #[zbus(signal)]
+    async fn state_changed(emitter: &SignalEmitter<'_>, state: &str) -> zbus::Result<()>;
+}
+
+/// Claims the bus name and serves the interface. Without a session bus the
+/// tray simply runs without it.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let connection = zbus::connection::Builder::session()
+            .and_then(|builder| builder.name(NAME))
+            .and_then(|builder| builder.serve_at(PATH, Service { app }));
+        let connection = match connection {
+            Ok(builder) => builder.build().await,
+            Err(err) => Err(err),
+        };
+        match connection {
+            Ok(connection) => {
+                let _ = CONNECTION.set(connection);
+            }
+            Err(err) => log::warn!("Failed to start the D-Bus service: {err}"),
+        }
+    });
+}
+
+pub fn state_changed(state: String) {
+    let Some(connection) = CONNECTION.get() else {
+        return;
+    };
+    let connection = connection.clone();
+    tauri::async_runtime::spawn(async move {
+        let Ok(service) = connection
+            .object_server()
+            .interface::<_, Service>(PATH)
+            .await
+        else {
+            return;
+        };
+        if let Err(err) = Service::state_changed(service.signal_emitter(), &state).await {
+            log::debug!("Failed to send StateChanged: {err}");
+        }
+    });
+}
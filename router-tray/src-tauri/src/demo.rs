@@ -0,0 +1,71 @@
+//! `--demo`: the tray and its windows run against a simulated router from
+//! keenetic-sim, served in-process on 127.0.0.1, for screenshots, UI work
+//! and trying the app before adding a real router. Settings, history and
+//! secrets go to a fresh private directory instead of the real
+//! configuration, and nothing leaves the machine: no update checks, outage
+//! pings, MQTT, webhooks, Telegram, push, email or InfluxDB.
+
+use crate::secrets;
+use anyhow::Result;
+use router_core::{local_interfaces, RouterInfo};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+pub const FLAG: &str = "--demo";
+const ROUTER: &str = "Demo";
+const LOGIN: &str = "admin";
+const PASSWORD: &str = "admin";
+
+/// This run's configuration directory, once demo mode is on.
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Turns demo mode on for this run, starting from an empty configuration in
+/// a new directory only this user can read. The system temp directory is
+/// shared, so a fixed name there could be someone else's.
+pub fn enable() -> Result<()> {
+    let dir = tempfile::Builder::new()
+        .prefix("router-tray-demo-")
+        .tempdir()?
+        .keep();
+    let _ = DIR.set(dir);
+    Ok(())
+}
+
+pub fn enabled() -> bool {
+    DIR.get().is_some()
+}
+
+pub fn config_dir() -> PathBuf {
+    DIR.get().cloned().expect("demo mode is enabled")
+}
+
+/// Starts the simulated router and returns it as the one saved router.
+pub fn start() -> Result<RouterInfo> {
+    let mut router = keenetic_sim::Router::new();
+    // The simulated hosts are made up, so this machine is added to have an
+    // active device in the menu.
+    let this = local_interfaces(&[])
+        .into_iter()
+        .find(|iface| iface.ip != "N/A" && iface.mac != "00:00:00:00:00:00");
+    if let Some(iface) = this {
+        router.add_host(&iface.mac, &iface.ip, "This computer");
+    }
+    let config = keenetic_sim::Config {
+        login: LOGIN.to_string(),
+        password: PASSWORD.to_string(),
+        verbose: false,
+    };
+    let address = keenetic_sim::spawn(config, router)?;
+    log::info!("Demo mode: simulated router at {address}");
+    secrets::set_override(ROUTER, PASSWORD);
+    Ok(RouterInfo {
+        name: ROUTER.to_string(),
+        address,
+        login: LOGIN.to_string(),
+        network_ip: None,
+        keendns_urls: None,
+        allow_remote: false,
+        refresh_interval_secs: None,
+        primary: true,
+    })
+}
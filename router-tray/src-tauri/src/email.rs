@@ -0,0 +1,207 @@
+//! Email for the few events worth an inbox: a new app version, a router
+//! that keeps losing its internet and, if asked for, new devices. Mail is
+//! sent with a small SMTP client over TLS, either implicit (port 465) or
+//! upgraded with STARTTLS (port 587).
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Name of the SMTP password in the secret store.
+pub const PASSWORD_KEY: &str = "smtp:password";
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Security {
+    #[default]
+    StartTls,
+    Tls,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub security: Security,
+    pub username: String,
+    pub from: String,
+    /// Recipients, separated by commas.
+    pub to: String,
+    pub update: bool,
+    pub repeated_outages: bool,
+    /// A new device joins while away mode is on.
+    pub new_device: bool,
+}
+
+impl Default for EmailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 587,
+            security: Security::default(),
+            username: String::new(),
+            from: String::new(),
+            to: String::new(),
+            update: true,
+            repeated_outages: true,
+            new_device: false,
+        }
+    }
+}
+
+/// Sends one plain-text message, waiting for the server to accept it.
+pub fn send(
+    settings: &EmailSettings,
+    password: Option<&str>,
+    subject: &str,
+    body: &str,
+) -> Result<()> {
+    let host = settings.host.trim();
+    let recipients: Vec<&str> = settings
+        .to
+        .split(',')
+        .map(str::trim)
+        .filter(|to| !to.is_empty())
+        .collect();
+    if host.is_empty() || settings.from.trim().is_empty() || recipients.is_empty() {
+        bail!("the mail server, sender and recipient are required");
+    }
+    let address = (host, settings.port)
+        .to_socket_addrs()?
+        .next()
+        .with_context(|| format!("{host} not found"))?;
+    let tcp = TcpStream::connect_timeout(&address, TIMEOUT)?;
+    tcp.set_read_timeout(Some(TIMEOUT))?;
+    tcp.set_write_timeout(Some(TIMEOUT))?;
+    let message = compose(settings, &recipients, subject, body);
+    match settings.security {
+        Security::Tls => {
+            let mut smtp = Smtp::new(tls(host, tcp)?);
+            smtp.expect(220)?;
+            deliver(&mut smtp, settings, password, &recipients, &message)
+        }
+        Security::StartTls => {
+            let mut plain = Smtp::new(tcp);
+            plain.expect(220)?;
+            plain.command("EHLO keenetic-tray", 250)?;
+            plain.command("STARTTLS", 220)?;
+            let mut smtp = Smtp::new(tls(host, plain.into_inner())?);
+            deliver(&mut smtp, settings, password, &recipients, &message)
+        }
+    }
+}
+
+fn deliver<S: Read + Write>(
+    smtp: &mut Smtp<S>,
+    settings: &EmailSettings,
+    password: Option<&str>,
+    recipients: &[&str],
+    message: &str,
+) -> Result<()> {
+    smtp.command("EHLO keenetic-tray", 250)?;
+    if !settings.username.is_empty() {
+        let credentials = format!("\0{}\0{}", settings.username, password.unwrap_or_default());
+        smtp.command(&format!("AUTH PLAIN {}", STANDARD.encode(credentials)), 235)
+            .context("the mail server rejected the login")?;
+    }
+    smtp.command(&format!("MAIL FROM:<{}>", settings.from.trim()), 250)?;
+    for to in recipients {
+        smtp.command(&format!("RCPT TO:<{to}>"), 250)?;
+    }
+    smtp.command("DATA", 354)?;
+    smtp.command(&format!("{message}\r\n."), 250)?;
+    let _ = smtp.command("QUIT", 221);
+    Ok(())
+}
+
+/// The body is base64 so no line can end the DATA section early.
+fn compose(settings: &EmailSettings, recipients: &[&str], subject: &str, body: &str) -> String {
+    let encoded = STANDARD.encode(body.replace('\n', "\r\n"));
+    let lines: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(76)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    format!(
+        "From: {}\r\nTo: {}\r\nSubject: =?UTF-8?B?{}?=\r\nDate: {}\r\nMIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+        settings.from.trim(),
+        recipients.join(", "),
+        STANDARD.encode(subject),
+        Local::now().to_rfc2822(),
+        lines.join("\r\n"),
+    )
+}
+
+fn tls(
+    host: &str,
+    tcp: TcpStream,
+) -> Result<rustls::StreamOwned<rustls::ClientConnection, TcpStream>> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let name = rustls::ServerName::try_from(host)?;
+    let connection = rustls::ClientConnection::new(Arc::new(config), name)?;
+    Ok(rustls::StreamOwned::new(connection, tcp))
+}
+
+struct Smtp<S: Read + Write> {
+    stream: BufReader<S>,
+}
+
+impl<S: Read + Write> Smtp<S> {
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+
+    fn command(&mut self, line: &str, code: u16) -> Result<()> {
+        let stream = self.stream.get_mut();
+        stream.write_all(line.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+        self.expect(code)
+    }
+
+    /// Reads a possibly multi-line reply and checks its code.
+    fn expect(&mut self, code: u16) -> Result<()> {
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line)? == 0 {
+                bail!("the mail server closed the connection");
+            }
+            let got: u16 = line.get(..3).and_then(|c| c.parse().ok()).unwrap_or(0);
+            if got != code {
+                bail!("the mail server answered: {}", line.trim());
+            }
+            // "250-..." continues, "250 ..." is the last line.
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return Ok(());
+            }
+        }
+    }
+}
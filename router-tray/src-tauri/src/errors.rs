@@ -0,0 +1,67 @@
+use chrono::Local;
+pub use router_tray_menu::ErrorEntry;
+use std::collections::{HashMap, VecDeque};
+
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Default)]
+pub struct ErrorLog {
+    entries: VecDeque<ErrorEntry>,
+    /// The error each failing router is in, by router name.
+    failing: HashMap<String, String>,
+}
+
+impl ErrorLog {
+    pub fn push(&mut self, message: String) {
+        self.push_entry(message, None);
+    }
+
+    /// Records a refresh failure for `router` unless it is the one already
+    /// recorded, and returns whether it was.
+    pub fn push_for_router(&mut self, router: &str, message: String) -> bool {
+        if self.failing.get(router) == Some(&message) {
+            return false;
+        }
+        self.failing.insert(router.to_string(), message.clone());
+        self.push_entry(message, Some(router.to_string()));
+        true
+    }
+
+    /// Marks `router` as refreshing fine again.
+    pub fn resolve(&mut self, router: &str) {
+        self.failing.remove(router);
+    }
+
+    /// The error to show in the tray: the newest one, or failing that the
+    /// newest refresh failure that hasn't been resolved.
+    pub fn current(&self) -> Option<&ErrorEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .enumerate()
+            .find(|(i, entry)| match &entry.router {
+                Some(router) => self.failing.get(router) == Some(&entry.message),
+                None => *i == 0,
+            })
+            .map(|(_, entry)| entry)
+    }
+
+    fn push_entry(&mut self, message: String, router: Option<String>) {
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ErrorEntry {
+            time: Local::now(),
+            message,
+            router,
+        });
+    }
+
+    pub fn entries(&self) -> Vec<ErrorEntry> {
+        self.entries.iter().rev().cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
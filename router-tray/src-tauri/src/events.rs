@@ -0,0 +1,115 @@
+//! Everything that happened, in one SQLite table meant to be read by other
+//! tools as well: `events.db` in the config folder. The schema is part of
+//! the app's interface and only ever gains columns:
+//!
+//! ```sql
+//! CREATE TABLE events (
+//!     id INTEGER PRIMARY KEY,
+//!     time INTEGER NOT NULL,  -- Unix seconds
+//!     kind TEXT NOT NULL,     -- see below
+//!     router TEXT,            -- router name, if the event has one
+//!     mac TEXT,               -- client MAC, lower case, if the event has one
+//!     data TEXT NOT NULL      -- JSON object with the details
+//! );
+//! ```
+//!
+//! Kinds are the webhook events (`device_joined`, `device_left`,
+//! `policy_changed`, `internet_down`, `internet_up`, `quota_exceeded`),
+//! whose `data` is the webhook's, and `notification`, with `category`,
+//! `title` and `body`. For example:
+//!
+//! ```sql
+//! SELECT datetime(time, 'unixepoch', 'localtime'), kind, data
+//! FROM events WHERE mac = 'aa:bb:cc:dd:ee:ff' ORDER BY time DESC LIMIT 20;
+//! ```
+//!
+//! The database is in WAL mode, so reading it while the app runs is fine.
+//! Events older than a year are dropped.
+
+use anyhow::Result;
+use chrono::{DateTime, Local, TimeZone};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+const KEEP_DAYS: i64 = 365;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Event {
+    pub time: DateTime<Local>,
+    pub kind: String,
+    pub router: Option<String>,
+    pub mac: Option<String>,
+    pub data: Value,
+}
+
+pub struct EventStore {
+    conn: Connection,
+}
+
+impl EventStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        // Setting the journal mode answers with the mode now in use.
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS events (
+                id INTEGER PRIMARY KEY,
+                time INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                router TEXT,
+                mac TEXT,
+                data TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS events_time ON events (time);
+            CREATE INDEX IF NOT EXISTS events_kind_time ON events (kind, time);
+            CREATE INDEX IF NOT EXISTS events_mac_time ON events (mac, time);",
+        )?;
+        let cutoff = Local::now().timestamp() - KEEP_DAYS * 24 * 60 * 60;
+        conn.execute("DELETE FROM events WHERE time < ?1", params![cutoff])?;
+        Ok(Self { conn })
+    }
+
+    pub fn record(&self, kind: &str, router: Option<&str>, data: &Value) -> Result<()> {
+        let mac = data
+            .get("mac")
+            .and_then(Value::as_str)
+            .map(str::to_lowercase);
+        self.conn.execute(
+            "INSERT INTO events (time, kind, router, mac, data) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                Local::now().timestamp(),
+                kind,
+                router,
+                mac,
+                data.to_string()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The most recent events, newest first, optionally of one kind or MAC.
+    pub fn list(&self, kind: Option<&str>, mac: Option<&str>, limit: u32) -> Result<Vec<Event>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT time, kind, router, mac, data FROM events
+             WHERE (?1 IS NULL OR kind = ?1) AND (?2 IS NULL OR mac = ?2)
+             ORDER BY time DESC, id DESC LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![kind, mac.map(str::to_lowercase), limit], |row| {
+            let time: i64 = row.get(0)?;
+            let data: String = row.get(4)?;
+            Ok(Event {
+                time: Local
+                    .timestamp_opt(time, 0)
+                    .single()
+                    .unwrap_or_else(Local::now),
+                kind: row.get(1)?,
+                router: row.get(2)?,
+                mac: row.get(3)?,
+                data: serde_json::from_str(&data).unwrap_or(Value::Null),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+/// A child's devices on one router, managed together from the Family window.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Child {
+    pub name: String,
+    pub router: String,
+    pub devices: Vec<String>,
+    /// Router policy the devices get outside bedtime; `None` is the router
+    /// default.
+    pub profile: Option<String>,
+    pub bedtime: Option<Bedtime>,
+}
+
+/// Nightly window during which every device of a child is blocked.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bedtime {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl Bedtime {
+    /// Whether `time` falls inside the window, which may span midnight.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// How a child's devices look on the router right now.
+#[derive(Clone, Debug, Serialize)]
+pub struct ChildStatus {
+    pub name: String,
+    pub devices: Vec<FamilyDevice>,
+    /// Every device of the child is blocked.
+    pub asleep: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FamilyDevice {
+    pub mac: String,
+    pub name: String,
+    pub online: bool,
+    pub policy: String,
+}
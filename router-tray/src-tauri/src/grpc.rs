@@ -0,0 +1,261 @@
+//! gRPC control interface, the same surface as the REST API for programs
+//! that prefer generated clients. The service is described in
+//! `proto/router.proto` and only built with the `grpc` feature; without it
+//! the settings are kept but nothing listens. Calls carry the REST API token
+//! as `authorization: Bearer <token>` metadata.
+
+use crate::api::Client;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrpcSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for GrpcSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9880,
+        }
+    }
+}
+
+/// A call the tray has to answer.
+#[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+pub enum Request {
+    Devices,
+    Policies {
+        router: Option<String>,
+    },
+    SetPolicy {
+        mac: String,
+        router: Option<String>,
+        policy: Option<String>,
+        blocked: bool,
+    },
+}
+
+#[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+pub enum Reply {
+    Devices(Vec<Client>),
+    /// Router, policy name and description.
+    Policies(Vec<(String, String, String)>),
+    Done,
+}
+
+pub use server::{publish, start};
+
+#[cfg(feature = "grpc")]
+// tonic's API returns its large Status by value.
+#[allow(clippy::result_large_err)]
+mod server {
+    use super::{GrpcSettings, Reply, Request};
+    use crate::api::Client;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::{oneshot, watch};
+    use tokio_stream::wrappers::WatchStream;
+    use tokio_stream::StreamExt;
+    use tonic::{Response, Status};
+
+    mod proto {
+        tonic::include_proto!("routertray.v1");
+    }
+
+    use proto::router_manager_server::{RouterManager, RouterManagerServer};
+
+    type Handler = dyn Fn(Request) -> Result<Reply, String> + Send + Sync;
+
+    struct Running {
+        settings: GrpcSettings,
+        token: String,
+        shutdown: oneshot::Sender<()>,
+    }
+
+    static SERVER: Mutex<Option<Running>> = Mutex::new(None);
+    /// The latest device list, for WatchState streams.
+    static STATE: Mutex<Option<watch::Sender<Vec<Client>>>> = Mutex::new(None);
+
+    /// Serves with `settings`, replacing the running server. Does nothing
+    /// when it already runs with the same settings and token.
+    pub fn start(
+        settings: &GrpcSettings,
+        token: &str,
+        handle: impl Fn(Request) -> Result<Reply, String> + Send + Sync + 'static,
+    ) {
+        let mut running = SERVER.lock().unwrap();
+        if running
+            .as_ref()
+            .is_some_and(|current| &current.settings == settings && current.token == token)
+        {
+            return;
+        }
+        if let Some(old) = running.take() {
+            let _ = old.shutdown.send(());
+        }
+        if !settings.enabled || token.is_empty() {
+            return;
+        }
+        let (shutdown, stopped) = oneshot::channel();
+        *running = Some(Running {
+            settings: settings.clone(),
+            token: token.to_string(),
+            shutdown,
+        });
+        let state = STATE
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| watch::channel(Vec::new()).0)
+            .subscribe();
+        let service = Service {
+            handle: Arc::new(handle),
+            state,
+        };
+        let token = format!("Bearer {token}");
+        let authorize = move |request: tonic::Request<()>| {
+            let given = request
+                .metadata()
+                .get("authorization")
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            if crate::api::constant_time_eq(given.as_bytes(), token.as_bytes()) {
+                Ok(request)
+            } else {
+                Err(Status::unauthenticated("missing or wrong token"))
+            }
+        };
+        let address = ([127, 0, 0, 1], settings.port).into();
+        tauri::async_runtime::spawn(async move {
+            let served = tonic::transport::Server::builder()
+                .add_service(RouterManagerServer::with_interceptor(service, authorize))
+                .serve_with_shutdown(address, async {
+                    let _ = stopped.await;
+                })
+                .await;
+            if let Err(err) = served {
+                log::warn!("Failed to start the gRPC server: {err}");
+            }
+        });
+    }
+
+    /// Sends the device list to every WatchState stream.
+    pub fn publish(clients: Vec<Client>) {
+        if let Some(sender) = STATE.lock().unwrap().as_ref() {
+            sender.send_replace(clients);
+        }
+    }
+
+    struct Service {
+        handle: Arc<Handler>,
+        state: watch::Receiver<Vec<Client>>,
+    }
+
+    impl Service {
+        /// Runs the handler off the async runtime; it waits on the routers.
+        async fn call(&self, request: Request) -> Result<Reply, Status> {
+            let handle = self.handle.clone();
+            tokio::task::spawn_blocking(move || handle(request))
+                .await
+                .map_err(|err| Status::internal(err.to_string()))?
+                .map_err(Status::failed_precondition)
+        }
+    }
+
+    fn device_list(clients: Vec<Client>) -> proto::DeviceList {
+        proto::DeviceList {
+            devices: clients
+                .into_iter()
+                .map(|client| proto::Device {
+                    router: client.router,
+                    mac: client.mac,
+                    name: client.name,
+                    ip: client.ip,
+                    online: client.online,
+                    policy: client.policy,
+                    blocked: client.blocked,
+                })
+                .collect(),
+        }
+    }
+
+    #[tonic::async_trait]
+    impl RouterManager for Service {
+        async fn list_devices(
+            &self,
+            _request: tonic::Request<proto::ListDevicesRequest>,
+        ) -> Result<Response<proto::DeviceList>, Status> {
+            match self.call(Request::Devices).await? {
+                Reply::Devices(clients) => Ok(Response::new(device_list(clients))),
+                _ => Err(Status::internal("unexpected reply")),
+            }
+        }
+
+        async fn list_policies(
+            &self,
+            request: tonic::Request<proto::ListPoliciesRequest>,
+        ) -> Result<Response<proto::PolicyList>, Status> {
+            let router = request.into_inner().router;
+            match self.call(Request::Policies { router }).await? {
+                Reply::Policies(policies) => Ok(Response::new(proto::PolicyList {
+                    policies: policies
+                        .into_iter()
+                        .map(|(router, name, description)| proto::Policy {
+                            router,
+                            name,
+                            description,
+                        })
+                        .collect(),
+                })),
+                _ => Err(Status::internal("unexpected reply")),
+            }
+        }
+
+        async fn set_policy(
+            &self,
+            request: tonic::Request<proto::SetPolicyRequest>,
+        ) -> Result<Response<proto::SetPolicyResponse>, Status> {
+            let request = request.into_inner();
+            self.call(Request::SetPolicy {
+                mac: request.mac,
+                router: request.router,
+                policy: request.policy,
+                blocked: request.blocked,
+            })
+            .await?;
+            Ok(Response::new(proto::SetPolicyResponse {}))
+        }
+
+        type WatchStateStream = std::pin::Pin<
+            Box<dyn tokio_stream::Stream<Item = Result<proto::DeviceList, Status>> + Send>,
+        >;
+
+        async fn watch_state(
+            &self,
+            _request: tonic::Request<proto::WatchStateRequest>,
+        ) -> Result<Response<Self::WatchStateStream>, Status> {
+            let stream =
+                WatchStream::new(self.state.clone()).map(|clients| Ok(device_list(clients)));
+            Ok(Response::new(Box::pin(stream)))
+        }
+    }
+}
+
+#[cfg(not(feature = "grpc"))]
+mod server {
+    use super::{GrpcSettings, Reply, Request};
+    use crate::api::Client;
+
+    pub fn start(
+        settings: &GrpcSettings,
+        _token: &str,
+        _handle: impl Fn(Request) -> Result<Reply, String> + Send + Sync + 'static,
+    ) {
+        if settings.enabled {
+            log::warn!("gRPC is enabled, but this build was made without the grpc feature");
+        }
+    }
+
+    pub fn publish(_clients: Vec<Client>) {}
+}
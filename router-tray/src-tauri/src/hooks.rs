@@ -0,0 +1,104 @@
+//! User scripts run on the same events webhooks get, for automations that
+//! live on this machine (a USB status light, a local log). Each command runs
+//! through the system shell with the event in its environment:
+//!
+//! - `ROUTER_EVENT`: `device_joined`, `internet_down`, ...
+//! - `ROUTER_NAME`: the router's name
+//! - `ROUTER_TIME`: when it happened, RFC 3339
+//! - `ROUTER_MAC`, `ROUTER_POLICY`, ...: each field of the event's data,
+//!   upper-cased, empty when unknown
+//! - `ROUTER_EVENT_JSON`: the whole event as webhooks get it
+
+use crate::webhooks::EventKind;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Scripts still running after this are killed.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScriptHook {
+    pub command: String,
+    /// The one event this command runs on; every event when unset.
+    #[serde(default)]
+    pub event: Option<EventKind>,
+}
+
+/// Runs the hooks that want `kind`, each on its own thread.
+pub fn run(hooks: &[ScriptHook], kind: EventKind, router: &str, data: &Value) {
+    let time = Local::now().to_rfc3339();
+    let event = serde_json::to_value(kind).unwrap_or_default();
+    let event = event.as_str().unwrap_or_default().to_string();
+    let payload = json!({ "event": event, "router": router, "time": time, "data": data });
+    let mut env = vec![
+        ("ROUTER_EVENT".to_string(), event),
+        ("ROUTER_NAME".to_string(), router.to_string()),
+        ("ROUTER_TIME".to_string(), time),
+        ("ROUTER_EVENT_JSON".to_string(), payload.to_string()),
+    ];
+    if let Some(fields) = data.as_object() {
+        for (key, value) in fields {
+            let value = match value {
+                Value::Null => String::new(),
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            env.push((format!("ROUTER_{}", key.to_uppercase()), value));
+        }
+    }
+    for hook in hooks {
+        if hook.event.is_some_and(|event| event != kind) || hook.command.trim().is_empty() {
+            continue;
+        }
+        let command = hook.command.clone();
+        let env = env.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = execute(&command, &env) {
+                log::warn!("Hook `{command}` failed: {err}");
+            }
+        });
+    }
+}
+
+fn execute(command: &str, env: &[(String, String)]) -> anyhow::Result<()> {
+    let mut child = shell(command)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            if !status.success() {
+                anyhow::bail!("exited with {status}");
+            }
+            return Ok(());
+        }
+        if started.elapsed() > TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("still running after {} seconds", TIMEOUT.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]).creation_flags(CREATE_NO_WINDOW);
+    shell
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
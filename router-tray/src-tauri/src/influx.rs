@@ -0,0 +1,169 @@
+//! Pushes traffic samples to InfluxDB, or anything that takes the line
+//! protocol, for Grafana dashboards without a separate collector. Samples
+//! are buffered as they are taken and written in one batch per interval:
+//!
+//! ```text
+//! client_traffic,router=Home,mac=aa:bb:cc:dd:ee:ff,name=TV rx_bps=81920,tx_bps=4096,rx_bytes=51200i,tx_bytes=2560i 1714580000000000000
+//! wan_traffic,router=Home rx_bps=819200,tx_bps=40960,rx_bytes=512000i,tx_bytes=25600i 1714580000000000000
+//! ```
+//!
+//! The URL is the full write endpoint, e.g.
+//! `http://localhost:8086/api/v2/write?org=home&bucket=router` for InfluxDB 2
+//! or `http://localhost:8086/write?db=router` for 1.x. Timestamps are in
+//! nanoseconds, the default precision of both.
+
+use crate::traffic::TrafficDelta;
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Name of the API token in the secret store.
+pub const TOKEN_KEY: &str = "influx:token";
+/// Lines kept while the endpoint is unreachable; the oldest go first.
+const MAX_PENDING: usize = 50_000;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InfluxSettings {
+    pub enabled: bool,
+    pub url: String,
+    pub interval_secs: u64,
+}
+
+impl Default for InfluxSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            interval_secs: 60,
+        }
+    }
+}
+
+struct Running {
+    settings: InfluxSettings,
+    token: String,
+    stopped: Arc<AtomicBool>,
+}
+
+static EXPORTER: Mutex<Option<Running>> = Mutex::new(None);
+static PENDING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Exports with `settings`, replacing the running exporter. Does nothing
+/// when it already runs with the same settings and token.
+pub fn start(settings: &InfluxSettings, token: Option<String>) {
+    let token = token.unwrap_or_default();
+    let mut running = EXPORTER.lock().unwrap();
+    if running
+        .as_ref()
+        .is_some_and(|current| &current.settings == settings && current.token == token)
+    {
+        return;
+    }
+    if let Some(old) = running.take() {
+        old.stopped.store(true, Ordering::Relaxed);
+    }
+    if !settings.enabled || settings.url.trim().is_empty() {
+        PENDING.lock().unwrap().clear();
+        return;
+    }
+    let stopped = Arc::new(AtomicBool::new(false));
+    *running = Some(Running {
+        settings: settings.clone(),
+        token: token.clone(),
+        stopped: stopped.clone(),
+    });
+    let url = settings.url.trim().to_string();
+    let interval = Duration::from_secs(settings.interval_secs.max(10));
+    std::thread::spawn(move || {
+        while !stopped.load(Ordering::Relaxed) {
+            std::thread::sleep(interval);
+            if stopped.load(Ordering::Relaxed) {
+                break;
+            }
+            let lines = std::mem::take(&mut *PENDING.lock().unwrap());
+            if lines.is_empty() {
+                continue;
+            }
+            if let Err(err) = write(&url, &token, &lines) {
+                log::warn!("Failed to export traffic to InfluxDB: {err}");
+                // Tried again with the next batch.
+                let mut pending = PENDING.lock().unwrap();
+                let newer = std::mem::replace(&mut *pending, lines);
+                pending.extend(newer);
+                let excess = pending.len().saturating_sub(MAX_PENDING);
+                pending.drain(..excess);
+            }
+        }
+    });
+}
+
+/// Buffers one sample of a router's clients and its WAN, if exporting.
+pub fn record(
+    router: &str,
+    clients: &[TrafficDelta],
+    names: &HashMap<String, String>,
+    wan: Option<&TrafficDelta>,
+) {
+    if EXPORTER.lock().unwrap().is_none() {
+        return;
+    }
+    let time = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+    let router = escape(router);
+    let mut lines: Vec<String> = clients
+        .iter()
+        .map(|delta| {
+            let mut tags = format!("router={router},mac={}", escape(&delta.mac));
+            if let Some(name) = names.get(&delta.mac).filter(|name| !name.is_empty()) {
+                tags.push_str(&format!(",name={}", escape(name)));
+            }
+            format!("client_traffic,{tags} {} {time}", fields(delta))
+        })
+        .collect();
+    if let Some(wan) = wan {
+        lines.push(format!(
+            "wan_traffic,router={router} {} {time}",
+            fields(wan)
+        ));
+    }
+    let mut pending = PENDING.lock().unwrap();
+    pending.extend(lines);
+    let excess = pending.len().saturating_sub(MAX_PENDING);
+    pending.drain(..excess);
+}
+
+fn fields(delta: &TrafficDelta) -> String {
+    format!(
+        "rx_bps={},tx_bps={},rx_bytes={}i,tx_bytes={}i",
+        delta.rx_bytes as f64 * 8.0 / delta.seconds,
+        delta.tx_bytes as f64 * 8.0 / delta.seconds,
+        delta.rx_bytes,
+        delta.tx_bytes,
+    )
+}
+
+/// Tag values escape commas, spaces and equals signs.
+fn escape(value: &str) -> String {
+    value
+        .replace(',', "\\,")
+        .replace(' ', "\\ ")
+        .replace('=', "\\=")
+}
+
+fn write(url: &str, token: &str, lines: &[String]) -> Result<()> {
+    let mut request = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()?
+        .post(url)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(lines.join("\n"));
+    if !token.is_empty() {
+        request = request.header("Authorization", format!("Token {token}"));
+    }
+    request.send()?.error_for_status()?;
+    Ok(())
+}
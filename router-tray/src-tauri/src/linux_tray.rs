@@ -0,0 +1,126 @@
+//! StatusNotifierItem tray for Linux desktops. GNOME (with the AppIndicator
+//! extension) and KDE talk to it over D-Bus directly, so menu and tooltip
+//! changes are sent as updates instead of replacing the whole indicator.
+
+use crate::menu::{Entry, TrayMenu};
+use ksni::blocking::{Handle, TrayMethods};
+use ksni::menu::{StandardItem, SubMenu};
+use tauri::AppHandle;
+
+/// Themed icon used when the panel's own icon colors are preferred; it is
+/// recolored for light and dark panels.
+const SYMBOLIC_ICON: &str = "network-wireless-symbolic";
+
+pub struct LinuxTray {
+    app: AppHandle,
+    pub menu: TrayMenu,
+    pub tooltip: String,
+    pub icon: Option<tauri::image::Image<'static>>,
+    pub symbolic: bool,
+}
+
+impl LinuxTray {
+    /// Registers the tray with the session's StatusNotifierWatcher. Fails when
+    /// there is none, in which case the caller falls back to Tauri's tray.
+    pub fn spawn(
+        app: AppHandle,
+        icon: Option<tauri::image::Image<'static>>,
+        symbolic: bool,
+    ) -> Result<Handle<LinuxTray>, ksni::Error> {
+        LinuxTray {
+            app,
+            menu: TrayMenu::new(),
+            tooltip: String::new(),
+            icon,
+            symbolic,
+        }
+        .spawn()
+    }
+}
+
+impl ksni::Tray for LinuxTray {
+    fn id(&self) -> String {
+        "keenetic-tray".into()
+    }
+
+    fn title(&self) -> String {
+        crate::i18n::t("app.name")
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        crate::handle_tray_click(&self.app, crate::TrayClick::Left);
+    }
+
+    /// Middle click. There is no double click in the protocol.
+    fn secondary_activate(&mut self, _x: i32, _y: i32) {
+        crate::handle_tray_click(&self.app, crate::TrayClick::Middle);
+    }
+
+    fn icon_name(&self) -> String {
+        if self.symbolic {
+            SYMBOLIC_ICON.into()
+        } else {
+            String::new()
+        }
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        let Some(icon) = &self.icon else {
+            return Vec::new();
+        };
+        // SNI wants ARGB in network byte order.
+        let data = icon
+            .rgba()
+            .chunks_exact(4)
+            .flat_map(|px| [px[3], px[0], px[1], px[2]])
+            .collect();
+        vec![ksni::Icon {
+            width: icon.width() as i32,
+            height: icon.height() as i32,
+            data,
+        }]
+    }
+
+    fn tool_tip(&self) -> ksni::ToolTip {
+        let (title, description) = self
+            .tooltip
+            .split_once('\n')
+            .unwrap_or((self.tooltip.as_str(), ""));
+        ksni::ToolTip {
+            title: title.to_string(),
+            description: description.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
+        items(&self.menu)
+    }
+}
+
+fn items(menu: &TrayMenu) -> Vec<ksni::MenuItem<LinuxTray>> {
+    menu.entries()
+        .iter()
+        .map(|entry| match entry {
+            Entry::Item(item) => {
+                let id = item.id.clone();
+                StandardItem {
+                    label: item.title.clone(),
+                    enabled: item.enabled,
+                    activate: Box::new(move |tray: &mut LinuxTray| {
+                        crate::handle_menu_click(&tray.app, &id)
+                    }),
+                    ..Default::default()
+                }
+                .into()
+            }
+            Entry::Separator => ksni::MenuItem::Separator,
+            Entry::Submenu(title, menu) => SubMenu {
+                label: title.clone(),
+                submenu: items(menu),
+                ..Default::default()
+            }
+            .into(),
+        })
+        .collect()
+}
@@ -0,0 +1,64 @@
+use anyhow::Result;
+use flexi_logger::{
+    detailed_format, Cleanup, Criterion, Duplicate, FileSpec, Logger, LoggerHandle, Naming,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Each file is rotated at this size; a handful of old ones are kept.
+const MAX_FILE_SIZE: u64 = 1_000_000;
+const KEEP_FILES: usize = 5;
+
+static HANDLE: OnceLock<LoggerHandle> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// Our own crates log at the chosen level; dependencies only warn, so
+    /// debug logging doesn't drown in HTTP internals.
+    fn spec(self) -> String {
+        let level = match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        };
+        format!("warn, router_tray={level}, router_core={level}")
+    }
+}
+
+/// Starts logging to rotating files in `dir`, mirroring warnings and errors
+/// to stderr.
+pub fn init(dir: &Path, level: LogLevel) -> Result<()> {
+    let handle = Logger::try_with_str(level.spec())?
+        .log_to_file(FileSpec::default().directory(dir).basename("keenetic-tray"))
+        .rotate(
+            Criterion::Size(MAX_FILE_SIZE),
+            Naming::Numbers,
+            Cleanup::KeepLogFiles(KEEP_FILES),
+        )
+        .duplicate_to_stderr(Duplicate::Warn)
+        .format_for_files(detailed_format)
+        .start()?;
+    let _ = HANDLE.set(handle);
+    Ok(())
+}
+
+pub fn set_level(level: LogLevel) {
+    if let Some(handle) = HANDLE.get() {
+        if let Err(err) = handle.parse_new_spec(&level.spec()) {
+            log::warn!("Failed to change log level: {err}");
+        }
+    }
+}
@@ -3,43 +3,311 @@
     windows_subsystem = "windows"
 )]
 
+mod alerts;
+mod api;
+mod arrivals;
+mod automation;
+mod availability;
+mod badge;
+mod crash;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod demo;
+mod email;
+mod errors;
+mod events;
+mod family;
+mod grpc;
+mod hooks;
+mod influx;
+#[cfg(target_os = "linux")]
+mod linux_tray;
+mod logging;
+mod menu;
+mod metrics;
+mod mqtt;
+mod notifications;
+mod os_auth;
+mod outages;
+#[cfg(target_os = "windows")]
+mod pipe;
+mod presence;
+mod push;
+mod quota;
+mod sound;
+mod syslog;
+#[cfg(target_os = "linux")]
+mod systemd;
+mod telegram;
+#[cfg(target_os = "windows")]
+mod toast;
+mod traffic;
+mod updates;
+mod usage;
+mod webhooks;
+mod websocket;
+mod wifi;
+mod worker;
+
 use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, Weekday};
 use directories::ProjectDirs;
-use keyring::Entry;
+use errors::{ErrorEntry, ErrorLog};
+use i18n::{t, tf};
+use menu::TrayMenu;
+use notifications::{Alert, Category};
+use notify::{RecursiveMode, Watcher};
 use router_core::{
-    extract_host, ip_in_networks, local_interfaces, local_networks, InterfaceInfo, KeeneticRouter,
-    PolicyInfo, RouterInfo,
+    client_interfaces, client_is_online, client_is_registered, client_traffic, discover_routers,
+    extract_host, ip_in_cidr, local_interfaces, local_interfaces_fingerprint, local_networks,
+    remote_address, router_address, ClientInfo, DeviceInfo, DiscoveredRouter, InterfaceInfo,
+    KeeneticRouter, PolicyInfo, RouterError, RouterInfo, Topology, WifiBand, WifiLink,
+};
+use router_secrets as secrets;
+use router_secrets::{crypto, SecretStore};
+use router_tray_menu::{
+    build_locked_menu, build_tray_menu, i18n, policy_label, ActiveState, MenuLayout, TrayMenuModel,
 };
+use router_tray_state::pause::{self, BulkOutcome};
+use router_tray_state::{away, guest, PolicyOverride};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tauri::{
-    CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu,
-    SystemTrayMenuItem, SystemTraySubmenu,
-};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::menu::{Menu, MenuEvent};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{Emitter, Manager, Wry};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_opener::OpenerExt;
+use zeroize::Zeroizing;
 
-#[derive(Clone, Debug, Serialize)]
-struct ActiveState {
-    router: RouterInfo,
-    interfaces: Vec<InterfaceInfo>,
-    policies: HashMap<String, PolicyInfo>,
-    active_iface: Option<InterfaceInfo>,
-    active_address: String,
+/// Policies set from the tray that the router hasn't reported back yet, by
+/// router name and then MAC.
+type Overrides = HashMap<String, HashMap<String, PolicyOverride>>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ClickAction {
+    None,
+    #[default]
+    Refresh,
+    OpenSettings,
+    CyclePolicy,
+    PauseInternet,
 }
 
-#[derive(Clone, Debug)]
-struct PolicyOverride {
-    policy: Option<String>,
-    deny: bool,
+/// What the macOS menu bar shows next to the icon.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MenuBarTitle {
+    None,
+    /// First three letters of the policy and the online count.
+    #[default]
+    PolicyShort,
+    PolicyName,
+    OnlineCount,
+    /// Current rate of this device, or of the WAN when it isn't known.
+    Throughput,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ShortcutAction {
+    ToggleBlock,
+    ApplyPolicy,
+    OpenSettings,
+    OpenPalette,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ShortcutSettings {
+    toggle_block: String,
+    apply_policy: String,
+    policy: String,
+    open_settings: String,
+    palette: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct AppSettings {
+    language: String,
+    left_click: ClickAction,
+    right_click: ClickAction,
+    double_click: ClickAction,
+    middle_click: ClickAction,
+    menu_bar_title: MenuBarTitle,
+    menu_layout: MenuLayout,
+    cycle_policies: Vec<String>,
+    shortcuts: ShortcutSettings,
+    stale_after_minutes: u64,
+    secret_store: SecretStore,
+    /// Ask before blocking a device or deleting a router.
+    confirm_prompts: bool,
+    confirm_destructive: bool,
+    /// User-given names for local network segments, keyed by CIDR.
+    segment_names: HashMap<String, String>,
+    weekly_usage_summary: bool,
+    /// Block devices that join without being registered on the router.
+    lockdown: bool,
+    bandwidth_alerts: Vec<alerts::BandwidthAlert>,
+    data_quotas: Vec<quota::DataQuota>,
+    /// Free-text notes about devices, keyed by MAC.
+    device_notes: HashMap<String, String>,
+    /// Draw the number of online clients over the tray icon.
+    icon_badge: bool,
+    check_updates: bool,
+    /// Write a report to the config dir when the app panics.
+    crash_reports: bool,
+    /// Where to post crash reports; empty keeps them local.
+    crash_report_url: String,
+    log_level: logging::LogLevel,
+    /// Collecting the routers' own logs.
+    router_log: syslog::SyslogSettings,
+    notifications: notifications::NotificationSettings,
+    /// Home Assistant over MQTT.
+    mqtt: mqtt::MqttSettings,
+    /// Prometheus endpoint on localhost.
+    metrics: metrics::MetricsSettings,
+    /// Traffic pushed to InfluxDB.
+    influx: influx::InfluxSettings,
+    /// REST API for scripts.
+    api: api::ApiSettings,
+    /// WebSocket for Stream Deck and similar controllers.
+    websocket: websocket::WebSocketSettings,
+    /// gRPC control interface; only served in builds with the grpc feature.
+    grpc: grpc::GrpcSettings,
+    webhooks: Vec<webhooks::Webhook>,
+    /// Shell commands run on the same events.
+    script_hooks: Vec<hooks::ScriptHook>,
+    /// Children managed from the Family window.
+    family: Vec<family::Child>,
+    /// Policy changes made while nobody is home.
+    away: away::AwaySettings,
+    start_at_login: bool,
+    /// Use the desktop's monochrome network icon on Linux panels.
+    symbolic_tray_icon: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            language: String::new(),
+            left_click: ClickAction::default(),
+            right_click: ClickAction::default(),
+            double_click: ClickAction::default(),
+            middle_click: ClickAction::default(),
+            menu_bar_title: MenuBarTitle::default(),
+            menu_layout: MenuLayout::default(),
+            cycle_policies: Vec::new(),
+            shortcuts: ShortcutSettings::default(),
+            stale_after_minutes: 5,
+            secret_store: SecretStore::default(),
+            confirm_prompts: true,
+            confirm_destructive: false,
+            segment_names: HashMap::new(),
+            weekly_usage_summary: false,
+            lockdown: false,
+            bandwidth_alerts: Vec::new(),
+            data_quotas: Vec::new(),
+            device_notes: HashMap::new(),
+            icon_badge: false,
+            check_updates: true,
+            crash_reports: false,
+            crash_report_url: String::new(),
+            log_level: logging::LogLevel::default(),
+            router_log: syslog::SyslogSettings::default(),
+            notifications: notifications::NotificationSettings::default(),
+            mqtt: mqtt::MqttSettings::default(),
+            metrics: metrics::MetricsSettings::default(),
+            api: api::ApiSettings::default(),
+            websocket: websocket::WebSocketSettings::default(),
+            grpc: grpc::GrpcSettings::default(),
+            influx: influx::InfluxSettings::default(),
+            webhooks: Vec::new(),
+            script_hooks: Vec::new(),
+            family: Vec::new(),
+            away: away::AwaySettings::default(),
+            start_at_login: false,
+            symbolic_tray_icon: false,
+        }
+    }
 }
 
 #[derive(Default)]
 struct AppState {
     routers: Mutex<Vec<RouterInfo>>,
-    policy_overrides: Mutex<HashMap<String, PolicyOverride>>,
+    settings: Mutex<AppSettings>,
+    active_states: Mutex<Vec<ActiveState>>,
+    policy_overrides: Mutex<Overrides>,
+    paused: Mutex<HashMap<String, PolicyOverride>>,
+    errors: Mutex<ErrorLog>,
+    last_updated: Mutex<Option<DateTime<Local>>>,
+    auth_failures: Mutex<HashMap<String, u32>>,
+    password_prompt: Mutex<Option<String>>,
+    traffic: Mutex<traffic::TrafficHistory>,
+    presence: Mutex<Option<presence::PresenceStore>>,
+    usage: Mutex<Option<usage::UsageStore>>,
+    alerts: Mutex<alerts::AlertTracker>,
+    /// WAN rates per router name.
+    wan_traffic: Mutex<traffic::TrafficHistory>,
+    outages: Mutex<Option<outages::OutageStore>>,
+    availability: Mutex<Option<availability::AvailabilityStore>>,
+    /// Every event, for history views and outside tools.
+    events: Mutex<Option<events::EventStore>>,
+    /// Newer release found by the update checker.
+    update: Mutex<Option<updates::Release>>,
+    /// Report left by a crash in a previous run, not yet viewed.
+    crash_report: Mutex<Option<PathBuf>>,
+    /// Consecutive failed internet checks per router.
+    wan_failures: Mutex<HashMap<String, u32>>,
+    /// Count currently drawn on the tray icon, if any.
+    badge: Mutex<Option<usize>>,
+    /// Whether each child was inside their bedtime at the last check.
+    bedtime: Mutex<HashMap<String, bool>>,
+    /// Devices changed by away mode while it's on.
+    away: Mutex<Option<Vec<away::Saved>>>,
+    /// Guest networks to take down when their time is up.
+    guest_vouchers: Mutex<Vec<guest::Voucher>>,
+    /// The menu currently shown by the tray icon, with the model it was
+    /// built from.
+    tray_menu: Mutex<Option<(TrayMenu, Menu<Wry>)>>,
+    /// Runs tray actions and refreshes one at a time.
+    worker: worker::Worker,
+    refresh_stats: Mutex<metrics::RefreshStats>,
+    /// StatusNotifierItem tray, used instead of Tauri's on Linux when the
+    /// desktop provides one.
+    #[cfg(target_os = "linux")]
+    linux_tray: Mutex<Option<ksni::blocking::Handle<linux_tray::LinuxTray>>>,
+}
+
+const TRAY_ID: &str = "main";
+/// Runs the background work, integrations and schedules without the tray,
+/// shortcuts or windows, for a machine that only keeps things running.
+const DAEMON_FLAG: &str = "--daemon";
+/// Where the daemon, which can't ask, takes the master password from.
+const MASTER_PASSWORD_VAR: &str = "ROUTER_MASTER_PASSWORD";
+/// A directory to save the routers' answers to as fixtures, for reproducing
+/// a problem with keenetic-sim.
+const RECORD_DIR_VAR: &str = "ROUTER_RECORD_DIR";
+
+/// Consecutive refreshes rejected with `AuthFailed` before the user is asked
+/// for a new password; one failure may just be a router reboot.
+const AUTH_FAILURE_THRESHOLD: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ConfigBundle {
+    version: u32,
+    routers: Vec<RouterInfo>,
+    settings: AppSettings,
+    #[serde(default)]
+    secrets: Option<crypto::Sealed>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,72 +317,190 @@ struct SaveRouterPayload {
     login: String,
     password: String,
     original_name: Option<String>,
+    #[serde(default)]
+    allow_remote: bool,
+    #[serde(default)]
+    refresh_interval_secs: Option<u64>,
+    #[serde(default)]
+    primary: bool,
+}
+
+/// Log files live in the platform's data dir rather than next to the
+/// config.
+fn log_dir() -> PathBuf {
+    ProjectDirs::from("ru", "toxblh", "KeeneticTray")
+        .map(|d| d.data_dir().join("logs"))
+        .unwrap_or_else(|| PathBuf::from("logs"))
+}
+
+/// Opens a file or folder with the system's default application.
+fn open_in_system(path: &std::path::Path) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut command = std::process::Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = std::process::Command::new("xdg-open");
+    command.arg(path).spawn()?;
+    Ok(())
+}
+
+fn open_log_folder(app: &tauri::AppHandle) {
+    let dir = log_dir();
+    let result = fs::create_dir_all(&dir)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| open_in_system(&dir));
+    if let Err(err) = result {
+        report_error(app, err.to_string());
+    }
 }
 
-fn config_path() -> PathBuf {
-    let dir = ProjectDirs::from("ru", "toxblh", "KeeneticTray")
-        .map(|d| d.config_dir().to_path_buf())
-        .unwrap_or_else(|| PathBuf::from("."));
+fn config_path(file: &str) -> PathBuf {
+    let dir = if demo::enabled() {
+        demo::config_dir()
+    } else {
+        ProjectDirs::from("ru", "toxblh", "KeeneticTray")
+            .map(|d| d.config_dir().to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."))
+    };
     if let Err(err) = fs::create_dir_all(&dir) {
-        eprintln!("Failed to create config dir: {err}");
+        log::error!("Failed to create config dir: {err}");
     }
-    dir.join("routers.json")
+    dir.join(file)
+}
+
+fn read_config<T: DeserializeOwned + Default>(file: &str) -> Result<T> {
+    let path = config_path(file);
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let data = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&data)?)
 }
 
 fn load_routers() -> Vec<RouterInfo> {
-    let path = config_path();
-    let data = fs::read_to_string(path).unwrap_or_default();
-    serde_json::from_str(&data).unwrap_or_default()
+    read_config("routers.json").unwrap_or_default()
 }
 
 fn save_routers(routers: &[RouterInfo]) -> Result<()> {
-    let path = config_path();
+    let path = config_path("routers.json");
     let data = serde_json::to_string_pretty(routers)?;
     fs::write(path, data)?;
     Ok(())
 }
 
-fn get_password(name: &str) -> Option<String> {
-    let entry = Entry::new("router_manager", name).ok()?;
-    entry.get_password().ok()
+fn load_settings() -> AppSettings {
+    read_config("settings.json").unwrap_or_default()
 }
 
-fn set_password(name: &str, password: &str) -> Result<()> {
-    let entry = Entry::new("router_manager", name)?;
-    entry.set_password(password)?;
+fn save_settings(settings: &AppSettings) -> Result<()> {
+    let path = config_path("settings.json");
+    let data = serde_json::to_string_pretty(settings)?;
+    fs::write(path, data)?;
     Ok(())
 }
 
-fn delete_password(name: &str) {
-    if let Ok(entry) = Entry::new("router_manager", name) {
-        let _ = entry.delete_password();
-    }
+/// Policy state that otherwise lives only in memory, kept on disk so a
+/// restart doesn't forget a pause or a bedtime in progress.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PendingState {
+    // Renamed when the overrides gained a router level, so the old flat map
+    // is dropped rather than failing the whole file.
+    #[serde(rename = "router_overrides")]
+    policy_overrides: Overrides,
+    paused: HashMap<String, PolicyOverride>,
+    bedtime: HashMap<String, bool>,
+    away: Option<Vec<away::Saved>>,
+    guest_vouchers: Vec<guest::Voucher>,
+}
+
+fn load_pending(state: &AppState) {
+    let pending: PendingState = read_config("pending.json").unwrap_or_default();
+    *state.policy_overrides.lock().unwrap() = pending.policy_overrides;
+    *state.paused.lock().unwrap() = pending.paused;
+    *state.bedtime.lock().unwrap() = pending.bedtime;
+    *state.away.lock().unwrap() = pending.away;
+    *state.guest_vouchers.lock().unwrap() = pending.guest_vouchers;
 }
 
-fn policy_label(policy: Option<&str>, deny: bool, policies: &HashMap<String, PolicyInfo>) -> String {
-    if deny {
-        return "Blocked".to_string();
+/// Writes the current overrides and timers out; call without holding any of
+/// their locks.
+fn save_pending(state: &AppState) {
+    if let Err(err) = write_pending(state) {
+        log::warn!("Failed to save pending policy state: {err}");
     }
-    if policy.is_none() {
-        return "Default".to_string();
+}
+
+fn write_pending(state: &AppState) -> Result<()> {
+    let pending = PendingState {
+        policy_overrides: state.policy_overrides.lock().unwrap().clone(),
+        paused: state.paused.lock().unwrap().clone(),
+        bedtime: state.bedtime.lock().unwrap().clone(),
+        away: state.away.lock().unwrap().clone(),
+        guest_vouchers: state.guest_vouchers.lock().unwrap().clone(),
+    };
+    // Guest vouchers carry the Wi-Fi passphrase.
+    let data = serde_json::to_string_pretty(&pending)?;
+    secrets::write_private(&config_path("pending.json"), &data)
+}
+
+fn get_password(name: &str) -> Option<Zeroizing<String>> {
+    secrets::get(name)
+}
+
+/// A stored token or password for a service that keeps it for as long as it
+/// runs.
+fn get_token(name: &str) -> Option<String> {
+    get_password(name).map(|token| token.to_string())
+}
+
+fn set_password(name: &str, password: &str) -> Result<()> {
+    secrets::set(name, password)
+}
+
+fn delete_password(name: &str) {
+    secrets::delete(name);
+}
+
+fn resolve_policy_label(
+    label: &str,
+    policies: &HashMap<String, PolicyInfo>,
+) -> Option<PolicyOverride> {
+    let label = label.trim();
+    let wanted = label.to_lowercase();
+    if wanted == "default" || wanted == t("policy.default").to_lowercase() {
+        return Some(PolicyOverride {
+            policy: None,
+            deny: false,
+        });
     }
-    let key = policy.unwrap_or_default();
-    if let Some(info) = policies.get(key) {
-        if let Some(desc) = &info.description {
-            return desc.clone();
-        }
+    if wanted == "blocked" || wanted == t("policy.blocked").to_lowercase() {
+        return Some(PolicyOverride {
+            policy: None,
+            deny: true,
+        });
     }
-    key.to_string()
+    policies
+        .iter()
+        .find(|(name, info)| {
+            name.to_lowercase() == wanted
+                || info.description.as_deref().map(str::to_lowercase) == Some(wanted.clone())
+        })
+        .map(|(name, _)| PolicyOverride {
+            policy: Some(name.clone()),
+            deny: false,
+        })
 }
 
 fn policy_short(label: &str) -> String {
     label.chars().take(3).collect::<String>()
 }
 
-fn encode_mac(mac: &str) -> String {
-    mac.replace(':', "")
-}
-
 fn decode_mac(value: &str) -> String {
     let cleaned = value.replace(':', "");
     let mut out = String::new();
@@ -127,208 +513,111 @@ fn decode_mac(value: &str) -> String {
     out
 }
 
-fn build_active_state(routers: &[RouterInfo]) -> Result<Option<ActiveState>> {
-    if routers.is_empty() {
-        return Ok(None);
-    }
-    let networks = local_networks();
-    let mut candidates: Vec<(RouterInfo, String)> = Vec::new();
-
-    for router in routers.iter().cloned() {
-        if let Some(ip) = router.network_ip.clone() {
-            if ip_in_networks(&ip, &networks) {
-                candidates.push((router, ip));
-                continue;
-            }
-        }
-        let host = extract_host(&router.address);
-        if !host.is_empty() && ip_in_networks(&host, &networks) {
-            let addr = router.address.clone();
-            candidates.push((router, addr));
-        }
+fn build_router_state(router: RouterInfo, address: String, remote: bool) -> Result<ActiveState> {
+    let password =
+        get_password(&router.name).ok_or_else(|| anyhow::anyhow!(t("error.no_password")))?;
+    let client = KeeneticRouter::new(&address, &router.login, &password, &router.name);
+    client.login()?;
+    let policies = client.get_policies()?;
+    let clients = client.get_online_clients()?;
+    let online_clients = clients.iter().filter(|c| client_is_online(c)).count();
+    // Routers with a single band have no second access point to read.
+    let band_locks = client.get_band_locks().unwrap_or_else(|err| {
+        log::debug!("{}: no band locks: {err}", router.name);
+        HashMap::new()
+    });
+    if remote {
+        // Off-network none of the local NICs belong to the router, so offer
+        // every registered client instead.
+        return Ok(ActiveState {
+            router,
+            interfaces: client_interfaces(&clients),
+            policies,
+            active_iface: None,
+            active_address: address,
+            remote,
+            segment: None,
+            online_clients,
+            band_locks,
+        });
     }
-
-    for (router, addr) in candidates {
-        let password = match get_password(&router.name) {
-            Some(p) => p,
-            None => continue,
-        };
-        let client = KeeneticRouter::new(&addr, &router.login, &password, &router.name);
-        if client.login().is_err() {
+    let mut interfaces = local_interfaces(&clients);
+    for iface in &mut interfaces {
+        if iface.iface_type != "Wi-Fi" || iface.wifi.as_ref().is_some_and(|w| w.ssid.is_some()) {
             continue;
         }
-        let policies = client.get_policies()?;
-        let clients = client.get_online_clients()?;
-        let interfaces = local_interfaces(&clients);
-        let mut active_iface = None;
-        for iface in &interfaces {
-            if iface.online {
-                active_iface = Some(iface.clone());
-                break;
-            }
+        if let Some(ssid) = wifi::current_ssid(&iface.name) {
+            iface.wifi.get_or_insert_with(WifiLink::default).ssid = Some(ssid);
         }
-        if active_iface.is_none() {
-            active_iface = interfaces.get(0).cloned();
+    }
+    let mut active_iface = None;
+    for iface in &interfaces {
+        if iface.online {
+            active_iface = Some(iface.clone());
+            break;
         }
-        return Ok(Some(ActiveState {
-            router,
-            interfaces,
-            policies,
-            active_iface,
-            active_address: addr,
-        }));
     }
-
-    Ok(None)
-}
-
-fn info_item(id: &str, title: &str) -> CustomMenuItem {
-    CustomMenuItem::new(id, title).disabled()
+    if active_iface.is_none() {
+        active_iface = interfaces.first().cloned();
+    }
+    Ok(ActiveState {
+        router,
+        interfaces,
+        policies,
+        active_iface,
+        active_address: address,
+        remote,
+        segment: None,
+        online_clients,
+        band_locks,
+    })
 }
 
-fn append_interface_section(
-    mut menu: SystemTrayMenu,
-    iface: &InterfaceInfo,
-    policies: &HashMap<String, PolicyInfo>,
-    prefix: &str,
-    with_header: bool,
-) -> SystemTrayMenu {
-    if with_header {
-        menu = menu.add_item(info_item(
-            &format!("{prefix}:header"),
-            &iface.display_name,
-        ));
+fn build_active_states(
+    routers: &[RouterInfo],
+    errors: &mut Vec<(String, anyhow::Error)>,
+) -> Vec<ActiveState> {
+    if routers.is_empty() {
+        return Vec::new();
     }
-    menu = menu.add_item(info_item(
-        &format!("{prefix}:iface"),
-        &format!("Interface: {}", iface.name),
-    ));
-    menu = menu.add_item(info_item(
-        &format!("{prefix}:ip"),
-        &format!("IP: {}", iface.ip),
-    ));
-    menu = menu.add_item(info_item(
-        &format!("{prefix}:mac"),
-        &format!("MAC: {}", iface.mac),
-    ));
-    menu = menu.add_item(info_item(
-        &format!("{prefix}:type"),
-        &format!("Type: {}", iface.iface_type),
-    ));
-    let state = if iface.online { "Online" } else { "Offline" };
-    menu = menu.add_item(info_item(
-        &format!("{prefix}:state"),
-        &format!("State: {}", state),
-    ));
-    menu = menu.add_native_item(SystemTrayMenuItem::Separator);
-
-    let current_label = policy_label(iface.policy.as_deref(), iface.deny, policies);
-    let mac_encoded = encode_mac(&iface.mac);
-    let default_label = if current_label == "Default" {
-        "• Default".to_string()
-    } else {
-        "Default".to_string()
-    };
-    let blocked_label = if current_label == "Blocked" {
-        "• Blocked".to_string()
+    let networks = local_networks();
+    let mut local: Vec<(&RouterInfo, String)> = Vec::new();
+    for router in routers {
+        if let Some(address) = router_address(router, &networks) {
+            local.push((router, address));
+        }
+    }
+    let remote = local.is_empty();
+    let mut candidates: Vec<(&RouterInfo, String)> = if remote {
+        routers
+            .iter()
+            .filter_map(|router| remote_address(router).map(|address| (router, address)))
+            .collect()
     } else {
-        "Blocked".to_string()
+        local
     };
+    // The primary router comes first, so it is the one the tray title,
+    // tooltip and shortcuts act on.
+    candidates.sort_by_key(|(router, _)| !router.primary);
 
-    menu = menu.add_item(CustomMenuItem::new(
-        format!("policy|{}|default", mac_encoded),
-        default_label,
-    ));
-    menu = menu.add_item(CustomMenuItem::new(
-        format!("policy|{}|blocked", mac_encoded),
-        blocked_label,
-    ));
-
-    for (name, info) in policies {
-        let label = info
-            .description
-            .as_ref()
-            .map(|s| s.as_str())
-            .unwrap_or(name);
-        let title = if label == current_label {
-            format!("• {}", label)
-        } else {
-            label.to_string()
-        };
-        menu = menu.add_item(CustomMenuItem::new(
-            format!("policy|{}|set|{}", mac_encoded, name),
-            title,
-        ));
+    let mut out = Vec::new();
+    for (router, address) in candidates {
+        match build_router_state(router.clone(), address, remote) {
+            Ok(state) => out.push(state),
+            Err(err) => errors.push((router.name.clone(), err)),
+        }
     }
-
-    menu
+    out
 }
 
-fn build_tray_menu(state: Option<&ActiveState>, has_routers: bool) -> SystemTrayMenu {
-    let mut menu = SystemTrayMenu::new();
-    if !has_routers {
-        menu = menu.add_item(info_item("info:no_routers", "No routers configured."));
-        menu = menu.add_native_item(SystemTrayMenuItem::Separator);
-        menu = menu.add_item(CustomMenuItem::new("add_router", "Add Router..."));
-        menu = menu.add_item(CustomMenuItem::new("quit", "Quit"));
-        return menu;
-    }
-
-    let Some(active) = state else {
-        menu = menu.add_item(info_item(
-            "info:none",
-            "No available routers in the current network.",
-        ));
-        menu = menu.add_native_item(SystemTrayMenuItem::Separator);
-        menu = menu.add_item(CustomMenuItem::new("add_router", "Add Router..."));
-        menu = menu.add_item(CustomMenuItem::new("settings", "Settings..."));
-        menu = menu.add_item(CustomMenuItem::new("refresh", "Refresh"));
-        menu = menu.add_item(CustomMenuItem::new("quit", "Quit"));
-        return menu;
-    };
-
-    if let Some(active_iface) = &active.active_iface {
-        let prefix = format!("iface{}", encode_mac(&active_iface.mac));
-        menu = append_interface_section(
-            menu,
-            active_iface,
-            &active.policies,
-            &prefix,
-            true,
-        );
-        menu = menu.add_native_item(SystemTrayMenuItem::Separator);
-    }
-
-    for iface in &active.interfaces {
-        if let Some(active_iface) = &active.active_iface {
-            if iface.mac == active_iface.mac {
-                continue;
-            }
-        }
-        let prefix = format!("iface{}", encode_mac(&iface.mac));
-        let sub = append_interface_section(
-            SystemTrayMenu::new(),
-            iface,
-            &active.policies,
-            &prefix,
-            false,
-        );
-        menu = menu.add_submenu(SystemTraySubmenu::new(
-            iface.display_name.clone(),
-            sub,
-        ));
+fn apply_segment_names(states: &mut [ActiveState], names: &HashMap<String, String>) {
+    for active in states.iter_mut().filter(|a| !a.remote) {
+        let host = extract_host(&active.active_address);
+        active.segment = names
+            .iter()
+            .find(|(cidr, _)| ip_in_cidr(&host, cidr))
+            .map(|(_, name)| name.clone());
     }
-
-    menu = menu.add_native_item(SystemTrayMenuItem::Separator);
-    menu = menu.add_item(info_item(
-        "router:name",
-        &format!("Router: {}", active.router.name),
-    ));
-    menu = menu.add_native_item(SystemTrayMenuItem::Separator);
-    menu = menu.add_item(CustomMenuItem::new("settings", "Settings..."));
-    menu = menu.add_item(CustomMenuItem::new("refresh", "Refresh"));
-    menu = menu.add_item(CustomMenuItem::new("quit", "Quit"));
-    menu
 }
 
 fn apply_policy(
@@ -350,11 +639,11 @@ fn apply_policy(
     Ok(())
 }
 
-fn refresh_tray(app: &tauri::AppHandle, state: &Arc<AppState>) {
-    let routers = state.routers.lock().unwrap().clone();
-    let mut active = build_active_state(&routers).ok().flatten();
-    if let Some(active_state) = active.as_mut() {
-        let mut overrides = state.policy_overrides.lock().unwrap();
+fn apply_overrides(states: &mut [ActiveState], all: &mut Overrides) {
+    for active_state in states {
+        let Some(overrides) = all.get_mut(&active_state.router.name) else {
+            continue;
+        };
         for iface in &mut active_state.interfaces {
             let current_policy = iface.policy.clone();
             let current_deny = iface.deny;
@@ -375,244 +664,4798 @@ fn refresh_tray(app: &tauri::AppHandle, state: &Arc<AppState>) {
             }
         }
     }
-    let menu = build_tray_menu(active.as_ref(), !routers.is_empty());
+    all.retain(|_, overrides| !overrides.is_empty());
+}
+
+fn refresh_tray(app: &tauri::AppHandle, state: &Arc<AppState>) {
+    // Nothing touches the routers until the session is unlocked.
+    if secrets::is_locked() {
+        set_tray_menu(app, state, build_locked_menu());
+        return;
+    }
+    let started = Instant::now();
+    let routers = state.routers.lock().unwrap().clone();
+    let mut errors = Vec::new();
+    let mut states = build_active_states(&routers, &mut errors);
+    for active in &states {
+        state
+            .auth_failures
+            .lock()
+            .unwrap()
+            .remove(&active.router.name);
+        state.errors.lock().unwrap().resolve(&active.router.name);
+    }
+    for (name, err) in errors {
+        if matches!(err.downcast_ref(), Some(RouterError::AuthFailed)) {
+            track_auth_failure(app, state, &name);
+        }
+        record_router_error(app, state, &name, format!("{name}: {err}"));
+    }
+    let settled = {
+        let mut overrides = state.policy_overrides.lock().unwrap();
+        let before: usize = overrides.values().map(HashMap::len).sum();
+        apply_overrides(&mut states, &mut overrides);
+        overrides.values().map(HashMap::len).sum::<usize>() != before
+    };
+    if settled {
+        save_pending(state);
+    }
+    apply_segment_names(&mut states, &state.settings.lock().unwrap().segment_names);
+    if !states.is_empty() {
+        *state.last_updated.lock().unwrap() = Some(Local::now());
+    }
+    *state.active_states.lock().unwrap() = states.clone();
+    let _ = app.emit("state-updated", &states);
+    mqtt::publish(&mqtt_devices(&states));
+    websocket::publish(&serde_json::json!({
+        "type": "state",
+        "clients": client_list(state),
+    }));
+    grpc::publish(client_list(state));
+    #[cfg(target_os = "linux")]
+    if let Ok(json) = serde_json::to_string(&states) {
+        dbus::state_changed(json);
+    }
+    let last_error = state.errors.lock().unwrap().current().cloned();
+    let updated = updated_label(state);
+    let settings = state.settings.lock().unwrap().clone();
+    let update = state.update.lock().unwrap().clone();
+    let menu = build_tray_menu(&TrayMenuModel {
+        states: &states,
+        layout: &settings.menu_layout,
+        device_notes: &settings.device_notes,
+        has_routers: !routers.is_empty(),
+        last_error: last_error.as_ref(),
+        updated: updated.as_deref(),
+        update: update.as_ref().map(|release| &release.version),
+        crash_report: state.crash_report.lock().unwrap().is_some(),
+    });
+    set_tray_menu(app, state, menu);
 
-    let tray = app.tray_handle();
-    let _ = tray.set_menu(menu);
+    update_tray_status(app, state);
+    state
+        .refresh_stats
+        .lock()
+        .unwrap()
+        .record(started.elapsed());
+}
 
-    if let Some(active) = &active {
-        if let Some(iface) = &active.active_iface {
-            let label = policy_label(iface.policy.as_deref(), iface.deny, &active.policies);
-            let short = policy_short(&label);
-            let tooltip = format!("Keenetic Tray - {}", short);
-            let _ = tray.set_tooltip(&tooltip);
-            #[cfg(target_os = "macos")]
-            {
-                let _ = tray.set_title(&short);
-            }
-        } else {
-            let _ = tray.set_tooltip("Keenetic Tray");
-            #[cfg(target_os = "macos")]
+/// Shows `menu` in the tray, keeping the native menu so single items can be
+/// updated in place later.
+fn set_tray_menu(app: &tauri::AppHandle, state: &AppState, menu: TrayMenu) {
+    #[cfg(target_os = "linux")]
+    if let Some(handle) = state.linux_tray.lock().unwrap().as_ref() {
+        handle.update(|tray| tray.menu = menu);
+        return;
+    }
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let mut shown = state.tray_menu.lock().unwrap();
+    // Rebuilding flickers and closes open submenus, so a menu with the same
+    // items is updated in place.
+    if let Some((model, native)) = shown.as_mut() {
+        if model.same_layout(&menu) {
+            match native
+                .items()
+                .and_then(|items| menu::update_native(model, &menu, &items))
             {
-                let _ = tray.set_title("");
+                Ok(()) => {
+                    *model = menu;
+                    return;
+                }
+                Err(err) => log::warn!("Failed to update the tray menu: {err}"),
             }
         }
-    } else {
-        let _ = tray.set_tooltip("Keenetic Tray");
-        #[cfg(target_os = "macos")]
-        {
-            let _ = tray.set_title("");
+    }
+    match menu::build(&menu, app) {
+        Ok(native) => {
+            let _ = tray.set_menu(Some(native.clone()));
+            *shown = Some((menu, native));
         }
+        Err(err) => log::warn!("Failed to build the tray menu: {err}"),
     }
 }
 
-fn open_settings_window(app: &tauri::AppHandle) {
-    if let Some(window) = app.get_window("settings") {
+fn track_auth_failure(app: &tauri::AppHandle, state: &AppState, name: &str) {
+    let count = {
+        let mut failures = state.auth_failures.lock().unwrap();
+        let count = failures.entry(name.to_string()).or_insert(0);
+        *count += 1;
+        *count
+    };
+    // Prompt once when the threshold is crossed rather than on every refresh.
+    if count != AUTH_FAILURE_THRESHOLD {
+        return;
+    }
+    show_notification(
+        app,
+        &t("notify.password_rejected"),
+        &tf("notify.password_rejected_body", &[("name", name)]),
+    );
+    *state.password_prompt.lock().unwrap() = Some(name.to_string());
+    if let Some(window) = app.get_webview_window("password") {
+        let _ = window.emit("password-required", name);
         let _ = window.show();
         let _ = window.set_focus();
     }
 }
 
-#[tauri::command]
-fn list_routers(state: tauri::State<Arc<AppState>>) -> Vec<RouterInfo> {
-    state.routers.lock().unwrap().clone()
+fn is_stale(state: &AppState) -> bool {
+    let stale_after = state.settings.lock().unwrap().stale_after_minutes;
+    match *state.last_updated.lock().unwrap() {
+        Some(updated) => Local::now() - updated > chrono::Duration::minutes(stale_after as i64),
+        None => false,
+    }
 }
 
-#[tauri::command]
-fn save_router(
-    payload: SaveRouterPayload,
-    state: tauri::State<'_, Arc<AppState>>,
-) -> Result<(), String> {
-    let SaveRouterPayload {
-        name,
-        address,
-        login,
-        password,
-        original_name,
-    } = payload;
-    let address = address.trim_end_matches('/').to_string();
-
-    let mut routers = state.routers.lock().unwrap();
-    if let Some(original) = original_name.as_ref() {
-        if original != &name && routers.iter().any(|r| r.name == name) {
-            return Err("Router with this name already exists".into());
-        }
-    } else if routers.iter().any(|r| r.name == name) {
-        return Err("Router with this name already exists".into());
+fn updated_label(state: &AppState) -> Option<String> {
+    let updated = (*state.last_updated.lock().unwrap())?;
+    let time = updated.format("%H:%M:%S").to_string();
+    if is_stale(state) {
+        Some(tf("tray.updated_stale", &[("time", &time)]))
+    } else {
+        Some(tf("tray.updated", &[("time", &time)]))
     }
+}
 
-    let client = KeeneticRouter::new(&address, &login, &password, &name);
-    if client.login().is_err() {
-        return Err("Authentication failed".into());
+fn update_tray_status(app: &tauri::AppHandle, state: &AppState) {
+    if let Some(label) = updated_label(state) {
+        #[cfg(target_os = "linux")]
+        if let Some(handle) = state.linux_tray.lock().unwrap().as_ref() {
+            handle.update(|tray| tray.menu.set_title("info:updated", &label));
+        }
+        if let Some((model, native)) = state.tray_menu.lock().unwrap().as_mut() {
+            model.set_title("info:updated", &label);
+            if let Some(item) = native.get("info:updated") {
+                if let Some(item) = item.as_menuitem() {
+                    let _ = item.set_text(label);
+                }
+            }
+        }
     }
-    let network_ip = client.get_network_ip().ok().flatten();
-    let keendns_urls = client.get_keendns_urls().ok();
-
-    let router_info = RouterInfo {
-        name: name.clone(),
-        address,
-        login,
-        network_ip,
-        keendns_urls,
+    let stale_suffix = if is_stale(state) {
+        format!(" ({})", t("tray.stale"))
+    } else {
+        String::new()
     };
 
-    if let Some(original) = original_name {
-        if let Some(pos) = routers.iter().position(|r| r.name == original) {
-            routers.remove(pos);
+    let states = state.active_states.lock().unwrap();
+    let online = online_count(&states);
+    update_badge(app, state, online);
+    if let Some(active) = states.first() {
+        let app_name = tf(
+            "tray.online_title",
+            &[("name", &t("app.name")), ("count", &online.to_string())],
+        );
+        if let Some(iface) = &active.active_iface {
+            let label = policy_label(iface.policy.as_deref(), iface.deny, &active.policies);
+            let short = policy_short(&label);
+            let mut tooltip = format!("{} - {}{}", app_name, short, stale_suffix);
+            if let Some(rates) = traffic_summary(state, active, iface) {
+                tooltip = format!("{tooltip}\n{rates}");
+            }
+            set_tray_tooltip(app, state, &tooltip);
+        } else {
+            set_tray_tooltip(app, state, &app_name);
         }
-        delete_password(&original);
+    } else {
+        set_tray_tooltip(app, state, &t("app.name"));
     }
+    #[cfg(target_os = "macos")]
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let title = states
+            .first()
+            .and_then(|active| menu_bar_title(state, active, online));
+        let _ = tray.set_title(title.as_deref());
+    }
+}
 
-    set_password(&name, &password).map_err(|e| e.to_string())?;
-    routers.push(router_info.clone());
-
-    save_routers(&routers).map_err(|e| e.to_string())?;
-    Ok(())
+#[cfg(target_os = "macos")]
+fn menu_bar_title(state: &AppState, active: &ActiveState, online: usize) -> Option<String> {
+    let format = state.settings.lock().unwrap().menu_bar_title;
+    let count = online.to_string();
+    let policy = active
+        .active_iface
+        .as_ref()
+        .map(|iface| policy_label(iface.policy.as_deref(), iface.deny, &active.policies));
+    match format {
+        MenuBarTitle::None => None,
+        MenuBarTitle::PolicyShort => policy.map(|label| {
+            tf(
+                "tray.online_title",
+                &[("name", &policy_short(&label)), ("count", &count)],
+            )
+        }),
+        MenuBarTitle::PolicyName => {
+            policy.map(|label| tf("tray.online_title", &[("name", &label), ("count", &count)]))
+        }
+        MenuBarTitle::OnlineCount => Some(tf("tray.title_online", &[("count", &count)])),
+        MenuBarTitle::Throughput => {
+            let device = active
+                .active_iface
+                .as_ref()
+                .and_then(|iface| state.traffic.lock().unwrap().latest(&iface.mac));
+            let point = device.or_else(|| {
+                state
+                    .wan_traffic
+                    .lock()
+                    .unwrap()
+                    .latest(&active.router.name)
+            })?;
+            Some(tf(
+                "tray.title_throughput",
+                &[
+                    ("rx", &format_mbps(point.rx_bps)),
+                    ("tx", &format_mbps(point.tx_bps)),
+                ],
+            ))
+        }
+    }
 }
 
-#[tauri::command]
-fn delete_router(name: String, state: tauri::State<Arc<AppState>>) -> Result<(), String> {
-    let mut routers = state.routers.lock().unwrap();
-    routers.retain(|r| r.name != name);
-    delete_password(&name);
-    save_routers(&routers).map_err(|e| e.to_string())?;
-    Ok(())
+fn set_tray_tooltip(app: &tauri::AppHandle, state: &AppState, tooltip: &str) {
+    #[cfg(target_os = "linux")]
+    if let Some(handle) = state.linux_tray.lock().unwrap().as_ref() {
+        let tooltip = tooltip.to_string();
+        handle.update(|tray| tray.tooltip = tooltip);
+        return;
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = state;
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
 }
 
-#[tauri::command]
-fn refresh_tray_cmd(app: tauri::AppHandle, state: tauri::State<Arc<AppState>>) {
-    refresh_tray(&app, &state);
+/// Latest WAN rate of the router and of the interface this computer uses,
+/// one per line.
+fn traffic_summary(
+    state: &AppState,
+    active: &ActiveState,
+    iface: &InterfaceInfo,
+) -> Option<String> {
+    let format = |key: &str, point: traffic::RatePoint| {
+        tf(
+            key,
+            &[
+                ("rx", &format_mbps(point.rx_bps)),
+                ("tx", &format_mbps(point.tx_bps)),
+            ],
+        )
+    };
+    let lines: Vec<String> = [
+        state
+            .wan_traffic
+            .lock()
+            .unwrap()
+            .latest(&active.router.name)
+            .map(|point| format("tray.traffic_wan", point)),
+        state
+            .traffic
+            .lock()
+            .unwrap()
+            .latest(&iface.mac)
+            .map(|point| format("tray.traffic_device", point)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
 }
 
-fn schedule_refresh_after(app: tauri::AppHandle, state: Arc<AppState>, delay: Duration) {
-    std::thread::spawn(move || {
-        std::thread::sleep(delay);
-        refresh_tray(&app, &state);
-    });
+fn online_count(states: &[ActiveState]) -> usize {
+    states.iter().map(|active| active.online_clients).sum()
 }
 
-fn main() {
-    let app_state = Arc::new(AppState::default());
-    {
-        let mut routers = app_state.routers.lock().unwrap();
-        *routers = load_routers();
+fn update_badge(app: &tauri::AppHandle, state: &AppState, online: usize) {
+    let wanted = state.settings.lock().unwrap().icon_badge.then_some(online);
+    let mut shown = state.badge.lock().unwrap();
+    if *shown == wanted {
+        return;
+    }
+    let icon = match wanted {
+        Some(count) => badge::with_count(count),
+        None => badge::plain(),
+    };
+    let Some(icon) = icon else {
+        return;
+    };
+    #[cfg(target_os = "linux")]
+    if let Some(handle) = state.linux_tray.lock().unwrap().as_ref() {
+        handle.update(|tray| tray.icon = Some(icon));
+        *shown = wanted;
+        return;
     }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_icon(Some(icon));
+        *shown = wanted;
+    }
+}
 
-    let state_handle = app_state.clone();
+fn spawn_staleness_watcher(app: tauri::AppHandle, state: Arc<AppState>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(30));
+        update_tray_status(&app, &state);
+    });
+}
 
-    let tray = SystemTray::new();
+const TRAFFIC_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
 
-    tauri::Builder::default()
-        .manage(app_state)
-        .system_tray(tray)
-        .on_system_tray_event(move |app, event| match event {
-            SystemTrayEvent::MenuItemClick { id, .. } => {
-                let id = id.as_str();
-                if id == "quit" {
-                    app.exit(0);
-                } else if id == "settings" || id == "add_router" {
-                    open_settings_window(app);
-                } else if id == "refresh" {
-                    if let Some(state) = app.try_state::<Arc<AppState>>() {
-                        refresh_tray(app, &state);
-                    }
-                } else if id.starts_with("policy|") {
-                    let rest = &id["policy|".len()..];
-                    let parts: Vec<&str> = rest.split('|').collect();
-                    if parts.len() >= 2 {
-                        let mac = decode_mac(parts[0]);
-                        let action = parts[1];
-                        if let Some(state) = app.try_state::<Arc<AppState>>() {
-                            let routers = state.routers.lock().unwrap().clone();
-                            if let Ok(Some(active)) = build_active_state(&routers) {
-                                let result = match action {
-                                    "default" => apply_policy(
-                                        &mac,
-                                        None,
-                                        false,
-                                        &active.router,
-                                        &active.active_address,
-                                    ),
-                                    "blocked" => apply_policy(
-                                        &mac,
-                                        None,
-                                        true,
-                                        &active.router,
-                                        &active.active_address,
-                                    ),
-                                    "set" => {
-                                        let policy = match parts.get(2) {
-                                            Some(value) => Some(*value),
-                                            None => None,
-                                        };
-                                        if policy.is_none() {
-                                            Err(anyhow::anyhow!("missing policy"))
-                                        } else {
-                                            apply_policy(
-                                                &mac,
-                                                policy,
-                                                false,
-                                                &active.router,
-                                                &active.active_address,
-                                            )
-                                        }
-                                    }
-                                    _ => Ok(()),
-                                };
-                                if result.is_err() {
-                                    eprintln!("Failed to apply policy");
-                                } else if let Some(state) = app.try_state::<Arc<AppState>>() {
-                                    let override_policy = match action {
-                                        "default" => Some(PolicyOverride {
-                                            policy: None,
-                                            deny: false,
-                                        }),
-                                        "blocked" => Some(PolicyOverride {
-                                            policy: None,
-                                            deny: true,
-                                        }),
-                                        "set" => parts.get(2).map(|value| PolicyOverride {
-                                            policy: Some((*value).to_string()),
-                                            deny: false,
-                                        }),
-                                        _ => None,
-                                    };
-                                    if let Some(override_policy) = override_policy {
-                                        let mut overrides =
-                                            state.policy_overrides.lock().unwrap();
-                                        overrides.insert(mac.clone(), override_policy);
-                                    }
-                                }
-                                refresh_tray(app, &state);
-                            }
-                        }
-                    }
+/// Polls the clients of every router currently shown in the tray for traffic
+/// counters and presence.
+fn spawn_client_monitor(app: tauri::AppHandle, state: Arc<AppState>) {
+    let mut last_polled: HashMap<String, Instant> = HashMap::new();
+    // Whether each router's clients were online at the last sample.
+    let mut was_online: HashMap<String, HashMap<String, bool>> = HashMap::new();
+    let mut arrivals = arrivals::ArrivalTracker::default();
+    let mut away_tracker = away::AwayTracker::default();
+    let mut network = local_interfaces_fingerprint();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TRAFFIC_SAMPLE_INTERVAL);
+        if secrets::is_locked() {
+            // Idle, not hung; a restart wouldn't unlock anything.
+            #[cfg(target_os = "linux")]
+            systemd::watchdog();
+            continue;
+        }
+        // Joining another network changes which routers are reachable.
+        let current = local_interfaces_fingerprint();
+        if current != network {
+            network = current;
+            log::info!("Local network changed, refreshing");
+            request_refresh(&state);
+        }
+        // Routers with their own interval are skipped until it has passed.
+        let now = Instant::now();
+        let routers: Vec<String> = state
+            .active_states
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|active| {
+                let interval = active
+                    .router
+                    .refresh_interval_secs
+                    .map_or(TRAFFIC_SAMPLE_INTERVAL, Duration::from_secs);
+                last_polled
+                    .get(&active.router.name)
+                    .is_none_or(|last| now.duration_since(*last) >= interval)
+            })
+            .map(|active| active.router.name.clone())
+            .collect();
+        for name in &routers {
+            last_polled.insert(name.clone(), now);
+        }
+        let mut tick_deltas = Vec::new();
+        let mut tick_names = HashMap::new();
+        let mut tick_clients = Vec::new();
+        let mut tick_new = Vec::new();
+        let mut tick_intruders = Vec::new();
+        let lockdown = state.settings.lock().unwrap().lockdown;
+        for name in routers {
+            // Failures already surface through the regular refresh; a missed
+            // sample only leaves a gap in the history.
+            let Ok(client) = connect_router(&state, &name) else {
+                continue;
+            };
+            let Ok(clients) = client.get_online_clients() else {
+                continue;
+            };
+            let wan = client.get_wan_traffic().ok().and_then(|wan| {
+                state
+                    .wan_traffic
+                    .lock()
+                    .unwrap()
+                    .record(&HashMap::from([(name.clone(), wan)]))
+                    .pop()
+            });
+            let deltas = state
+                .traffic
+                .lock()
+                .unwrap()
+                .record(&client_traffic(&clients));
+            let names: HashMap<String, String> = clients
+                .iter()
+                .filter_map(|c| c.name.clone().map(|name| (c.mac.clone(), name)))
+                .collect();
+            influx::record(&name, &deltas, &names, wan.as_ref());
+            if let Some(store) = state.usage.lock().unwrap().as_mut() {
+                if let Err(err) = store.add(&deltas, &names) {
+                    log::warn!("Failed to record usage: {err}");
                 }
             }
-            SystemTrayEvent::LeftClick { .. }
-            | SystemTrayEvent::RightClick { .. }
-            | SystemTrayEvent::DoubleClick { .. } => {
-                if let Some(state) = app.try_state::<Arc<AppState>>() {
-                    refresh_tray(app, &state);
-                    schedule_refresh_after(
-                        app.clone(),
-                        state.inner().clone(),
-                        Duration::from_secs(5),
-                    );
+            if let Some(store) = state.presence.lock().unwrap().as_mut() {
+                let devices: Vec<(String, bool)> = clients
+                    .iter()
+                    .map(|c| (c.mac.clone(), client_is_online(c)))
+                    .collect();
+                match store.record(&devices) {
+                    Ok(new) => tick_new.extend(new.into_iter().map(|mac| (name.clone(), mac))),
+                    Err(err) => log::warn!("Failed to record presence: {err}"),
                 }
             }
-            _ => {}
-        })
-        .invoke_handler(tauri::generate_handler![
-            list_routers,
-            save_router,
-            delete_router,
-            refresh_tray_cmd
-        ])
+            let first_sample = was_online.get(&name).is_none_or(HashMap::is_empty);
+            let joined = report_joins(
+                &state,
+                &name,
+                &clients,
+                was_online.entry(name.clone()).or_default(),
+            );
+            if lockdown {
+                // The first sample only sets the baseline for join events,
+                // but unknown devices already online then must not get a
+                // free pass.
+                tick_intruders.extend(
+                    clients
+                        .iter()
+                        .filter(|c| {
+                            let arrived = if first_sample {
+                                client_is_online(c)
+                            } else {
+                                joined.contains(&c.mac)
+                            };
+                            arrived && !c.deny && !client_is_registered(c)
+                        })
+                        .map(|c| (name.clone(), c.clone())),
+                );
+            }
+            tick_deltas.extend(deltas);
+            tick_names.extend(names);
+            tick_clients.extend(clients.into_iter().map(|c| (name.clone(), c)));
+        }
+        check_bandwidth_alerts(&app, &state, &tick_deltas, &tick_names);
+        // Blocked devices get their own notification with an approve action.
+        tick_new.retain(|(router, mac)| {
+            !tick_intruders
+                .iter()
+                .any(|(r, c)| r == router && &c.mac == mac)
+        });
+        notify_new_devices(&app, &state, &tick_new, &tick_names);
+        notify_arrivals(&app, &state, &mut arrivals, &was_online, &tick_names);
+        let away = away_switch(&state, &mut away_tracker, &was_online);
+        // Policy changes go through the worker so they can't race a tray
+        // action on the same device.
+        state
+            .worker
+            .send(worker::Message::Action(Box::new(move |app, state| {
+                lock_down(app, state, &tick_intruders);
+                if let Some(away) = away {
+                    set_away_mode(app, state, away);
+                }
+                enforce_quotas(app, state, &tick_clients);
+                enforce_bedtimes(app, state);
+                expire_guest_vouchers(app, state);
+                // Reaching here means neither thread is stuck.
+                #[cfg(target_os = "linux")]
+                systemd::watchdog();
+            })));
+        update_tray_status(&app, &state);
+        let _ = app.emit("traffic-updated", ());
+        send_weekly_summary(&app, &state);
+    });
+}
+
+/// Fires device_joined and device_left events for clients that came online
+/// or went away since the last sample. The first sample of a router only
+/// sets the baseline.
+fn report_joins(
+    state: &AppState,
+    router: &str,
+    clients: &[ClientInfo],
+    was_online: &mut HashMap<String, bool>,
+) -> Vec<String> {
+    let first = was_online.is_empty();
+    let mut now_online: HashMap<String, bool> = clients
+        .iter()
+        .map(|c| (c.mac.clone(), client_is_online(c)))
+        .collect();
+    // Clients the router stopped listing have left too.
+    for (mac, online) in was_online.iter() {
+        if *online {
+            now_online.entry(mac.clone()).or_insert(false);
+        }
+    }
+    let mut joined = Vec::new();
+    for (mac, online) in &now_online {
+        if first || was_online.get(mac).copied().unwrap_or(false) == *online {
+            continue;
+        }
+        let client = clients.iter().find(|c| &c.mac == mac);
+        let kind = if *online {
+            joined.push(mac.clone());
+            webhooks::EventKind::DeviceJoined
+        } else {
+            webhooks::EventKind::DeviceLeft
+        };
+        fire_event(
+            state,
+            kind,
+            router,
+            serde_json::json!({
+                "mac": mac,
+                "name": client.and_then(|c| c.name.clone()),
+                "ip": client.and_then(|c| c.ip.clone()),
+            }),
+        );
+    }
+    *was_online = now_online;
+    joined
+}
+
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+const ROUTER_LOG_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Copies new lines of the routers' logs to files and the syslog server
+/// while collection is on.
+fn spawn_log_collector(state: Arc<AppState>) {
+    std::thread::spawn(move || {
+        let mut collector = syslog::Collector::new(log_dir());
+        loop {
+            std::thread::sleep(ROUTER_LOG_INTERVAL);
+            let settings = state.settings.lock().unwrap().router_log.clone();
+            if !settings.enabled || secrets::is_locked() {
+                continue;
+            }
+            let routers: Vec<String> = state
+                .active_states
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|active| active.router.name.clone())
+                .collect();
+            for name in routers {
+                let entries = connect_router(&state, &name)
+                    .and_then(|client| Ok(client.get_log(syslog::MAX_LINES)?));
+                match entries {
+                    Ok(entries) => collector.collect(&settings, &name, entries),
+                    Err(err) => log::debug!("Failed to read the log of {name}: {err}"),
+                }
+            }
+        }
+    });
+}
+
+/// Looks for a newer GitHub release shortly after start and then twice a
+/// day, notifying once per new version.
+fn spawn_update_checker(app: tauri::AppHandle, state: Arc<AppState>) {
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_secs(60));
+        loop {
+            if state.settings.lock().unwrap().check_updates {
+                check_for_update(&app, &state);
+            }
+            std::thread::sleep(UPDATE_CHECK_INTERVAL);
+        }
+    });
+}
+
+fn check_for_update(app: &tauri::AppHandle, state: &Arc<AppState>) {
+    let release = match updates::check(&app.package_info().version) {
+        Ok(release) => release,
+        Err(err) => {
+            log::info!("Update check failed: {err}");
+            return;
+        }
+    };
+    let previous = std::mem::replace(&mut *state.update.lock().unwrap(), release.clone());
+    let Some(release) = release else {
+        return;
+    };
+    if previous.as_ref() != Some(&release) {
+        let version = release.version.to_string();
+        send_email(
+            state,
+            |email| email.update,
+            tf("email.update_subject", &[("version", &version)]),
+            tf(
+                "email.update_body",
+                &[("version", &version), ("url", &release.url)],
+            ),
+        );
+        notify(
+            app,
+            state,
+            Alert::new(
+                Category::Update,
+                t("notify.update_available"),
+                tf(
+                    "notify.update_available_body",
+                    &[("version", &release.version.to_string())],
+                ),
+            ),
+        );
+        request_refresh(state);
+    }
+}
+
+/// Configuration details for crash reports, without addresses, names or
+/// credentials.
+fn crash_summary(routers: &[RouterInfo], settings: &AppSettings) -> String {
+    format!(
+        "  routers: {} ({} with remote access)\n  secret store: {:?}\n  language: {}\n  \
+         bandwidth alerts: {}\n  data quotas: {}",
+        routers.len(),
+        routers.iter().filter(|r| r.allow_remote).count(),
+        settings.secret_store,
+        settings.language,
+        settings.bandwidth_alerts.len(),
+        settings.data_quotas.len(),
+    )
+}
+
+/// Tells the user about a crash in the previous run and, when configured,
+/// sends the report off.
+fn offer_crash_report(app: &tauri::AppHandle, state: &Arc<AppState>) {
+    let Some(report) = crash::pending() else {
+        return;
+    };
+    *state.crash_report.lock().unwrap() = Some(report.clone());
+    show_notification(app, &t("notify.crashed"), &t("notify.crashed_body"));
+    request_refresh(state);
+    let url = state.settings.lock().unwrap().crash_report_url.clone();
+    if url.trim().is_empty() {
+        return;
+    }
+    std::thread::spawn(move || {
+        if let Err(err) = crash::submit(url.trim(), &report) {
+            log::warn!("Failed to send crash report: {err}");
+        }
+    });
+}
+
+fn open_crash_report(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let Some(report) = state.crash_report.lock().unwrap().take() else {
+        return;
+    };
+    let report = crash::mark_seen(&report);
+    if let Err(err) = open_in_system(&report) {
+        record_error(app, &state, err.to_string());
+    }
+    request_refresh(&state);
+}
+
+fn open_update_page(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let Some(url) = state.update.lock().unwrap().as_ref().map(|r| r.url.clone()) else {
+        return;
+    };
+    if let Err(err) = app.opener().open_url(url, None::<&str>) {
+        record_error(app, &state, err.to_string());
+    }
+}
+
+const OUTAGE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Failed checks in a row before the internet counts as down, so a single
+/// lost probe doesn't log an outage.
+const OUTAGE_THRESHOLD: u32 = 2;
+
+/// Checks the internet connection behind every router shown in the tray,
+/// asking the router's own checker first and falling back to probing public
+/// hosts from this computer.
+fn spawn_outage_monitor(app: tauri::AppHandle, state: Arc<AppState>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(OUTAGE_CHECK_INTERVAL);
+        if secrets::is_locked() {
+            continue;
+        }
+        let routers: Vec<(String, bool)> = state
+            .active_states
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|active| (active.router.name.clone(), active.remote))
+            .collect();
+        sample_availability(&state);
+        for (name, remote) in routers {
+            let reported = connect_router(&state, &name)
+                .and_then(|client| Ok(client.check_internet()?))
+                .ok();
+            // Local probes say nothing about a router we only reach remotely.
+            if remote && reported.is_none() {
+                continue;
+            }
+            let online = reported == Some(true) || (!remote && outages::probe_internet());
+            record_wan_status(&app, &state, &name, online);
+        }
+    });
+}
+
+/// Records whether each saved router answers at the address it would be
+/// used at now. Routers with no usable address are not sampled, so being
+/// away from home doesn't count as downtime.
+fn sample_availability(state: &AppState) {
+    let routers = state.routers.lock().unwrap().clone();
+    let networks = local_networks();
+    for router in routers {
+        let Some(address) = router_address(&router, &networks).or_else(|| remote_address(&router))
+        else {
+            continue;
+        };
+        let up = KeeneticRouter::new(&address, &router.login, "", &router.name)
+            .check_reachable()
+            .is_ok();
+        if let Some(store) = state.availability.lock().unwrap().as_ref() {
+            if let Err(err) = store.record(&router.name, up) {
+                log::warn!("Failed to record availability: {err}");
+            }
+        }
+    }
+}
+
+fn record_wan_status(app: &tauri::AppHandle, state: &AppState, router: &str, online: bool) {
+    let failures = {
+        let mut failures = state.wan_failures.lock().unwrap();
+        if online {
+            failures.remove(router);
+            0
+        } else {
+            let count = failures.entry(router.to_string()).or_insert(0);
+            *count += 1;
+            *count
+        }
+    };
+    let guard = state.outages.lock().unwrap();
+    let Some(store) = guard.as_ref() else {
+        return;
+    };
+    let down = store.is_down(router).unwrap_or(false);
+    let now = Local::now();
+    if online && down {
+        if let Ok(Some(seconds)) = store.end(router, now) {
+            fire_event(
+                state,
+                webhooks::EventKind::InternetUp,
+                router,
+                serde_json::json!({ "duration_seconds": seconds }),
+            );
+            notify(
+                app,
+                state,
+                Alert::new(
+                    Category::Outage,
+                    t("notify.internet_restored"),
+                    tf(
+                        "notify.internet_restored_body",
+                        &[
+                            ("name", router),
+                            ("duration", &outages::format_duration(seconds)),
+                        ],
+                    ),
+                )
+                .router(router),
+            );
+        }
+    } else if !online && !down && failures == OUTAGE_THRESHOLD {
+        // Date the outage from the first failed check.
+        let start = now - OUTAGE_CHECK_INTERVAL * (OUTAGE_THRESHOLD - 1);
+        if let Err(err) = store.begin(router, start) {
+            log::warn!("Failed to record outage: {err}");
+        }
+        fire_event(
+            state,
+            webhooks::EventKind::InternetDown,
+            router,
+            serde_json::json!({ "since": start.to_rfc3339() }),
+        );
+        // One mail when outages start to pile up, not one per outage.
+        let recent = store.count_since(router, now - REPEATED_OUTAGE_WINDOW);
+        if recent.is_ok_and(|count| count == REPEATED_OUTAGES) {
+            send_email(
+                state,
+                |email| email.repeated_outages,
+                tf("email.outages_subject", &[("name", router)]),
+                tf(
+                    "email.outages_body",
+                    &[("name", router), ("count", &REPEATED_OUTAGES.to_string())],
+                ),
+            );
+        }
+        notify(
+            app,
+            state,
+            Alert::new(
+                Category::Outage,
+                t("notify.internet_down"),
+                tf("notify.internet_down_body", &[("name", router)]),
+            )
+            .router(router)
+            .critical()
+            .action("outages", t("notify.action_outages"))
+            .action("ignore", t("notify.action_ignore")),
+        );
+    }
+}
+
+/// Outages of one router within a day that are worth an email.
+const REPEATED_OUTAGES: u32 = 3;
+const REPEATED_OUTAGE_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Mails `subject` in the background if email is set up and `wanted` says
+/// this kind of event should be mailed. Quiet hours don't apply; mail
+/// doesn't wake anyone.
+fn send_email(
+    state: &AppState,
+    wanted: impl Fn(&email::EmailSettings) -> bool,
+    subject: String,
+    body: String,
+) {
+    let settings = state.settings.lock().unwrap().notifications.email.clone();
+    if !settings.enabled || !wanted(&settings) || demo::enabled() {
+        return;
+    }
+    let password = get_password(email::PASSWORD_KEY);
+    std::thread::spawn(move || {
+        if let Err(err) = email::send(
+            &settings,
+            password.as_deref().map(String::as_str),
+            &subject,
+            &body,
+        ) {
+            log::warn!("Failed to send email: {err:#}");
+        }
+    });
+}
+
+/// Announces watched devices coming home or leaving. A device counts as
+/// home while any router sees it online.
+fn notify_arrivals(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    tracker: &mut arrivals::ArrivalTracker,
+    was_online: &HashMap<String, HashMap<String, bool>>,
+    names: &HashMap<String, String>,
+) {
+    let settings = state.settings.lock().unwrap().notifications.clone();
+    let router_of = |mac: &str| {
+        was_online
+            .iter()
+            .find(|(_, clients)| clients.get(mac).copied().unwrap_or(false))
+            .map(|(router, _)| router.clone())
+    };
+    let devices: Vec<(String, bool)> = settings
+        .watched_devices
+        .iter()
+        .map(|device| {
+            let mac = device.mac.to_lowercase();
+            let online = router_of(&mac).is_some();
+            (mac, online)
+        })
+        .collect();
+    let debounce = Duration::from_secs(settings.presence_debounce_minutes * 60);
+    for (mac, online) in tracker.update(&devices, debounce, Instant::now()) {
+        let label = settings
+            .watched_devices
+            .iter()
+            .find(|device| device.mac.eq_ignore_ascii_case(&mac))
+            .map(|device| device.label.trim())
+            .filter(|label| !label.is_empty());
+        let name = label
+            .or_else(|| names.get(&mac).map(String::as_str))
+            .unwrap_or(&mac);
+        let (title, body) = if online {
+            ("notify.arrived", "notify.arrived_body")
+        } else {
+            ("notify.left", "notify.left_body")
+        };
+        let mut alert = Alert::new(
+            Category::Presence,
+            tf(title, &[("name", name)]),
+            tf(body, &[("name", name)]),
+        );
+        if let Some(router) = router_of(&mac) {
+            alert = alert.router(&router);
+        }
+        notify(app, state, alert);
+    }
+}
+
+/// Lockdown mode: blocks unregistered devices that just joined and offers to
+/// let each one in.
+fn lock_down(app: &tauri::AppHandle, state: &AppState, joined: &[(String, ClientInfo)]) {
+    for (router, client) in joined {
+        let target = PolicyOverride {
+            policy: client.policy.clone(),
+            deny: true,
+        };
+        if let Err(err) = set_client_policy(state, router, &client.mac, target) {
+            record_error(app, state, err.to_string());
+            continue;
+        }
+        let name = client.name.as_deref().unwrap_or(&client.mac);
+        notify(
+            app,
+            state,
+            Alert::new(
+                Category::NewDevice,
+                t("notify.lockdown"),
+                tf(
+                    "notify.lockdown_body",
+                    &[("name", name), ("router", router)],
+                ),
+            )
+            .router(router)
+            .critical()
+            .action(
+                format!("approve|{router}|{}", client.mac),
+                t("notify.action_approve"),
+            )
+            .action("ignore", t("notify.action_ignore")),
+        );
+    }
+}
+
+/// Lets a device blocked by lockdown in: registers it on the router so it
+/// isn't blocked again, then lifts the block.
+fn approve_device(state: &AppState, router: &str, mac: &str) -> Result<()> {
+    let client = connect_router(state, router)?;
+    let host = client
+        .get_online_clients()?
+        .into_iter()
+        .find(|c| c.mac == mac);
+    let name = host
+        .as_ref()
+        .and_then(|c| c.name.clone())
+        .unwrap_or_else(|| mac.to_string());
+    client.register_device(mac, &name)?;
+    let target = PolicyOverride {
+        policy: host.and_then(|c| c.policy),
+        deny: false,
+    };
+    set_client_policy(state, router, mac, target)
+}
+
+fn notify_new_devices(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    new: &[(String, String)],
+    names: &HashMap<String, String>,
+) {
+    // A new device is only worth an email when nobody should be home.
+    let away = state.away.lock().unwrap().is_some();
+    for (router, mac) in new {
+        let name = names.get(mac).map_or(mac.as_str(), |name| name.as_str());
+        if away {
+            send_email(
+                state,
+                |email| email.new_device,
+                tf("email.new_device_subject", &[("name", name)]),
+                tf(
+                    "email.new_device_body",
+                    &[("name", name), ("mac", mac), ("router", router)],
+                ),
+            );
+        }
+        notify(
+            app,
+            state,
+            Alert::new(
+                Category::NewDevice,
+                t("notify.new_device"),
+                tf(
+                    "notify.new_device_body",
+                    &[("name", name), ("router", router)],
+                ),
+            )
+            .router(router)
+            .critical()
+            .action(format!("block|{router}|{mac}"), t("notify.action_block"))
+            .action("ignore", t("notify.action_ignore")),
+        );
+    }
+}
+
+fn format_mbps(bps: f64) -> String {
+    format!("{:.0}", bps / 1_000_000.0)
+}
+
+fn check_bandwidth_alerts(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    deltas: &[traffic::TrafficDelta],
+    names: &HashMap<String, String>,
+) {
+    let alerts = state.settings.lock().unwrap().bandwidth_alerts.clone();
+    if alerts.is_empty() {
+        return;
+    }
+    let triggered = state.alerts.lock().unwrap().check(&alerts, deltas);
+    for hit in triggered {
+        let offender = hit
+            .offender
+            .as_ref()
+            .map(|mac| names.get(mac).unwrap_or(mac).clone())
+            .unwrap_or_default();
+        let args = [
+            ("name", offender.as_str()),
+            ("limit", &format!("{}", hit.alert.mbps)),
+            ("minutes", &hit.alert.minutes.to_string()),
+            ("rate", &format_mbps(hit.bps)),
+        ];
+        let body = if hit.alert.mac.is_some() {
+            tf("notify.bandwidth_device", &args)
+        } else {
+            tf("notify.bandwidth_total", &args)
+        };
+        notify(
+            app,
+            state,
+            Alert::new(Category::Usage, t("notify.bandwidth_alert"), body),
+        );
+    }
+}
+
+/// Blocks devices that went over today's quota and lifts yesterday's blocks,
+/// putting back the policy each device had before.
+fn enforce_quotas(app: &tauri::AppHandle, state: &Arc<AppState>, clients: &[(String, ClientInfo)]) {
+    let quotas = state.settings.lock().unwrap().data_quotas.clone();
+    let today = Local::now().date_naive();
+    let (blocks, used) = {
+        let guard = state.usage.lock().unwrap();
+        let Some(store) = guard.as_ref() else {
+            return;
+        };
+        let blocks = quota::load_blocks(store);
+        if quotas.is_empty() && blocks.is_empty() {
+            return;
+        }
+        (blocks, store.day_totals(today).unwrap_or_default())
+    };
+    let name_of = |mac: &str| {
+        clients
+            .iter()
+            .find(|(_, c)| c.mac == mac)
+            .and_then(|(_, c)| c.name.clone())
+            .unwrap_or_else(|| mac.to_string())
+    };
+    let mut changed = false;
+    let mut kept = Vec::new();
+    for block in blocks {
+        if block.day == today {
+            kept.push(block);
+            continue;
+        }
+        let target = PolicyOverride {
+            policy: block.policy.clone(),
+            deny: false,
+        };
+        match set_client_policy(state, &block.router, &block.mac, target) {
+            Ok(()) => {
+                changed = true;
+                notify(
+                    app,
+                    state,
+                    Alert::new(
+                        Category::PolicyChange,
+                        t("notify.quota_reset"),
+                        tf("notify.quota_reset_body", &[("name", &name_of(&block.mac))]),
+                    )
+                    .router(&block.router),
+                );
+            }
+            // Try again on the next tick; the router may be unreachable.
+            Err(_) => kept.push(block),
+        }
+    }
+    for limit in &quotas {
+        let mac = limit.mac.to_lowercase();
+        if kept.iter().any(|b| b.mac == mac) || used.get(&mac).copied().unwrap_or(0) < limit.bytes()
+        {
+            continue;
+        }
+        let Some((router, client)) = clients.iter().find(|(_, c)| c.mac == mac) else {
+            continue;
+        };
+        if client.deny {
+            continue;
+        }
+        let target = PolicyOverride {
+            policy: client.policy.clone(),
+            deny: true,
+        };
+        if let Err(err) = set_client_policy(state, router, &mac, target) {
+            record_error(app, state, err.to_string());
+            continue;
+        }
+        changed = true;
+        fire_event(
+            state,
+            webhooks::EventKind::QuotaExceeded,
+            router,
+            serde_json::json!({
+                "mac": mac,
+                "name": name_of(&mac),
+                "limit_bytes": limit.bytes(),
+                "used_bytes": used.get(&mac).copied().unwrap_or(0),
+            }),
+        );
+        kept.push(quota::QuotaBlock {
+            mac: mac.clone(),
+            router: router.clone(),
+            day: today,
+            policy: client.policy.clone(),
+        });
+        notify(
+            app,
+            state,
+            Alert::new(
+                Category::PolicyChange,
+                t("notify.quota_blocked"),
+                tf(
+                    "notify.quota_blocked_body",
+                    &[
+                        ("name", &name_of(&mac)),
+                        ("limit", &usage::format_bytes(limit.bytes())),
+                    ],
+                ),
+            )
+            .router(router),
+        );
+    }
+    if !changed {
+        return;
+    }
+    if let Some(store) = state.usage.lock().unwrap().as_ref() {
+        if let Err(err) = quota::save_blocks(store, &kept) {
+            log::warn!("Failed to save quota blocks: {err}");
+        }
+    }
+    request_refresh(state);
+}
+
+/// Blocks a child's devices when their bedtime starts and puts their profile
+/// back when it ends. Only the start and end act, so bedtime started or
+/// ended by hand in between is left alone.
+fn enforce_bedtimes(app: &tauri::AppHandle, state: &Arc<AppState>) {
+    let children = state.settings.lock().unwrap().family.clone();
+    let now = Local::now().time();
+    for child in children {
+        let Some(bedtime) = child.bedtime else {
+            continue;
+        };
+        let inside = bedtime.contains(now);
+        let previous = state
+            .bedtime
+            .lock()
+            .unwrap()
+            .insert(child.name.clone(), inside);
+        // Right after start only a bedtime already under way acts.
+        if previous == Some(inside) {
+            continue;
+        }
+        save_pending(state);
+        if previous.is_none() && !inside {
+            continue;
+        }
+        if let Err(err) = set_bedtime_mode(app, state, &child, inside) {
+            record_error(app, state, err.to_string());
+        }
+    }
+}
+
+/// Takes down guest networks whose voucher ran out; one the router refused
+/// is tried again on the next tick.
+fn expire_guest_vouchers(app: &tauri::AppHandle, state: &AppState) {
+    let expired = guest::expired(&state.guest_vouchers.lock().unwrap(), Local::now());
+    if expired.is_empty() {
+        return;
+    }
+    for voucher in expired {
+        let result = connect_router(state, &voucher.router)
+            .and_then(|client| Ok(client.set_guest_wifi(false, None)?));
+        if let Err(err) = result {
+            log::warn!("{}: failed to end guest access: {err}", voucher.router);
+            continue;
+        }
+        state
+            .guest_vouchers
+            .lock()
+            .unwrap()
+            .retain(|v| v.router != voucher.router);
+        notify(
+            app,
+            state,
+            Alert::new(
+                Category::PolicyChange,
+                t("notify.guest_ended"),
+                tf("notify.guest_ended_body", &[("ssid", &voucher.ssid)]),
+            )
+            .router(&voucher.router),
+        );
+    }
+    save_pending(state);
+}
+
+/// Whether away mode should turn on or off now, going by the user's devices
+/// on the away router; `None` also when the router hasn't been reached.
+fn away_switch(
+    state: &AppState,
+    tracker: &mut away::AwayTracker,
+    was_online: &HashMap<String, HashMap<String, bool>>,
+) -> Option<bool> {
+    let settings = state.settings.lock().unwrap().away.clone();
+    let active = state.away.lock().unwrap().is_some();
+    let remote = state
+        .active_states
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|active| active.router.name == settings.router)
+        .map(|active| active.remote);
+    let away = settings.is_away(remote, was_online.get(&settings.router))?;
+    tracker.update(active, away, settings.delay(), Instant::now())
+}
+
+/// Applies the away changes, remembering what each device had, or puts that
+/// back. Devices that couldn't be put back are kept and retried.
+fn set_away_mode(app: &tauri::AppHandle, state: &AppState, away: bool) {
+    if away == state.away.lock().unwrap().is_some() {
+        return;
+    }
+    let mut changed = Vec::new();
+    let router;
+    if away {
+        let settings = state.settings.lock().unwrap().away.clone();
+        let clients = match connect_router(state, &settings.router)
+            .and_then(|client| Ok(client.get_online_clients()?))
+        {
+            Ok(clients) => clients,
+            Err(err) => {
+                record_error(app, state, err.to_string());
+                return;
+            }
+        };
+        let mut saved = Vec::new();
+        for (target, device) in settings.plan(&clients) {
+            if let Err(err) = set_client_policy(state, &settings.router, &device.mac, target) {
+                record_error(app, state, err.to_string());
+                continue;
+            }
+            changed.push(client_display_name(state, &device.mac));
+            saved.push(device);
+        }
+        *state.away.lock().unwrap() = Some(saved);
+        router = settings.router;
+    } else {
+        let saved = state.away.lock().unwrap().take().unwrap_or_default();
+        let mut kept = Vec::new();
+        router = saved.first().map(|s| s.router.clone()).unwrap_or_default();
+        for device in saved {
+            match set_client_policy(state, &device.router, &device.mac, device.restore()) {
+                Ok(()) => changed.push(client_display_name(state, &device.mac)),
+                Err(err) => {
+                    record_error(app, state, err.to_string());
+                    kept.push(device);
+                }
+            }
+        }
+        if !kept.is_empty() {
+            *state.away.lock().unwrap() = Some(kept);
+        }
+    }
+    save_pending(state);
+    request_refresh(state);
+    let title = if away {
+        t("notify.away_started")
+    } else {
+        t("notify.away_ended")
+    };
+    notify(
+        app,
+        state,
+        Alert::new(Category::PolicyChange, title, changed.join(", ")).router(&router),
+    );
+}
+
+/// Blocks or unblocks every device of `child`, keeping their profile.
+fn set_bedtime_mode(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    child: &family::Child,
+    asleep: bool,
+) -> Result<()> {
+    let target = PolicyOverride {
+        policy: child.profile.clone(),
+        deny: asleep,
+    };
+    let results = apply_to_devices(app, state, &child.router, child.devices.clone(), &target);
+    let failed: Vec<String> = results
+        .iter()
+        .filter(|r| r.error.is_some())
+        .map(|r| client_display_name(state, &r.mac))
+        .collect();
+    if !failed.is_empty() {
+        anyhow::bail!(tf(
+            "error.bedtime_failed",
+            &[("name", &child.name), ("names", &failed.join(", "))]
+        ));
+    }
+    let title = if asleep {
+        t("notify.bedtime_started")
+    } else {
+        t("notify.bedtime_ended")
+    };
+    notify(
+        app,
+        state,
+        Alert::new(Category::PolicyChange, title, child.name.clone()).router(&child.router),
+    );
+    Ok(())
+}
+
+/// Applies a policy change without asking. The quota enforcer runs
+/// unattended; interactive callers confirm before calling this.
+fn set_client_policy(
+    state: &AppState,
+    router_name: &str,
+    mac: &str,
+    target: PolicyOverride,
+) -> Result<()> {
+    let router = find_router(state, router_name)?;
+    let address = router_address(&router, &local_networks())
+        .or_else(|| remote_address(&router))
+        .ok_or_else(|| anyhow::anyhow!(tf("error.unreachable", &[("name", &router.name)])))?;
+    set_interface_policy(state, &router, &address, mac, target)
+}
+
+/// On Mondays, once, notifies about the heaviest users of the past week.
+fn send_weekly_summary(app: &tauri::AppHandle, state: &AppState) {
+    const META_KEY: &str = "weekly_summary";
+    if !state.settings.lock().unwrap().weekly_usage_summary {
+        return;
+    }
+    let today = Local::now().date_naive();
+    if today.weekday() != Weekday::Mon {
+        return;
+    }
+    let guard = state.usage.lock().unwrap();
+    let Some(store) = guard.as_ref() else {
+        return;
+    };
+    if store.meta(META_KEY).ok().flatten() == Some(today.to_string()) {
+        return;
+    }
+    let rows: Vec<usage::DailyUsage> = match store.daily(8) {
+        Ok(rows) => rows.into_iter().filter(|r| r.day < today).collect(),
+        Err(_) => return,
+    };
+    let lines: Vec<String> = usage::totals(&rows)
+        .into_iter()
+        .take(3)
+        .map(|(name, bytes)| {
+            tf(
+                "notify.weekly_usage_line",
+                &[("name", &name), ("amount", &usage::format_bytes(bytes))],
+            )
+        })
+        .collect();
+    if !lines.is_empty() {
+        notify(
+            app,
+            state,
+            Alert::new(Category::Usage, t("notify.weekly_usage"), lines.join("\n")),
+        );
+    }
+    let _ = store.set_meta(META_KEY, &today.to_string());
+}
+
+fn record_error(app: &tauri::AppHandle, state: &AppState, message: String) {
+    log::error!("{message}");
+    state.errors.lock().unwrap().push(message);
+    let _ = app.emit("errors-updated", ());
+}
+
+/// Like `record_error`, but a router failing the same way on every poll is
+/// only logged once.
+fn record_router_error(app: &tauri::AppHandle, state: &AppState, router: &str, message: String) {
+    if !state
+        .errors
+        .lock()
+        .unwrap()
+        .push_for_router(router, message.clone())
+    {
+        return;
+    }
+    log::error!("{message}");
+    let _ = app.emit("errors-updated", ());
+}
+
+fn report_error(app: &tauri::AppHandle, message: String) {
+    match app.try_state::<Arc<AppState>>() {
+        Some(state) => record_error(app, &state, message),
+        None => log::error!("{message}"),
+    }
+}
+
+fn show_notification(app: &tauri::AppHandle, title: &str, body: &str) {
+    let _ = app.notification().builder().title(title).body(body).show();
+}
+
+/// Shows an alert and plays its sound unless the notification settings hold
+/// them back. Buttons send their ids to `handle_notification_action`; only
+/// Windows toasts can carry them, elsewhere the plain notification is shown.
+fn notify(app: &tauri::AppHandle, state: &AppState, alert: Alert) {
+    let settings = state.settings.lock().unwrap().notifications.clone();
+    if settings.silenced(&alert, Local::now().time()) {
+        log::debug!("Notification held back: {}", alert.title);
+        return;
+    }
+    record_event(
+        state,
+        "notification",
+        alert.router.as_deref(),
+        &serde_json::json!({
+            "category": alert.category,
+            "title": alert.title,
+            "body": alert.body,
+        }),
+    );
+    if let Some(sound) = settings.sound_for(&alert) {
+        sound::play(sound);
+    }
+    if settings.push.sends(alert.category) && !demo::enabled() {
+        push::send(&settings.push, get_token(push::TOKEN_KEY), &alert);
+    }
+    if !settings.shows(alert.category) {
+        return;
+    }
+    telegram::send(&format!("{}\n{}", alert.title, alert.body));
+    let Alert {
+        title,
+        body,
+        actions,
+        ..
+    } = alert;
+    #[cfg(target_os = "windows")]
+    {
+        let actions: Vec<toast::ToastAction> = actions
+            .iter()
+            .map(|(id, label)| toast::ToastAction {
+                id: id.clone(),
+                label: label.clone(),
+            })
+            .collect();
+        let handle = app.clone();
+        let shown = toast::show(
+            &app.config().identifier,
+            &title,
+            &body,
+            &actions,
+            move |id| handle_notification_action(&handle, &id),
+        );
+        match shown {
+            Ok(()) => return,
+            Err(err) => log::warn!("Failed to show toast: {err}"),
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    let _ = actions;
+    show_notification(app, &title, &body);
+}
+
+#[cfg(target_os = "windows")]
+fn handle_notification_action(app: &tauri::AppHandle, id: &str) {
+    if id == "outages" {
+        open_settings_page(app, "outages.html");
+    } else if let Some(rest) = id.strip_prefix("block|") {
+        let Some((router, mac)) = rest.split_once('|') else {
+            return;
+        };
+        let (router, mac) = (router.to_string(), mac.to_string());
+        queue_action(app, move |app, state| {
+            let Some(target) = policy_target("blocked", "") else {
+                return;
+            };
+            apply_policy_action(app, state, router, mac, target);
+        });
+    } else if let Some(rest) = id.strip_prefix("approve|") {
+        let Some((router, mac)) = rest.split_once('|') else {
+            return;
+        };
+        let (router, mac) = (router.to_string(), mac.to_string());
+        queue_action(app, move |app, state| {
+            if let Err(err) = approve_device(state, &router, &mac) {
+                record_error(app, state, err.to_string());
+            }
+            request_refresh(state);
+        });
+    }
+}
+
+fn copy_to_clipboard(app: &tauri::AppHandle, label: &str, value: &str) {
+    match app.clipboard().write_text(value) {
+        Ok(()) => show_notification(app, &t("notify.copied"), &format!("{label}: {value}")),
+        Err(err) => report_error(app, tf("error.copy_failed", &[("error", &err.to_string())])),
+    }
+}
+
+fn set_interface_policy(
+    state: &AppState,
+    router: &RouterInfo,
+    address: &str,
+    mac: &str,
+    target: PolicyOverride,
+) -> Result<()> {
+    apply_policy(mac, target.policy.as_deref(), target.deny, router, address)?;
+    fire_event(
+        state,
+        webhooks::EventKind::PolicyChanged,
+        &router.name,
+        serde_json::json!({
+            "mac": mac,
+            "name": client_display_name(state, mac),
+            "policy": target.policy,
+            "blocked": target.deny,
+        }),
+    );
+    state
+        .policy_overrides
+        .lock()
+        .unwrap()
+        .entry(router.name.clone())
+        .or_default()
+        .insert(mac.to_string(), target);
+    save_pending(state);
+    Ok(())
+}
+
+fn active_interface(state: &AppState) -> Option<(ActiveState, InterfaceInfo)> {
+    state
+        .active_states
+        .lock()
+        .unwrap()
+        .iter()
+        .find_map(|s| s.active_iface.clone().map(|iface| (s.clone(), iface)))
+}
+
+/// The question to ask before `target` is applied to `iface`, if it blocks it.
+fn block_prompt(
+    state: &AppState,
+    iface: &InterfaceInfo,
+    target: &PolicyOverride,
+) -> Option<String> {
+    (target.deny && !iface.deny).then(|| block_question(state, &iface.mac))
+}
+
+/// Applies `target` to this computer's interface; ask with `block_prompt`
+/// first.
+fn apply_to_active(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    active: &ActiveState,
+    iface: &InterfaceInfo,
+    target: PolicyOverride,
+) -> Result<()> {
+    let label = policy_label(target.policy.as_deref(), target.deny, &active.policies);
+    let result = set_interface_policy(
+        state,
+        &active.router,
+        &active.active_address,
+        &iface.mac,
+        target,
+    );
+    match &result {
+        Ok(()) => notify(
+            app,
+            state,
+            Alert::new(
+                Category::PolicyChange,
+                t("notify.policy_changed"),
+                format!("{}: {}", iface.display_name, label),
+            )
+            .router(&active.router.name),
+        ),
+        Err(err) => record_error(
+            app,
+            state,
+            tf("error.apply_failed", &[("error", &err.to_string())]),
+        ),
+    }
+    request_refresh(state);
+    result
+}
+
+fn cycle_active_policy(app: &tauri::AppHandle, state: &Arc<AppState>) {
+    let labels = state.settings.lock().unwrap().cycle_policies.clone();
+    let Some((active, iface)) = active_interface(state) else {
+        show_notification(app, &t("app.name"), &t("notify.no_active_iface"));
+        return;
+    };
+    let targets: Vec<PolicyOverride> = labels
+        .iter()
+        .filter_map(|label| resolve_policy_label(label, &active.policies))
+        .collect();
+    if targets.is_empty() {
+        show_notification(app, &t("app.name"), &t("notify.no_cycle_policies"));
+        return;
+    }
+    let current = PolicyOverride {
+        policy: if iface.deny {
+            None
+        } else {
+            iface.policy.clone()
+        },
+        deny: iface.deny,
+    };
+    let next = match targets.iter().position(|t| *t == current) {
+        Some(pos) => targets[(pos + 1) % targets.len()].clone(),
+        None => targets[0].clone(),
+    };
+    let question = block_prompt(state, &iface, &next);
+    after_confirmation(app, state, question, move |app, state| {
+        let _ = apply_to_active(app, state, &active, &iface, next);
+    });
+}
+
+fn apply_named_policy(app: &tauri::AppHandle, state: &Arc<AppState>, label: &str) {
+    let Some((active, iface)) = active_interface(state) else {
+        show_notification(app, &t("app.name"), &t("notify.no_active_iface"));
+        return;
+    };
+    let Some(target) = resolve_policy_label(label, &active.policies) else {
+        show_notification(
+            app,
+            &t("app.name"),
+            &tf("notify.unknown_policy", &[("name", label)]),
+        );
+        return;
+    };
+    let question = block_prompt(state, &iface, &target);
+    after_confirmation(app, state, question, move |app, state| {
+        let _ = apply_to_active(app, state, &active, &iface, target);
+    });
+}
+
+/// Gates a destructive action behind a yes/no prompt and, when the user
+/// asked for it in the settings, OS authentication. It blocks until they are
+/// answered, so it must not be called on the main thread or the worker.
+fn require_confirmation(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    reason: &str,
+    question: &str,
+) -> Result<()> {
+    let (prompt, authenticate) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.confirm_prompts, settings.confirm_destructive)
+    };
+    let not_confirmed = || anyhow::anyhow!(t("error.not_confirmed"));
+    if prompt
+        && !app
+            .dialog()
+            .message(question)
+            .title(t("confirm.title"))
+            .buttons(MessageDialogButtons::YesNo)
+            .blocking_show()
+    {
+        return Err(not_confirmed());
+    }
+    if authenticate && !os_auth::confirm(reason)? {
+        return Err(not_confirmed());
+    }
+    Ok(())
+}
+
+/// Runs `action` right away, or when there is a `question`, on the worker
+/// once the user confirms it. The prompt waits in an async task, so the
+/// worker goes on refreshing and running other actions while it is open.
+/// Called from the worker.
+fn after_confirmation(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    question: Option<String>,
+    action: impl FnOnce(&tauri::AppHandle, &Arc<AppState>) + Send + 'static,
+) {
+    let Some(question) = question else {
+        action(app, state);
+        return;
+    };
+    let (app, state) = (app.clone(), state.clone());
+    tauri::async_runtime::spawn(async move {
+        match confirm_destructive(&app, &state, t("confirm.block"), question).await {
+            Ok(()) => queue_action(&app, action),
+            Err(err) => record_error(&app, &state, err.to_string()),
+        }
+    });
+}
+
+/// `require_confirmation` for async commands. The prompts wait on a blocking
+/// thread, so the command's runtime thread stays free while they are open.
+async fn confirm_destructive(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    reason: String,
+    question: String,
+) -> Result<()> {
+    let (app, state) = (app.clone(), state.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        require_confirmation(&app, &state, &reason, &question)
+    })
+    .await?
+}
+
+/// The prompt for blocking `mac`, with a stronger warning when it is the
+/// interface this computer is connected through.
+fn block_question(state: &AppState, mac: &str) -> String {
+    if let Some(own) = own_interface_name(state, mac) {
+        return tf("confirm.block_self_question", &[("name", &own)]);
+    }
+    tf(
+        "confirm.block_question",
+        &[("name", &client_display_name(state, mac))],
+    )
+}
+
+/// The name a client is shown under in the tray, or its MAC if it isn't
+/// listed there.
+fn client_display_name(state: &AppState, mac: &str) -> String {
+    state
+        .active_states
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|active| active.interfaces.iter())
+        .find(|iface| iface.mac == mac)
+        .map(|iface| iface.display_name.clone())
+        .unwrap_or_else(|| mac.to_string())
+}
+
+/// The name of `mac` if it is the interface this computer is connected
+/// through.
+fn own_interface_name(state: &AppState, mac: &str) -> Option<String> {
+    state
+        .active_states
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|active| active.active_iface.as_ref())
+        .find(|iface| iface.mac == mac)
+        .map(|iface| iface.display_name.clone())
+}
+
+/// Hands a tray or shortcut action to the state worker, since it may wait on
+/// the router.
+fn queue_action(
+    app: &tauri::AppHandle,
+    action: impl FnOnce(&tauri::AppHandle, &Arc<AppState>) + Send + 'static,
+) {
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        state.worker.send(worker::Message::Action(Box::new(action)));
+    }
+}
+
+fn toggle_pause_internet(app: &tauri::AppHandle, state: &Arc<AppState>) {
+    let Some((active, iface)) = active_interface(state) else {
+        show_notification(app, &t("app.name"), &t("notify.no_active_pause"));
+        return;
+    };
+
+    let pausing = !iface.deny;
+    let target = if pausing {
+        PolicyOverride {
+            policy: None,
+            deny: true,
+        }
+    } else {
+        state
+            .paused
+            .lock()
+            .unwrap()
+            .remove(&iface.mac)
+            .unwrap_or(PolicyOverride {
+                policy: None,
+                deny: false,
+            })
+    };
+    let question = block_prompt(state, &iface, &target);
+    after_confirmation(app, state, question, move |app, state| {
+        if pausing {
+            state.paused.lock().unwrap().insert(
+                iface.mac.clone(),
+                PolicyOverride {
+                    policy: iface.policy.clone(),
+                    deny: false,
+                },
+            );
+        }
+        if apply_to_active(app, state, &active, &iface, target).is_err() && pausing {
+            state.paused.lock().unwrap().remove(&iface.mac);
+        }
+        save_pending(state);
+    });
+}
+
+fn register_shortcuts(app: &tauri::AppHandle, shortcuts: &ShortcutSettings) {
+    let manager = app.global_shortcut();
+    if let Err(err) = manager.unregister_all() {
+        report_error(
+            app,
+            tf("error.shortcut_failed", &[("error", &err.to_string())]),
+        );
+    }
+    let bindings = [
+        (&shortcuts.toggle_block, ShortcutAction::ToggleBlock),
+        (&shortcuts.apply_policy, ShortcutAction::ApplyPolicy),
+        (&shortcuts.open_settings, ShortcutAction::OpenSettings),
+        (&shortcuts.palette, ShortcutAction::OpenPalette),
+    ];
+    for (accelerator, action) in bindings {
+        let accelerator = accelerator.trim();
+        if accelerator.is_empty() {
+            continue;
+        }
+        let policy = shortcuts.policy.clone();
+        let result = manager.on_shortcut(accelerator, move |app, _, event| {
+            if event.state != ShortcutState::Pressed {
+                return;
+            }
+            match action {
+                ShortcutAction::ToggleBlock => queue_action(app, toggle_pause_internet),
+                ShortcutAction::ApplyPolicy => {
+                    let policy = policy.clone();
+                    queue_action(app, move |app, state| {
+                        apply_named_policy(app, state, &policy)
+                    });
+                }
+                ShortcutAction::OpenSettings => open_settings_window(app),
+                ShortcutAction::OpenPalette => open_palette_window(app),
+            }
+        });
+        if let Err(err) = result {
+            report_error(
+                app,
+                tf(
+                    "error.shortcut_failed",
+                    &[("error", &format!("{accelerator}: {err}"))],
+                ),
+            );
+        }
+    }
+}
+
+fn run_click_action(app: &tauri::AppHandle, action: ClickAction) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    match action {
+        ClickAction::None => {}
+        ClickAction::Refresh => {
+            state
+                .worker
+                .send(worker::Message::RefreshWithFollowUp(Duration::from_secs(5)));
+        }
+        ClickAction::OpenSettings => open_settings_window(app),
+        ClickAction::CyclePolicy => queue_action(app, cycle_active_policy),
+        ClickAction::PauseInternet => queue_action(app, toggle_pause_internet),
+    }
+}
+
+fn policy_target(action: &str, policy: &str) -> Option<PolicyOverride> {
+    match action {
+        "default" => Some(PolicyOverride {
+            policy: None,
+            deny: false,
+        }),
+        "blocked" => Some(PolicyOverride {
+            policy: None,
+            deny: true,
+        }),
+        "set" if !policy.is_empty() => Some(PolicyOverride {
+            policy: Some(policy.to_string()),
+            deny: false,
+        }),
+        _ => None,
+    }
+}
+
+/// Applies a policy to one client of a saved router from the tray, asking
+/// first if it blocks the client. Called from the worker.
+fn apply_policy_action(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    router_name: String,
+    mac: String,
+    target: PolicyOverride,
+) {
+    let question = target.deny.then(|| block_question(state, &mac));
+    after_confirmation(app, state, question, move |app, state| {
+        if let Err(err) = set_confirmed_policy(state, &router_name, &mac, target) {
+            record_error(app, state, err.to_string());
+        }
+        request_refresh(state);
+    });
+}
+
+/// Sets a client's policy once any confirmation has been given.
+fn set_confirmed_policy(
+    state: &AppState,
+    router_name: &str,
+    mac: &str,
+    target: PolicyOverride,
+) -> Result<()> {
+    set_client_policy(state, router_name, mac, target)
+        .map_err(|err| anyhow::anyhow!(tf("error.apply_failed", &[("error", &err.to_string())])))
+}
+
+/// Toggles "Prefer 5 GHz" for a client: `band|<mac>|5|<router>`.
+fn handle_band_click(app: &tauri::AppHandle, rest: &str) {
+    let parts: Vec<&str> = rest.splitn(3, '|').collect();
+    let [mac, "5", router_name] = parts.as_slice() else {
+        return;
+    };
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let mac = decode_mac(mac);
+    let locked = state
+        .active_states
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|active| active.router.name == *router_name)
+        .and_then(|active| active.band_locks.get(&mac).copied());
+    let band = (locked != Some(WifiBand::Ghz5)).then_some(WifiBand::Ghz5);
+    let result = connect_router(&state, router_name).and_then(|client| {
+        client.set_band_lock(&mac, band)?;
+        Ok(())
+    });
+    if let Err(err) = result {
+        record_error(app, &state, format!("{router_name}: {err}"));
+    }
+    request_refresh(&state);
+}
+
+fn handle_policy_click(app: &tauri::AppHandle, rest: &str) {
+    let parts: Vec<&str> = rest.splitn(4, '|').collect();
+    let [mac, action, policy, router_name] = parts.as_slice() else {
+        return;
+    };
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let Some(target) = policy_target(action, policy) else {
+        return;
+    };
+    apply_policy_action(
+        app,
+        &state,
+        router_name.to_string(),
+        decode_mac(mac),
+        target,
+    );
+}
+
+/// Window labels and the keys of their titles. Screen readers announce the
+/// native title, which otherwise stays in English from the config.
+const WINDOW_TITLES: &[(&str, &str)] = &[
+    ("settings", "ui.title"),
+    ("errors", "ui.error_log"),
+    ("topology", "ui.network_map"),
+    ("traffic", "ui.bandwidth"),
+    ("family", "ui.family"),
+    ("unlock", "ui.unlock"),
+    ("password", "ui.update_password"),
+    ("palette", "ui.palette_title"),
+];
+
+fn localize_window_titles(app: &tauri::AppHandle) {
+    for (label, key) in WINDOW_TITLES {
+        if let Some(window) = app.get_webview_window(label) {
+            let _ = window.set_title(&t(key));
+        }
+    }
+}
+
+fn open_settings_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("settings") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Opens the settings window on one of its sub-pages.
+fn open_settings_page(app: &tauri::AppHandle, page: &str) {
+    if let Some(window) = app.get_webview_window("settings") {
+        let _ = window.eval(format!("location.assign({page:?})"));
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn open_error_log_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("errors") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn open_palette_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("palette") {
+        let _ = window.emit("palette-opened", ());
+        let _ = window.center();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn open_topology_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("topology") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn open_family_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("family") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn open_traffic_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("traffic") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn open_unlock_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("unlock") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+#[tauri::command]
+fn list_routers(state: tauri::State<Arc<AppState>>) -> Vec<RouterInfo> {
+    state.routers.lock().unwrap().clone()
+}
+
+#[tauri::command]
+fn save_router(
+    payload: SaveRouterPayload,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let SaveRouterPayload {
+        name,
+        address,
+        login,
+        password,
+        original_name,
+        allow_remote,
+        refresh_interval_secs,
+        primary,
+    } = payload;
+    let address = address.trim_end_matches('/').to_string();
+
+    let mut routers = state.routers.lock().unwrap();
+    if let Some(original) = original_name.as_ref() {
+        if original != &name && routers.iter().any(|r| r.name == name) {
+            return Err(t("error.router_exists"));
+        }
+    } else if routers.iter().any(|r| r.name == name) {
+        return Err(t("error.router_exists"));
+    }
+
+    let client = KeeneticRouter::new(&address, &login, &password, &name);
+    if client.login().is_err() {
+        return Err(t("error.auth_failed"));
+    }
+    let network_ip = client.get_network_ip().ok().flatten();
+    let keendns_urls = client.get_keendns_urls().ok();
+
+    let router_info = RouterInfo {
+        name: name.clone(),
+        address,
+        login,
+        network_ip,
+        keendns_urls,
+        allow_remote,
+        refresh_interval_secs: refresh_interval_secs
+            .map(|secs| secs.max(TRAFFIC_SAMPLE_INTERVAL.as_secs())),
+        primary,
+    };
+
+    if let Some(original) = original_name {
+        if let Some(pos) = routers.iter().position(|r| r.name == original) {
+            routers.remove(pos);
+        }
+        delete_password(&original);
+        if original != name {
+            if let Some(store) = state.availability.lock().unwrap().as_ref() {
+                let _ = store.rename(&original, &name);
+            }
+        }
+    }
+
+    set_password(&name, &password).map_err(|e| e.to_string())?;
+    // Only one router can be preferred.
+    if primary {
+        for router in routers.iter_mut() {
+            router.primary = false;
+        }
+    }
+    routers.push(router_info.clone());
+
+    save_routers(&routers).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn find_router(state: &AppState, name: &str) -> Result<RouterInfo> {
+    state
+        .routers
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|r| r.name == name)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!(tf("error.router_not_found", &[("name", name)])))
+}
+
+/// A client for a saved router at whichever address is usable right now.
+fn connect_router(state: &AppState, name: &str) -> Result<KeeneticRouter> {
+    let router = find_router(state, name)?;
+    let address = router_address(&router, &local_networks())
+        .or_else(|| remote_address(&router))
+        .ok_or_else(|| anyhow::anyhow!(tf("error.unreachable", &[("name", name)])))?;
+    let password = get_password(name).ok_or_else(|| anyhow::anyhow!(t("error.no_password")))?;
+    Ok(KeeneticRouter::new(
+        &address,
+        &router.login,
+        &password,
+        &router.name,
+    ))
+}
+
+/// Runs `task` on a blocking thread, for async commands that wait on a
+/// router: its client blocks, and the async runtime has few threads.
+async fn run_blocking<T: Send + 'static>(
+    state: &Arc<AppState>,
+    task: impl FnOnce(&Arc<AppState>) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let state = state.clone();
+    tauri::async_runtime::spawn_blocking(move || task(&state))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn list_devices(
+    router: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<DeviceInfo>, String> {
+    run_blocking(&state, move |state| {
+        let client = connect_router(state, &router).map_err(|e| e.to_string())?;
+        client.get_devices().map_err(|e| e.to_string())
+    })
+    .await
+}
+
+#[tauri::command]
+async fn rename_device(
+    router: String,
+    mac: String,
+    name: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    state
+        .worker
+        .call(move |_, state| {
+            let client = connect_router(state, &router).map_err(|e| e.to_string())?;
+            client
+                .register_device(&mac, name.trim())
+                .map_err(|e| e.to_string())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn forget_devices(
+    router: String,
+    macs: Vec<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    state
+        .worker
+        .call(move |_, state| {
+            let client = connect_router(state, &router).map_err(|e| e.to_string())?;
+            let failed: Vec<String> = macs
+                .iter()
+                .filter_map(|mac| {
+                    client
+                        .forget_device(mac)
+                        .err()
+                        .map(|err| format!("{mac}: {err}"))
+                })
+                .collect();
+            if failed.is_empty() {
+                Ok(())
+            } else {
+                Err(failed.join("\n"))
+            }
+        })
+        .await
+}
+
+#[tauri::command]
+async fn approve_devices(
+    router: String,
+    macs: Vec<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    state
+        .worker
+        .call(move |_, state| {
+            let failed: Vec<String> = macs
+                .iter()
+                .filter_map(|mac| {
+                    approve_device(state, &router, mac)
+                        .err()
+                        .map(|err| format!("{mac}: {err}"))
+                })
+                .collect();
+            if failed.is_empty() {
+                Ok(())
+            } else {
+                Err(failed.join("\n"))
+            }
+        })
+        .await
+}
+
+#[derive(Debug, Serialize)]
+struct TopologyView {
+    router: String,
+    topology: Topology,
+    policies: HashMap<String, PolicyInfo>,
+}
+
+#[tauri::command]
+async fn get_topology(
+    router: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<TopologyView, String> {
+    run_blocking(&state, move |state| {
+        let client = connect_router(state, &router).map_err(|e| e.to_string())?;
+        let topology = client.get_topology().map_err(|e| e.to_string())?;
+        let policies = client.get_policies().map_err(|e| e.to_string())?;
+        Ok(TopologyView {
+            router,
+            topology,
+            policies,
+        })
+    })
+    .await
+}
+
+#[derive(Debug, Serialize)]
+struct DeviceRecord {
+    name: String,
+    mac: String,
+    ip: String,
+    vendor: String,
+    policy: String,
+    last_seen: String,
+}
+
+impl DeviceRecord {
+    fn new(device: DeviceInfo, now: DateTime<Local>) -> Self {
+        let policy = if device.deny {
+            "blocked".to_string()
+        } else {
+            device.policy.unwrap_or_else(|| "default".to_string())
+        };
+        let last_seen = if device.online {
+            now.to_rfc3339()
+        } else {
+            device
+                .last_seen
+                .map(|secs| (now - chrono::Duration::seconds(secs as i64)).to_rfc3339())
+                .unwrap_or_default()
+        };
+        Self {
+            name: device.name.unwrap_or_default(),
+            mac: device.mac,
+            ip: device.ip.unwrap_or_default(),
+            vendor: device.vendor.unwrap_or_default(),
+            policy,
+            last_seen,
+        }
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn devices_csv(records: &[DeviceRecord]) -> String {
+    let mut out = String::from("name,mac,ip,vendor,policy,last_seen\n");
+    for r in records {
+        let fields = [&r.name, &r.mac, &r.ip, &r.vendor, &r.policy, &r.last_seen];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes the router's client list as CSV when `path` ends in `.csv`, JSON
+/// otherwise.
+#[tauri::command]
+async fn export_devices(
+    router: String,
+    path: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let devices = run_blocking(&state, move |state| {
+        let client = connect_router(state, &router).map_err(|e| e.to_string())?;
+        client.get_devices().map_err(|e| e.to_string())
+    })
+    .await?;
+    let now = Local::now();
+    let records: Vec<DeviceRecord> = devices
+        .into_iter()
+        .map(|d| DeviceRecord::new(d, now))
+        .collect();
+    let is_csv = PathBuf::from(&path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let data = if is_csv {
+        devices_csv(&records)
+    } else {
+        serde_json::to_string_pretty(&records).map_err(|e| e.to_string())?
+    };
+    fs::write(&path, data).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Serialize)]
+struct ConnectionStep {
+    name: String,
+    ok: bool,
+    detail: Option<String>,
+}
+
+fn connection_step<T>(
+    steps: &mut Vec<ConnectionStep>,
+    key: &str,
+    result: Result<T, RouterError>,
+    detail: impl FnOnce(&T) -> Option<String>,
+) -> bool {
+    let (ok, detail) = match &result {
+        Ok(value) => (true, detail(value)),
+        Err(err) => (false, Some(err.to_string())),
+    };
+    steps.push(ConnectionStep {
+        name: t(key),
+        ok,
+        detail,
+    });
+    ok
+}
+
+/// Runs the same requests the tray makes, one step at a time, and stops at
+/// the first failing step. An empty password falls back to the one stored
+/// for `original_name` so existing routers can be re-tested.
+#[tauri::command]
+async fn test_router_connection(
+    address: String,
+    login: String,
+    password: String,
+    original_name: Option<String>,
+) -> Result<Vec<ConnectionStep>, String> {
+    let password = match original_name.filter(|_| password.is_empty()) {
+        Some(name) => get_password(&name).unwrap_or_default(),
+        None => Zeroizing::new(password),
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let client = KeeneticRouter::new(address.trim_end_matches('/'), &login, &password, "");
+        connection_steps(&client)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+fn connection_steps(client: &KeeneticRouter) -> Vec<ConnectionStep> {
+    let mut steps = Vec::new();
+    let _ = connection_step(
+        &mut steps,
+        "test.reachable",
+        client.check_reachable(),
+        |_| None,
+    ) && connection_step(&mut steps, "test.auth", client.login(), |_| None)
+        && connection_step(&mut steps, "test.policies", client.get_policies(), |p| {
+            Some(tf("test.count", &[("count", &p.len().to_string())]))
+        })
+        && connection_step(
+            &mut steps,
+            "test.clients",
+            client.get_online_clients(),
+            |c| Some(tf("test.count", &[("count", &c.len().to_string())])),
+        );
+    steps
+}
+
+/// Probes every local network for routers, which takes a while, so it runs
+/// on a blocking thread.
+#[tauri::command]
+async fn scan_routers() -> Result<Vec<DiscoveredRouter>, String> {
+    tauri::async_runtime::spawn_blocking(|| discover_routers(&local_networks()))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_segment_name(
+    network: String,
+    name: String,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<(), String> {
+    let mut settings = state.settings.lock().unwrap();
+    let name = name.trim();
+    if name.is_empty() {
+        settings.segment_names.remove(&network);
+    } else {
+        settings.segment_names.insert(network, name.to_string());
+    }
+    save_settings(&settings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_device_notes(state: tauri::State<Arc<AppState>>) -> HashMap<String, String> {
+    state.settings.lock().unwrap().device_notes.clone()
+}
+
+#[tauri::command]
+fn set_device_note(
+    mac: String,
+    note: String,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<(), String> {
+    {
+        let mut settings = state.settings.lock().unwrap();
+        let mac = mac.to_lowercase();
+        let note = note.trim();
+        if note.is_empty() {
+            settings.device_notes.remove(&mac);
+        } else {
+            settings.device_notes.insert(mac, note.to_string());
+        }
+        save_settings(&settings).map_err(|e| e.to_string())?;
+    }
+    request_refresh(&state);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_password_prompt(state: tauri::State<Arc<AppState>>) -> Option<String> {
+    state.password_prompt.lock().unwrap().clone()
+}
+
+/// Checks the new password against the router before saving it, so the
+/// login runs on a blocking thread.
+#[tauri::command]
+async fn update_router_password(
+    name: String,
+    password: String,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let router = find_router(&state, &name).map_err(|e| e.to_string())?;
+    let candidate = Zeroizing::new(password.clone());
+    run_blocking(&state, move |_| {
+        let address = router_address(&router, &local_networks())
+            .or_else(|| remote_address(&router))
+            .unwrap_or_else(|| router.address.clone());
+        KeeneticRouter::new(&address, &router.login, &candidate, &router.name)
+            .login()
+            .map_err(|_| t("error.auth_failed"))
+    })
+    .await?;
+    set_password(&name, &password).map_err(|e| e.to_string())?;
+    state.auth_failures.lock().unwrap().remove(&name);
+    *state.password_prompt.lock().unwrap() = None;
+    if let Some(window) = app.get_webview_window("password") {
+        let _ = window.hide();
+    }
+    request_refresh(&state);
+    Ok(())
+}
+
+#[tauri::command]
+// Async so the OS authentication prompt doesn't block the webview.
+async fn delete_router(
+    name: String,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    confirm_destructive(
+        &app,
+        &state,
+        tf("confirm.delete_router", &[("name", &name)]),
+        tf("confirm.delete_router_question", &[("name", &name)]),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    let mut routers = state.routers.lock().unwrap();
+    routers.retain(|r| r.name != name);
+    delete_password(&name);
+    save_routers(&routers).map_err(|e| e.to_string())?;
+    state.errors.lock().unwrap().resolve(&name);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_active_state(state: tauri::State<Arc<AppState>>) -> Vec<ActiveState> {
+    state.active_states.lock().unwrap().clone()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PaletteItem {
+    label: String,
+    detail: String,
+    action: String,
+    router: Option<String>,
+    mac: Option<String>,
+    policy: Option<String>,
+}
+
+/// Every policy action for every known device, plus a few app actions, for
+/// the quick search palette to filter.
+#[tauri::command]
+fn palette_items(state: tauri::State<Arc<AppState>>) -> Vec<PaletteItem> {
+    let mut items = Vec::new();
+    for active in state.active_states.lock().unwrap().iter() {
+        let mut policies: Vec<(&String, &PolicyInfo)> = active.policies.iter().collect();
+        policies.sort_by_key(|(name, _)| name.as_str());
+        for iface in &active.interfaces {
+            let detail = format!("{} · {} · {}", active.router.name, iface.ip, iface.mac);
+            let device = iface.display_name.as_str();
+            let mut push = |label: String, action: &str, policy: Option<&str>| {
+                items.push(PaletteItem {
+                    label,
+                    detail: detail.clone(),
+                    action: action.to_string(),
+                    router: Some(active.router.name.clone()),
+                    mac: Some(iface.mac.clone()),
+                    policy: policy.map(str::to_string),
+                });
+            };
+            push(tf("palette.block", &[("device", device)]), "blocked", None);
+            push(
+                tf("palette.default", &[("device", device)]),
+                "default",
+                None,
+            );
+            for (name, info) in &policies {
+                let policy = info.description.as_deref().unwrap_or(name);
+                push(
+                    tf("palette.set", &[("policy", policy), ("device", device)]),
+                    "set",
+                    Some(name),
+                );
+            }
+        }
+    }
+    for (action, key) in [
+        ("refresh", "tray.refresh"),
+        ("open_settings", "tray.settings"),
+    ] {
+        items.push(PaletteItem {
+            label: t(key),
+            detail: String::new(),
+            action: action.to_string(),
+            router: None,
+            mac: None,
+            policy: None,
+        });
+    }
+    items
+}
+
+#[tauri::command]
+async fn run_palette_item(
+    item: PaletteItem,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("palette") {
+        let _ = window.hide();
+    }
+    match (item.action.as_str(), item.router, item.mac) {
+        ("refresh", _, _) => request_refresh(&state),
+        ("open_settings", _, _) => open_settings_window(&app),
+        (action, Some(router), Some(mac)) => {
+            return apply_policy_ui(mac, action.to_string(), item.policy, router, app, state).await;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Emits the expected state right away, then the state read back from the
+/// router once the change has been applied.
+#[tauri::command]
+async fn apply_policy_ui(
+    mac: String,
+    action: String,
+    policy: Option<String>,
+    router: String,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let target = policy_target(&action, policy.as_deref().unwrap_or_default())
+        .ok_or_else(|| tf("notify.unknown_policy", &[("name", &action)]))?;
+    if target.deny {
+        confirm_destructive(
+            &app,
+            &state,
+            t("confirm.block"),
+            block_question(&state, &mac),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    {
+        // Only this router's client changes; the same MAC can be listed
+        // under another router with its own policy.
+        let mut states = state.active_states.lock().unwrap();
+        let mut optimistic = HashMap::from([(
+            router.clone(),
+            HashMap::from([(mac.clone(), target.clone())]),
+        )]);
+        apply_overrides(&mut states, &mut optimistic);
+        let _ = app.emit("state-updated", &*states);
+    }
+    let result = {
+        let (router, mac) = (router.clone(), mac.clone());
+        state
+            .worker
+            .call(move |_, state| {
+                set_confirmed_policy(state, &router, &mac, target).map_err(|e| e.to_string())
+            })
+            .await
+    };
+    if let Err(err) = &result {
+        record_error(&app, &state, err.clone());
+    }
+    request_refresh(&state);
+    result
+}
+
+#[tauri::command]
+async fn list_policies(
+    router: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<HashMap<String, PolicyInfo>, String> {
+    run_blocking(&state, move |state| {
+        let client = connect_router(state, &router).map_err(|e| e.to_string())?;
+        client.get_policies().map_err(|e| e.to_string())
+    })
+    .await
+}
+
+/// A guest voucher as the Guest Access page shows it.
+#[derive(Debug, Serialize)]
+struct GuestVoucherView {
+    router: String,
+    ssid: String,
+    passphrase: String,
+    expires: DateTime<Local>,
+    /// SVG of the `WIFI:` code.
+    qr: Option<String>,
+}
+
+impl From<&guest::Voucher> for GuestVoucherView {
+    fn from(voucher: &guest::Voucher) -> Self {
+        Self {
+            router: voucher.router.clone(),
+            ssid: voucher.ssid.clone(),
+            passphrase: voucher.passphrase.clone(),
+            expires: voucher.expires,
+            qr: guest::qr_svg(&voucher.wifi_uri()),
+        }
+    }
+}
+
+/// Brings the guest Wi-Fi up with a new passphrase for `hours`, replacing
+/// any voucher the router already had.
+#[tauri::command]
+async fn create_guest_voucher(
+    router: String,
+    hours: u32,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<GuestVoucherView, String> {
+    if !(1..=168).contains(&hours) {
+        return Err(t("error.guest_hours"));
+    }
+    state
+        .worker
+        .call(move |_, state| {
+            let client = connect_router(state, &router).map_err(|e| e.to_string())?;
+            let ssid = client
+                .get_guest_wifi()
+                .map_err(|e| e.to_string())?
+                .ssid
+                .ok_or_else(|| t("error.no_guest_wifi"))?;
+            let passphrase = guest::random_passphrase();
+            client
+                .set_guest_wifi(true, Some(&passphrase))
+                .map_err(|e| e.to_string())?;
+            let voucher = guest::Voucher {
+                router: router.clone(),
+                ssid,
+                passphrase,
+                expires: Local::now() + chrono::Duration::hours(i64::from(hours)),
+            };
+            let view = GuestVoucherView::from(&voucher);
+            {
+                let mut vouchers = state.guest_vouchers.lock().unwrap();
+                vouchers.retain(|v| v.router != router);
+                vouchers.push(voucher);
+            }
+            save_pending(state);
+            Ok(view)
+        })
+        .await
+}
+
+#[tauri::command]
+fn list_guest_vouchers(state: tauri::State<'_, Arc<AppState>>) -> Vec<GuestVoucherView> {
+    state
+        .guest_vouchers
+        .lock()
+        .unwrap()
+        .iter()
+        .map(GuestVoucherView::from)
+        .collect()
+}
+
+/// Takes the guest Wi-Fi down before its voucher runs out.
+#[tauri::command]
+async fn end_guest_voucher(
+    router: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    state
+        .worker
+        .call(move |_, state| {
+            let client = connect_router(state, &router).map_err(|e| e.to_string())?;
+            client
+                .set_guest_wifi(false, None)
+                .map_err(|e| e.to_string())?;
+            state
+                .guest_vouchers
+                .lock()
+                .unwrap()
+                .retain(|v| v.router != router);
+            save_pending(state);
+            Ok(())
+        })
+        .await
+}
+
+/// What a bulk policy change would do to one device.
+#[derive(Debug, Serialize)]
+struct PlannedChange {
+    mac: String,
+    name: String,
+    current: String,
+    target: String,
+    changes: bool,
+}
+
+/// Reads the devices' current policies from the router and lists what
+/// applying `action` to each of them would change, without sending anything.
+#[tauri::command]
+async fn preview_policy_change(
+    router: String,
+    macs: Vec<String>,
+    action: String,
+    policy: Option<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<PlannedChange>, String> {
+    let target = policy_target(&action, policy.as_deref().unwrap_or_default())
+        .ok_or_else(|| tf("notify.unknown_policy", &[("name", &action)]))?;
+    let (devices, policies) = run_blocking(&state, move |state| {
+        let client = connect_router(state, &router).map_err(|e| e.to_string())?;
+        let devices = client.get_devices().map_err(|e| e.to_string())?;
+        let policies = client.get_policies().map_err(|e| e.to_string())?;
+        Ok((devices, policies))
+    })
+    .await?;
+    let target_label = policy_label(target.policy.as_deref(), target.deny, &policies);
+    Ok(macs
+        .into_iter()
+        .map(|mac| {
+            let device = devices.iter().find(|d| d.mac == mac);
+            let (policy, deny) = device
+                .map(|d| (d.policy.clone(), d.deny))
+                .unwrap_or_default();
+            let changes = deny != target.deny || (!target.deny && policy != target.policy);
+            PlannedChange {
+                name: device
+                    .and_then(|d| d.name.clone())
+                    .unwrap_or_else(|| mac.clone()),
+                current: policy_label(policy.as_deref(), deny, &policies),
+                target: target_label.clone(),
+                changes,
+                mac,
+            }
+        })
+        .collect())
+}
+
+/// How one device fared in a bulk policy change.
+#[derive(Debug, Serialize)]
+struct ApplyResult {
+    mac: String,
+    error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct BulkProgress {
+    done: usize,
+    total: usize,
+}
+
+/// Writes `target` to each device in turn, retrying each once, so a flaky
+/// device only fails itself. Progress is emitted as `bulk-progress`.
+fn apply_to_devices(
+    app: &tauri::AppHandle,
+    state: &Arc<AppState>,
+    router: &str,
+    macs: Vec<String>,
+    target: &PolicyOverride,
+) -> Vec<ApplyResult> {
+    let total = macs.len();
+    let mut results = Vec::with_capacity(total);
+    for (done, mac) in macs.into_iter().enumerate() {
+        let result = set_client_policy(state, router, &mac, target.clone()).or_else(|err| {
+            log::warn!("Applying policy to {mac} failed, retrying: {err}");
+            set_client_policy(state, router, &mac, target.clone())
+        });
+        let error = result.err().map(|err| err.to_string());
+        if let Some(err) = &error {
+            let name = client_display_name(state, &mac);
+            record_error(
+                app,
+                state,
+                tf("error.bulk_item_failed", &[("name", &name), ("error", err)]),
+            );
+        }
+        results.push(ApplyResult { mac, error });
+        let _ = app.emit(
+            "bulk-progress",
+            BulkProgress {
+                done: done + 1,
+                total,
+            },
+        );
+    }
+    request_refresh(state);
+    results
+}
+
+/// Applies one policy to several devices after a single confirmation,
+/// returning the result for every device.
+#[tauri::command]
+async fn apply_policy_changes(
+    router: String,
+    macs: Vec<String>,
+    action: String,
+    policy: Option<String>,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<ApplyResult>, String> {
+    let target = policy_target(&action, policy.as_deref().unwrap_or_default())
+        .ok_or_else(|| tf("notify.unknown_policy", &[("name", &action)]))?;
+    if macs.is_empty() {
+        return Ok(Vec::new());
+    }
+    if target.deny {
+        let own = macs
+            .iter()
+            .find(|mac| own_interface_name(&state, mac).is_some());
+        let question = match (macs.as_slice(), own) {
+            (_, Some(mac)) | ([mac], None) => block_question(&state, mac),
+            _ => tf(
+                "confirm.block_many_question",
+                &[("count", &macs.len().to_string())],
+            ),
+        };
+        confirm_destructive(&app, &state, t("confirm.block"), question)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let total = macs.len();
+    if total > 1 {
+        show_notification(
+            &app,
+            &t("notify.bulk_started"),
+            &tf("notify.bulk_started_body", &[("count", &total.to_string())]),
+        );
+    }
+    let results = {
+        state
+            .worker
+            .call(move |app, state| Ok(apply_to_devices(app, state, &router, macs, &target)))
+            .await?
+    };
+    let failed: Vec<String> = results
+        .iter()
+        .filter(|r| r.error.is_some())
+        .map(|r| client_display_name(&state, &r.mac))
+        .collect();
+    if total > 1 || !failed.is_empty() {
+        let body = if failed.is_empty() {
+            tf("notify.bulk_done_body", &[("count", &total.to_string())])
+        } else {
+            tf(
+                "notify.bulk_failed_body",
+                &[
+                    ("done", &(total - failed.len()).to_string()),
+                    ("count", &total.to_string()),
+                    ("names", &failed.join(", ")),
+                ],
+            )
+        };
+        show_notification(&app, &t("notify.bulk_done"), &body);
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+fn get_notification_settings(
+    state: tauri::State<Arc<AppState>>,
+) -> notifications::NotificationSettings {
+    state.settings.lock().unwrap().notifications.clone()
+}
+
+/// Saves the alert settings and restarts the Telegram bot. The bot and push
+/// tokens and the SMTP password are only replaced when new ones are given.
+#[tauri::command]
+fn save_notification_settings(
+    notifications: notifications::NotificationSettings,
+    telegram_token: Option<String>,
+    push_token: Option<String>,
+    smtp_password: Option<String>,
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<(), String> {
+    for (name, token) in [
+        (telegram::TOKEN_KEY, telegram_token),
+        (push::TOKEN_KEY, push_token),
+        (email::PASSWORD_KEY, smtp_password),
+    ] {
+        if let Some(token) = token.filter(|t| !t.trim().is_empty()) {
+            set_password(name, token.trim()).map_err(|e| e.to_string())?;
+        }
+    }
+    {
+        let mut settings = state.settings.lock().unwrap();
+        settings.notifications = notifications.clone();
+        save_settings(&settings).map_err(|e| e.to_string())?;
+    }
+    start_telegram(&app, &notifications.telegram);
+    Ok(())
+}
+
+/// Sends a test message with the settings in the form, which may not be
+/// saved yet, using the stored password unless a new one is given.
+#[tauri::command]
+async fn send_test_email(
+    email: email::EmailSettings,
+    password: Option<String>,
+) -> Result<(), String> {
+    let password = password
+        .filter(|p| !p.is_empty())
+        .map(Zeroizing::new)
+        .or_else(|| get_password(email::PASSWORD_KEY));
+    tauri::async_runtime::spawn_blocking(move || {
+        email::send(
+            &email,
+            password.as_deref().map(String::as_str),
+            &t("email.test_subject"),
+            &t("email.test_body"),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("{e:#}"))
+}
+
+/// Chat commands are checked against the configured chat, so they don't
+/// ask for confirmation on this computer.
+fn start_telegram(app: &tauri::AppHandle, settings: &telegram::TelegramSettings) {
+    if demo::enabled() {
+        return;
+    }
+    let handle = app.clone();
+    telegram::start(settings, get_token(telegram::TOKEN_KEY), move |action| {
+        run_automation(&handle, &action, false)
+    });
+}
+
+#[tauri::command]
+fn get_mqtt_settings(state: tauri::State<Arc<AppState>>) -> mqtt::MqttSettings {
+    state.settings.lock().unwrap().mqtt.clone()
+}
+
+/// Saves the broker settings and reconnects. The password is only replaced
+/// when a new one is given.
+#[tauri::command]
+fn save_mqtt_settings(
+    mqtt: mqtt::MqttSettings,
+    password: Option<String>,
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<(), String> {
+    if let Some(password) = password.filter(|p| !p.is_empty()) {
+        set_password(mqtt::PASSWORD_KEY, &password).map_err(|e| e.to_string())?;
+    }
+    {
+        let mut settings = state.settings.lock().unwrap();
+        settings.mqtt = mqtt.clone();
+        save_settings(&settings).map_err(|e| e.to_string())?;
+    }
+    start_mqtt(&app, &mqtt, true);
+    request_refresh(&state);
+    Ok(())
+}
+
+/// A generated secret such as the API token, created the first time it's
+/// needed. Empty when there is none or the secret store is locked.
+fn stored_token(name: &str, create: bool) -> String {
+    if secrets::is_locked() {
+        return String::new();
+    }
+    match get_password(name) {
+        Some(token) => token.to_string(),
+        None if create => {
+            let token = crypto::random_key();
+            if let Err(err) = set_password(name, &token) {
+                log::warn!("Failed to store {name}: {err}");
+                return String::new();
+            }
+            token
+        }
+        None => String::new(),
+    }
+}
+
+fn start_api(app: &tauri::AppHandle, settings: &api::ApiSettings) {
+    let token = stored_token(api::TOKEN_KEY, settings.enabled);
+    let handle = app.clone();
+    api::start(settings, &token, move |route| handle_api(&handle, route));
+}
+
+fn start_websocket(app: &tauri::AppHandle, settings: &websocket::WebSocketSettings) {
+    let token = stored_token(api::TOKEN_KEY, settings.enabled);
+    let handle = app.clone();
+    websocket::start(settings, &token, move |command| {
+        handle_websocket(&handle, command)
+    });
+}
+
+fn handle_websocket(app: &tauri::AppHandle, command: websocket::Command) -> Result<(), String> {
+    let state = app
+        .try_state::<Arc<AppState>>()
+        .ok_or_else(|| "not ready".to_string())?;
+    let (client, target) = match command {
+        websocket::Command::SetPolicy {
+            mac,
+            router,
+            policy,
+            blocked,
+        } => (
+            find_client(&state, &mac, router.as_deref())?,
+            PolicyOverride {
+                policy,
+                deny: blocked,
+            },
+        ),
+        websocket::Command::ToggleBlock { mac, router } => {
+            let client = find_client(&state, &mac, router.as_deref())?;
+            let target = PolicyOverride {
+                policy: client.policy.clone(),
+                deny: !client.blocked,
+            };
+            (client, target)
+        }
+        // Answered from the last state without asking the tray.
+        websocket::Command::GetState => return Ok(()),
+    };
+    apply_external_policy(app, client.router, client.mac, target)
+}
+
+fn start_grpc(app: &tauri::AppHandle, settings: &grpc::GrpcSettings) {
+    let token = stored_token(api::TOKEN_KEY, settings.enabled);
+    let handle = app.clone();
+    grpc::start(settings, &token, move |request| {
+        handle_grpc(&handle, request)
+    });
+}
+
+fn handle_grpc(app: &tauri::AppHandle, request: grpc::Request) -> Result<grpc::Reply, String> {
+    let state = app
+        .try_state::<Arc<AppState>>()
+        .ok_or_else(|| "not ready".to_string())?;
+    match request {
+        grpc::Request::Devices => Ok(grpc::Reply::Devices(client_list(&state))),
+        grpc::Request::Policies { router } => {
+            let mut policies: Vec<(String, String, String)> = state
+                .active_states
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|active| {
+                    router
+                        .as_ref()
+                        .is_none_or(|name| &active.router.name == name)
+                })
+                .flat_map(|active| {
+                    active.policies.iter().map(|(name, info)| {
+                        (
+                            active.router.name.clone(),
+                            name.clone(),
+                            info.description.clone().unwrap_or_default(),
+                        )
+                    })
+                })
+                .collect();
+            policies.sort();
+            Ok(grpc::Reply::Policies(policies))
+        }
+        grpc::Request::SetPolicy {
+            mac,
+            router,
+            policy,
+            blocked,
+        } => {
+            let client = find_client(&state, &mac, router.as_deref())?;
+            let target = PolicyOverride {
+                policy,
+                deny: blocked,
+            };
+            apply_external_policy(app, client.router, client.mac, target)?;
+            Ok(grpc::Reply::Done)
+        }
+    }
+}
+
+fn handle_api(
+    app: &tauri::AppHandle,
+    route: api::Route,
+) -> Result<serde_json::Value, api::ApiError> {
+    let state = app
+        .try_state::<Arc<AppState>>()
+        .ok_or((503, "not ready".to_string()))?;
+    match route {
+        api::Route::State => {
+            let states = state.active_states.lock().unwrap().clone();
+            serde_json::to_value(states).map_err(|err| (500, err.to_string()))
+        }
+        api::Route::Clients => {
+            serde_json::to_value(client_list(&state)).map_err(|err| (500, err.to_string()))
+        }
+        api::Route::SetPolicy { mac, request } => {
+            let routers: Vec<String> = state
+                .active_states
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|active| {
+                    active
+                        .interfaces
+                        .iter()
+                        .any(|iface| iface.mac.eq_ignore_ascii_case(&mac))
+                })
+                .map(|active| active.router.name.clone())
+                .collect();
+            let router = match (request.router, routers.as_slice()) {
+                (Some(router), _) => router,
+                (None, [router]) => router.clone(),
+                (None, []) => return Err((404, format!("no router knows {mac}"))),
+                (None, _) => {
+                    return Err((
+                        400,
+                        "several routers know this client; pass \"router\"".to_string(),
+                    ))
+                }
+            };
+            let target = PolicyOverride {
+                policy: request.policy,
+                deny: request.blocked,
+            };
+            apply_external_policy(app, router, mac, target).map_err(|err| (502, err))?;
+            Ok(serde_json::json!({ "ok": true }))
+        }
+    }
+}
+
+/// Every client of the reachable routers, as other programs see them.
+fn client_list(state: &AppState) -> Vec<api::Client> {
+    state
+        .active_states
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|active| {
+            active.interfaces.iter().map(|iface| api::Client {
+                router: active.router.name.clone(),
+                mac: iface.mac.clone(),
+                name: iface.display_name.clone(),
+                ip: iface.ip.clone(),
+                online: iface.online,
+                policy: iface.policy.clone(),
+                blocked: iface.deny,
+            })
+        })
+        .collect()
+}
+
+/// How long another program waits for its policy change to be applied.
+const EXTERNAL_ACTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs an action asked for by another program on the state worker, like a
+/// tray action, so the two can't interleave, and waits for its result.
+fn run_external<T: Send + 'static>(
+    app: &tauri::AppHandle,
+    action: impl FnOnce(&tauri::AppHandle, &Arc<AppState>) -> Result<T> + Send + 'static,
+) -> Result<T, String> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    queue_action(app, move |app, state| {
+        let result = action(app, state);
+        request_refresh(state);
+        let _ = sender.send(result.map_err(|err| err.to_string()));
+    });
+    receiver
+        .recv_timeout(EXTERNAL_ACTION_TIMEOUT)
+        .unwrap_or_else(|_| Err("the router didn't answer in time".to_string()))
+}
+
+fn apply_external_policy(
+    app: &tauri::AppHandle,
+    router: String,
+    mac: String,
+    target: PolicyOverride,
+) -> Result<(), String> {
+    run_external(app, move |_, state| {
+        set_client_policy(state, &router, &mac, target)
+    })
+}
+
+/// Runs a `routertray://` link and reports back to its x-callback URLs.
+fn handle_automation_url(app: &tauri::AppHandle, url: &tauri::Url) {
+    let request = match automation::Request::parse(url) {
+        Ok(request) => request,
+        Err(err) => {
+            log::warn!("Ignoring {url}: {err}");
+            return;
+        }
+    };
+    let app = app.clone();
+    // Policy changes wait for the router, which the event loop mustn't.
+    std::thread::spawn(move || {
+        if let Some(state) = app.try_state::<Arc<AppState>>() {
+            wait_for_first_refresh(&state);
+        }
+        // Anything can open a link, so blocking through one asks first.
+        let outcome = run_automation(&app, &request.action, true);
+        if let Err(err) = &outcome {
+            log::warn!("Automation failed: {err}");
+        }
+        if let Some(callback) = request.callback(&outcome) {
+            if let Err(err) = app.opener().open_url(callback.as_str(), None::<&str>) {
+                log::warn!("Failed to open the automation callback: {err}");
+            }
+        }
+    });
+}
+
+/// A link can start the app, and clients are only known after the first
+/// refresh.
+fn wait_for_first_refresh(state: &AppState) {
+    let started = Instant::now();
+    while state.last_updated.lock().unwrap().is_none()
+        && started.elapsed() < EXTERNAL_ACTION_TIMEOUT
+    {
+        std::thread::sleep(Duration::from_millis(250));
+    }
+}
+
+/// Runs an action from a link or a chat. `confirm` asks before blocking like
+/// the tray does, for callers that don't prove who they are.
+fn run_automation(
+    app: &tauri::AppHandle,
+    action: &automation::Action,
+    confirm: bool,
+) -> Result<String, String> {
+    let state = app
+        .try_state::<Arc<AppState>>()
+        .ok_or_else(|| "not ready".to_string())?;
+    match action {
+        automation::Action::Status => Ok(automation_status(&state)),
+        automation::Action::Policy {
+            device,
+            router,
+            policy,
+        } => {
+            let client = find_client(&state, device, router.as_deref())?;
+            let target = PolicyOverride {
+                policy: policy.clone(),
+                deny: client.blocked,
+            };
+            apply_external_policy(app, client.router, client.mac, target)?;
+            Ok(match policy {
+                Some(policy) => tf(
+                    "automation.policy_set",
+                    &[("device", &client.name), ("policy", policy)],
+                ),
+                None => tf("automation.policy_default", &[("device", &client.name)]),
+            })
+        }
+        automation::Action::Block {
+            device,
+            router,
+            blocked,
+        } => {
+            let client = find_client(&state, device, router.as_deref())?;
+            if confirm && *blocked && !client.blocked {
+                let question = block_question(&state, &client.mac);
+                require_confirmation(app, &state, &t("confirm.block"), &question)
+                    .map_err(|err| err.to_string())?;
+            }
+            let target = PolicyOverride {
+                policy: client.policy,
+                deny: *blocked,
+            };
+            apply_external_policy(app, client.router, client.mac, target)?;
+            let key = if *blocked {
+                "automation.blocked"
+            } else {
+                "automation.unblocked"
+            };
+            Ok(tf(key, &[("device", &client.name)]))
+        }
+        automation::Action::PauseAll => {
+            if confirm {
+                require_confirmation(
+                    app,
+                    &state,
+                    &t("confirm.block"),
+                    &t("confirm.pause_all_question"),
+                )
+                .map_err(|err| err.to_string())?;
+            }
+            run_external(app, |_, state| Ok(pause_all(state)))?
+                .report("automation.paused_all", "automation.paused_some")
+        }
+        automation::Action::ResumeAll => run_external(app, |_, state| Ok(resume_all(state)))?
+            .report("automation.resumed_all", "automation.resumed_some"),
+    }
+}
+
+/// Blocks every client but this computer, remembering their policies for
+/// `resume_all`. A client that fails doesn't stop the others.
+fn pause_all(state: &AppState) -> BulkOutcome {
+    let states = state.active_states.lock().unwrap().clone();
+    let mut outcome = BulkOutcome::default();
+    for (active, iface) in pause::to_pause(&states) {
+        let result = set_interface_policy(
+            state,
+            &active.router,
+            &active.active_address,
+            &iface.mac,
+            PolicyOverride::paused(),
+        );
+        if !outcome.record(&iface.display_name, result) {
+            continue;
+        }
+        state
+            .paused
+            .lock()
+            .unwrap()
+            .insert(iface.mac.clone(), PolicyOverride::resumed(iface));
+        save_pending(state);
+    }
+    outcome
+}
+
+/// Restores every paused client that is on a reachable router. A client
+/// that fails stays paused and doesn't stop the others.
+fn resume_all(state: &AppState) -> BulkOutcome {
+    let states = state.active_states.lock().unwrap().clone();
+    let paused = state.paused.lock().unwrap().clone();
+    let mut outcome = BulkOutcome::default();
+    for (active, mac, previous) in pause::to_resume(&paused, &states) {
+        let result = set_interface_policy(
+            state,
+            &active.router,
+            &active.active_address,
+            mac,
+            previous.clone(),
+        );
+        if !outcome.record(&client_display_name(state, mac), result) {
+            continue;
+        }
+        state.paused.lock().unwrap().remove(mac);
+        save_pending(state);
+    }
+    outcome
+}
+
+/// Finds a client by name or MAC, so "block the TV" doesn't need the MAC.
+fn find_client(
+    state: &AppState,
+    device: &str,
+    router: Option<&str>,
+) -> Result<api::Client, String> {
+    let mut matches: Vec<api::Client> = client_list(state)
+        .into_iter()
+        .filter(|client| router.is_none_or(|router| client.router == router))
+        .filter(|client| {
+            client.name.eq_ignore_ascii_case(device) || client.mac.eq_ignore_ascii_case(device)
+        })
+        .collect();
+    match matches.len() {
+        0 => Err(format!("no client matches {device}")),
+        1 => Ok(matches.remove(0)),
+        _ => Err(format!(
+            "several clients match {device}; pass router to pick one"
+        )),
+    }
+}
+
+/// One line per router, short enough for Siri to read out.
+fn automation_status(state: &AppState) -> String {
+    let routers = state.routers.lock().unwrap().clone();
+    let states = state.active_states.lock().unwrap().clone();
+    routers
+        .iter()
+        .map(|router| {
+            match states
+                .iter()
+                .find(|active| active.router.name == router.name)
+            {
+                Some(active) => {
+                    let online = active
+                        .interfaces
+                        .iter()
+                        .filter(|iface| iface.online)
+                        .count();
+                    tf(
+                        "automation.router_status",
+                        &[
+                            ("router", &router.name),
+                            ("online", &online.to_string()),
+                            ("total", &active.interfaces.len().to_string()),
+                        ],
+                    )
+                }
+                None => tf("automation.router_offline", &[("router", &router.name)]),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Adds an event to the event log.
+fn record_event(state: &AppState, kind: &str, router: Option<&str>, data: &serde_json::Value) {
+    if let Some(store) = state.events.lock().unwrap().as_ref() {
+        if let Err(err) = store.record(kind, router, data) {
+            log::warn!("Failed to record {kind} event: {err}");
+        }
+    }
+}
+
+/// Logs an event, runs the script hooks for it and posts it to the
+/// configured webhooks, if there are any.
+fn fire_event(state: &AppState, kind: webhooks::EventKind, router: &str, data: serde_json::Value) {
+    if let Ok(serde_json::Value::String(name)) = serde_json::to_value(kind) {
+        record_event(state, &name, Some(router), &data);
+    }
+    let (urls, scripts) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.webhooks.clone(), settings.script_hooks.clone())
+    };
+    hooks::run(&scripts, kind, router, &data);
+    if urls.is_empty() || demo::enabled() {
+        return;
+    }
+    let secret = stored_token(webhooks::SECRET_KEY, true);
+    if !secret.is_empty() {
+        webhooks::dispatch(&urls, &secret, kind, router, data);
+    }
+}
+
+fn start_metrics(state: &Arc<AppState>, settings: &metrics::MetricsSettings) {
+    let state = state.clone();
+    metrics::start(settings, move || render_metrics(&state));
+}
+
+fn render_metrics(state: &AppState) -> String {
+    let routers = state.routers.lock().unwrap().clone();
+    let states = state.active_states.lock().unwrap().clone();
+    let mut out = metrics::Exposition::default();
+
+    out.family(
+        "keenetic_router_reachable",
+        "gauge",
+        "Whether the router answered the last refresh.",
+    );
+    for router in &routers {
+        let reachable = states
+            .iter()
+            .any(|active| active.router.name == router.name);
+        out.sample(
+            "keenetic_router_reachable",
+            &[("router", &router.name)],
+            if reachable { 1.0 } else { 0.0 },
+        );
+    }
+    out.family(
+        "keenetic_router_clients",
+        "gauge",
+        "Clients registered on the router.",
+    );
+    for active in &states {
+        out.sample(
+            "keenetic_router_clients",
+            &[("router", &active.router.name)],
+            active.interfaces.len() as f64,
+        );
+    }
+    out.family(
+        "keenetic_router_clients_online",
+        "gauge",
+        "Clients currently online.",
+    );
+    for active in &states {
+        out.sample(
+            "keenetic_router_clients_online",
+            &[("router", &active.router.name)],
+            active.online_clients as f64,
+        );
+    }
+
+    {
+        let traffic = state.traffic.lock().unwrap();
+        let rates: Vec<(&str, &InterfaceInfo, traffic::RatePoint)> = states
+            .iter()
+            .flat_map(|active| {
+                active
+                    .interfaces
+                    .iter()
+                    .map(move |iface| (active.router.name.as_str(), iface))
+            })
+            .filter_map(|(router, iface)| Some((router, iface, traffic.latest(&iface.mac)?)))
+            .collect();
+        out.family(
+            "keenetic_client_receive_bits_per_second",
+            "gauge",
+            "Latest download rate of the client.",
+        );
+        for (router, iface, point) in &rates {
+            out.sample(
+                "keenetic_client_receive_bits_per_second",
+                &[
+                    ("router", router),
+                    ("mac", &iface.mac),
+                    ("name", &iface.display_name),
+                ],
+                point.rx_bps,
+            );
+        }
+        out.family(
+            "keenetic_client_transmit_bits_per_second",
+            "gauge",
+            "Latest upload rate of the client.",
+        );
+        for (router, iface, point) in &rates {
+            out.sample(
+                "keenetic_client_transmit_bits_per_second",
+                &[
+                    ("router", router),
+                    ("mac", &iface.mac),
+                    ("name", &iface.display_name),
+                ],
+                point.tx_bps,
+            );
+        }
+    }
+
+    {
+        let wan = state.wan_traffic.lock().unwrap();
+        let rates: Vec<(&str, traffic::RatePoint)> = states
+            .iter()
+            .filter_map(|active| {
+                let name = active.router.name.as_str();
+                Some((name, wan.latest(name)?))
+            })
+            .collect();
+        out.family(
+            "keenetic_wan_receive_bits_per_second",
+            "gauge",
+            "Latest WAN download rate.",
+        );
+        for (router, point) in &rates {
+            out.sample(
+                "keenetic_wan_receive_bits_per_second",
+                &[("router", router)],
+                point.rx_bps,
+            );
+        }
+        out.family(
+            "keenetic_wan_transmit_bits_per_second",
+            "gauge",
+            "Latest WAN upload rate.",
+        );
+        for (router, point) in &rates {
+            out.sample(
+                "keenetic_wan_transmit_bits_per_second",
+                &[("router", router)],
+                point.tx_bps,
+            );
+        }
+    }
+
+    let refreshes = state.refresh_stats.lock().unwrap();
+    out.family(
+        "keenetic_tray_refresh_duration_seconds",
+        "summary",
+        "Time spent refreshing the tray from the routers.",
+    )
+    .sample(
+        "keenetic_tray_refresh_duration_seconds_sum",
+        &[],
+        refreshes.total_seconds,
+    )
+    .sample(
+        "keenetic_tray_refresh_duration_seconds_count",
+        &[],
+        refreshes.count as f64,
+    );
+    out.family(
+        "keenetic_tray_last_refresh_duration_seconds",
+        "gauge",
+        "Duration of the most recent refresh.",
+    )
+    .sample(
+        "keenetic_tray_last_refresh_duration_seconds",
+        &[],
+        refreshes.last_seconds,
+    );
+    drop(refreshes);
+    out.finish()
+}
+
+fn start_mqtt(app: &tauri::AppHandle, settings: &mqtt::MqttSettings, force: bool) {
+    if demo::enabled() {
+        return;
+    }
+    let handle = app.clone();
+    mqtt::start(
+        settings,
+        get_token(mqtt::PASSWORD_KEY),
+        force,
+        move |command| handle_mqtt_command(&handle, command),
+    );
+}
+
+fn start_influx(settings: &influx::InfluxSettings, token: Option<String>) {
+    if demo::enabled() {
+        return;
+    }
+    influx::start(settings, token);
+}
+
+fn mqtt_devices(states: &[ActiveState]) -> Vec<mqtt::Device> {
+    states
+        .iter()
+        .flat_map(|active| {
+            let mut policies: Vec<String> = active.policies.keys().cloned().collect();
+            policies.sort();
+            active.interfaces.iter().map(move |iface| mqtt::Device {
+                router: active.router.name.clone(),
+                mac: iface.mac.clone(),
+                name: iface.display_name.clone(),
+                policy: iface.policy.clone(),
+                blocked: iface.deny,
+                policies: policies.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Applies a change from Home Assistant like the matching tray item would,
+/// except that blocking isn't confirmed: nobody is at the screen for it.
+fn handle_mqtt_command(app: &tauri::AppHandle, command: mqtt::Command) {
+    queue_action(app, move |app, state| {
+        let (router, mac, target) = match command {
+            mqtt::Command::Policy {
+                router,
+                mac,
+                policy,
+            } => (
+                router,
+                mac,
+                PolicyOverride {
+                    policy,
+                    deny: false,
+                },
+            ),
+            mqtt::Command::Blocked {
+                router,
+                mac,
+                blocked,
+            } => {
+                // Unblocking goes back to the policy the client had.
+                let policy = if blocked {
+                    None
+                } else {
+                    current_policy(state, &router, &mac)
+                };
+                let target = PolicyOverride {
+                    policy,
+                    deny: blocked,
+                };
+                (router, mac, target)
+            }
+        };
+        if let Err(err) = set_client_policy(state, &router, &mac, target) {
+            record_error(app, state, format!("{router}: {err}"));
+        }
+        request_refresh(state);
+    });
+}
+
+fn current_policy(state: &AppState, router: &str, mac: &str) -> Option<String> {
+    state
+        .active_states
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|active| active.router.name == router)
+        .flat_map(|active| &active.interfaces)
+        .find(|iface| iface.mac == mac)
+        .and_then(|iface| iface.policy.clone())
+}
+
+#[tauri::command]
+fn get_api_token() -> Option<String> {
+    get_token(api::TOKEN_KEY)
+}
+
+/// Replaces the API token; scripts and WebSocket clients using the old one
+/// stop working.
+#[tauri::command]
+fn regenerate_api_token(
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<String, String> {
+    let token = crypto::random_key();
+    set_password(api::TOKEN_KEY, &token).map_err(|e| e.to_string())?;
+    let settings = state.settings.lock().unwrap().clone();
+    start_api(&app, &settings.api);
+    start_websocket(&app, &settings.websocket);
+    start_grpc(&app, &settings.grpc);
+    Ok(token)
+}
+
+/// Stores the InfluxDB token and restarts the exporter with it.
+#[tauri::command]
+fn set_influx_token(token: String, state: tauri::State<Arc<AppState>>) -> Result<(), String> {
+    set_password(influx::TOKEN_KEY, &token).map_err(|e| e.to_string())?;
+    let settings = state.settings.lock().unwrap().influx.clone();
+    start_influx(&settings, Some(token));
+    Ok(())
+}
+
+/// The secret webhook signatures are made with.
+#[tauri::command]
+fn get_webhook_secret() -> String {
+    stored_token(webhooks::SECRET_KEY, true)
+}
+
+/// Replaces the webhook secret; receivers must be given the new one.
+#[tauri::command]
+fn regenerate_webhook_secret() -> Result<String, String> {
+    let secret = crypto::random_key();
+    set_password(webhooks::SECRET_KEY, &secret).map_err(|e| e.to_string())?;
+    Ok(secret)
+}
+
+#[tauri::command]
+fn play_sound(sound: sound::Sound) {
+    sound::play(sound);
+}
+
+#[tauri::command]
+fn get_family(state: tauri::State<Arc<AppState>>) -> Vec<family::Child> {
+    state.settings.lock().unwrap().family.clone()
+}
+
+#[tauri::command]
+fn save_family(
+    children: Vec<family::Child>,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<(), String> {
+    let children = children
+        .into_iter()
+        .filter(|child| !child.name.trim().is_empty())
+        .map(|child| family::Child {
+            name: child.name.trim().to_string(),
+            devices: child.devices.iter().map(|mac| mac.to_lowercase()).collect(),
+            ..child
+        })
+        .collect();
+    let mut settings = state.settings.lock().unwrap();
+    settings.family = children;
+    save_settings(&settings).map_err(|e| e.to_string())
+}
+
+/// Each child's devices as the router sees them now.
+#[tauri::command]
+async fn family_status(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<family::ChildStatus>, String> {
+    run_blocking(&state, |state| {
+        let children = state.settings.lock().unwrap().family.clone();
+        let mut routers: HashMap<String, Result<_, String>> = HashMap::new();
+        Ok(children
+            .into_iter()
+            .map(|child| {
+                let fetched = routers.entry(child.router.clone()).or_insert_with(|| {
+                    let client = connect_router(state, &child.router).map_err(|e| e.to_string())?;
+                    let devices = client.get_devices().map_err(|e| e.to_string())?;
+                    let policies = client.get_policies().map_err(|e| e.to_string())?;
+                    Ok((devices, policies))
+                });
+                let (router_devices, policies) = match fetched {
+                    Ok(fetched) => fetched,
+                    Err(err) => {
+                        return family::ChildStatus {
+                            name: child.name,
+                            devices: Vec::new(),
+                            asleep: false,
+                            error: Some(err.clone()),
+                        }
+                    }
+                };
+                let devices: Vec<family::FamilyDevice> = child
+                    .devices
+                    .iter()
+                    .map(|mac| {
+                        let device = router_devices.iter().find(|d| &d.mac == mac);
+                        family::FamilyDevice {
+                            mac: mac.clone(),
+                            name: device
+                                .and_then(|d| d.name.clone())
+                                .unwrap_or_else(|| mac.clone()),
+                            online: device.is_some_and(|d| d.online),
+                            policy: device
+                                .map(|d| policy_label(d.policy.as_deref(), d.deny, policies))
+                                .unwrap_or_default(),
+                        }
+                    })
+                    .collect();
+                let asleep = !devices.is_empty()
+                    && child
+                        .devices
+                        .iter()
+                        .all(|mac| router_devices.iter().any(|d| &d.mac == mac && d.deny));
+                family::ChildStatus {
+                    name: child.name,
+                    devices,
+                    asleep,
+                    error: None,
+                }
+            })
+            .collect())
+    })
+    .await
+}
+
+/// Starts or ends bedtime for one child right away.
+#[tauri::command]
+async fn set_bedtime(
+    name: String,
+    asleep: bool,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let child = state
+        .settings
+        .lock()
+        .unwrap()
+        .family
+        .iter()
+        .find(|child| child.name == name)
+        .cloned()
+        .ok_or_else(|| tf("error.unknown_child", &[("name", &name)]))?;
+    if asleep {
+        confirm_destructive(
+            &app,
+            &state,
+            t("confirm.block"),
+            tf("confirm.bedtime_question", &[("name", &child.name)]),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    state
+        .worker
+        .call(move |app, state| {
+            set_bedtime_mode(app, state, &child, asleep).map_err(|e| e.to_string())
+        })
+        .await
+}
+
+#[tauri::command]
+fn get_traffic_history(mac: String, state: tauri::State<Arc<AppState>>) -> Vec<traffic::RatePoint> {
+    state.traffic.lock().unwrap().series(&mac)
+}
+
+#[tauri::command]
+fn get_presence(
+    mac: String,
+    hours: i64,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<Vec<presence::PresenceInterval>, String> {
+    let since = Local::now() - chrono::Duration::hours(hours);
+    match state.presence.lock().unwrap().as_ref() {
+        Some(store) => store.timeline(&mac, since).map_err(|e| e.to_string()),
+        None => Err(t("error.presence_unavailable")),
+    }
+}
+
+#[tauri::command]
+fn get_usage_report(
+    days: u32,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<Vec<usage::DailyUsage>, String> {
+    match state.usage.lock().unwrap().as_ref() {
+        Some(store) => store.daily(days).map_err(|e| e.to_string()),
+        None => Err(t("error.usage_unavailable")),
+    }
+}
+
+#[tauri::command]
+fn get_router_availability(
+    days: i64,
+    state: tauri::State<Arc<AppState>>,
+) -> HashMap<String, availability::Availability> {
+    state
+        .availability
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|store| store.stats(days).ok())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+fn get_outages(
+    limit: u32,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<Vec<outages::Outage>, String> {
+    match state.outages.lock().unwrap().as_ref() {
+        Some(store) => store.list(limit).map_err(|e| e.to_string()),
+        None => Err(t("error.outages_unavailable")),
+    }
+}
+
+/// The latest events from the event log, optionally of one kind or device.
+#[tauri::command]
+fn list_events(
+    kind: Option<String>,
+    mac: Option<String>,
+    limit: u32,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<Vec<events::Event>, String> {
+    match state.events.lock().unwrap().as_ref() {
+        Some(store) => store
+            .list(kind.as_deref(), mac.as_deref(), limit)
+            .map_err(|e| e.to_string()),
+        None => Err(t("error.events_unavailable")),
+    }
+}
+
+#[tauri::command]
+fn clear_outages(state: tauri::State<Arc<AppState>>) -> Result<(), String> {
+    match state.outages.lock().unwrap().as_ref() {
+        Some(store) => store.clear().map_err(|e| e.to_string()),
+        None => Err(t("error.outages_unavailable")),
+    }
+}
+
+#[tauri::command]
+fn get_settings(state: tauri::State<Arc<AppState>>) -> AppSettings {
+    state.settings.lock().unwrap().clone()
+}
+
+/// Whether the file store's key had to be written beside it for want of a
+/// keyring, which the settings window warns about.
+#[tauri::command]
+fn is_secret_file_obfuscated() -> bool {
+    secrets::file_key_exposed()
+}
+
+#[tauri::command]
+fn get_error_log(state: tauri::State<Arc<AppState>>) -> Vec<ErrorEntry> {
+    state.errors.lock().unwrap().entries()
+}
+
+#[tauri::command]
+fn clear_error_log(state: tauri::State<Arc<AppState>>) {
+    state.errors.lock().unwrap().clear();
+    request_refresh(&state);
+}
+
+#[tauri::command]
+fn get_translations() -> HashMap<String, String> {
+    i18n::translations()
+}
+
+fn apply_settings(app: &tauri::AppHandle, state: &Arc<AppState>, settings: AppSettings) {
+    register_shortcuts(app, &settings.shortcuts);
+    sync_autostart(app, settings.start_at_login);
+    i18n::set_language(&settings.language);
+    localize_window_titles(app);
+    secrets::set_store(settings.secret_store);
+    crash::set_enabled(settings.crash_reports);
+    crash::set_summary(crash_summary(&state.routers.lock().unwrap(), &settings));
+    logging::set_level(settings.log_level);
+    start_metrics(state, &settings.metrics);
+    start_influx(&settings.influx, get_token(influx::TOKEN_KEY));
+    start_api(app, &settings.api);
+    start_websocket(app, &settings.websocket);
+    start_grpc(app, &settings.grpc);
+    #[cfg(target_os = "linux")]
+    if let Some(handle) = state.linux_tray.lock().unwrap().as_ref() {
+        let symbolic = settings.symbolic_tray_icon;
+        handle.update(|tray| tray.symbolic = symbolic);
+    }
+    {
+        let mut current = state.settings.lock().unwrap();
+        if current.bandwidth_alerts != settings.bandwidth_alerts {
+            state.alerts.lock().unwrap().reset();
+        }
+        *current = settings;
+    }
+    request_refresh(state);
+}
+
+#[tauri::command]
+fn update_settings(
+    settings: AppSettings,
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<(), String> {
+    let mut settings = settings;
+    // Entering or leaving master password mode moves the stored secrets, so
+    // it only happens through `set_master_password`.
+    let current = state.settings.lock().unwrap().secret_store;
+    if (current == SecretStore::MasterPassword)
+        != (settings.secret_store == SecretStore::MasterPassword)
+    {
+        settings.secret_store = current;
+    }
+    // These are edited on their own pages and aren't part of the form.
+    {
+        let current = state.settings.lock().unwrap();
+        settings.segment_names = current.segment_names.clone();
+        settings.device_notes = current.device_notes.clone();
+        settings.family = current.family.clone();
+        settings.notifications = current.notifications.clone();
+        settings.mqtt = current.mqtt.clone();
+    }
+    save_settings(&settings).map_err(|e| e.to_string())?;
+    apply_settings(&app, &state, settings);
+    Ok(())
+}
+
+#[tauri::command]
+fn unlock_credentials(
+    password: String,
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<(), String> {
+    if !secrets::has_vault() {
+        // The passwords went with the vault; they have to be entered again.
+        let mut settings = state.settings.lock().unwrap().clone();
+        settings.secret_store = SecretStore::Auto;
+        save_settings(&settings).map_err(|e| e.to_string())?;
+        apply_settings(&app, &state, settings);
+        return Err(t("error.vault_missing"));
+    }
+    secrets::unlock(&password).map_err(|_| t("error.wrong_master_password"))?;
+    if let Some(window) = app.get_webview_window("unlock") {
+        let _ = window.hide();
+    }
+    // The broker password and the API token could not be read while locked.
+    let settings = state.settings.lock().unwrap().clone();
+    start_mqtt(&app, &settings.mqtt, true);
+    start_api(&app, &settings.api);
+    start_websocket(&app, &settings.websocket);
+    start_grpc(&app, &settings.grpc);
+    start_influx(&settings.influx, get_token(influx::TOKEN_KEY));
+    start_telegram(&app, &settings.notifications.telegram);
+    request_refresh(&state);
+    Ok(())
+}
+
+/// Sets, changes or (with `None`) removes the master password. A new
+/// password has to match `confirmation`.
+#[tauri::command]
+fn set_master_password(
+    password: Option<String>,
+    confirmation: Option<String>,
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<(), String> {
+    if secrets::is_locked() {
+        return Err(t("error.credentials_locked"));
+    }
+    let mut settings = state.settings.lock().unwrap().clone();
+    let enabled = settings.secret_store == SecretStore::MasterPassword;
+    let confirmation = confirmation.unwrap_or_default();
+    if password
+        .as_ref()
+        .is_some_and(|p| !p.is_empty() && *p != confirmation)
+    {
+        return Err(t("error.master_password_mismatch"));
+    }
+    let result = match password.filter(|p| !p.is_empty()) {
+        Some(password) if enabled => secrets::change_master_password(&password, &confirmation),
+        Some(password) => {
+            let names: Vec<String> = state
+                .routers
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|r| r.name.clone())
+                .collect();
+            settings.secret_store = SecretStore::MasterPassword;
+            secrets::enable_master_password(&password, &confirmation, &names)
+        }
+        None if enabled => {
+            settings.secret_store = SecretStore::Auto;
+            secrets::disable_master_password(SecretStore::Auto)
+        }
+        None => Ok(()),
+    };
+    result.map_err(|e| e.to_string())?;
+    save_settings(&settings).map_err(|e| e.to_string())?;
+    apply_settings(&app, &state, settings);
+    Ok(())
+}
+
+fn export_bundle(state: &AppState, passphrase: Option<&str>) -> Result<ConfigBundle> {
+    let routers = state.routers.lock().unwrap().clone();
+    let settings = state.settings.lock().unwrap().clone();
+    let secrets = match passphrase.filter(|p| !p.is_empty()) {
+        Some(passphrase) => {
+            let passwords: HashMap<String, String> = routers
+                .iter()
+                .filter_map(|r| get_password(&r.name).map(|p| (r.name.clone(), p.to_string())))
+                .collect();
+            Some(crypto::seal(passphrase, &serde_json::to_vec(&passwords)?)?)
+        }
+        None => None,
+    };
+    Ok(ConfigBundle {
+        version: 1,
+        routers,
+        settings,
+        secrets,
+    })
+}
+
+#[tauri::command]
+fn export_config(
+    path: String,
+    passphrase: Option<String>,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<(), String> {
+    let bundle = export_bundle(&state, passphrase.as_deref()).map_err(|e| e.to_string())?;
+    let data = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    // The bundle holds the API token and, sealed, the router passwords.
+    secrets::write_private(Path::new(&path), &data).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn import_config(
+    path: String,
+    passphrase: Option<String>,
+    app: tauri::AppHandle,
+    state: tauri::State<Arc<AppState>>,
+) -> Result<(), String> {
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let bundle: ConfigBundle = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let passwords: HashMap<String, String> = match (&bundle.secrets, passphrase.as_deref()) {
+        (Some(sealed), Some(passphrase)) if !passphrase.is_empty() => {
+            let plain = crypto::open(passphrase, sealed).map_err(|e| e.to_string())?;
+            serde_json::from_slice(&plain).map_err(|e| e.to_string())?
+        }
+        (Some(_), _) => return Err(t("error.passphrase_required")),
+        (None, _) => HashMap::new(),
+    };
+
+    {
+        let mut routers = state.routers.lock().unwrap();
+        for router in bundle.routers {
+            if let Some(password) = passwords.get(&router.name) {
+                set_password(&router.name, password).map_err(|e| e.to_string())?;
+            }
+            routers.retain(|r| r.name != router.name);
+            routers.push(router);
+        }
+        save_routers(&routers).map_err(|e| e.to_string())?;
+    }
+    let settings = merge_imported_settings(&state.settings.lock().unwrap(), bundle.settings);
+    save_settings(&settings).map_err(|e| e.to_string())?;
+    apply_settings(&app, &state, settings);
+    Ok(())
+}
+
+/// The settings after importing `imported` over `current`. The secret store
+/// stays as it is, since only `set_master_password` moves the stored
+/// passwords between stores. What is edited on its own page
+/// is merged: imported segment names, notes and children are added to the
+/// current ones, and notifications and MQTT are only taken when the bundle
+/// has them set up.
+fn merge_imported_settings(current: &AppSettings, imported: AppSettings) -> AppSettings {
+    let mut settings = imported;
+    settings.secret_store = current.secret_store;
+
+    let mut segment_names = current.segment_names.clone();
+    segment_names.extend(std::mem::take(&mut settings.segment_names));
+    settings.segment_names = segment_names;
+
+    let mut device_notes = current.device_notes.clone();
+    device_notes.extend(std::mem::take(&mut settings.device_notes));
+    settings.device_notes = device_notes;
+
+    let mut family: Vec<family::Child> = current
+        .family
+        .iter()
+        .filter(|child| {
+            !settings
+                .family
+                .iter()
+                .any(|new| new.name == child.name && new.router == child.router)
+        })
+        .cloned()
+        .collect();
+    family.append(&mut settings.family);
+    settings.family = family;
+
+    if settings.notifications == notifications::NotificationSettings::default() {
+        settings.notifications = current.notifications.clone();
+    }
+    if settings.mqtt == mqtt::MqttSettings::default() {
+        settings.mqtt = current.mqtt.clone();
+    }
+    settings
+}
+
+#[tauri::command]
+fn refresh_tray_cmd(state: tauri::State<Arc<AppState>>) {
+    request_refresh(&state);
+}
+
+fn reload_config(app: &tauri::AppHandle, state: &Arc<AppState>) {
+    // A half-written file fails to parse; keep the current config until the
+    // writer is done and the next event arrives.
+    let (Ok(routers), Ok(settings)) = (
+        read_config::<Vec<RouterInfo>>("routers.json"),
+        read_config::<AppSettings>("settings.json"),
+    ) else {
+        return;
+    };
+    let routers_changed = {
+        let mut current = state.routers.lock().unwrap();
+        let changed = serde_json::to_value(&routers).ok() != serde_json::to_value(&*current).ok();
+        if changed {
+            *current = routers;
+        }
+        changed
+    };
+    let settings_changed = serde_json::to_value(&settings).ok()
+        != serde_json::to_value(&*state.settings.lock().unwrap()).ok();
+    if settings_changed {
+        apply_settings(app, state, settings);
+    } else if routers_changed {
+        request_refresh(state);
+    }
+    if routers_changed || settings_changed {
+        let _ = app.emit("config-reloaded", ());
+    }
+}
+
+fn spawn_config_watcher(app: tauri::AppHandle, state: Arc<AppState>) {
+    let routers_path = config_path("routers.json");
+    let settings_path = config_path("settings.json");
+    let Some(dir) = routers_path.parent().map(|p| p.to_path_buf()) else {
+        return;
+    };
+    std::thread::spawn(move || {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(tx) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                report_error(
+                    &app,
+                    tf("error.watch_failed", &[("error", &err.to_string())]),
+                );
+                return;
+            }
+        };
+        if let Err(err) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            report_error(
+                &app,
+                tf("error.watch_failed", &[("error", &err.to_string())]),
+            );
+            return;
+        }
+        while let Ok(event) = rx.recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            let relevant =
+                event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove();
+            let touches_config = event.paths.iter().any(|p| {
+                p.file_name() == routers_path.file_name()
+                    || p.file_name() == settings_path.file_name()
+            });
+            if !relevant || !touches_config {
+                continue;
+            }
+            // Coalesce the burst of events editors and sync tools produce.
+            std::thread::sleep(Duration::from_millis(300));
+            while rx.try_recv().is_ok() {}
+            reload_config(&app, &state);
+        }
+    });
+}
+
+fn handle_menu_event(app: &tauri::AppHandle, event: MenuEvent) {
+    handle_menu_click(app, event.id().as_ref());
+}
+
+fn handle_menu_click(app: &tauri::AppHandle, id: &str) {
+    if id == "quit" {
+        app.exit(0);
+    } else if id == "settings" || id == "add_router" {
+        open_settings_window(app);
+    } else if id == "topology" {
+        open_topology_window(app);
+    } else if id == "traffic" {
+        open_traffic_window(app);
+    } else if id == "family" {
+        open_family_window(app);
+    } else if id == "guest" {
+        open_settings_page(app, "guest.html");
+    } else if id == "unlock" {
+        open_unlock_window(app);
+    } else if id == "log_folder" {
+        open_log_folder(app);
+    } else if id == "crash_report" {
+        open_crash_report(app);
+    } else if id == "update" {
+        open_update_page(app);
+    } else if id == "error_details" {
+        open_error_log_window(app);
+    } else if id.ends_with(":all_devices") {
+        open_settings_page(app, "devices.html");
+    } else if id == "refresh" {
+        if let Some(state) = app.try_state::<Arc<AppState>>() {
+            request_refresh(&state);
+        }
+    } else if let Some(rest) = id.strip_prefix("copy|") {
+        let parts: Vec<&str> = rest.split('|').collect();
+        match parts.as_slice() {
+            [_, "ip", ip] => copy_to_clipboard(app, "IP", ip),
+            [mac, "mac"] => copy_to_clipboard(app, "MAC", &decode_mac(mac)),
+            _ => {}
+        }
+    } else if let Some(rest) = id.strip_prefix("policy|") {
+        let rest = rest.to_string();
+        queue_action(app, move |app, _| handle_policy_click(app, &rest));
+    } else if let Some(rest) = id.strip_prefix("band|") {
+        let rest = rest.to_string();
+        queue_action(app, move |app, _| handle_band_click(app, &rest));
+    }
+}
+
+#[derive(Clone, Copy)]
+enum TrayClick {
+    Left,
+    Right,
+    Double,
+    Middle,
+}
+
+fn handle_tray_event(app: &tauri::AppHandle, event: TrayIconEvent) {
+    let click = match event {
+        TrayIconEvent::Click {
+            button: MouseButton::Left,
+            button_state: MouseButtonState::Up,
+            ..
+        } => TrayClick::Left,
+        TrayIconEvent::Click {
+            button: MouseButton::Right,
+            button_state: MouseButtonState::Up,
+            ..
+        } => TrayClick::Right,
+        TrayIconEvent::Click {
+            button: MouseButton::Middle,
+            button_state: MouseButtonState::Up,
+            ..
+        } => TrayClick::Middle,
+        TrayIconEvent::DoubleClick {
+            button: MouseButton::Left,
+            ..
+        } => TrayClick::Double,
+        _ => return,
+    };
+    handle_tray_click(app, click);
+}
+
+fn handle_tray_click(app: &tauri::AppHandle, click: TrayClick) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let action = {
+        let settings = state.settings.lock().unwrap();
+        match click {
+            TrayClick::Left => settings.left_click,
+            TrayClick::Right => settings.right_click,
+            TrayClick::Double => settings.double_click,
+            TrayClick::Middle => settings.middle_click,
+        }
+    };
+    run_click_action(app, action);
+}
+
+/// Tauri's tray on Linux goes through libappindicator, which ignores clicks
+/// and rebuilds the whole indicator on every menu change. Talking to the
+/// StatusNotifierWatcher directly avoids both; desktops without one still get
+/// Tauri's tray.
+#[cfg(target_os = "linux")]
+fn spawn_linux_tray(app: &tauri::AppHandle, state: &AppState) -> bool {
+    let symbolic = state.settings.lock().unwrap().symbolic_tray_icon;
+    match linux_tray::LinuxTray::spawn(app.clone(), badge::plain(), symbolic) {
+        Ok(handle) => {
+            *state.linux_tray.lock().unwrap() = Some(handle);
+            true
+        }
+        Err(err) => {
+            log::info!("No StatusNotifier host, using the AppIndicator tray: {err}");
+            false
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn spawn_linux_tray(_app: &tauri::AppHandle, _state: &AppState) -> bool {
+    false
+}
+
+/// Registers or removes the login item to match the setting.
+fn sync_autostart(app: &tauri::AppHandle, enabled: bool) {
+    use tauri_plugin_autostart::ManagerExt;
+    let autolaunch = app.autolaunch();
+    if autolaunch.is_enabled().unwrap_or(false) == enabled {
+        return;
+    }
+    let result = if enabled {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    if let Err(err) = result {
+        report_error(
+            app,
+            tf("error.autostart_failed", &[("error", &err.to_string())]),
+        );
+    }
+}
+
+/// Asks the state worker to rebuild the tray; requests queued together are
+/// merged into one.
+fn request_refresh(state: &AppState) {
+    state.worker.send(worker::Message::Refresh);
+}
+
+/// Unlocks the credentials with the password in the environment, and drops
+/// it from there so script hooks don't inherit it.
+fn unlock_from_env() {
+    let password = std::env::var(MASTER_PASSWORD_VAR).ok();
+    std::env::remove_var(MASTER_PASSWORD_VAR);
+    if !secrets::is_locked() {
+        return;
+    }
+    match password {
+        Some(password) => {
+            if let Err(err) = secrets::unlock(&password) {
+                log::error!("Failed to unlock credentials from {MASTER_PASSWORD_VAR}: {err}");
+            }
+        }
+        None => log::error!("Credentials are locked; set {MASTER_PASSWORD_VAR} to unlock them"),
+    }
+}
+
+fn main() {
+    let daemon = std::env::args().any(|arg| arg == DAEMON_FLAG);
+    let demo = std::env::args().any(|arg| arg == demo::FLAG);
+    if demo {
+        if let Err(err) = demo::enable() {
+            eprintln!("Failed to set up demo mode: {err}");
+            std::process::exit(1);
+        }
+    }
+    let app_state = Arc::new(AppState::default());
+    {
+        let mut routers = app_state.routers.lock().unwrap();
+        *routers = load_routers();
+        *app_state.settings.lock().unwrap() = load_settings();
+    }
+    if let Err(err) = logging::init(&log_dir(), app_state.settings.lock().unwrap().log_level) {
+        eprintln!("Failed to start logging: {err}");
+    }
+    crash::install(config_path("crashes"));
+    {
+        let settings = app_state.settings.lock().unwrap();
+        crash::set_enabled(settings.crash_reports);
+        crash::set_summary(crash_summary(&app_state.routers.lock().unwrap(), &settings));
+    }
+    load_pending(&app_state);
+    i18n::set_language(&app_state.settings.lock().unwrap().language);
+    secrets::set_store(app_state.settings.lock().unwrap().secret_store);
+    if let Err(err) = secrets::load_overrides() {
+        log::error!("Failed to load the given credentials: {err}");
+    }
+    if demo {
+        // Nothing from the demo belongs in the keyring.
+        secrets::isolate(demo::config_dir());
+        match demo::start() {
+            Ok(router) => *app_state.routers.lock().unwrap() = vec![router],
+            Err(err) => log::error!("Failed to start the demo router: {err}"),
+        }
+    }
+    if daemon {
+        unlock_from_env();
+    }
+    if let Some(dir) = std::env::var_os(RECORD_DIR_VAR) {
+        let dir = PathBuf::from(dir);
+        match router_core::record_fixtures(&dir) {
+            Ok(()) => log::info!("Recording router answers to {}", dir.display()),
+            Err(err) => log::error!("Failed to record to {}: {err}", dir.display()),
+        }
+    }
+    match presence::PresenceStore::open(&config_path("presence.db")) {
+        Ok(store) => *app_state.presence.lock().unwrap() = Some(store),
+        Err(err) => log::error!("Failed to open presence history: {err}"),
+    }
+    match usage::UsageStore::open(&config_path("usage.db")) {
+        Ok(store) => *app_state.usage.lock().unwrap() = Some(store),
+        Err(err) => log::error!("Failed to open usage history: {err}"),
+    }
+    match outages::OutageStore::open(&config_path("outages.db")) {
+        Ok(store) => *app_state.outages.lock().unwrap() = Some(store),
+        Err(err) => log::error!("Failed to open outage log: {err}"),
+    }
+    match availability::AvailabilityStore::open(&config_path("availability.db")) {
+        Ok(store) => *app_state.availability.lock().unwrap() = Some(store),
+        Err(err) => log::error!("Failed to open availability history: {err}"),
+    }
+    match events::EventStore::open(&config_path("events.db")) {
+        Ok(store) => *app_state.events.lock().unwrap() = Some(store),
+        Err(err) => log::error!("Failed to open the event log: {err}"),
+    }
+
+    let state_handle = app_state.clone();
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_single_instance::init(move |app, args, _| {
+            let links = automation::links_in(&args);
+            if links.is_empty() && !daemon {
+                open_settings_window(app);
+            }
+            for url in &links {
+                handle_automation_url(app, url);
+            }
+        }))
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            None,
+        ))
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_opener::init())
+        .manage(app_state)
+        .invoke_handler(tauri::generate_handler![
+            list_routers,
+            save_router,
+            delete_router,
+            get_settings,
+            get_active_state,
+            get_traffic_history,
+            get_presence,
+            get_usage_report,
+            get_outages,
+            list_events,
+            get_router_availability,
+            clear_outages,
+            apply_policy_ui,
+            list_policies,
+            preview_policy_change,
+            apply_policy_changes,
+            get_notification_settings,
+            save_notification_settings,
+            send_test_email,
+            play_sound,
+            get_api_token,
+            regenerate_api_token,
+            get_webhook_secret,
+            set_influx_token,
+            regenerate_webhook_secret,
+            get_mqtt_settings,
+            save_mqtt_settings,
+            get_family,
+            save_family,
+            family_status,
+            set_bedtime,
+            palette_items,
+            run_palette_item,
+            list_devices,
+            rename_device,
+            forget_devices,
+            approve_devices,
+            create_guest_voucher,
+            list_guest_vouchers,
+            end_guest_voucher,
+            export_devices,
+            get_topology,
+            get_translations,
+            get_error_log,
+            is_secret_file_obfuscated,
+            clear_error_log,
+            update_settings,
+            unlock_credentials,
+            get_password_prompt,
+            test_router_connection,
+            scan_routers,
+            set_segment_name,
+            get_device_notes,
+            set_device_note,
+            update_router_password,
+            set_master_password,
+            export_config,
+            import_config,
+            refresh_tray_cmd
+        ])
         .setup(move |app| {
-            refresh_tray(&app.handle(), &state_handle);
+            let handle = app.handle();
+            if daemon {
+                log::info!("Running as a daemon, without the tray");
+            } else {
+                if !spawn_linux_tray(handle, &state_handle) {
+                    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+                        .show_menu_on_left_click(false)
+                        .on_menu_event(handle_menu_event)
+                        .on_tray_icon_event(|tray, event| {
+                            handle_tray_event(tray.app_handle(), event)
+                        });
+                    if let Some(icon) = badge::plain() {
+                        tray = tray.icon(icon);
+                    }
+                    tray.build(app)?;
+                }
+                let shortcuts = state_handle.settings.lock().unwrap().shortcuts.clone();
+                register_shortcuts(handle, &shortcuts);
+                sync_autostart(handle, state_handle.settings.lock().unwrap().start_at_login);
+            }
+            localize_window_titles(handle);
+            automation::register();
+            worker::spawn(handle.clone(), state_handle.clone());
+            request_refresh(&state_handle);
+            let mqtt = state_handle.settings.lock().unwrap().mqtt.clone();
+            start_mqtt(handle, &mqtt, false);
+            let metrics = state_handle.settings.lock().unwrap().metrics.clone();
+            start_metrics(&state_handle, &metrics);
+            let influx = state_handle.settings.lock().unwrap().influx.clone();
+            start_influx(&influx, get_token(influx::TOKEN_KEY));
+            let api = state_handle.settings.lock().unwrap().api.clone();
+            start_api(handle, &api);
+            let socket = state_handle.settings.lock().unwrap().websocket.clone();
+            start_websocket(handle, &socket);
+            let grpc = state_handle.settings.lock().unwrap().grpc.clone();
+            start_grpc(handle, &grpc);
+            let bot = state_handle
+                .settings
+                .lock()
+                .unwrap()
+                .notifications
+                .telegram
+                .clone();
+            start_telegram(handle, &bot);
+            #[cfg(target_os = "linux")]
+            dbus::start(handle.clone());
+            #[cfg(target_os = "windows")]
+            pipe::start(handle.clone());
+            if secrets::is_locked() && !daemon {
+                open_unlock_window(handle);
+            }
+            spawn_staleness_watcher(handle.clone(), state_handle.clone());
+            spawn_client_monitor(handle.clone(), state_handle.clone());
+            if !demo::enabled() {
+                spawn_outage_monitor(handle.clone(), state_handle.clone());
+                spawn_update_checker(handle.clone(), state_handle.clone());
+            }
+            spawn_log_collector(state_handle.clone());
+            spawn_config_watcher(handle.clone(), state_handle.clone());
+            if !daemon {
+                offer_crash_report(handle, &state_handle);
+            }
+            let args: Vec<String> = std::env::args().collect();
+            for url in &automation::links_in(&args) {
+                handle_automation_url(handle, url);
+            }
+            #[cfg(target_os = "linux")]
+            systemd::ready();
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app_handle, event| match event {
+            tauri::RunEvent::Exit => {
+                #[cfg(target_os = "linux")]
+                systemd::stopping();
+                secrets::clear_cache();
+            }
+            // Links reach a macOS app as an Apple Event, not as an argument.
+            #[cfg(target_os = "macos")]
+            tauri::RunEvent::Opened { urls } => {
+                for url in &urls {
+                    handle_automation_url(_app_handle, url);
+                }
+            }
+            _ => {}
+        });
 }
@@ -0,0 +1,74 @@
+//! Turns the tray menu, worked out as plain data by `router_tray_menu`, into
+//! a native menu, and keeps a native menu up to date with a new one.
+
+use tauri::menu::{IsMenuItem, Menu, MenuItem, MenuItemKind, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, Runtime};
+
+pub use router_tray_menu::{Entry, TrayMenu};
+
+pub fn build<R: Runtime>(menu: &TrayMenu, app: &AppHandle<R>) -> tauri::Result<Menu<R>> {
+    let native = Menu::new(app)?;
+    for entry in menu.entries() {
+        native.append(&*build_entry(entry, app)?)?;
+    }
+    Ok(native)
+}
+
+/// Brings `native`, built from `old`, up to date with `new`, which must have
+/// the same layout. Only titles and enabled states that differ are touched,
+/// so open submenus stay open.
+pub fn update_native<R: Runtime>(
+    old: &TrayMenu,
+    new: &TrayMenu,
+    native: &[MenuItemKind<R>],
+) -> tauri::Result<()> {
+    for ((old, new), native) in old.entries().iter().zip(new.entries()).zip(native) {
+        match (old, new) {
+            (Entry::Item(old), Entry::Item(new)) => {
+                let Some(item) = native.as_menuitem() else {
+                    continue;
+                };
+                if old.title != new.title {
+                    item.set_text(&new.title)?;
+                }
+                if old.enabled != new.enabled {
+                    item.set_enabled(new.enabled)?;
+                }
+            }
+            (Entry::Submenu(old_title, old), Entry::Submenu(new_title, new)) => {
+                let Some(submenu) = native.as_submenu() else {
+                    continue;
+                };
+                if old_title != new_title {
+                    submenu.set_text(new_title)?;
+                }
+                update_native(old, new, &submenu.items()?)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn build_entry<R: Runtime>(
+    entry: &Entry,
+    app: &AppHandle<R>,
+) -> tauri::Result<Box<dyn IsMenuItem<R>>> {
+    Ok(match entry {
+        Entry::Item(item) => Box::new(MenuItem::with_id(
+            app,
+            &item.id,
+            &item.title,
+            item.enabled,
+            None::<&str>,
+        )?),
+        Entry::Separator => Box::new(PredefinedMenuItem::separator(app)?),
+        Entry::Submenu(title, menu) => {
+            let submenu = Submenu::new(app, title, true)?;
+            for entry in menu.entries() {
+                submenu.append(&*build_entry(entry, app)?)?;
+            }
+            Box::new(submenu)
+        }
+    })
+}
@@ -0,0 +1,119 @@
+//! Optional Prometheus endpoint on localhost. Metrics are rendered from the
+//! tray's state on each scrape, so nothing extra is collected in between.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tiny_http::{Header, Response, Server};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for MetricsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9877,
+        }
+    }
+}
+
+/// Durations of tray refreshes since the app started.
+#[derive(Default)]
+pub struct RefreshStats {
+    pub count: u64,
+    pub total_seconds: f64,
+    pub last_seconds: f64,
+}
+
+impl RefreshStats {
+    pub fn record(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        self.count += 1;
+        self.total_seconds += seconds;
+        self.last_seconds = seconds;
+    }
+}
+
+/// The Prometheus text format.
+#[derive(Default)]
+pub struct Exposition {
+    out: String,
+}
+
+impl Exposition {
+    pub fn family(&mut self, name: &str, kind: &str, help: &str) -> &mut Self {
+        let _ = writeln!(self.out, "# HELP {name} {help}");
+        let _ = writeln!(self.out, "# TYPE {name} {kind}");
+        self
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.out, " {value}");
+        self
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+static SERVER: Mutex<Option<(MetricsSettings, Arc<Server>)>> = Mutex::new(None);
+
+/// Serves `/metrics` on 127.0.0.1 with `settings`, replacing the running
+/// server. Does nothing when it already runs with the same settings.
+pub fn start(settings: &MetricsSettings, render: impl Fn() -> String + Send + 'static) {
+    let mut running = SERVER.lock().unwrap();
+    if running
+        .as_ref()
+        .is_some_and(|(current, _)| current == settings)
+    {
+        return;
+    }
+    if let Some((_, server)) = running.take() {
+        server.unblock();
+    }
+    if !settings.enabled {
+        return;
+    }
+    let server = match Server::http(("127.0.0.1", settings.port)) {
+        Ok(server) => Arc::new(server),
+        Err(err) => {
+            log::warn!("Failed to start the metrics endpoint: {err}");
+            return;
+        }
+    };
+    *running = Some((settings.clone(), server.clone()));
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = if request.url() == "/metrics" {
+                let content_type =
+                    Header::from_bytes("Content-Type", "text/plain; version=0.0.4").unwrap();
+                Response::from_string(render()).with_header(content_type)
+            } else {
+                Response::from_string("Not found").with_status_code(404)
+            };
+            let _ = request.respond(response);
+        }
+    });
+}
@@ -0,0 +1,295 @@
+//! Home Assistant integration over MQTT. Every client gets a `select` with
+//! its policies and a `switch` for the Blocked state, announced through MQTT
+//! discovery, so automations flip the same policies the tray does.
+
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const DISCOVERY_PREFIX: &str = "homeassistant";
+/// Name of the broker password in the secret store.
+pub const PASSWORD_KEY: &str = "mqtt:broker";
+/// Select option for "no policy".
+pub const DEFAULT_OPTION: &str = "default";
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// Root of the state and command topics.
+    pub topic: String,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 1883,
+            username: String::new(),
+            topic: "keenetic-tray".to_string(),
+        }
+    }
+}
+
+/// A client as Home Assistant sees it.
+pub struct Device {
+    pub router: String,
+    pub mac: String,
+    pub name: String,
+    pub policy: Option<String>,
+    pub blocked: bool,
+    /// Policies the router offers.
+    pub policies: Vec<String>,
+}
+
+/// A change requested from Home Assistant.
+pub enum Command {
+    Policy {
+        router: String,
+        mac: String,
+        policy: Option<String>,
+    },
+    Blocked {
+        router: String,
+        mac: String,
+        blocked: bool,
+    },
+}
+
+struct Connection {
+    client: Client,
+    settings: MqttSettings,
+    stopped: Arc<AtomicBool>,
+    /// Discovery payloads already sent, by topic, so unchanged entities
+    /// aren't announced on every refresh.
+    announced: HashMap<String, String>,
+    /// Router and MAC behind each device topic, for incoming commands.
+    devices: HashMap<String, (String, String)>,
+}
+
+static CONNECTION: Mutex<Option<Connection>> = Mutex::new(None);
+
+/// Connects with `settings`, replacing the current connection. Does nothing
+/// when already connected with the same settings, unless `force` is set.
+pub fn start(
+    settings: &MqttSettings,
+    password: Option<String>,
+    force: bool,
+    on_command: impl Fn(Command) + Send + 'static,
+) {
+    let mut connection = CONNECTION.lock().unwrap();
+    if !force
+        && connection
+            .as_ref()
+            .is_some_and(|current| &current.settings == settings)
+    {
+        return;
+    }
+    if let Some(old) = connection.take() {
+        old.stopped.store(true, Ordering::Relaxed);
+        let _ = old.client.disconnect();
+    }
+    if !settings.enabled || settings.host.is_empty() {
+        return;
+    }
+
+    let mut options = MqttOptions::new(
+        format!("keenetic-tray-{}", std::process::id()),
+        settings.host.clone(),
+        settings.port,
+    );
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        status_topic(&settings.topic),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if !settings.username.is_empty() {
+        options.set_credentials(settings.username.clone(), password.unwrap_or_default());
+    }
+    let (client, mut events) = Client::new(options, 64);
+    let stopped = Arc::new(AtomicBool::new(false));
+    *connection = Some(Connection {
+        client,
+        settings: settings.clone(),
+        stopped: stopped.clone(),
+        announced: HashMap::new(),
+        devices: HashMap::new(),
+    });
+    drop(connection);
+
+    let root = settings.topic.clone();
+    std::thread::spawn(move || {
+        for event in events.iter() {
+            if stopped.load(Ordering::Relaxed) {
+                break;
+            }
+            match event {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => on_connected(&root),
+                Ok(Event::Incoming(Packet::Publish(message))) => {
+                    if let Some(command) = parse_command(&message.topic, &message.payload) {
+                        on_command(command);
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    log::warn!("MQTT connection failed: {err}");
+                    std::thread::sleep(RETRY_DELAY);
+                }
+            }
+        }
+    });
+}
+
+/// Subscriptions and retained discovery don't survive every broker restart,
+/// so both are sent again on each connect.
+fn on_connected(root: &str) {
+    let mut connection = CONNECTION.lock().unwrap();
+    let Some(connection) = connection.as_mut() else {
+        return;
+    };
+    connection.announced.clear();
+    let _ = connection
+        .client
+        .try_subscribe(format!("{root}/+/+/+/set"), QoS::AtLeastOnce);
+    let _ = connection
+        .client
+        .try_publish(status_topic(root), QoS::AtLeastOnce, true, "online");
+}
+
+/// Publishes the state of every device, announcing new or changed ones.
+pub fn publish(devices: &[Device]) {
+    let mut connection = CONNECTION.lock().unwrap();
+    let Some(connection) = connection.as_mut() else {
+        return;
+    };
+    let root = connection.settings.topic.clone();
+    for device in devices {
+        let id = object_id(&device.mac);
+        let base = format!("{root}/{}/{id}", topic_part(&device.router));
+        connection
+            .devices
+            .insert(base.clone(), (device.router.clone(), device.mac.clone()));
+        for (topic, payload) in discovery(&root, &base, &id, device) {
+            if connection.announced.get(&topic) != Some(&payload) {
+                let _ = connection.client.try_publish(
+                    topic.clone(),
+                    QoS::AtLeastOnce,
+                    true,
+                    payload.clone(),
+                );
+                connection.announced.insert(topic, payload);
+            }
+        }
+        let policy = device.policy.as_deref().unwrap_or(DEFAULT_OPTION);
+        let blocked = if device.blocked { "ON" } else { "OFF" };
+        let _ = connection.client.try_publish(
+            format!("{base}/policy/state"),
+            QoS::AtLeastOnce,
+            true,
+            policy,
+        );
+        let _ = connection.client.try_publish(
+            format!("{base}/blocked/state"),
+            QoS::AtLeastOnce,
+            true,
+            blocked,
+        );
+    }
+}
+
+fn discovery(root: &str, base: &str, id: &str, device: &Device) -> [(String, String); 2] {
+    let ha_device = json!({
+        "identifiers": [format!("keenetic_{id}")],
+        "connections": [["mac", device.mac]],
+        "name": device.name,
+    });
+    let options: Vec<&str> = std::iter::once(DEFAULT_OPTION)
+        .chain(device.policies.iter().map(String::as_str))
+        .collect();
+    let select = json!({
+        "name": "Policy",
+        "unique_id": format!("keenetic_{id}_policy"),
+        "state_topic": format!("{base}/policy/state"),
+        "command_topic": format!("{base}/policy/set"),
+        "availability_topic": status_topic(root),
+        "options": options,
+        "device": ha_device,
+    });
+    let switch = json!({
+        "name": "Blocked",
+        "unique_id": format!("keenetic_{id}_blocked"),
+        "state_topic": format!("{base}/blocked/state"),
+        "command_topic": format!("{base}/blocked/set"),
+        "availability_topic": status_topic(root),
+        "icon": "mdi:web-off",
+        "device": ha_device,
+    });
+    [
+        (
+            format!("{DISCOVERY_PREFIX}/select/keenetic_{id}/policy/config"),
+            select.to_string(),
+        ),
+        (
+            format!("{DISCOVERY_PREFIX}/switch/keenetic_{id}/blocked/config"),
+            switch.to_string(),
+        ),
+    ]
+}
+
+fn parse_command(topic: &str, payload: &[u8]) -> Option<Command> {
+    let rest = topic.strip_suffix("/set")?;
+    let (base, kind) = rest.rsplit_once('/')?;
+    let (router, mac) = CONNECTION
+        .lock()
+        .unwrap()
+        .as_ref()?
+        .devices
+        .get(base)?
+        .clone();
+    let value = std::str::from_utf8(payload).ok()?.trim();
+    match kind {
+        "policy" => Some(Command::Policy {
+            router,
+            mac,
+            policy: (value != DEFAULT_OPTION).then(|| value.to_string()),
+        }),
+        "blocked" => Some(Command::Blocked {
+            router,
+            mac,
+            blocked: value.eq_ignore_ascii_case("ON"),
+        }),
+        _ => None,
+    }
+}
+
+fn status_topic(root: &str) -> String {
+    format!("{root}/status")
+}
+
+fn object_id(mac: &str) -> String {
+    mac.to_lowercase().replace([':', '-'], "")
+}
+
+/// Router names can hold characters with a meaning in topics.
+fn topic_part(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
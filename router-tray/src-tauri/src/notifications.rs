@@ -0,0 +1,169 @@
+//! Which alerts reach the user. Each category can be turned off, single
+//! routers can be muted, and quiet hours hold back every alert. Replies to
+//! something the user just did aren't alerts and always show.
+
+use crate::arrivals::WatchedDevice;
+use crate::email::EmailSettings;
+use crate::push::PushSettings;
+use crate::sound::Sound;
+use crate::telegram::TelegramSettings;
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    NewDevice,
+    Outage,
+    /// Policies changed by quotas, bedtimes or shortcuts.
+    PolicyChange,
+    /// Bandwidth alerts and usage summaries.
+    Usage,
+    Update,
+    /// Watched devices arriving or leaving.
+    Presence,
+}
+
+/// A notification that goes through the user's settings before it's shown.
+pub struct Alert {
+    pub category: Category,
+    pub router: Option<String>,
+    pub title: String,
+    pub body: String,
+    /// Buttons as (id, label); only Windows toasts show them.
+    pub actions: Vec<(String, String)>,
+    /// Critical alerts can also play a sound.
+    pub critical: bool,
+}
+
+impl Alert {
+    pub fn new(category: Category, title: String, body: String) -> Self {
+        Self {
+            category,
+            router: None,
+            title,
+            body,
+            actions: Vec::new(),
+            critical: false,
+        }
+    }
+
+    pub fn router(mut self, router: &str) -> Self {
+        self.router = Some(router.to_string());
+        self
+    }
+
+    pub fn action(mut self, id: impl Into<String>, label: String) -> Self {
+        self.actions.push((id.into(), label));
+        self
+    }
+
+    pub fn critical(mut self) -> Self {
+        self.critical = true;
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub new_device: bool,
+    pub outage: bool,
+    pub policy_change: bool,
+    pub usage: bool,
+    pub update: bool,
+    pub presence: bool,
+    /// Devices whose arrivals and departures are announced.
+    pub watched_devices: Vec<WatchedDevice>,
+    /// How long a watched device has to stay online or offline before it
+    /// counts as arrived or left.
+    pub presence_debounce_minutes: u64,
+    pub quiet_hours: Option<QuietHours>,
+    /// Routers whose alerts are never shown.
+    pub muted_routers: Vec<String>,
+    /// Played when the internet goes down, whether or not outage alerts
+    /// are shown.
+    pub outage_sound: Option<Sound>,
+    /// Played when an unknown device joins, whether or not new device
+    /// alerts are shown.
+    pub new_device_sound: Option<Sound>,
+    /// Alerts that are shown also go to this chat.
+    pub telegram: TelegramSettings,
+    /// Phone push, with its own choice of categories.
+    pub push: PushSettings,
+    pub email: EmailSettings,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            new_device: false,
+            outage: true,
+            policy_change: true,
+            usage: true,
+            update: true,
+            presence: true,
+            watched_devices: Vec::new(),
+            presence_debounce_minutes: 3,
+            quiet_hours: None,
+            muted_routers: Vec::new(),
+            outage_sound: None,
+            new_device_sound: None,
+            telegram: TelegramSettings::default(),
+            push: PushSettings::default(),
+            email: EmailSettings::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Whether `time` falls inside the window, which may span midnight.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Whether the alert's router is muted or it's quiet hours, which hold
+    /// back sounds as well as notifications.
+    pub fn silenced(&self, alert: &Alert, now: NaiveTime) -> bool {
+        let muted = alert
+            .router
+            .as_ref()
+            .is_some_and(|router| self.muted_routers.contains(router));
+        let quiet = self.quiet_hours.is_some_and(|hours| hours.contains(now));
+        muted || quiet
+    }
+
+    pub fn shows(&self, category: Category) -> bool {
+        match category {
+            Category::NewDevice => self.new_device,
+            Category::Outage => self.outage,
+            Category::PolicyChange => self.policy_change,
+            Category::Usage => self.usage,
+            Category::Update => self.update,
+            Category::Presence => self.presence,
+        }
+    }
+
+    pub fn sound_for(&self, alert: &Alert) -> Option<Sound> {
+        if !alert.critical {
+            return None;
+        }
+        match alert.category {
+            Category::Outage => self.outage_sound,
+            Category::NewDevice => self.new_device_sound,
+            _ => None,
+        }
+    }
+}
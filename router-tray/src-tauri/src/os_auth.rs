@@ -0,0 +1,86 @@
+//! Asks the operating system to confirm the user's identity (Touch ID or the
+//! account password on macOS, Windows Hello, polkit on Linux) before a
+//! destructive action goes through.
+
+use anyhow::Result;
+
+/// Returns `Ok(true)` when the user confirmed, `Ok(false)` when they cancelled
+/// or failed verification. Blocks until the system prompt is dismissed.
+pub fn confirm(reason: &str) -> Result<bool> {
+    platform::confirm(reason)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use anyhow::{anyhow, Result};
+    use block::ConcreteBlock;
+    use objc::runtime::{Object, BOOL, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CString;
+    use std::sync::mpsc;
+
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    extern "C" {}
+
+    /// `LAPolicyDeviceOwnerAuthentication`: biometrics with password fallback.
+    const DEVICE_OWNER_AUTHENTICATION: i64 = 2;
+
+    pub fn confirm(reason: &str) -> Result<bool> {
+        let reason = CString::new(reason)?;
+        let (tx, rx) = mpsc::channel();
+        unsafe {
+            let context: *mut Object = msg_send![class!(LAContext), new];
+            let reason: *mut Object =
+                msg_send![class!(NSString), stringWithUTF8String: reason.as_ptr()];
+            let reply = ConcreteBlock::new(move |success: BOOL, _error: *mut Object| {
+                let _ = tx.send(success == YES);
+            })
+            .copy();
+            let _: () = msg_send![context,
+                evaluatePolicy: DEVICE_OWNER_AUTHENTICATION
+                localizedReason: reason
+                reply: &*reply];
+            let confirmed = rx
+                .recv()
+                .map_err(|_| anyhow!("authentication was interrupted"));
+            let _: () = msg_send![context, release];
+            confirmed
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use anyhow::Result;
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{UserConsentVerificationResult, UserConsentVerifier};
+
+    pub fn confirm(reason: &str) -> Result<bool> {
+        let result =
+            UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))?.get()?;
+        Ok(result == UserConsentVerificationResult::Verified)
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use anyhow::{anyhow, Result};
+    use std::process::Command;
+
+    /// Requires admin authentication through the running polkit agent.
+    const ACTION_ID: &str = "org.freedesktop.policykit.exec";
+
+    pub fn confirm(_reason: &str) -> Result<bool> {
+        let status = Command::new("pkcheck")
+            .args([
+                "--action-id",
+                ACTION_ID,
+                "--allow-user-interaction",
+                "--process",
+            ])
+            .arg(std::process::id().to_string())
+            .status()
+            .map_err(|err| anyhow!("polkit is not available: {err}"))?;
+        Ok(status.success())
+    }
+}
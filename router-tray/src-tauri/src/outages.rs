@@ -0,0 +1,139 @@
+use anyhow::Result;
+use chrono::{DateTime, Local, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::net::{SocketAddr, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+/// Well-known anycast resolvers probed over TCP; any one answering means the
+/// internet is reachable from this computer.
+const PROBES: [&str; 3] = ["1.1.1.1:443", "8.8.8.8:443", "9.9.9.9:443"];
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A WAN outage; `end` is `None` while it is still going on.
+#[derive(Clone, Debug, Serialize)]
+pub struct Outage {
+    pub router: String,
+    pub start: DateTime<Local>,
+    pub end: Option<DateTime<Local>>,
+    pub seconds: i64,
+}
+
+/// Outages per router, kept in a local SQLite database.
+pub struct OutageStore {
+    conn: Connection,
+}
+
+impl OutageStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS outages (
+                router TEXT NOT NULL,
+                start INTEGER NOT NULL,
+                end INTEGER
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Whether an outage of `router` is currently open.
+    pub fn is_down(&self, router: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT 1 FROM outages WHERE router = ?1 AND end IS NULL",
+                params![router],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    pub fn begin(&self, router: &str, at: DateTime<Local>) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO outages (router, start) VALUES (?1, ?2)",
+            params![router, at.timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Closes the open outage of `router`, returning how long it lasted.
+    pub fn end(&self, router: &str, at: DateTime<Local>) -> Result<Option<i64>> {
+        let start: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT start FROM outages WHERE router = ?1 AND end IS NULL",
+                params![router],
+                |row| row.get(0),
+            )
+            .optional()?;
+        self.conn.execute(
+            "UPDATE outages SET end = ?2 WHERE router = ?1 AND end IS NULL",
+            params![router, at.timestamp()],
+        )?;
+        Ok(start.map(|start| at.timestamp() - start))
+    }
+
+    /// How many outages of `router` began at or after `since`.
+    pub fn count_since(&self, router: &str, since: DateTime<Local>) -> Result<u32> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM outages WHERE router = ?1 AND start >= ?2",
+            params![router, since.timestamp()],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// The most recent outages, newest first.
+    pub fn list(&self, limit: u32) -> Result<Vec<Outage>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT router, start, end FROM outages ORDER BY start DESC LIMIT ?1")?;
+        let now = Local::now();
+        let rows = stmt.query_map(params![limit], |row| {
+            let start: i64 = row.get(1)?;
+            let end: Option<i64> = row.get(2)?;
+            Ok(Outage {
+                router: row.get(0)?,
+                start: timestamp(start),
+                end: end.map(timestamp),
+                seconds: end.unwrap_or(now.timestamp()) - start,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        self.conn.execute("DELETE FROM outages", [])?;
+        Ok(())
+    }
+}
+
+fn timestamp(secs: i64) -> DateTime<Local> {
+    Local
+        .timestamp_opt(secs, 0)
+        .single()
+        .unwrap_or_else(Local::now)
+}
+
+/// Tries to open a TCP connection to a few public hosts.
+pub fn probe_internet() -> bool {
+    PROBES.iter().any(|addr| {
+        addr.parse::<SocketAddr>()
+            .map(|addr| TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok())
+            .unwrap_or(false)
+    })
+}
+
+/// "1h 5m", "3m 20s" or "45s".
+pub fn format_duration(seconds: i64) -> String {
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if h > 0 {
+        format!("{h}h {m}m")
+    } else if m > 0 {
+        format!("{m}m {s}s")
+    } else {
+        format!("{s}s")
+    }
+}
@@ -0,0 +1,160 @@
+//! `\\.\pipe\routertray`, the Windows counterpart of the D-Bus service, so
+//! PowerShell and AutoHotkey scripts can list clients and change policies
+//! without a network port. Remote clients are refused.
+//!
+//! Each request is one line of JSON and gets one line back:
+//!
+//! ```powershell
+//! $pipe = New-Object System.IO.Pipes.NamedPipeClientStream('.', 'routertray', 'InOut')
+//! $pipe.Connect(2000)
+//! $writer = New-Object System.IO.StreamWriter($pipe); $writer.AutoFlush = $true
+//! $reader = New-Object System.IO.StreamReader($pipe)
+//! $writer.WriteLine('{"method": "list_clients"}'); $reader.ReadLine()
+//! ```
+//!
+//! Methods are `list_clients`, `get_active_state` and `set_policy` with
+//! `mac`, `policy` (`null` for the router's default), `blocked` and, when
+//! several routers know the MAC, `router`. Replies are
+//! `{"ok": true, "result": ...}` or `{"ok": false, "error": "..."}`.
+
+use crate::{AppState, PolicyOverride};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::os::windows::io::{FromRawHandle, RawHandle};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use windows::core::HSTRING;
+use windows::Win32::Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX};
+use windows::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+    PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+
+const NAME: &str = r"\\.\pipe\routertray";
+const BUFFER_SIZE: u32 = 64 * 1024;
+/// ConnectNamedPipe fails with this when the client was faster.
+const ERROR_PIPE_CONNECTED: i32 = 535;
+
+#[derive(Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    ListClients,
+    GetActiveState,
+    SetPolicy {
+        mac: String,
+        #[serde(default)]
+        router: Option<String>,
+        #[serde(default)]
+        policy: Option<String>,
+        #[serde(default)]
+        blocked: bool,
+    },
+}
+
+/// Serves the pipe for as long as the app runs, one thread per client.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut first = true;
+        loop {
+            let pipe = match create(first) {
+                Ok(pipe) => pipe,
+                Err(err) => {
+                    log::warn!("Failed to open the named pipe: {err}");
+                    return;
+                }
+            };
+            first = false;
+            let app = app.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = serve(&app, pipe) {
+                    log::debug!("Named pipe client dropped: {err}");
+                }
+            });
+        }
+    });
+}
+
+/// Opens a new instance of the pipe and waits for a client to connect.
+/// The first instance claims the name, so no other program can take it.
+fn create(first: bool) -> std::io::Result<File> {
+    let mut mode = PIPE_ACCESS_DUPLEX;
+    if first {
+        mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+    }
+    let handle = unsafe {
+        CreateNamedPipeW(
+            &HSTRING::from(NAME),
+            mode,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            BUFFER_SIZE,
+            BUFFER_SIZE,
+            0,
+            None,
+        )
+    };
+    if handle.is_invalid() {
+        return Err(std::io::Error::last_os_error());
+    }
+    // The file owns the handle from here on and closes it when dropped.
+    let pipe = unsafe { File::from_raw_handle(handle.0 as RawHandle) };
+    if !unsafe { ConnectNamedPipe(handle, None) }.as_bool() {
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() != Some(ERROR_PIPE_CONNECTED) {
+            return Err(err);
+        }
+    }
+    Ok(pipe)
+}
+
+fn serve(app: &AppHandle, pipe: File) -> std::io::Result<()> {
+    let mut writer = pipe.try_clone()?;
+    for line in BufReader::new(pipe).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str(&line) {
+            Ok(request) => answer(app, request),
+            Err(err) => Err(err.to_string()),
+        };
+        let reply = match reply {
+            Ok(result) => json!({ "ok": true, "result": result }),
+            Err(err) => json!({ "ok": false, "error": err }),
+        };
+        writeln!(writer, "{reply}")?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn answer(app: &AppHandle, request: Request) -> Result<Value, String> {
+    let state = app
+        .try_state::<Arc<AppState>>()
+        .ok_or_else(|| "not ready".to_string())?;
+    match request {
+        Request::ListClients => {
+            serde_json::to_value(crate::client_list(&state)).map_err(|err| err.to_string())
+        }
+        Request::GetActiveState => {
+            let states = state.active_states.lock().unwrap().clone();
+            serde_json::to_value(states).map_err(|err| err.to_string())
+        }
+        Request::SetPolicy {
+            mac,
+            router,
+            policy,
+            blocked,
+        } => {
+            let client = crate::find_client(&state, &mac, router.as_deref())?;
+            let target = PolicyOverride {
+                policy,
+                deny: blocked,
+            };
+            crate::apply_external_policy(app, client.router, client.mac, target)?;
+            Ok(Value::Null)
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use chrono::{DateTime, Local, TimeZone};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A stretch of time a device was online; `end` is `None` while it still is.
+#[derive(Clone, Debug, Serialize)]
+pub struct PresenceInterval {
+    pub start: DateTime<Local>,
+    pub end: Option<DateTime<Local>>,
+}
+
+/// Online/offline transitions per MAC, kept in a local SQLite database.
+pub struct PresenceStore {
+    conn: Connection,
+    current: HashMap<String, bool>,
+}
+
+impl PresenceStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS presence (
+                mac TEXT NOT NULL,
+                online INTEGER NOT NULL,
+                time INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS presence_mac_time ON presence (mac, time);",
+        )?;
+        let current = {
+            let mut stmt = conn.prepare(
+                "SELECT mac, online FROM presence p
+                 WHERE time = (SELECT MAX(time) FROM presence WHERE mac = p.mac)",
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<rusqlite::Result<HashMap<String, bool>>>()?
+        };
+        Ok(Self { conn, current })
+    }
+
+    /// Stores a row only when a device's state differs from the last one
+    /// recorded for it. Returns the devices never seen before; on the very
+    /// first run every device is taken as known.
+    pub fn record(&mut self, devices: &[(String, bool)]) -> Result<Vec<String>> {
+        let now = Local::now().timestamp();
+        let first_run = self.current.is_empty();
+        let mut new = Vec::new();
+        let tx = self.conn.transaction()?;
+        for (mac, online) in devices {
+            if self.current.get(mac) == Some(online) {
+                continue;
+            }
+            if !first_run && !self.current.contains_key(mac) {
+                new.push(mac.clone());
+            }
+            tx.execute(
+                "INSERT INTO presence (mac, online, time) VALUES (?1, ?2, ?3)",
+                params![mac, online, now],
+            )?;
+            self.current.insert(mac.clone(), *online);
+        }
+        tx.commit()?;
+        Ok(new)
+    }
+
+    pub fn timeline(&self, mac: &str, since: DateTime<Local>) -> Result<Vec<PresenceInterval>> {
+        // Include the last transition before the window so a device that was
+        // already online at `since` starts there.
+        let mut stmt = self.conn.prepare(
+            "SELECT online, time FROM presence
+             WHERE mac = ?1 AND time >= COALESCE(
+                 (SELECT MAX(time) FROM presence WHERE mac = ?1 AND time <= ?2), ?2)
+             ORDER BY time",
+        )?;
+        let rows = stmt.query_map(params![mac, since.timestamp()], |row| {
+            Ok((row.get::<_, bool>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        let mut out: Vec<PresenceInterval> = Vec::new();
+        for row in rows {
+            let (online, time) = row?;
+            let time = Local
+                .timestamp_opt(time, 0)
+                .single()
+                .unwrap_or_else(Local::now)
+                .max(since);
+            match out.last_mut() {
+                Some(last) if last.end.is_none() && !online => last.end = Some(time),
+                Some(last) if last.end.is_none() => {}
+                _ if online => out.push(PresenceInterval {
+                    start: time,
+                    end: None,
+                }),
+                _ => {}
+            }
+        }
+        Ok(out)
+    }
+}
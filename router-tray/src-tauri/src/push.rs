@@ -0,0 +1,129 @@
+//! Phone push through ntfy or Pushover, for alerts away from the desktop
+//! without running a bot. Each alert category can be sent or not on its
+//! own, separately from what the desktop shows.
+
+use crate::notifications::{Alert, Category};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+/// Name of the ntfy access token or Pushover application token in the
+/// secret store.
+pub const TOKEN_KEY: &str = "push:token";
+const PUSHOVER_URL: &str = "https://api.pushover.net/1/messages.json";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PushService {
+    Ntfy,
+    Pushover,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PushSettings {
+    /// Off when unset.
+    pub service: Option<PushService>,
+    pub ntfy_server: String,
+    pub ntfy_topic: String,
+    pub pushover_user: String,
+    pub categories: Vec<Category>,
+}
+
+impl Default for PushSettings {
+    fn default() -> Self {
+        Self {
+            service: None,
+            ntfy_server: "https://ntfy.sh".to_string(),
+            ntfy_topic: String::new(),
+            pushover_user: String::new(),
+            categories: vec![Category::Outage, Category::NewDevice],
+        }
+    }
+}
+
+impl PushSettings {
+    pub fn sends(&self, category: Category) -> bool {
+        self.service.is_some() && self.categories.contains(&category)
+    }
+}
+
+/// Pushes the alert in the background; failures are only logged.
+pub fn send(settings: &PushSettings, token: Option<String>, alert: &Alert) {
+    let Some(service) = settings.service else {
+        return;
+    };
+    let settings = settings.clone();
+    let title = alert.title.clone();
+    let body = alert.body.clone();
+    let critical = alert.critical;
+    std::thread::spawn(move || {
+        let sent = match service {
+            PushService::Ntfy => ntfy(&settings, token.as_deref(), &title, &body, critical),
+            PushService::Pushover => pushover(&settings, token.as_deref(), &title, &body, critical),
+        };
+        if let Err(err) = sent {
+            log::warn!("Failed to send a push notification: {err}");
+        }
+    });
+}
+
+fn client() -> Result<reqwest::blocking::Client> {
+    Ok(reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()?)
+}
+
+fn ntfy(
+    settings: &PushSettings,
+    token: Option<&str>,
+    title: &str,
+    body: &str,
+    critical: bool,
+) -> Result<()> {
+    let topic = settings.ntfy_topic.trim();
+    if topic.is_empty() {
+        anyhow::bail!("no ntfy topic set");
+    }
+    // Publishing as JSON keeps non-ASCII titles intact, which headers can't.
+    let mut request = client()?
+        .post(settings.ntfy_server.trim().trim_end_matches('/'))
+        .json(&json!({
+            "topic": topic,
+            "title": title,
+            "message": body,
+            "priority": if critical { 4 } else { 3 },
+        }));
+    // Self-hosted servers may require an access token.
+    if let Some(token) = token.filter(|token| !token.is_empty()) {
+        request = request.bearer_auth(token);
+    }
+    request.send()?.error_for_status()?;
+    Ok(())
+}
+
+fn pushover(
+    settings: &PushSettings,
+    token: Option<&str>,
+    title: &str,
+    body: &str,
+    critical: bool,
+) -> Result<()> {
+    let Some(token) = token.filter(|token| !token.is_empty()) else {
+        anyhow::bail!("no Pushover application token set");
+    };
+    let priority = if critical { "1" } else { "0" };
+    client()?
+        .post(PUSHOVER_URL)
+        .form(&[
+            ("token", token),
+            ("user", settings.pushover_user.trim()),
+            ("title", title),
+            ("message", body),
+            ("priority", priority),
+        ])
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::usage::UsageStore;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+const BLOCKS_KEY: &str = "quota_blocks";
+
+/// Daily data allowance for one device, counting both directions.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DataQuota {
+    pub mac: String,
+    pub gigabytes: f64,
+}
+
+impl DataQuota {
+    pub fn bytes(&self) -> u64 {
+        (self.gigabytes * 1_000_000_000.0) as u64
+    }
+}
+
+/// A device blocked for going over its quota, with the policy it had before
+/// so it can be put back at midnight.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuotaBlock {
+    pub mac: String,
+    pub router: String,
+    pub day: NaiveDate,
+    pub policy: Option<String>,
+}
+
+/// Blocks are kept next to the usage data so they survive a restart.
+pub fn load_blocks(store: &UsageStore) -> Vec<QuotaBlock> {
+    store
+        .meta(BLOCKS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_blocks(store: &UsageStore, blocks: &[QuotaBlock]) -> Result<()> {
+    store.set_meta(BLOCKS_KEY, &serde_json::to_string(blocks)?)
+}
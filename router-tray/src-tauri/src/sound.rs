@@ -0,0 +1,111 @@
+//! Short sounds for critical alerts. They use the sounds that ship with the
+//! system, so nothing is bundled and the user's sound theme applies.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sound {
+    /// A warning tone for things that need attention now.
+    Alert,
+    /// A softer tone for things worth a look.
+    Chime,
+}
+
+/// Starts the sound and returns without waiting for it to finish.
+pub fn play(sound: Sound) {
+    if let Err(err) = platform::play(sound) {
+        log::warn!("Failed to play {sound:?} sound: {err}");
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::Sound;
+    use anyhow::{anyhow, Result};
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CString;
+
+    pub fn play(sound: Sound) -> Result<()> {
+        let name = CString::new(match sound {
+            Sound::Alert => "Basso",
+            Sound::Chime => "Glass",
+        })?;
+        unsafe {
+            let name: *mut Object =
+                msg_send![class!(NSString), stringWithUTF8String: name.as_ptr()];
+            let sound: *mut Object = msg_send![class!(NSSound), soundNamed: name];
+            if sound.is_null() {
+                return Err(anyhow!("system sound is missing"));
+            }
+            let _: objc::runtime::BOOL = msg_send![sound, play];
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::Sound;
+    use anyhow::{anyhow, Result};
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::HMODULE;
+    use windows::Win32::Media::Audio::{PlaySoundW, SND_ALIAS, SND_ASYNC};
+
+    pub fn play(sound: Sound) -> Result<()> {
+        let alias = match sound {
+            Sound::Alert => "SystemExclamation",
+            Sound::Chime => "SystemNotification",
+        };
+        let played = unsafe {
+            PlaySoundW(
+                &HSTRING::from(alias),
+                HMODULE::default(),
+                SND_ALIAS | SND_ASYNC,
+            )
+        };
+        if played.as_bool() {
+            Ok(())
+        } else {
+            Err(anyhow!("PlaySound failed"))
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::Sound;
+    use anyhow::{anyhow, Result};
+    use std::process::{Command, Stdio};
+
+    /// Used when libcanberra's player isn't installed.
+    const SOUND_DIR: &str = "/usr/share/sounds/freedesktop/stereo";
+
+    pub fn play(sound: Sound) -> Result<()> {
+        // Names from the freedesktop sound naming spec.
+        let id = match sound {
+            Sound::Alert => "dialog-warning",
+            Sound::Chime => "message-new-instant",
+        };
+        let spawned = Command::new("canberra-gtk-play")
+            .args(["--id", id])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .or_else(|_| {
+                Command::new("paplay")
+                    .arg(format!("{SOUND_DIR}/{id}.oga"))
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+            })
+            .map_err(|err| anyhow!("no sound player found: {err}"))?;
+        // Reap the player once it's done instead of leaving a zombie.
+        std::thread::spawn(move || {
+            let mut child = spawned;
+            let _ = child.wait();
+        });
+        Ok(())
+    }
+}
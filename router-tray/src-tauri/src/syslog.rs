@@ -0,0 +1,174 @@
+//! Keeps the routers' system logs, which otherwise only live in a small
+//! buffer on the router. New lines are appended to a file per router in the
+//! log folder, rotated by size, and can also be forwarded to a syslog
+//! server over UDP.
+
+use anyhow::Result;
+use router_core::LogEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::net::UdpSocket;
+use std::path::{Path, PathBuf};
+
+/// Lines asked for on every poll; more than a poll interval's worth.
+pub const MAX_LINES: u32 = 200;
+/// Files are rotated past this size, keeping one old file.
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+/// The syslog facility lines are forwarded under: local0.
+const FACILITY: u8 = 16;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyslogSettings {
+    pub enabled: bool,
+    pub save_to_file: bool,
+    /// `host:port` of a syslog server taking UDP; not forwarded when empty.
+    pub server: String,
+}
+
+impl Default for SyslogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            save_to_file: true,
+            server: String::new(),
+        }
+    }
+}
+
+/// Remembers which lines were already kept, so lines seen again on the next
+/// poll are skipped.
+pub struct Collector {
+    dir: PathBuf,
+    seen: HashMap<String, (HashSet<LogEntry>, VecDeque<LogEntry>)>,
+}
+
+impl Collector {
+    /// Files go to `dir`.
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Stores and forwards the lines of `entries` not collected before.
+    pub fn collect(&mut self, settings: &SyslogSettings, router: &str, entries: Vec<LogEntry>) {
+        let first = !self.seen.contains_key(router);
+        let path = file_path(&self.dir, router);
+        let (set, order) = self.seen.entry(router.to_string()).or_default();
+        // After a restart the router still has lines saved last time.
+        let saved: HashSet<String> = if first {
+            fs::read_to_string(&path)
+                .map(|text| {
+                    let lines: Vec<&str> = text.lines().collect();
+                    let tail = lines.len().saturating_sub(MAX_LINES as usize * 2);
+                    lines[tail..].iter().map(|line| line.to_string()).collect()
+                })
+                .unwrap_or_default()
+        } else {
+            HashSet::new()
+        };
+        let mut fresh = Vec::new();
+        for entry in entries {
+            if set.insert(entry.clone()) {
+                order.push_back(entry.clone());
+                if !saved.contains(&format_line(&entry)) {
+                    fresh.push(entry);
+                }
+            }
+        }
+        // Anything older than the router's buffer can't come back.
+        while order.len() > MAX_LINES as usize * 2 {
+            if let Some(old) = order.pop_front() {
+                set.remove(&old);
+            }
+        }
+        keep(settings, &path, router, &fresh);
+    }
+}
+
+fn keep(settings: &SyslogSettings, path: &Path, router: &str, entries: &[LogEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    if settings.save_to_file {
+        if let Err(err) = append(path, entries) {
+            log::warn!("Failed to save the log of {router}: {err}");
+        }
+    }
+    let server = settings.server.trim();
+    if !server.is_empty() {
+        if let Err(err) = forward(server, router, entries) {
+            log::warn!("Failed to forward the log of {router}: {err}");
+        }
+    }
+}
+
+fn file_path(dir: &Path, router: &str) -> PathBuf {
+    let name: String = router
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("router-{name}.log"))
+}
+
+fn append(path: &Path, entries: &[LogEntry]) -> Result<()> {
+    if fs::metadata(path).is_ok_and(|meta| meta.len() > MAX_FILE_SIZE) {
+        fs::rename(path, path.with_extension("log.1"))?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for entry in entries {
+        writeln!(file, "{}", format_line(entry))?;
+    }
+    Ok(())
+}
+
+fn format_line(entry: &LogEntry) -> String {
+    let level = entry.level.as_deref().unwrap_or("-");
+    // Messages can span lines; the file keeps one line per entry.
+    let message = entry.message.replace('\n', " ");
+    format!("{} {} [{level}] {message}", entry.timestamp, entry.ident)
+}
+
+/// Sends each line as an RFC 3164 message, with the router as the host.
+fn forward(server: &str, router: &str, entries: &[LogEntry]) -> Result<()> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+    socket.connect(server)?;
+    let host: String = router
+        .chars()
+        .map(|c| if c.is_whitespace() { '-' } else { c })
+        .collect();
+    for entry in entries {
+        let priority = FACILITY * 8 + severity(entry.level.as_deref());
+        let ident = if entry.ident.is_empty() {
+            "router"
+        } else {
+            &entry.ident
+        };
+        let message = format!(
+            "<{priority}>{} {host} {ident}: {}",
+            entry.timestamp, entry.message
+        );
+        socket.send(message.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn severity(level: Option<&str>) -> u8 {
+    match level.map(str::to_ascii_lowercase).as_deref() {
+        Some("critical") => 2,
+        Some("error") => 3,
+        Some("warning") => 4,
+        Some("debug") => 7,
+        _ => 6,
+    }
+}
@@ -0,0 +1,37 @@
+//! Tells systemd how the daemon is doing, for units with `Type=notify` and
+//! `WatchdogSec=`: readiness once everything has started, and a watchdog
+//! ping every time the traffic monitor and the state worker both got through
+//! a round. Does nothing outside systemd, where `$NOTIFY_SOCKET` is unset.
+
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+
+pub fn ready() {
+    send("READY=1");
+}
+
+pub fn stopping() {
+    send("STOPPING=1");
+}
+
+pub fn watchdog() {
+    send("WATCHDOG=1");
+}
+
+fn send(message: &str) {
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let path = path.to_string_lossy();
+    let result = UnixDatagram::unbound().and_then(|socket| {
+        // A leading `@` names a socket in the abstract namespace.
+        let address = match path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name)?,
+            None => SocketAddr::from_pathname(path.as_ref())?,
+        };
+        socket.send_to_addr(message.as_bytes(), &address)
+    });
+    if let Err(err) = result {
+        log::warn!("Failed to notify systemd: {err}");
+    }
+}
@@ -0,0 +1,253 @@
+//! Telegram bot: alerts go to one chat, and that chat can send commands
+//! back. Messages from any other chat are ignored, except that before a
+//! chat is set the bot answers with the chat's id so it can be copied into
+//! settings.
+
+use crate::automation::Action;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Name of the bot token in the secret store.
+pub const TOKEN_KEY: &str = "telegram:bot";
+const API_URL: &str = "https://api.telegram.org";
+/// How long Telegram holds a getUpdates call open when nothing happens.
+const POLL_SECONDS: u64 = 50;
+const RETRY_DELAY: Duration = Duration::from_secs(15);
+/// `/policy TV default` puts back the router's default.
+const DEFAULT_POLICY: &str = "default";
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelegramSettings {
+    pub enabled: bool,
+    pub chat_id: String,
+}
+
+#[derive(Deserialize)]
+struct Reply<T> {
+    ok: bool,
+    #[serde(default)]
+    description: Option<String>,
+    result: Option<T>,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    chat: Chat,
+    /// Unix time it was sent.
+    date: i64,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+struct Running {
+    settings: TelegramSettings,
+    token: String,
+    stopped: Arc<AtomicBool>,
+}
+
+static BOT: Mutex<Option<Running>> = Mutex::new(None);
+
+/// Polls for commands with `settings`, replacing the running bot. Does
+/// nothing when it already runs with the same settings and token.
+pub fn start(
+    settings: &TelegramSettings,
+    token: Option<String>,
+    on_command: impl Fn(Action) -> Result<String, String> + Send + 'static,
+) {
+    let token = token.unwrap_or_default();
+    let mut running = BOT.lock().unwrap();
+    if running
+        .as_ref()
+        .is_some_and(|current| &current.settings == settings && current.token == token)
+    {
+        return;
+    }
+    if let Some(old) = running.take() {
+        old.stopped.store(true, Ordering::Relaxed);
+    }
+    if !settings.enabled || token.is_empty() {
+        return;
+    }
+    let stopped = Arc::new(AtomicBool::new(false));
+    *running = Some(Running {
+        settings: settings.clone(),
+        token: token.clone(),
+        stopped: stopped.clone(),
+    });
+    let chat_id = settings.chat_id.trim().to_string();
+    std::thread::spawn(move || {
+        let client = match reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(POLL_SECONDS + 10))
+            .build()
+        {
+            Ok(client) => client,
+            Err(err) => {
+                log::warn!("Failed to start the Telegram bot: {err}");
+                return;
+            }
+        };
+        // Telegram keeps messages for a day; commands sent while the app
+        // wasn't running are too old to act on.
+        let started = chrono::Utc::now().timestamp();
+        let mut offset = 0;
+        while !stopped.load(Ordering::Relaxed) {
+            let updates = match get_updates(&client, &token, offset) {
+                Ok(updates) => updates,
+                Err(err) => {
+                    log::warn!("Telegram polling failed: {err}");
+                    std::thread::sleep(RETRY_DELAY);
+                    continue;
+                }
+            };
+            for update in updates {
+                offset = update.update_id + 1;
+                // The bot may have been replaced while the call was open.
+                if stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Some(Message {
+                    chat,
+                    date,
+                    text: Some(text),
+                }) = update.message
+                else {
+                    continue;
+                };
+                if date < started {
+                    continue;
+                }
+                let reply = if chat_id.is_empty() {
+                    crate::i18n::tf("telegram.chat_id", &[("id", &chat.id.to_string())])
+                } else if chat.id.to_string() == chat_id {
+                    match parse_command(&text) {
+                        Some(Ok(action)) => on_command(action).unwrap_or_else(|err| err),
+                        Some(Err(err)) => err,
+                        None => crate::i18n::t("telegram.help"),
+                    }
+                } else {
+                    continue;
+                };
+                if let Err(err) = send_message(&client, &token, &chat.id.to_string(), &reply) {
+                    log::warn!("Failed to answer on Telegram: {err}");
+                }
+            }
+        }
+    });
+}
+
+/// Sends an alert to the configured chat, if the bot runs.
+pub fn send(text: &str) {
+    let (token, chat_id) = match BOT.lock().unwrap().as_ref() {
+        Some(running) if !running.settings.chat_id.trim().is_empty() => (
+            running.token.clone(),
+            running.settings.chat_id.trim().to_string(),
+        ),
+        _ => return,
+    };
+    let text = text.to_string();
+    std::thread::spawn(move || {
+        let sent = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .map_err(anyhow::Error::from)
+            .and_then(|client| send_message(&client, &token, &chat_id, &text));
+        if let Err(err) = sent {
+            log::warn!("Failed to send the alert to Telegram: {err}");
+        }
+    });
+}
+
+/// `/status`, `/block <device>`, `/unblock <device>`,
+/// `/policy <device> <policy|default>`, `/pauseall` and `/resumeall`.
+/// Anything else gets the help text.
+fn parse_command(text: &str) -> Option<Result<Action, String>> {
+    let text = text.trim();
+    let (command, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    // In groups commands can be addressed as /status@SomeBot.
+    let command = command.split('@').next().unwrap_or_default();
+    let rest = rest.trim();
+    let device = || {
+        (!rest.is_empty())
+            .then(|| rest.to_string())
+            .ok_or_else(|| crate::i18n::t("telegram.missing_device"))
+    };
+    let action = match command {
+        "/status" => Ok(Action::Status),
+        "/block" | "/unblock" => device().map(|device| Action::Block {
+            device,
+            router: None,
+            blocked: command == "/block",
+        }),
+        "/policy" => match rest.rsplit_once(char::is_whitespace) {
+            // Names can have spaces, so the policy is the last word.
+            Some((device, policy)) => Ok(Action::Policy {
+                device: device.trim().to_string(),
+                router: None,
+                policy: (!policy.eq_ignore_ascii_case(DEFAULT_POLICY)).then(|| policy.to_string()),
+            }),
+            None => Err(crate::i18n::t("telegram.policy_usage")),
+        },
+        "/pauseall" => Ok(Action::PauseAll),
+        "/resumeall" => Ok(Action::ResumeAll),
+        _ => return None,
+    };
+    Some(action)
+}
+
+fn get_updates(
+    client: &reqwest::blocking::Client,
+    token: &str,
+    offset: i64,
+) -> Result<Vec<Update>> {
+    let reply: Reply<Vec<Update>> = client
+        .post(format!("{API_URL}/bot{token}/getUpdates"))
+        .json(&json!({
+            "offset": offset,
+            "timeout": POLL_SECONDS,
+            "allowed_updates": ["message"],
+        }))
+        .send()
+        .and_then(|response| response.json())
+        // Errors carry the URL, and with it the token.
+        .map_err(reqwest::Error::without_url)?;
+    result(reply)
+}
+
+fn send_message(
+    client: &reqwest::blocking::Client,
+    token: &str,
+    chat_id: &str,
+    text: &str,
+) -> Result<()> {
+    let reply: Reply<serde_json::Value> = client
+        .post(format!("{API_URL}/bot{token}/sendMessage"))
+        .json(&json!({ "chat_id": chat_id, "text": text }))
+        .send()
+        .and_then(|response| response.json())
+        .map_err(reqwest::Error::without_url)?;
+    result(reply).map(|_| ())
+}
+
+fn result<T>(reply: Reply<T>) -> Result<T> {
+    match reply.result {
+        Some(result) if reply.ok => Ok(result),
+        _ => Err(anyhow::anyhow!(reply
+            .description
+            .unwrap_or_else(|| "Telegram refused the request".to_string()))),
+    }
+}
@@ -0,0 +1,89 @@
+//! Windows toast notifications with action buttons. The notification plugin
+//! only shows plain toasts; these go through WinRT directly so a button can
+//! act on the alert without opening a window.
+
+use anyhow::Result;
+use std::sync::Mutex;
+use windows::core::{ComInterface, IInspectable, HSTRING};
+use windows::Data::Xml::Dom::XmlDocument;
+use windows::Foundation::TypedEventHandler;
+use windows::UI::Notifications::{
+    ToastActivatedEventArgs, ToastNotification, ToastNotificationManager,
+};
+
+/// Unpackaged development builds have no Start menu shortcut to take an app
+/// ID from, so they borrow PowerShell's like the notification plugin does.
+const POWERSHELL_APP_ID: &str =
+    "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
+
+/// Toasts are kept alive so buttons still work once they've moved to the
+/// Action Center.
+const KEEP_TOASTS: usize = 20;
+
+static SHOWN: Mutex<Vec<ToastNotification>> = Mutex::new(Vec::new());
+
+pub struct ToastAction {
+    /// Passed to the handler when the button is clicked.
+    pub id: String,
+    pub label: String,
+}
+
+/// Shows a toast whose buttons call `on_action` with their id.
+pub fn show(
+    app_id: &str,
+    title: &str,
+    body: &str,
+    actions: &[ToastAction],
+    on_action: impl Fn(String) + Send + 'static,
+) -> Result<()> {
+    let buttons: String = actions
+        .iter()
+        .map(|action| {
+            format!(
+                r#"<action content="{}" arguments="{}" activationType="foreground"/>"#,
+                escape(&action.label),
+                escape(&action.id)
+            )
+        })
+        .collect();
+    let xml = format!(
+        r#"<toast><visual><binding template="ToastGeneric"><text>{}</text><text>{}</text></binding></visual><actions>{buttons}</actions></toast>"#,
+        escape(title),
+        escape(body)
+    );
+    let doc = XmlDocument::new()?;
+    doc.LoadXml(&HSTRING::from(xml))?;
+    let toast = ToastNotification::CreateToastNotification(&doc)?;
+    toast.Activated(&TypedEventHandler::new(
+        move |_: &Option<ToastNotification>, args: &Option<IInspectable>| {
+            let Some(args) = args else {
+                return Ok(());
+            };
+            let id = args.cast::<ToastActivatedEventArgs>()?.Arguments()?;
+            // Clicking the toast itself has no arguments.
+            if !id.is_empty() {
+                on_action(id.to_string());
+            }
+            Ok(())
+        },
+    ))?;
+    let app_id = if cfg!(debug_assertions) {
+        POWERSHELL_APP_ID
+    } else {
+        app_id
+    };
+    ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(app_id))?.Show(&toast)?;
+    let mut shown = SHOWN.lock().unwrap();
+    shown.push(toast);
+    if shown.len() > KEEP_TOASTS {
+        shown.remove(0);
+    }
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
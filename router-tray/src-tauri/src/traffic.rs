@@ -0,0 +1,91 @@
+use chrono::{DateTime, Local};
+use router_core::TrafficCounters;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+/// Five minutes of samples at the sampler's interval.
+const MAX_POINTS: usize = 60;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RatePoint {
+    pub time: DateTime<Local>,
+    /// Bits per second received by the client.
+    pub rx_bps: f64,
+    /// Bits per second sent by the client.
+    pub tx_bps: f64,
+}
+
+/// Bytes a client transferred since the previous sample.
+#[derive(Clone, Debug)]
+pub struct TrafficDelta {
+    pub mac: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub seconds: f64,
+}
+
+impl TrafficDelta {
+    /// Combined rx + tx rate in bits per second.
+    pub fn bps(&self) -> f64 {
+        (self.rx_bytes + self.tx_bytes) as f64 * 8.0 / self.seconds
+    }
+}
+
+#[derive(Default)]
+pub struct TrafficHistory {
+    last: HashMap<String, (Instant, TrafficCounters)>,
+    series: HashMap<String, VecDeque<RatePoint>>,
+}
+
+impl TrafficHistory {
+    /// Turns a fresh set of cumulative counters into rates against the
+    /// previous sample of each client, returning the byte deltas.
+    pub fn record(&mut self, counters: &HashMap<String, TrafficCounters>) -> Vec<TrafficDelta> {
+        let now = Instant::now();
+        let mut deltas = Vec::new();
+        for (mac, current) in counters {
+            let previous = self.last.insert(mac.clone(), (now, *current));
+            let Some((then, previous)) = previous else {
+                continue;
+            };
+            let seconds = now.duration_since(then).as_secs_f64();
+            if seconds <= 0.0 {
+                continue;
+            }
+            // Counters restart when the client reconnects; treat that sample
+            // as idle rather than a huge negative spike.
+            let rx = current.rx_bytes.saturating_sub(previous.rx_bytes);
+            let tx = current.tx_bytes.saturating_sub(previous.tx_bytes);
+            let series = self.series.entry(mac.clone()).or_default();
+            if series.len() == MAX_POINTS {
+                series.pop_front();
+            }
+            series.push_back(RatePoint {
+                time: Local::now(),
+                rx_bps: rx as f64 * 8.0 / seconds,
+                tx_bps: tx as f64 * 8.0 / seconds,
+            });
+            deltas.push(TrafficDelta {
+                mac: mac.clone(),
+                rx_bytes: rx,
+                tx_bytes: tx,
+                seconds,
+            });
+        }
+        deltas
+    }
+
+    pub fn latest(&self, mac: &str) -> Option<RatePoint> {
+        self.series
+            .get(mac)
+            .and_then(|points| points.back().cloned())
+    }
+
+    pub fn series(&self, mac: &str) -> Vec<RatePoint> {
+        self.series
+            .get(mac)
+            .map(|points| points.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
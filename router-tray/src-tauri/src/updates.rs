@@ -0,0 +1,45 @@
+use anyhow::Result;
+use semver::Version;
+use serde::Deserialize;
+use std::time::Duration;
+
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/Toxblh/router-manager-tray/releases/latest";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Release {
+    pub version: Version,
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+/// The latest published release on GitHub if it is newer than `current`.
+pub fn check(current: &Version) -> Result<Option<Release>> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(15))
+        // The GitHub API rejects requests without a user agent.
+        .user_agent(concat!("keenetic-tray/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let release: GithubRelease = client
+        .get(LATEST_RELEASE_URL)
+        .send()?
+        .error_for_status()?
+        .json()?;
+    if release.draft || release.prerelease {
+        return Ok(None);
+    }
+    let version = Version::parse(release.tag_name.trim_start_matches('v'))?;
+    Ok((version > *current).then_some(Release {
+        version,
+        url: release.html_url,
+    }))
+}
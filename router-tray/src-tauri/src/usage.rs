@@ -0,0 +1,141 @@
+use crate::traffic::TrafficDelta;
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DailyUsage {
+    pub day: NaiveDate,
+    pub mac: String,
+    pub name: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// Per-day traffic totals per MAC, kept in a local SQLite database.
+pub struct UsageStore {
+    conn: Connection,
+}
+
+impl UsageStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS usage (
+                day TEXT NOT NULL,
+                mac TEXT NOT NULL,
+                name TEXT NOT NULL,
+                rx INTEGER NOT NULL,
+                tx INTEGER NOT NULL,
+                PRIMARY KEY (day, mac)
+            );
+            CREATE TABLE IF NOT EXISTS meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Adds the deltas to today's totals; `names` maps MACs to the display
+    /// name to keep for the report.
+    pub fn add(&mut self, deltas: &[TrafficDelta], names: &HashMap<String, String>) -> Result<()> {
+        let day = Local::now().date_naive().to_string();
+        let tx = self.conn.transaction()?;
+        for delta in deltas {
+            if delta.rx_bytes == 0 && delta.tx_bytes == 0 {
+                continue;
+            }
+            let name = names.get(&delta.mac).unwrap_or(&delta.mac);
+            tx.execute(
+                "INSERT INTO usage (day, mac, name, rx, tx) VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (day, mac) DO UPDATE SET
+                     name = excluded.name, rx = rx + excluded.rx, tx = tx + excluded.tx",
+                params![day, delta.mac, name, delta.rx_bytes, delta.tx_bytes],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Daily rows for the last `days` days, today included.
+    pub fn daily(&self, days: u32) -> Result<Vec<DailyUsage>> {
+        let since =
+            Local::now().date_naive() - chrono::Duration::days(days.saturating_sub(1) as i64);
+        let mut stmt = self.conn.prepare(
+            "SELECT day, mac, name, rx, tx FROM usage WHERE day >= ?1 ORDER BY day, mac",
+        )?;
+        let rows = stmt.query_map(params![since.to_string()], |row| {
+            Ok(DailyUsage {
+                day: row.get::<_, String>(0)?.parse().unwrap_or(since),
+                mac: row.get(1)?,
+                name: row.get(2)?,
+                rx_bytes: row.get(3)?,
+                tx_bytes: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Combined rx + tx per MAC for a single day.
+    pub fn day_totals(&self, day: NaiveDate) -> Result<HashMap<String, u64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT mac, rx + tx FROM usage WHERE day = ?1")?;
+        let rows = stmt.query_map(params![day.to_string()], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn meta(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+}
+
+/// Per-device totals over `rows`, largest first.
+pub fn totals(rows: &[DailyUsage]) -> Vec<(String, u64)> {
+    let mut by_mac: HashMap<&str, (String, u64)> = HashMap::new();
+    for row in rows {
+        let entry = by_mac.entry(&row.mac).or_insert((row.name.clone(), 0));
+        entry.0 = row.name.clone();
+        entry.1 += row.rx_bytes + row.tx_bytes;
+    }
+    let mut out: Vec<(String, u64)> = by_mac.into_values().collect();
+    out.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    out
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,276 @@
+//! Local WebSocket for Stream Deck plugins and similar controllers, which
+//! want state pushed to them instead of polling the REST API. It listens on
+//! 127.0.0.1 and takes the REST API token.
+//!
+//! Connect to `ws://127.0.0.1:9879/`. All messages are JSON text frames.
+//! The first one has to carry the token, so it stays out of URLs and logs:
+//!
+//! ```json
+//! {"id": 0, "type": "auth", "token": "<API token>"}
+//! ```
+//!
+//! A wrong token, or none within 10 seconds, closes the connection with
+//! status 1008. Once in, the server sends the state, and again after every
+//! refresh:
+//!
+//! ```json
+//! {"type": "state", "clients": [{"router": "Home", "mac": "aa:bb:cc:dd:ee:ff",
+//!   "name": "TV", "ip": "192.168.1.20", "online": true, "policy": "VPN",
+//!   "blocked": false}]}
+//! ```
+//!
+//! `policy` is `null` for the router's default. Clients send commands, with
+//! an optional `id` that is echoed in the reply:
+//!
+//! ```json
+//! {"id": 1, "type": "set_policy", "mac": "aa:bb:cc:dd:ee:ff", "policy": "VPN", "blocked": false}
+//! {"id": 2, "type": "toggle_block", "mac": "aa:bb:cc:dd:ee:ff"}
+//! {"id": 3, "type": "get_state"}
+//! ```
+//!
+//! `router` may be added to the first two when several routers know the
+//! MAC. Replies are `{"type": "result", "id": 1, "ok": true}` or carry
+//! `"ok": false` and an `"error"`; `get_state` is answered with a state
+//! message.
+
+use router_websocket::{
+    close_payload, read_message, read_upgrade, upgrade_response, write_frame, Message,
+    CLOSE_POLICY_VIOLATION, OP_CLOSE, OP_TEXT,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Largest message accepted from a client; commands are tiny.
+const MAX_MESSAGE: u64 = 64 * 1024;
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a new connection has to send its token.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebSocketSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for WebSocketSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9879,
+        }
+    }
+}
+
+/// A command from a client.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Command {
+    SetPolicy {
+        mac: String,
+        #[serde(default)]
+        router: Option<String>,
+        #[serde(default)]
+        policy: Option<String>,
+        #[serde(default)]
+        blocked: bool,
+    },
+    ToggleBlock {
+        mac: String,
+        #[serde(default)]
+        router: Option<String>,
+    },
+    GetState,
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    id: Value,
+    #[serde(flatten)]
+    command: Command,
+}
+
+type Handler = dyn Fn(Command) -> Result<(), String> + Send + Sync;
+type Writer = Arc<Mutex<TcpStream>>;
+
+struct Running {
+    settings: WebSocketSettings,
+    token: String,
+    stopped: Arc<AtomicBool>,
+}
+
+static SERVER: Mutex<Option<Running>> = Mutex::new(None);
+/// Connected clients, for broadcasting. Each one's writer is locked so a
+/// broadcast and a reply can't interleave their frames.
+static CLIENTS: Mutex<Vec<Writer>> = Mutex::new(Vec::new());
+/// The last state sent, for clients that connect in between refreshes.
+static LAST_STATE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Listens with `settings`, replacing the running server and dropping its
+/// clients. Does nothing when it already runs with the same settings and
+/// token.
+pub fn start(
+    settings: &WebSocketSettings,
+    token: &str,
+    handle: impl Fn(Command) -> Result<(), String> + Send + Sync + 'static,
+) {
+    let mut running = SERVER.lock().unwrap();
+    if running
+        .as_ref()
+        .is_some_and(|current| &current.settings == settings && current.token == token)
+    {
+        return;
+    }
+    if let Some(old) = running.take() {
+        old.stopped.store(true, Ordering::Relaxed);
+        // Wakes the accept loop so it sees the flag.
+        let _ = TcpStream::connect(("127.0.0.1", old.settings.port));
+        for client in CLIENTS.lock().unwrap().drain(..) {
+            let _ = client.lock().unwrap().shutdown(Shutdown::Both);
+        }
+    }
+    if !settings.enabled || token.is_empty() {
+        return;
+    }
+    let listener = match TcpListener::bind(("127.0.0.1", settings.port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            log::warn!("Failed to start the WebSocket server: {err}");
+            return;
+        }
+    };
+    let stopped = Arc::new(AtomicBool::new(false));
+    *running = Some(Running {
+        settings: settings.clone(),
+        token: token.to_string(),
+        stopped: stopped.clone(),
+    });
+    let token = token.to_string();
+    let handle: Arc<Handler> = Arc::new(handle);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::Relaxed) {
+                break;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let token = token.clone();
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = serve(stream, &token, &*handle) {
+                    log::debug!("WebSocket client dropped: {err}");
+                }
+            });
+        }
+    });
+}
+
+/// Sends `message` to every connected client.
+pub fn publish(message: &Value) {
+    let text = message.to_string();
+    *LAST_STATE.lock().unwrap() = Some(text.clone());
+    CLIENTS
+        .lock()
+        .unwrap()
+        .retain(|client| send(client, OP_TEXT, text.as_bytes()).is_ok());
+}
+
+fn serve(mut stream: TcpStream, token: &str, handle: &Handler) -> io::Result<()> {
+    // A client that stops reading mustn't hold up the broadcast.
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let Some(key) = read_upgrade(&stream)? else {
+        stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+        return Ok(());
+    };
+    stream.write_all(upgrade_response(&key).as_bytes())?;
+    let writer: Writer = Arc::new(Mutex::new(stream.try_clone()?));
+    let reply = |opcode: u8, payload: &[u8]| send(&writer, opcode, payload);
+
+    stream.set_read_timeout(Some(AUTH_TIMEOUT))?;
+    let id = match read_message(&mut stream, MAX_MESSAGE, reply) {
+        Ok(Message::Text(text)) => authenticate(&text, token),
+        Ok(Message::Close(_)) => return Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::WouldBlock => None,
+        Err(err) if err.kind() == io::ErrorKind::TimedOut => None,
+        Err(err) => return Err(err),
+    };
+    let Some(id) = id else {
+        let _ = send(
+            &writer,
+            OP_CLOSE,
+            &close_payload(CLOSE_POLICY_VIOLATION, "missing or wrong token"),
+        );
+        return Ok(());
+    };
+    stream.set_read_timeout(None)?;
+    let ok = json!({ "type": "result", "id": id, "ok": true }).to_string();
+    send(&writer, OP_TEXT, ok.as_bytes())?;
+    if let Some(state) = LAST_STATE.lock().unwrap().clone() {
+        send(&writer, OP_TEXT, state.as_bytes())?;
+    }
+    CLIENTS.lock().unwrap().push(writer.clone());
+
+    let result = loop {
+        match read_message(&mut stream, MAX_MESSAGE, reply) {
+            Ok(Message::Text(text)) => {
+                let answer = reply_to(&text, handle);
+                if let Err(err) = send(&writer, OP_TEXT, answer.as_bytes()) {
+                    break Err(err);
+                }
+            }
+            Ok(Message::Close(_)) => break Ok(()),
+            Err(err) => break Err(err),
+        }
+    };
+    CLIENTS
+        .lock()
+        .unwrap()
+        .retain(|client| !Arc::ptr_eq(client, &writer));
+    result
+}
+
+/// The `id` of an auth message carrying `token`, or `None` for anything
+/// else.
+fn authenticate(text: &str, token: &str) -> Option<Value> {
+    let message: Value = serde_json::from_str(text).ok()?;
+    let given = message
+        .get("type")
+        .filter(|kind| *kind == "auth")
+        .and(message.get("token"))?
+        .as_str()?;
+    crate::api::constant_time_eq(given.as_bytes(), token.as_bytes())
+        .then(|| message.get("id").cloned().unwrap_or(Value::Null))
+}
+
+fn reply_to(text: &str, handle: &Handler) -> String {
+    let envelope: Envelope = match serde_json::from_str(text) {
+        Ok(envelope) => envelope,
+        Err(err) => {
+            return json!({ "type": "result", "id": null, "ok": false, "error": err.to_string() })
+                .to_string()
+        }
+    };
+    if let Command::GetState = envelope.command {
+        return LAST_STATE
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| json!({ "type": "state", "clients": [] }).to_string());
+    }
+    match handle(envelope.command) {
+        Ok(()) => json!({ "type": "result", "id": envelope.id, "ok": true }),
+        Err(err) => json!({ "type": "result", "id": envelope.id, "ok": false, "error": err }),
+    }
+    .to_string()
+}
+
+fn send(writer: &Writer, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    write_frame(&mut *writer.lock().unwrap(), opcode, payload)
+}
@@ -0,0 +1,49 @@
+//! Reads the SSID this computer is associated with from the operating system,
+//! for when the router's hotspot data leaves it out.
+
+use std::process::Command;
+
+/// SSID of the given wireless interface, if it is connected.
+pub fn current_ssid(iface: &str) -> Option<String> {
+    platform::current_ssid(iface).filter(|ssid| !ssid.is_empty())
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    pub fn current_ssid(iface: &str) -> Option<String> {
+        // "Current Wi-Fi Network: Home"
+        let out = super::run("networksetup", &["-getairportnetwork", iface])?;
+        let (_, ssid) = out.trim().split_once(": ")?;
+        Some(ssid.to_string())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    pub fn current_ssid(_iface: &str) -> Option<String> {
+        // Interface names from pnet are device GUIDs that netsh doesn't
+        // show, so take the first connected adapter. `BSSID` lines share
+        // the suffix, hence the exact key match.
+        let out = super::run("netsh", &["wlan", "show", "interfaces"])?;
+        out.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == "SSID").then(|| value.trim().to_string())
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    pub fn current_ssid(iface: &str) -> Option<String> {
+        let out = super::run("iwgetid", &[iface, "--raw"])?;
+        Some(out.trim().to_string())
+    }
+}
@@ -0,0 +1,119 @@
+//! The state worker: a single thread that runs tray actions and refreshes
+//! one after another. Tray, menu and shortcut handlers and the windows'
+//! commands only send it messages, so they never wait on a router or on each
+//! other's locks, and two actions can't interleave their reads and writes of
+//! the shared state. Actions must not wait on the user: confirmation prompts
+//! are answered before an action is queued.
+
+use crate::AppState;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+pub type Action = Box<dyn FnOnce(&AppHandle, &Arc<AppState>) + Send>;
+
+pub enum Message {
+    /// Rebuild the tray. Requests queued together are merged into one.
+    Refresh,
+    /// Refresh now and again after the delay, for changes the router takes a
+    /// moment to report.
+    RefreshWithFollowUp(Duration),
+    /// Something that reads or changes router state.
+    Action(Action),
+}
+
+#[derive(Default)]
+pub struct Worker {
+    sender: OnceLock<Sender<Message>>,
+}
+
+impl Worker {
+    pub fn send(&self, message: Message) {
+        match self.sender.get() {
+            Some(sender) => {
+                let _ = sender.send(message);
+            }
+            None => log::warn!("State worker is not running"),
+        }
+    }
+
+    /// Runs `task` on the worker and waits for its result, for the windows'
+    /// commands that change router state.
+    pub async fn call<T: Send + 'static>(
+        &self,
+        task: impl FnOnce(&AppHandle, &Arc<AppState>) -> Result<T, String> + Send + 'static,
+    ) -> Result<T, String> {
+        let (sender, mut receiver) = tauri::async_runtime::channel(1);
+        self.send(Message::Action(Box::new(move |app, state| {
+            let _ = sender.blocking_send(task(app, state));
+        })));
+        // The sender is dropped without a value if the task panicked.
+        receiver
+            .recv()
+            .await
+            .unwrap_or_else(|| Err("the action failed; see the log for details".to_string()))
+    }
+}
+
+pub fn spawn(app: AppHandle, state: Arc<AppState>) {
+    let (sender, receiver) = mpsc::channel();
+    if state.worker.sender.set(sender).is_err() {
+        return;
+    }
+    std::thread::Builder::new()
+        .name("state-worker".into())
+        .spawn(move || run(app, state, receiver))
+        .expect("failed to start the state worker");
+}
+
+fn run(app: AppHandle, state: Arc<AppState>, receiver: Receiver<Message>) {
+    let mut follow_up: Option<Instant> = None;
+    loop {
+        let first = match follow_up {
+            Some(at) => match receiver.recv_timeout(at.saturating_duration_since(Instant::now())) {
+                Ok(message) => Some(message),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => return,
+            },
+            None => match receiver.recv() {
+                Ok(message) => Some(message),
+                Err(_) => return,
+            },
+        };
+        // A timeout means the follow-up refresh is due.
+        let mut refresh = first.is_none();
+        if refresh {
+            follow_up = None;
+        }
+        // Everything queued so far, including what the actions themselves
+        // send, is handled before the one refresh that covers it all.
+        for message in first.into_iter().chain(receiver.try_iter()) {
+            match message {
+                Message::Refresh => refresh = true,
+                Message::RefreshWithFollowUp(delay) => {
+                    refresh = true;
+                    follow_up = Some(Instant::now() + delay);
+                }
+                Message::Action(action) => guard("action", || action(&app, &state)),
+            }
+        }
+        if refresh {
+            guard("refresh", || crate::refresh_tray(&app, &state));
+        }
+    }
+}
+
+/// Runs `work`, logging a panic instead of letting it end the worker and
+/// with it every later refresh.
+fn guard(what: &str, work: impl FnOnce()) {
+    if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(work)) {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        log::error!("State worker {what} panicked: {message}");
+    }
+}
@@ -1,15 +1,75 @@
-const tauriApi = window.__TAURI__ && window.__TAURI__.tauri;
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
 const invoke = tauriApi ? tauriApi.invoke : null;
+const dialog = window.__TAURI__ && window.__TAURI__.dialog;
+const events = window.__TAURI__ && window.__TAURI__.event;
 
 const listEl = document.getElementById("router-list");
 const form = document.getElementById("router-form");
 const statusEl = document.getElementById("status");
 const deleteBtn = document.getElementById("delete-btn");
+const testBtn = document.getElementById("test-btn");
+const testResultsEl = document.getElementById("test-results");
 const addBtn = document.getElementById("add-btn");
+const scanBtn = document.getElementById("scan-btn");
+const wizardEl = document.getElementById("wizard");
+const wizardScanEl = document.getElementById("wizard-scan");
+const scanResultsEl = document.getElementById("scan-results");
+const rescanBtn = document.getElementById("rescan-btn");
+const wizardCredentials = document.getElementById("wizard-credentials");
+const wizardAddressEl = document.getElementById("wizard-address");
+const wizardTestResultsEl = document.getElementById("wizard-test-results");
+const wizardBackBtn = document.getElementById("wizard-back-btn");
+const wizardSegment = document.getElementById("wizard-segment");
+const wizardSegmentLabel = document.getElementById("wizard-segment-label");
+const wizardStatusEl = document.getElementById("wizard-status");
+const prefsForm = document.getElementById("prefs-form");
+const prefsStatusEl = document.getElementById("prefs-status");
+const exportBtn = document.getElementById("export-btn");
+const importBtn = document.getElementById("import-btn");
+const passphraseEl = document.getElementById("transfer-passphrase");
+const transferStatusEl = document.getElementById("transfer-status");
+const masterPasswordEl = document.getElementById("master-password");
+const masterConfirmEl = document.getElementById("master-password-confirm");
+const masterSetBtn = document.getElementById("master-set-btn");
+const masterRemoveBtn = document.getElementById("master-remove-btn");
+const masterStatusEl = document.getElementById("master-status");
+const secretStoreNoteEl = document.getElementById("secret-store-note");
+const alertsListEl = document.getElementById("alerts-list");
+const knownDevicesEl = document.getElementById("known-devices");
+const quotasListEl = document.getElementById("quotas-list");
+const addQuotaBtn = document.getElementById("add-quota-btn");
+const awayListEl = document.getElementById("away-list");
+const addAwayBtn = document.getElementById("add-away-btn");
+const addAlertBtn = document.getElementById("add-alert-btn");
+const apiTokenBtn = document.getElementById("api-token-btn");
+const webhooksListEl = document.getElementById("webhooks-list");
+const addWebhookBtn = document.getElementById("add-webhook-btn");
+const scriptHooksListEl = document.getElementById("script-hooks-list");
+const addScriptHookBtn = document.getElementById("add-script-hook-btn");
+const webhookSecretBtn = document.getElementById("webhook-secret-btn");
 
 let routers = [];
+let availability = {};
 let selected = null;
 
+const AVAILABILITY_DAYS = 7;
+let translations = {};
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+  document.querySelectorAll("[data-i18n-placeholder]").forEach((el) => {
+    el.placeholder = tr(el.dataset.i18nPlaceholder);
+  });
+}
+
 function setStatus(text, isError = false) {
   statusEl.textContent = text;
   statusEl.style.color = isError ? "#f05b5b" : "#98a3b3";
@@ -20,6 +80,7 @@ function clearForm() {
   selected = null;
   deleteBtn.disabled = true;
   setStatus("");
+  testResultsEl.innerHTML = "";
 }
 
 function fillForm(router) {
@@ -27,21 +88,50 @@ function fillForm(router) {
   form.address.value = router.address || "";
   form.login.value = router.login || "";
   form.password.value = "";
+  form.allow_remote.checked = !!router.allow_remote;
+  form.refresh_interval_secs.value = router.refresh_interval_secs ?? "";
+  form.primary.checked = !!router.primary;
   deleteBtn.disabled = false;
 }
 
+function availabilityLabel(stats) {
+  if (!stats || !stats.samples) return "";
+  const parts = [
+    tr("ui.uptime")
+      .replace("{percent}", stats.uptime_percent.toFixed(1))
+      .replace("{days}", String(AVAILABILITY_DAYS)),
+  ];
+  if (stats.last_down) {
+    parts.push(
+      tr("ui.last_down").replace("{time}", new Date(stats.last_down).toLocaleString()),
+    );
+  }
+  return parts.join(" · ");
+}
+
 function renderList() {
   listEl.innerHTML = "";
   routers.forEach((router) => {
     const li = document.createElement("li");
     li.textContent = `${router.name} — ${router.address}`;
+    const uptime = availabilityLabel(availability[router.name]);
+    if (uptime) {
+      const meta = document.createElement("div");
+      meta.className = "router-meta";
+      meta.textContent = uptime;
+      li.appendChild(meta);
+    }
     if (selected && selected.name === router.name) {
       li.classList.add("active");
+      li.setAttribute("aria-current", "true");
     }
+    makeActivatable(li);
     li.onclick = () => {
       selected = router;
       fillForm(router);
       renderList();
+      // Re-rendering drops focus; keep it on the router for keyboard users.
+      listEl.querySelector(".active").focus();
     };
     listEl.appendChild(li);
   });
@@ -49,10 +139,13 @@ function renderList() {
 
 async function loadRouters() {
   if (!invoke) {
-    setStatus("Tauri API unavailable", true);
+    setStatus(tr("ui.api_unavailable"), true);
     return;
   }
-  routers = await invoke("list_routers");
+  [routers, availability] = await Promise.all([
+    invoke("list_routers"),
+    invoke("get_router_availability", { days: AVAILABILITY_DAYS }),
+  ]);
   renderList();
   if (!selected && routers.length) {
     selected = routers[0];
@@ -64,20 +157,23 @@ async function loadRouters() {
 form.addEventListener("submit", async (e) => {
   e.preventDefault();
   if (!invoke) {
-    setStatus("Tauri API unavailable", true);
+    setStatus(tr("ui.api_unavailable"), true);
     return;
   }
-  setStatus("Saving...");
+  setStatus(tr("ui.saving"));
   const payload = {
     name: form.name.value.trim(),
     address: form.address.value.trim(),
     login: form.login.value.trim(),
     password: form.password.value,
     original_name: selected ? selected.name : null,
+    allow_remote: form.allow_remote.checked,
+    refresh_interval_secs: Number(form.refresh_interval_secs.value) || null,
+    primary: form.primary.checked,
   };
   try {
     await invoke("save_router", { payload });
-    setStatus("Saved");
+    setStatus(tr("ui.saved"));
     selected = null;
     await loadRouters();
     await invoke("refresh_tray_cmd");
@@ -86,19 +182,56 @@ form.addEventListener("submit", async (e) => {
   }
 });
 
+function renderTestResults(steps, target = testResultsEl) {
+  target.innerHTML = "";
+  steps.forEach((step) => {
+    const li = document.createElement("li");
+    li.className = step.ok ? "ok" : "failed";
+    li.textContent = `${step.ok ? "✓" : "✗"} ${step.name}`;
+    if (step.detail) {
+      li.textContent += ` — ${step.detail}`;
+    }
+    target.appendChild(li);
+  });
+}
+
+testBtn.addEventListener("click", async () => {
+  if (!invoke) {
+    setStatus(tr("ui.api_unavailable"), true);
+    return;
+  }
+  setStatus(tr("ui.testing"));
+  testResultsEl.innerHTML = "";
+  testBtn.disabled = true;
+  try {
+    const steps = await invoke("test_router_connection", {
+      address: form.address.value.trim(),
+      login: form.login.value.trim(),
+      password: form.password.value,
+      originalName: selected ? selected.name : null,
+    });
+    renderTestResults(steps);
+    setStatus("");
+  } catch (err) {
+    setStatus(err, true);
+  } finally {
+    testBtn.disabled = false;
+  }
+});
+
 deleteBtn.addEventListener("click", async () => {
   if (!selected) return;
   if (!invoke) {
-    setStatus("Tauri API unavailable", true);
+    setStatus(tr("ui.api_unavailable"), true);
     return;
   }
-  setStatus("Deleting...");
+  setStatus(tr("ui.deleting"));
   try {
     await invoke("delete_router", { name: selected.name });
     selected = null;
     await loadRouters();
     await invoke("refresh_tray_cmd");
-    setStatus("Deleted");
+    setStatus(tr("ui.deleted"));
   } catch (err) {
     setStatus(err, true);
   }
@@ -108,4 +241,670 @@ addBtn.addEventListener("click", () => {
   clearForm();
 });
 
-loadRouters();
+let discovered = null;
+
+function setWizardStatus(text, isError = false) {
+  wizardStatusEl.textContent = text;
+  wizardStatusEl.style.color = isError ? "#f05b5b" : "#98a3b3";
+}
+
+function showWizardStep(step) {
+  [wizardScanEl, wizardCredentials, wizardSegment].forEach((el) => {
+    el.hidden = el !== step;
+  });
+  setWizardStatus("");
+}
+
+function closeWizard() {
+  wizardEl.hidden = true;
+  discovered = null;
+  wizardCredentials.reset();
+  wizardSegment.reset();
+  wizardTestResultsEl.innerHTML = "";
+}
+
+async function scanNetwork() {
+  showWizardStep(wizardScanEl);
+  scanResultsEl.innerHTML = "";
+  setWizardStatus(tr("ui.scanning"));
+  rescanBtn.disabled = true;
+  try {
+    const found = await invoke("scan_routers");
+    setWizardStatus(found.length ? "" : tr("ui.scan_none"), !found.length);
+    found.forEach((router) => {
+      const li = document.createElement("li");
+      li.textContent = router.realm
+        ? `${router.realm} — ${router.address}`
+        : router.address;
+      makeActivatable(li);
+      li.onclick = () => {
+        discovered = router;
+        wizardAddressEl.textContent = router.realm
+          ? `${router.realm} (${router.address})`
+          : router.address;
+        wizardCredentials.name.value = router.realm || router.address;
+        wizardTestResultsEl.innerHTML = "";
+        showWizardStep(wizardCredentials);
+      };
+      scanResultsEl.appendChild(li);
+    });
+  } catch (err) {
+    setWizardStatus(err, true);
+  } finally {
+    rescanBtn.disabled = false;
+  }
+}
+
+scanBtn.addEventListener("click", () => {
+  if (!invoke) {
+    setStatus(tr("ui.api_unavailable"), true);
+    return;
+  }
+  wizardEl.hidden = false;
+  scanNetwork();
+});
+
+rescanBtn.addEventListener("click", () => scanNetwork());
+wizardBackBtn.addEventListener("click", () => showWizardStep(wizardScanEl));
+document.querySelectorAll(".wizard-cancel").forEach((btn) => {
+  btn.addEventListener("click", () => closeWizard());
+});
+
+wizardCredentials.addEventListener("submit", async (e) => {
+  e.preventDefault();
+  setWizardStatus(tr("ui.testing"));
+  let steps;
+  try {
+    steps = await invoke("test_router_connection", {
+      address: discovered.address,
+      login: wizardCredentials.login.value.trim(),
+      password: wizardCredentials.password.value,
+      originalName: null,
+    });
+  } catch (err) {
+    setWizardStatus(err, true);
+    return;
+  }
+  renderTestResults(steps, wizardTestResultsEl);
+  if (!steps.every((step) => step.ok)) {
+    setWizardStatus("");
+    return;
+  }
+  wizardSegmentLabel.textContent = tr("ui.segment_name").replace(
+    "{network}",
+    discovered.network,
+  );
+  showWizardStep(wizardSegment);
+});
+
+wizardSegment.addEventListener("submit", async (e) => {
+  e.preventDefault();
+  setWizardStatus(tr("ui.saving"));
+  const payload = {
+    name: wizardCredentials.name.value.trim(),
+    address: discovered.address,
+    login: wizardCredentials.login.value.trim(),
+    password: wizardCredentials.password.value,
+    original_name: null,
+    allow_remote: false,
+  };
+  try {
+    await invoke("save_router", { payload });
+    const segment = wizardSegment.segment.value.trim();
+    if (segment) {
+      await invoke("set_segment_name", {
+        network: discovered.network,
+        name: segment,
+      });
+    }
+    closeWizard();
+    selected = null;
+    await loadRouters();
+    await invoke("refresh_tray_cmd");
+    setStatus(tr("ui.saved"));
+  } catch (err) {
+    setWizardStatus(err, true);
+  }
+});
+
+// One row per alert: device MAC (empty for all devices together), Mbit/s and
+// minutes the rate has to be sustained.
+function addAlertRow(alert = { mac: null, mbps: 50, minutes: 10 }) {
+  const row = document.createElement("div");
+  row.className = "alert-row";
+  const mac = document.createElement("input");
+  mac.className = "alert-mac";
+  mac.setAttribute("list", "known-devices");
+  mac.placeholder = tr("ui.alert_all_devices");
+  mac.value = alert.mac || "";
+  const mbps = document.createElement("input");
+  mbps.className = "alert-mbps";
+  mbps.type = "number";
+  mbps.min = "1";
+  mbps.value = alert.mbps;
+  mbps.title = "Mbit/s";
+  const minutes = document.createElement("input");
+  minutes.className = "alert-minutes";
+  minutes.type = "number";
+  minutes.min = "1";
+  minutes.value = alert.minutes;
+  minutes.title = tr("ui.alert_minutes");
+  const remove = document.createElement("button");
+  remove.type = "button";
+  remove.className = "secondary";
+  remove.textContent = "×";
+  remove.setAttribute("aria-label", tr("ui.remove"));
+  remove.addEventListener("click", () => row.remove());
+  row.append(mac, mbps, "Mbit/s", minutes, tr("ui.alert_minutes"), remove);
+  alertsListEl.appendChild(row);
+}
+
+function readAlerts() {
+  return [...alertsListEl.querySelectorAll(".alert-row")]
+    .map((row) => ({
+      mac: row.querySelector(".alert-mac").value.trim().toLowerCase() || null,
+      mbps: Number(row.querySelector(".alert-mbps").value),
+      minutes: Number(row.querySelector(".alert-minutes").value),
+    }))
+    .filter((alert) => alert.mbps > 0 && alert.minutes > 0);
+}
+
+function addQuotaRow(quota = { mac: "", gigabytes: 5 }) {
+  const row = document.createElement("div");
+  row.className = "alert-row";
+  const mac = document.createElement("input");
+  mac.className = "quota-mac";
+  mac.setAttribute("list", "known-devices");
+  mac.placeholder = "MAC";
+  mac.value = quota.mac;
+  const gigabytes = document.createElement("input");
+  gigabytes.className = "quota-gb";
+  gigabytes.type = "number";
+  gigabytes.min = "0.1";
+  gigabytes.step = "0.1";
+  gigabytes.value = quota.gigabytes;
+  const remove = document.createElement("button");
+  remove.type = "button";
+  remove.className = "secondary";
+  remove.textContent = "×";
+  remove.setAttribute("aria-label", tr("ui.remove"));
+  remove.addEventListener("click", () => row.remove());
+  row.append(mac, gigabytes, tr("ui.quota_per_day"), remove);
+  quotasListEl.appendChild(row);
+}
+
+function readQuotas() {
+  return [...quotasListEl.querySelectorAll(".alert-row")]
+    .map((row) => ({
+      mac: row.querySelector(".quota-mac").value.trim().toLowerCase(),
+      gigabytes: Number(row.querySelector(".quota-gb").value),
+    }))
+    .filter((quota) => quota.mac && quota.gigabytes > 0);
+}
+
+// Each away row either blocks the device or sets a policy; an empty policy
+// is the router default.
+function addAwayRow(change = { mac: "", policy: null, block: true }) {
+  const row = document.createElement("div");
+  row.className = "alert-row";
+  const mac = document.createElement("input");
+  mac.className = "away-mac";
+  mac.setAttribute("list", "known-devices");
+  mac.placeholder = "MAC";
+  mac.value = change.mac;
+  const action = document.createElement("select");
+  action.className = "away-action";
+  [
+    ["block", tr("ui.away_block")],
+    ["policy", tr("ui.away_policy")],
+  ].forEach(([value, label]) => {
+    const option = document.createElement("option");
+    option.value = value;
+    option.textContent = label;
+    action.appendChild(option);
+  });
+  action.value = change.block ? "block" : "policy";
+  const policy = document.createElement("input");
+  policy.className = "away-policy";
+  policy.placeholder = tr("ui.away_policy_default");
+  policy.value = change.policy || "";
+  const syncPolicy = () => {
+    policy.hidden = action.value === "block";
+  };
+  action.addEventListener("change", syncPolicy);
+  syncPolicy();
+  const remove = document.createElement("button");
+  remove.type = "button";
+  remove.className = "secondary";
+  remove.textContent = "×";
+  remove.setAttribute("aria-label", tr("ui.remove"));
+  remove.addEventListener("click", () => row.remove());
+  row.append(mac, action, policy, remove);
+  awayListEl.appendChild(row);
+}
+
+function readAwayChanges() {
+  return [...awayListEl.querySelectorAll(".alert-row")]
+    .map((row) => {
+      const block = row.querySelector(".away-action").value === "block";
+      return {
+        mac: row.querySelector(".away-mac").value.trim().toLowerCase(),
+        policy: block ? null : row.querySelector(".away-policy").value.trim() || null,
+        block,
+      };
+    })
+    .filter((change) => change.mac);
+}
+
+async function loadAwayRouters(current) {
+  const names = (await invoke("list_routers")).map((router) => router.name);
+  if (current && !names.includes(current)) names.push(current);
+  prefsForm.away_router.innerHTML = "";
+  names.forEach((name) => {
+    const option = document.createElement("option");
+    option.value = name;
+    option.textContent = name;
+    prefsForm.away_router.appendChild(option);
+  });
+  prefsForm.away_router.value = current || names[0] || "";
+}
+
+async function loadKnownDevices() {
+  if (!invoke) return;
+  const states = await invoke("get_active_state");
+  knownDevicesEl.innerHTML = "";
+  states.forEach((active) => {
+    active.interfaces.forEach((iface) => {
+      const option = document.createElement("option");
+      option.value = iface.mac;
+      option.label = iface.display_name;
+      knownDevicesEl.appendChild(option);
+    });
+  });
+}
+
+addAlertBtn.addEventListener("click", () => addAlertRow());
+addQuotaBtn.addEventListener("click", () => addQuotaRow());
+addAwayBtn.addEventListener("click", () => addAwayRow());
+
+const WEBHOOK_EVENTS = [
+  "device_joined",
+  "device_left",
+  "policy_changed",
+  "internet_down",
+  "internet_up",
+  "quota_exceeded",
+];
+
+function eventSelect(className, value) {
+  const event = document.createElement("select");
+  event.className = className;
+  event.add(new Option(tr("ui.webhook_all_events"), ""));
+  WEBHOOK_EVENTS.forEach((name) => event.add(new Option(tr(`ui.webhook_event_${name}`), name)));
+  event.value = value || "";
+  return event;
+}
+
+function removeButton(row) {
+  const remove = document.createElement("button");
+  remove.type = "button";
+  remove.className = "secondary";
+  remove.textContent = "×";
+  remove.setAttribute("aria-label", tr("ui.remove"));
+  remove.addEventListener("click", () => row.remove());
+  return remove;
+}
+
+function addWebhookRow(hook = { url: "", event: null }) {
+  const row = document.createElement("div");
+  row.className = "alert-row";
+  const url = document.createElement("input");
+  url.className = "webhook-url";
+  url.type = "url";
+  url.placeholder = "https://";
+  url.value = hook.url;
+  row.append(url, eventSelect("webhook-event", hook.event), removeButton(row));
+  webhooksListEl.appendChild(row);
+}
+
+function addScriptHookRow(hook = { command: "", event: null }) {
+  const row = document.createElement("div");
+  row.className = "alert-row";
+  const command = document.createElement("input");
+  command.className = "script-hook-command";
+  command.placeholder = tr("ui.script_hook_command");
+  command.value = hook.command;
+  row.append(command, eventSelect("script-hook-event", hook.event), removeButton(row));
+  scriptHooksListEl.appendChild(row);
+}
+
+function readWebhooks() {
+  return [...webhooksListEl.querySelectorAll(".alert-row")]
+    .map((row) => ({
+      url: row.querySelector(".webhook-url").value.trim(),
+      event: row.querySelector(".webhook-event").value || null,
+    }))
+    .filter((hook) => hook.url);
+}
+
+function readScriptHooks() {
+  return [...scriptHooksListEl.querySelectorAll(".alert-row")]
+    .map((row) => ({
+      command: row.querySelector(".script-hook-command").value.trim(),
+      event: row.querySelector(".script-hook-event").value || null,
+    }))
+    .filter((hook) => hook.command);
+}
+
+addWebhookBtn.addEventListener("click", () => addWebhookRow());
+addScriptHookBtn.addEventListener("click", () => addScriptHookRow());
+
+function setPrefsStatus(text, isError = false) {
+  prefsStatusEl.textContent = text;
+  prefsStatusEl.style.color = isError ? "#f05b5b" : "#98a3b3";
+}
+
+async function loadSettings() {
+  if (!invoke) return;
+  const settings = await invoke("get_settings");
+  prefsForm.language.value = settings.language || "";
+  prefsForm.left_click.value = settings.left_click;
+  prefsForm.right_click.value = settings.right_click;
+  prefsForm.double_click.value = settings.double_click;
+  prefsForm.middle_click.value = settings.middle_click;
+  prefsForm.menu_bar_title.value = settings.menu_bar_title;
+  prefsForm.cycle_policies.value = (settings.cycle_policies || []).join(", ");
+  prefsForm.stale_after_minutes.value = settings.stale_after_minutes;
+  prefsForm.secret_store.value = settings.secret_store;
+  prefsForm.confirm_prompts.checked = !!settings.confirm_prompts;
+  prefsForm.confirm_destructive.checked = !!settings.confirm_destructive;
+  prefsForm.weekly_usage_summary.checked = !!settings.weekly_usage_summary;
+  prefsForm.lockdown.checked = !!settings.lockdown;
+  prefsForm.icon_badge.checked = !!settings.icon_badge;
+  prefsForm.start_at_login.checked = !!settings.start_at_login;
+  prefsForm.symbolic_tray_icon.checked = !!settings.symbolic_tray_icon;
+  prefsForm.check_updates.checked = !!settings.check_updates;
+  const metrics = settings.metrics || {};
+  prefsForm.metrics_enabled.checked = !!metrics.enabled;
+  prefsForm.metrics_port.value = metrics.port || 9877;
+  const influx = settings.influx || {};
+  prefsForm.influx_enabled.checked = !!influx.enabled;
+  prefsForm.influx_url.value = influx.url || "";
+  prefsForm.influx_interval.value = influx.interval_secs || 60;
+  const api = settings.api || {};
+  prefsForm.api_enabled.checked = !!api.enabled;
+  prefsForm.api_port.value = api.port || 9878;
+  prefsForm.api_listen_all.checked = !!api.listen_all;
+  prefsForm.api_token.value = (await invoke("get_api_token")) || "";
+  const websocket = settings.websocket || {};
+  prefsForm.websocket_enabled.checked = !!websocket.enabled;
+  prefsForm.websocket_port.value = websocket.port || 9879;
+  const grpc = settings.grpc || {};
+  prefsForm.grpc_enabled.checked = !!grpc.enabled;
+  prefsForm.grpc_port.value = grpc.port || 9880;
+  prefsForm.crash_reports.checked = !!settings.crash_reports;
+  prefsForm.crash_report_url.value = settings.crash_report_url || "";
+  prefsForm.log_level.value = settings.log_level;
+  const routerLog = settings.router_log || {};
+  prefsForm.router_log_enabled.checked = !!routerLog.enabled;
+  prefsForm.router_log_file.checked = routerLog.save_to_file !== false;
+  prefsForm.router_log_server.value = routerLog.server || "";
+  alertsListEl.innerHTML = "";
+  (settings.bandwidth_alerts || []).forEach((alert) => addAlertRow(alert));
+  quotasListEl.innerHTML = "";
+  (settings.data_quotas || []).forEach((quota) => addQuotaRow(quota));
+  const away = settings.away || {};
+  prefsForm.away_enabled.checked = !!away.enabled;
+  await loadAwayRouters(away.router);
+  prefsForm.away_devices.value = (away.devices || []).join(", ");
+  prefsForm.away_after.value = away.after_minutes ?? 10;
+  awayListEl.innerHTML = "";
+  (away.changes || []).forEach((change) => addAwayRow(change));
+  webhooksListEl.innerHTML = "";
+  (settings.webhooks || []).forEach((hook) => addWebhookRow(hook));
+  prefsForm.webhook_secret.value = (await invoke("get_webhook_secret")) || "";
+  scriptHooksListEl.innerHTML = "";
+  (settings.script_hooks || []).forEach((hook) => addScriptHookRow(hook));
+  const masterEnabled = settings.secret_store === "master_password";
+  prefsForm.secret_store.disabled = masterEnabled;
+  masterRemoveBtn.disabled = !masterEnabled;
+  secretStoreNoteEl.hidden = masterEnabled || !(await invoke("is_secret_file_obfuscated"));
+  const layout = settings.menu_layout || {};
+  prefsForm.layout_show_interface.checked = !!layout.show_interface;
+  prefsForm.layout_show_wifi.checked = !!layout.show_wifi;
+  prefsForm.layout_show_note.checked = !!layout.show_note;
+  prefsForm.layout_show_ip.checked = !!layout.show_ip;
+  prefsForm.layout_show_mac.checked = !!layout.show_mac;
+  prefsForm.layout_show_type.checked = !!layout.show_type;
+  prefsForm.layout_show_state.checked = !!layout.show_state;
+  prefsForm.layout_active_inline.checked = !!layout.active_inline;
+  prefsForm.layout_show_router_info.checked = !!layout.show_router_info;
+  prefsForm.layout_max_devices.value = layout.max_devices || 25;
+  const shortcuts = settings.shortcuts || {};
+  prefsForm.shortcut_toggle_block.value = shortcuts.toggle_block || "";
+  prefsForm.shortcut_apply_policy.value = shortcuts.apply_policy || "";
+  prefsForm.shortcut_policy.value = shortcuts.policy || "";
+  prefsForm.shortcut_open_settings.value = shortcuts.open_settings || "";
+  prefsForm.shortcut_palette.value = shortcuts.palette || "";
+}
+
+prefsForm.addEventListener("submit", async (e) => {
+  e.preventDefault();
+  if (!invoke) {
+    setPrefsStatus(tr("ui.api_unavailable"), true);
+    return;
+  }
+  const settings = {
+    language: prefsForm.language.value,
+    left_click: prefsForm.left_click.value,
+    right_click: prefsForm.right_click.value,
+    double_click: prefsForm.double_click.value,
+    middle_click: prefsForm.middle_click.value,
+    menu_bar_title: prefsForm.menu_bar_title.value,
+    cycle_policies: prefsForm.cycle_policies.value
+      .split(",")
+      .map((s) => s.trim())
+      .filter((s) => s.length),
+    stale_after_minutes: Number(prefsForm.stale_after_minutes.value) || 5,
+    secret_store: prefsForm.secret_store.value,
+    confirm_prompts: prefsForm.confirm_prompts.checked,
+    confirm_destructive: prefsForm.confirm_destructive.checked,
+    weekly_usage_summary: prefsForm.weekly_usage_summary.checked,
+    lockdown: prefsForm.lockdown.checked,
+    icon_badge: prefsForm.icon_badge.checked,
+    start_at_login: prefsForm.start_at_login.checked,
+    symbolic_tray_icon: prefsForm.symbolic_tray_icon.checked,
+    check_updates: prefsForm.check_updates.checked,
+    metrics: {
+      enabled: prefsForm.metrics_enabled.checked,
+      port: Number(prefsForm.metrics_port.value) || 9877,
+    },
+    influx: {
+      enabled: prefsForm.influx_enabled.checked,
+      url: prefsForm.influx_url.value.trim(),
+      interval_secs: Math.max(10, Number(prefsForm.influx_interval.value) || 60),
+    },
+    api: {
+      enabled: prefsForm.api_enabled.checked,
+      port: Number(prefsForm.api_port.value) || 9878,
+      listen_all: prefsForm.api_listen_all.checked,
+    },
+    websocket: {
+      enabled: prefsForm.websocket_enabled.checked,
+      port: Number(prefsForm.websocket_port.value) || 9879,
+    },
+    grpc: {
+      enabled: prefsForm.grpc_enabled.checked,
+      port: Number(prefsForm.grpc_port.value) || 9880,
+    },
+    crash_reports: prefsForm.crash_reports.checked,
+    crash_report_url: prefsForm.crash_report_url.value.trim(),
+    log_level: prefsForm.log_level.value,
+    router_log: {
+      enabled: prefsForm.router_log_enabled.checked,
+      save_to_file: prefsForm.router_log_file.checked,
+      server: prefsForm.router_log_server.value.trim(),
+    },
+    bandwidth_alerts: readAlerts(),
+    data_quotas: readQuotas(),
+    away: {
+      enabled: prefsForm.away_enabled.checked,
+      router: prefsForm.away_router.value,
+      devices: prefsForm.away_devices.value
+        .split(",")
+        .map((s) => s.trim().toLowerCase())
+        .filter((s) => s.length),
+      after_minutes: Math.max(0, Number(prefsForm.away_after.value) || 0),
+      changes: readAwayChanges(),
+    },
+    webhooks: readWebhooks(),
+    script_hooks: readScriptHooks(),
+    menu_layout: {
+      show_interface: prefsForm.layout_show_interface.checked,
+      show_wifi: prefsForm.layout_show_wifi.checked,
+      show_note: prefsForm.layout_show_note.checked,
+      show_ip: prefsForm.layout_show_ip.checked,
+      show_mac: prefsForm.layout_show_mac.checked,
+      show_type: prefsForm.layout_show_type.checked,
+      show_state: prefsForm.layout_show_state.checked,
+      active_inline: prefsForm.layout_active_inline.checked,
+      show_router_info: prefsForm.layout_show_router_info.checked,
+      max_devices: Number(prefsForm.layout_max_devices.value) || 25,
+    },
+    shortcuts: {
+      toggle_block: prefsForm.shortcut_toggle_block.value.trim(),
+      apply_policy: prefsForm.shortcut_apply_policy.value.trim(),
+      policy: prefsForm.shortcut_policy.value.trim(),
+      open_settings: prefsForm.shortcut_open_settings.value.trim(),
+      palette: prefsForm.shortcut_palette.value.trim(),
+    },
+  };
+  try {
+    await invoke("update_settings", { settings });
+    if (prefsForm.influx_token.value) {
+      await invoke("set_influx_token", { token: prefsForm.influx_token.value });
+      prefsForm.influx_token.value = "";
+    }
+    await loadTranslations();
+    setPrefsStatus(tr("ui.saved"));
+  } catch (err) {
+    setPrefsStatus(err, true);
+  }
+});
+
+function setMasterStatus(text, isError = false) {
+  masterStatusEl.textContent = text;
+  masterStatusEl.style.color = isError ? "#f05b5b" : "#98a3b3";
+}
+
+async function setMasterPassword(password, confirmation = null) {
+  if (!invoke) {
+    setMasterStatus(tr("ui.api_unavailable"), true);
+    return;
+  }
+  try {
+    await invoke("set_master_password", { password, confirmation });
+    masterPasswordEl.value = "";
+    masterConfirmEl.value = "";
+    await loadSettings();
+    setMasterStatus(tr("ui.saved"));
+  } catch (err) {
+    setMasterStatus(err, true);
+  }
+}
+
+masterSetBtn.addEventListener("click", () => {
+  if (!masterPasswordEl.value) {
+    setMasterStatus(tr("ui.master_password_empty"), true);
+    return;
+  }
+  if (masterPasswordEl.value !== masterConfirmEl.value) {
+    setMasterStatus(tr("error.master_password_mismatch"), true);
+    return;
+  }
+  setMasterPassword(masterPasswordEl.value, masterConfirmEl.value);
+});
+
+masterRemoveBtn.addEventListener("click", () => setMasterPassword(null));
+
+apiTokenBtn.addEventListener("click", async () => {
+  if (!invoke || !window.confirm(tr("ui.api_regenerate_confirm"))) return;
+  try {
+    prefsForm.api_token.value = await invoke("regenerate_api_token");
+  } catch (err) {
+    setPrefsStatus(err, true);
+  }
+});
+
+webhookSecretBtn.addEventListener("click", async () => {
+  if (!invoke || !window.confirm(tr("ui.webhook_regenerate_confirm"))) return;
+  try {
+    prefsForm.webhook_secret.value = await invoke("regenerate_webhook_secret");
+  } catch (err) {
+    setPrefsStatus(err, true);
+  }
+});
+
+function setTransferStatus(text, isError = false) {
+  transferStatusEl.textContent = text;
+  transferStatusEl.style.color = isError ? "#f05b5b" : "#98a3b3";
+}
+
+const configFilters = [{ name: "JSON", extensions: ["json"] }];
+
+exportBtn.addEventListener("click", async () => {
+  if (!invoke || !dialog) {
+    setTransferStatus(tr("ui.api_unavailable"), true);
+    return;
+  }
+  const path = await dialog.save({
+    defaultPath: "keenetic-tray.json",
+    filters: configFilters,
+  });
+  if (!path) return;
+  try {
+    await invoke("export_config", {
+      path,
+      passphrase: passphraseEl.value || null,
+    });
+    setTransferStatus(tr("ui.exported"));
+  } catch (err) {
+    setTransferStatus(err, true);
+  }
+});
+
+importBtn.addEventListener("click", async () => {
+  if (!invoke || !dialog) {
+    setTransferStatus(tr("ui.api_unavailable"), true);
+    return;
+  }
+  const path = await dialog.open({ multiple: false, filters: configFilters });
+  if (!path) return;
+  try {
+    await invoke("import_config", {
+      path,
+      passphrase: passphraseEl.value || null,
+    });
+    selected = null;
+    await loadTranslations();
+    await loadRouters();
+    await loadSettings();
+    setTransferStatus(tr("ui.imported"));
+  } catch (err) {
+    setTransferStatus(err, true);
+  }
+});
+
+if (events) {
+  events.listen("config-reloaded", async () => {
+    await loadTranslations();
+    await loadRouters();
+    await loadSettings();
+  });
+}
+
+loadTranslations().then(() => {
+  loadRouters();
+  loadSettings();
+  loadKnownDevices().catch(() => {});
+});
@@ -0,0 +1,427 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+const dialog = window.__TAURI__ && window.__TAURI__.dialog;
+const events = window.__TAURI__ && window.__TAURI__.event;
+
+const routerSelect = document.getElementById("router-select");
+const onlyOfflineEl = document.getElementById("only-offline");
+const loadBtn = document.getElementById("load-btn");
+const saveNamesBtn = document.getElementById("save-names-btn");
+const approveBtn = document.getElementById("approve-btn");
+const forgetBtn = document.getElementById("forget-btn");
+const exportBtn = document.getElementById("export-btn");
+const statusEl = document.getElementById("status");
+const selectAllEl = document.getElementById("select-all");
+const bodyEl = document.getElementById("devices-body");
+const presenceEl = document.getElementById("presence");
+const presenceTitleEl = document.getElementById("presence-title");
+const presenceBarEl = document.getElementById("presence-bar");
+const presenceListEl = document.getElementById("presence-list");
+const bulkPolicyEl = document.getElementById("bulk-policy");
+const previewBtn = document.getElementById("preview-btn");
+const previewEl = document.getElementById("preview");
+const previewBodyEl = document.getElementById("preview-body");
+const applyBtn = document.getElementById("apply-btn");
+const cancelPreviewBtn = document.getElementById("cancel-preview-btn");
+
+const PRESENCE_HOURS = 24;
+
+let translations = {};
+let devices = [];
+let notes = {};
+let plannedAction = null;
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+}
+
+function setStatus(text, isError = false) {
+  statusEl.textContent = text;
+  statusEl.style.color = isError ? "#f05b5b" : "#98a3b3";
+}
+
+function deviceStatus(device) {
+  const parts = [];
+  if (device.online) parts.push(tr("ui.status_online"));
+  parts.push(
+    device.registered ? tr("ui.status_registered") : tr("ui.status_unregistered"),
+  );
+  return parts.join(", ");
+}
+
+function renderDevices() {
+  bodyEl.innerHTML = "";
+  selectAllEl.checked = false;
+  devices
+    .filter((device) => !onlyOfflineEl.checked || !device.online)
+    .forEach((device) => {
+      const row = document.createElement("tr");
+      row.dataset.mac = device.mac;
+      if (!device.online) row.className = "offline";
+
+      const select = document.createElement("input");
+      select.type = "checkbox";
+      select.className = "row-select";
+      const name = document.createElement("input");
+      name.className = "row-name";
+      name.value = device.name || "";
+      name.dataset.original = name.value;
+      const note = document.createElement("input");
+      note.className = "row-note";
+      note.value = notes[device.mac] || "";
+      note.dataset.original = note.value;
+      if (note.value) row.title = note.value;
+
+      [select, name, device.mac, device.ip || "", deviceStatus(device), note].forEach(
+        (content) => {
+          const cell = document.createElement("td");
+          if (typeof content === "string") {
+            cell.textContent = content;
+          } else {
+            cell.appendChild(content);
+          }
+          row.appendChild(cell);
+        },
+      );
+      // Rows hold inputs, so they stay rows rather than becoming buttons.
+      row.tabIndex = 0;
+      row.dataset.activatable = "";
+      row.addEventListener("click", (e) => {
+        if (e.target.tagName !== "INPUT") showPresence(device);
+      });
+      bodyEl.appendChild(row);
+    });
+}
+
+function formatTime(date) {
+  return date.toLocaleTimeString([], { hour: "2-digit", minute: "2-digit" });
+}
+
+async function showPresence(device) {
+  if (!invoke) return;
+  presenceTitleEl.textContent = tr("ui.presence_title").replace(
+    "{name}",
+    device.name || device.mac,
+  );
+  presenceBarEl.innerHTML = "";
+  presenceListEl.innerHTML = "";
+  presenceEl.hidden = false;
+  let intervals;
+  try {
+    intervals = await invoke("get_presence", { mac: device.mac, hours: PRESENCE_HOURS });
+  } catch (err) {
+    setStatus(err, true);
+    return;
+  }
+  const now = Date.now();
+  const since = now - PRESENCE_HOURS * 3600 * 1000;
+  if (!intervals.length) {
+    const li = document.createElement("li");
+    li.className = "empty";
+    li.textContent = tr("ui.presence_none");
+    presenceListEl.appendChild(li);
+  }
+  intervals.forEach((interval) => {
+    const start = new Date(interval.start);
+    const end = interval.end ? new Date(interval.end) : null;
+    const segment = document.createElement("span");
+    const left = ((start.getTime() - since) / (now - since)) * 100;
+    const right = (((end ? end.getTime() : now) - since) / (now - since)) * 100;
+    segment.style.left = `${left}%`;
+    segment.style.width = `${Math.max(right - left, 0.5)}%`;
+    presenceBarEl.appendChild(segment);
+
+    const li = document.createElement("li");
+    li.textContent = `${formatTime(start)}–${end ? formatTime(end) : tr("ui.presence_now")}`;
+    presenceListEl.appendChild(li);
+  });
+}
+
+async function loadRouterList() {
+  const routers = await invoke("list_routers");
+  routerSelect.innerHTML = "";
+  routers.forEach((router) => {
+    const option = document.createElement("option");
+    option.value = router.name;
+    option.textContent = router.name;
+    routerSelect.appendChild(option);
+  });
+}
+
+function addPolicyOption(value, label) {
+  const option = document.createElement("option");
+  option.value = value;
+  option.textContent = label;
+  bulkPolicyEl.appendChild(option);
+}
+
+function renderPolicies(policies) {
+  bulkPolicyEl.innerHTML = "";
+  addPolicyOption("default", tr("policy.default"));
+  addPolicyOption("blocked", tr("policy.blocked"));
+  Object.keys(policies)
+    .sort()
+    .forEach((name) => {
+      addPolicyOption(`set:${name}`, policies[name].description || name);
+    });
+}
+
+// Splits a bulk policy option into the action and policy apply commands take.
+function parsePolicyOption(value) {
+  if (value.startsWith("set:")) {
+    return { action: "set", policy: value.slice(4) };
+  }
+  return { action: value, policy: null };
+}
+
+function hidePreview() {
+  previewEl.hidden = true;
+  previewBodyEl.innerHTML = "";
+  plannedAction = null;
+}
+
+function renderPreview(changes) {
+  previewBodyEl.innerHTML = "";
+  changes.forEach((change) => {
+    const row = document.createElement("tr");
+    row.dataset.mac = change.mac;
+    if (!change.changes) row.className = "unchanged";
+    const include = document.createElement("input");
+    include.type = "checkbox";
+    include.className = "row-select";
+    include.checked = change.changes;
+    const target = change.changes ? change.target : tr("ui.no_change");
+    const result = document.createElement("span");
+    result.className = "row-result";
+    [include, change.name, change.current, target, result].forEach((content) => {
+      const cell = document.createElement("td");
+      if (typeof content === "string") {
+        cell.textContent = content;
+      } else {
+        cell.appendChild(content);
+      }
+      row.appendChild(cell);
+    });
+    previewBodyEl.appendChild(row);
+  });
+  previewEl.hidden = false;
+}
+
+async function loadDevices() {
+  if (!invoke) {
+    setStatus(tr("ui.api_unavailable"), true);
+    return;
+  }
+  if (!routerSelect.value) return;
+  hidePreview();
+  setStatus(tr("ui.loading"));
+  try {
+    let policies;
+    [devices, notes, policies] = await Promise.all([
+      invoke("list_devices", { router: routerSelect.value }),
+      invoke("get_device_notes"),
+      invoke("list_policies", { router: routerSelect.value }),
+    ]);
+    renderPolicies(policies);
+    renderDevices();
+    setStatus("");
+  } catch (err) {
+    setStatus(err, true);
+  }
+}
+
+function selectedMacs() {
+  return [...bodyEl.querySelectorAll("tr")]
+    .filter((row) => row.querySelector(".row-select").checked)
+    .map((row) => row.dataset.mac);
+}
+
+saveNamesBtn.addEventListener("click", async () => {
+  if (!invoke) return;
+  const changed = [...bodyEl.querySelectorAll(".row-name")].filter(
+    (input) => input.value.trim() && input.value !== input.dataset.original,
+  );
+  const changedNotes = [...bodyEl.querySelectorAll(".row-note")].filter(
+    (input) => input.value !== input.dataset.original,
+  );
+  setStatus(tr("ui.saving"));
+  try {
+    for (const input of changedNotes) {
+      await invoke("set_device_note", {
+        mac: input.closest("tr").dataset.mac,
+        note: input.value,
+      });
+    }
+    for (const input of changed) {
+      await invoke("rename_device", {
+        router: routerSelect.value,
+        mac: input.closest("tr").dataset.mac,
+        name: input.value,
+      });
+    }
+    await loadDevices();
+    setStatus(tr("ui.saved"));
+  } catch (err) {
+    setStatus(err, true);
+  }
+});
+
+// Registers the devices and lifts the block lockdown put on them.
+approveBtn.addEventListener("click", async () => {
+  if (!invoke) return;
+  const macs = selectedMacs();
+  if (!macs.length) return;
+  try {
+    await invoke("approve_devices", { router: routerSelect.value, macs });
+    await loadDevices();
+    setStatus(tr("ui.approved").replace("{count}", String(macs.length)));
+  } catch (err) {
+    await loadDevices();
+    setStatus(err, true);
+  }
+});
+
+forgetBtn.addEventListener("click", async () => {
+  if (!invoke) return;
+  const macs = selectedMacs();
+  if (!macs.length) return;
+  const count = String(macs.length);
+  if (!window.confirm(tr("ui.forget_confirm").replace("{count}", count))) {
+    return;
+  }
+  try {
+    await invoke("forget_devices", { router: routerSelect.value, macs });
+    await loadDevices();
+    setStatus(tr("ui.forgotten").replace("{count}", count));
+  } catch (err) {
+    await loadDevices();
+    setStatus(err, true);
+  }
+});
+
+exportBtn.addEventListener("click", async () => {
+  if (!invoke || !dialog) {
+    setStatus(tr("ui.api_unavailable"), true);
+    return;
+  }
+  const path = await dialog.save({
+    defaultPath: `${routerSelect.value || "devices"}.csv`,
+    filters: [
+      { name: "CSV", extensions: ["csv"] },
+      { name: "JSON", extensions: ["json"] },
+    ],
+  });
+  if (!path) return;
+  try {
+    await invoke("export_devices", { router: routerSelect.value, path });
+    setStatus(tr("ui.devices_exported"));
+  } catch (err) {
+    setStatus(err, true);
+  }
+});
+
+previewBtn.addEventListener("click", async () => {
+  if (!invoke) return;
+  const macs = selectedMacs();
+  if (!macs.length) {
+    setStatus(tr("ui.select_devices"), true);
+    return;
+  }
+  const { action, policy } = parsePolicyOption(bulkPolicyEl.value);
+  setStatus(tr("ui.loading"));
+  try {
+    const changes = await invoke("preview_policy_change", {
+      router: routerSelect.value,
+      macs,
+      action,
+      policy,
+    });
+    plannedAction = { action, policy };
+    renderPreview(changes);
+    setStatus("");
+  } catch (err) {
+    setStatus(err, true);
+  }
+});
+
+applyBtn.addEventListener("click", async () => {
+  if (!invoke || !plannedAction) return;
+  const macs = [...previewBodyEl.querySelectorAll("tr")]
+    .filter((row) => row.querySelector(".row-select").checked)
+    .map((row) => row.dataset.mac);
+  if (!macs.length) {
+    hidePreview();
+    return;
+  }
+  setStatus(tr("ui.saving"));
+  applyBtn.disabled = true;
+  let results;
+  try {
+    results = await invoke("apply_policy_changes", {
+      router: routerSelect.value,
+      macs,
+      ...plannedAction,
+    });
+  } catch (err) {
+    setStatus(err, true);
+    return;
+  } finally {
+    applyBtn.disabled = false;
+  }
+  const failed = results.filter((result) => result.error);
+  if (!failed.length) {
+    await loadDevices();
+    setStatus(tr("ui.policy_applied").replace("{count}", String(results.length)));
+    return;
+  }
+  // Keep the preview open with each device's outcome; only the failed ones
+  // stay checked, so Apply retries just those.
+  results.forEach((result) => {
+    const row = previewBodyEl.querySelector(`tr[data-mac="${result.mac}"]`);
+    if (!row) return;
+    row.querySelector(".row-select").checked = !!result.error;
+    const cell = row.querySelector(".row-result");
+    cell.textContent = result.error || tr("ui.result_done");
+    cell.style.color = result.error ? "#f05b5b" : "";
+  });
+  setStatus(
+    tr("ui.policy_partial")
+      .replace("{done}", String(results.length - failed.length))
+      .replace("{count}", String(results.length)),
+    true,
+  );
+});
+
+cancelPreviewBtn.addEventListener("click", () => hidePreview());
+
+selectAllEl.addEventListener("change", () => {
+  bodyEl.querySelectorAll(".row-select").forEach((box) => {
+    box.checked = selectAllEl.checked;
+  });
+});
+
+if (events) {
+  events.listen("bulk-progress", (event) => {
+    setStatus(
+      tr("ui.applying")
+        .replace("{done}", String(event.payload.done))
+        .replace("{total}", String(event.payload.total)),
+    );
+  });
+}
+
+loadBtn.addEventListener("click", () => loadDevices());
+routerSelect.addEventListener("change", () => loadDevices());
+onlyOfflineEl.addEventListener("change", () => renderDevices());
+
+loadTranslations()
+  .then(() => (invoke ? loadRouterList() : null))
+  .then(loadDevices);
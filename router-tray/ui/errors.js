@@ -0,0 +1,57 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+const events = window.__TAURI__ && window.__TAURI__.event;
+
+const listEl = document.getElementById("error-list");
+const clearBtn = document.getElementById("clear-btn");
+
+let translations = {};
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+}
+
+async function loadErrors() {
+  if (!invoke) return;
+  const entries = await invoke("get_error_log");
+  listEl.innerHTML = "";
+  if (!entries.length) {
+    const li = document.createElement("li");
+    li.className = "empty";
+    li.textContent = tr("ui.no_errors");
+    listEl.appendChild(li);
+    return;
+  }
+  entries.forEach((entry) => {
+    const li = document.createElement("li");
+    const time = document.createElement("span");
+    time.className = "log-time";
+    time.textContent = new Date(entry.time).toLocaleString();
+    const message = document.createElement("span");
+    message.textContent = entry.message;
+    li.appendChild(time);
+    li.appendChild(message);
+    listEl.appendChild(li);
+  });
+}
+
+clearBtn.addEventListener("click", async () => {
+  if (!invoke) return;
+  await invoke("clear_error_log");
+  await loadErrors();
+});
+
+if (events) {
+  events.listen("errors-updated", () => loadErrors());
+}
+window.addEventListener("focus", () => loadErrors());
+
+loadTranslations().then(loadErrors);
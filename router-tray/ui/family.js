@@ -0,0 +1,253 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+const events = window.__TAURI__ && window.__TAURI__.event;
+
+const childrenEl = document.getElementById("children");
+const statusEl = document.getElementById("status");
+const addChildBtn = document.getElementById("add-child-btn");
+const refreshBtn = document.getElementById("refresh-btn");
+const childForm = document.getElementById("child-form");
+const deviceChoicesEl = document.getElementById("device-choices");
+const cancelChildBtn = document.getElementById("cancel-child-btn");
+const deleteChildBtn = document.getElementById("delete-child-btn");
+
+let translations = {};
+let children = [];
+let statuses = [];
+// Index of the child being edited; equal to `children.length` for a new one.
+let editing = null;
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+}
+
+function setStatus(text, isError = false) {
+  statusEl.textContent = text;
+  statusEl.style.color = isError ? "#f05b5b" : "#98a3b3";
+}
+
+// "21:00:00" from the backend, "21:00" for time inputs.
+function shortTime(time) {
+  return time.slice(0, 5);
+}
+
+function scheduleText(child) {
+  if (!child.bedtime) return tr("ui.no_bedtime");
+  return tr("ui.bedtime_range")
+    .replace("{start}", shortTime(child.bedtime.start))
+    .replace("{end}", shortTime(child.bedtime.end));
+}
+
+function renderChildren() {
+  childrenEl.innerHTML = "";
+  if (!children.length) {
+    const empty = document.createElement("div");
+    empty.className = "status";
+    empty.textContent = tr("ui.no_children");
+    childrenEl.appendChild(empty);
+    return;
+  }
+  children.forEach((child, index) => {
+    const status = statuses.find((s) => s.name === child.name);
+    const card = document.createElement("div");
+    card.className = "child-card";
+
+    const header = document.createElement("div");
+    header.className = "child-header";
+    const name = document.createElement("strong");
+    name.textContent = child.name;
+    const badge = document.createElement("span");
+    badge.className = status && status.asleep ? "child-badge asleep" : "child-badge";
+    badge.textContent = status && status.asleep ? tr("ui.child_asleep") : tr("ui.child_awake");
+    header.append(name, badge);
+
+    const schedule = document.createElement("div");
+    schedule.className = "status";
+    schedule.textContent = scheduleText(child);
+
+    const list = document.createElement("ul");
+    list.className = "log-list";
+    if (status && status.error) {
+      const li = document.createElement("li");
+      li.className = "empty";
+      li.textContent = status.error;
+      list.appendChild(li);
+    }
+    ((status && status.devices) || []).forEach((device) => {
+      const li = document.createElement("li");
+      if (!device.online) li.className = "empty";
+      const state = device.online ? tr("ui.status_online") : tr("ui.status_offline");
+      li.textContent = `${device.name} — ${state}, ${device.policy}`;
+      list.appendChild(li);
+    });
+
+    const actions = document.createElement("div");
+    actions.className = "actions";
+    const asleep = status && status.asleep;
+    const bedtimeBtn = document.createElement("button");
+    bedtimeBtn.type = "button";
+    bedtimeBtn.textContent = asleep ? tr("ui.wake_up") : tr("ui.bedtime_now");
+    if (!asleep) bedtimeBtn.className = "danger";
+    bedtimeBtn.addEventListener("click", () => setBedtime(child, !asleep));
+    const editBtn = document.createElement("button");
+    editBtn.type = "button";
+    editBtn.className = "secondary";
+    editBtn.textContent = tr("ui.edit");
+    editBtn.addEventListener("click", () => openEditor(index));
+    actions.append(bedtimeBtn, editBtn);
+
+    card.append(header, schedule, list, actions);
+    childrenEl.appendChild(card);
+  });
+}
+
+async function loadFamily() {
+  if (!invoke) {
+    setStatus(tr("ui.api_unavailable"), true);
+    return;
+  }
+  setStatus(tr("ui.loading"));
+  try {
+    children = await invoke("get_family");
+    renderChildren();
+    statuses = await invoke("family_status");
+    renderChildren();
+    setStatus("");
+  } catch (err) {
+    setStatus(err, true);
+  }
+}
+
+async function setBedtime(child, asleep) {
+  setStatus(tr("ui.saving"));
+  try {
+    await invoke("set_bedtime", { name: child.name, asleep });
+    await loadFamily();
+  } catch (err) {
+    setStatus(err, true);
+  }
+}
+
+function addOption(select, value, label) {
+  const option = document.createElement("option");
+  option.value = value;
+  option.textContent = label;
+  select.appendChild(option);
+}
+
+async function loadChoices(router, child) {
+  deviceChoicesEl.innerHTML = "";
+  childForm.profile.innerHTML = "";
+  addOption(childForm.profile, "", tr("policy.default"));
+  if (!router) return;
+  try {
+    const [devices, policies] = await Promise.all([
+      invoke("list_devices", { router }),
+      invoke("list_policies", { router }),
+    ]);
+    Object.keys(policies)
+      .sort()
+      .forEach((name) => {
+        addOption(childForm.profile, name, policies[name].description || name);
+      });
+    childForm.profile.value = (child && child.profile) || "";
+    const chosen = new Set((child && child.devices) || []);
+    devices.forEach((device) => {
+      const label = document.createElement("label");
+      label.className = "checkbox";
+      const box = document.createElement("input");
+      box.type = "checkbox";
+      box.value = device.mac;
+      box.checked = chosen.has(device.mac);
+      const text = document.createElement("span");
+      text.textContent = device.name ? `${device.name} (${device.mac})` : device.mac;
+      label.append(box, text);
+      deviceChoicesEl.appendChild(label);
+    });
+  } catch (err) {
+    setStatus(err, true);
+  }
+}
+
+async function openEditor(index) {
+  editing = index;
+  const child = children[index];
+  const routers = await invoke("list_routers");
+  childForm.router.innerHTML = "";
+  routers.forEach((router) => addOption(childForm.router, router.name, router.name));
+  childForm.name.value = child ? child.name : "";
+  if (child) childForm.router.value = child.router;
+  childForm.bedtime_enabled.checked = !!(child && child.bedtime);
+  if (child && child.bedtime) {
+    childForm.bedtime_start.value = shortTime(child.bedtime.start);
+    childForm.bedtime_end.value = shortTime(child.bedtime.end);
+  }
+  deleteChildBtn.hidden = !child;
+  childForm.hidden = false;
+  await loadChoices(childForm.router.value, child);
+}
+
+function closeEditor() {
+  editing = null;
+  childForm.hidden = true;
+}
+
+async function saveFamily(updated) {
+  try {
+    await invoke("save_family", { children: updated });
+    closeEditor();
+    await loadFamily();
+  } catch (err) {
+    setStatus(err, true);
+  }
+}
+
+childForm.addEventListener("submit", async (e) => {
+  e.preventDefault();
+  if (!invoke || editing === null) return;
+  const child = {
+    name: childForm.name.value.trim(),
+    router: childForm.router.value,
+    devices: [...deviceChoicesEl.querySelectorAll("input:checked")].map((box) => box.value),
+    profile: childForm.profile.value || null,
+    bedtime: childForm.bedtime_enabled.checked
+      ? {
+          start: `${childForm.bedtime_start.value}:00`,
+          end: `${childForm.bedtime_end.value}:00`,
+        }
+      : null,
+  };
+  const updated = [...children];
+  updated[editing] = child;
+  await saveFamily(updated);
+});
+
+deleteChildBtn.addEventListener("click", async () => {
+  if (!invoke || editing === null) return;
+  const child = children[editing];
+  if (!window.confirm(tr("ui.delete_child_confirm").replace("{name}", child.name))) {
+    return;
+  }
+  await saveFamily(children.filter((_, index) => index !== editing));
+});
+
+childForm.router.addEventListener("change", () => loadChoices(childForm.router.value, null));
+cancelChildBtn.addEventListener("click", () => closeEditor());
+addChildBtn.addEventListener("click", () => openEditor(children.length));
+refreshBtn.addEventListener("click", () => loadFamily());
+
+if (events) {
+  events.listen("state-updated", () => {
+    if (childForm.hidden) loadFamily();
+  });
+}
+
+loadTranslations().then(loadFamily);
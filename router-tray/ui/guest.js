@@ -0,0 +1,107 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+
+const form = document.getElementById("guest-form");
+const routerSelect = document.getElementById("router-select");
+const createBtn = document.getElementById("create-btn");
+const endBtn = document.getElementById("end-btn");
+const statusEl = document.getElementById("status");
+const voucherEl = document.getElementById("voucher");
+const qrEl = document.getElementById("voucher-qr");
+const ssidEl = document.getElementById("voucher-ssid");
+const passphraseEl = document.getElementById("voucher-passphrase");
+const expiresEl = document.getElementById("voucher-expires");
+
+let translations = {};
+let vouchers = [];
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+}
+
+// The voucher of the selected router, if its guest network is on.
+function showVoucher() {
+  const voucher = vouchers.find((v) => v.router === routerSelect.value);
+  voucherEl.hidden = !voucher;
+  endBtn.disabled = !voucher;
+  if (!voucher) {
+    statusEl.textContent = tr("ui.guest_none");
+    return;
+  }
+  statusEl.textContent = "";
+  // The SVG is generated by the app, not taken from the router.
+  qrEl.innerHTML = voucher.qr || "";
+  ssidEl.textContent = voucher.ssid;
+  passphraseEl.textContent = voucher.passphrase;
+  expiresEl.textContent = tr("ui.guest_expires").replace(
+    "{time}",
+    new Date(voucher.expires).toLocaleString(),
+  );
+}
+
+async function load() {
+  if (!invoke) {
+    statusEl.textContent = tr("ui.api_unavailable");
+    return;
+  }
+  let routers;
+  try {
+    [routers, vouchers] = await Promise.all([
+      invoke("list_routers"),
+      invoke("list_guest_vouchers"),
+    ]);
+  } catch (err) {
+    statusEl.textContent = err;
+    return;
+  }
+  routerSelect.innerHTML = "";
+  routers.forEach((router) => {
+    const option = document.createElement("option");
+    option.value = router.name;
+    option.textContent = router.name;
+    routerSelect.appendChild(option);
+  });
+  showVoucher();
+}
+
+routerSelect.addEventListener("change", showVoucher);
+
+form.addEventListener("submit", async (event) => {
+  event.preventDefault();
+  if (!invoke || !routerSelect.value) return;
+  createBtn.disabled = true;
+  statusEl.textContent = tr("ui.saving");
+  try {
+    const voucher = await invoke("create_guest_voucher", {
+      router: routerSelect.value,
+      hours: Number(form.hours.value) || 0,
+    });
+    vouchers = vouchers.filter((v) => v.router !== voucher.router).concat(voucher);
+    showVoucher();
+  } catch (err) {
+    statusEl.textContent = err;
+  } finally {
+    createBtn.disabled = false;
+  }
+});
+
+endBtn.addEventListener("click", async () => {
+  if (!invoke) return;
+  try {
+    await invoke("end_guest_voucher", { router: routerSelect.value });
+    vouchers = vouchers.filter((v) => v.router !== routerSelect.value);
+    showVoucher();
+  } catch (err) {
+    statusEl.textContent = err;
+  }
+});
+
+loadTranslations().then(load);
@@ -0,0 +1,62 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+
+const form = document.getElementById("mqtt-form");
+const statusEl = document.getElementById("status");
+
+let translations = {};
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+  document.querySelectorAll("[data-i18n-placeholder]").forEach((el) => {
+    el.placeholder = tr(el.dataset.i18nPlaceholder);
+  });
+}
+
+async function loadSettings() {
+  if (!invoke) {
+    statusEl.textContent = tr("ui.api_unavailable");
+    return;
+  }
+  let mqtt;
+  try {
+    mqtt = await invoke("get_mqtt_settings");
+  } catch (err) {
+    statusEl.textContent = err;
+    return;
+  }
+  form.enabled.checked = !!mqtt.enabled;
+  form.host.value = mqtt.host;
+  form.port.value = mqtt.port;
+  form.username.value = mqtt.username;
+  form.topic.value = mqtt.topic;
+}
+
+form.addEventListener("submit", async (event) => {
+  event.preventDefault();
+  if (!invoke) return;
+  const mqtt = {
+    enabled: form.enabled.checked,
+    host: form.host.value.trim(),
+    port: Number(form.port.value) || 1883,
+    username: form.username.value.trim(),
+    topic: form.topic.value.trim() || "keenetic-tray",
+  };
+  try {
+    await invoke("save_mqtt_settings", { mqtt, password: form.password.value || null });
+    form.password.value = "";
+    statusEl.textContent = tr("ui.saved");
+  } catch (err) {
+    statusEl.textContent = err;
+  }
+});
+
+loadTranslations().then(loadSettings);
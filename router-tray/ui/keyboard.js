@@ -0,0 +1,92 @@
+// Keyboard handling shared by every window. Buttons and links can carry a
+// data-shortcut ("mod+s", "alt+arrowleft", "escape"; "mod" is Cmd on macOS
+// and Ctrl elsewhere) that fires while they're visible. Escape hides the
+// window when nothing on the page claims it, and anything made activatable
+// works with Enter and Space like a button.
+
+const keyboardWindow = window.__TAURI__ && window.__TAURI__.window.getCurrentWindow();
+const isMac = navigator.platform.startsWith("Mac");
+
+function parseShortcut(shortcut) {
+  const parts = shortcut.toLowerCase().split("+");
+  return {
+    key: parts.pop(),
+    mod: parts.includes("mod"),
+    alt: parts.includes("alt"),
+    shift: parts.includes("shift"),
+  };
+}
+
+function matchesShortcut(event, shortcut) {
+  const { key, mod, alt, shift } = parseShortcut(shortcut);
+  const modPressed = isMac ? event.metaKey : event.ctrlKey;
+  return (
+    event.key.toLowerCase() === key &&
+    modPressed === mod &&
+    event.altKey === alt &&
+    event.shiftKey === shift
+  );
+}
+
+// aria-keyshortcuts spells out the modifier, so screen readers announce it.
+function ariaShortcut(shortcut) {
+  return shortcut
+    .split("+")
+    .map((part) => {
+      if (part === "mod") return isMac ? "Meta" : "Control";
+      if (part === "alt") return "Alt";
+      if (part === "shift") return "Shift";
+      if (part === "arrowleft") return "ArrowLeft";
+      if (part === "escape") return "Escape";
+      return part.length === 1 ? part.toUpperCase() : part;
+    })
+    .join("+");
+}
+
+function isVisible(el) {
+  return el.getClientRects().length > 0 && !el.closest("[hidden]");
+}
+
+// Makes a clickable element that isn't a button reachable with Tab and
+// announced as a button. Its click handler also runs on Enter and Space.
+function makeActivatable(el, label = null) {
+  el.setAttribute("tabindex", "0");
+  el.setAttribute("role", "button");
+  el.dataset.activatable = "";
+  if (label) el.setAttribute("aria-label", label);
+}
+
+function describeShortcuts() {
+  document.querySelectorAll("[data-shortcut]").forEach((el) => {
+    el.setAttribute("aria-keyshortcuts", ariaShortcut(el.dataset.shortcut));
+  });
+}
+
+document.addEventListener("keydown", (event) => {
+  if (event.defaultPrevented) return;
+  const target = event.target;
+  if (
+    (event.key === "Enter" || event.key === " ") &&
+    target.dataset &&
+    "activatable" in target.dataset
+  ) {
+    event.preventDefault();
+    // SVG elements have no click().
+    target.dispatchEvent(new MouseEvent("click", { bubbles: true }));
+    return;
+  }
+  const match = [...document.querySelectorAll("[data-shortcut]")].find(
+    (el) => !el.disabled && isVisible(el) && matchesShortcut(event, el.dataset.shortcut),
+  );
+  if (match) {
+    event.preventDefault();
+    match.click();
+    return;
+  }
+  if (event.key === "Escape" && keyboardWindow) {
+    event.preventDefault();
+    keyboardWindow.hide();
+  }
+});
+
+describeShortcuts();
@@ -0,0 +1,270 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+
+const form = document.getElementById("notifications-form");
+const routerList = document.getElementById("router-list");
+const statusEl = document.getElementById("status");
+const testSoundBtn = document.getElementById("test-sound-btn");
+const testEmailBtn = document.getElementById("test-email-btn");
+const watchedListEl = document.getElementById("watched-list");
+const knownDevicesEl = document.getElementById("known-devices");
+const addWatchedBtn = document.getElementById("add-watched-btn");
+const pushCategoryInputs = document.querySelectorAll("input[name=push_category]");
+
+const CATEGORIES = ["new_device", "outage", "policy_change", "usage", "update", "presence"];
+const SOUNDS = ["outage_sound", "new_device_sound"];
+
+let translations = {};
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+  document.querySelectorAll("[data-i18n-placeholder]").forEach((el) => {
+    el.placeholder = tr(el.dataset.i18nPlaceholder);
+  });
+}
+
+// Settings store times as HH:MM:SS; the inputs show HH:MM.
+function toInput(time) {
+  return time.slice(0, 5);
+}
+
+function fromInput(value) {
+  return `${value}:00`;
+}
+
+function renderRouters(routers, muted) {
+  routerList.innerHTML = "";
+  if (!routers.length) {
+    routerList.textContent = tr("ui.no_routers");
+    return;
+  }
+  routers.forEach((router) => {
+    const label = document.createElement("label");
+    label.className = "checkbox";
+    const input = document.createElement("input");
+    input.type = "checkbox";
+    input.name = "muted";
+    input.value = router.name;
+    input.checked = muted.includes(router.name);
+    const span = document.createElement("span");
+    span.textContent = router.name;
+    label.append(input, span);
+    routerList.appendChild(label);
+  });
+}
+
+function addWatchedRow(device = { mac: "", label: "" }) {
+  const row = document.createElement("div");
+  row.className = "alert-row";
+  const mac = document.createElement("input");
+  mac.className = "watch-mac";
+  mac.setAttribute("list", "known-devices");
+  mac.placeholder = "MAC";
+  mac.value = device.mac;
+  const label = document.createElement("input");
+  label.className = "watch-label";
+  label.placeholder = tr("ui.watch_label");
+  label.value = device.label;
+  const remove = document.createElement("button");
+  remove.type = "button";
+  remove.className = "secondary";
+  remove.textContent = "×";
+  remove.setAttribute("aria-label", tr("ui.remove"));
+  remove.addEventListener("click", () => row.remove());
+  row.append(mac, label, remove);
+  watchedListEl.appendChild(row);
+}
+
+function readWatched() {
+  return [...watchedListEl.querySelectorAll(".alert-row")]
+    .map((row) => ({
+      mac: row.querySelector(".watch-mac").value.trim().toLowerCase(),
+      label: row.querySelector(".watch-label").value.trim(),
+    }))
+    .filter((device) => device.mac);
+}
+
+async function loadKnownDevices() {
+  const states = await invoke("get_active_state");
+  knownDevicesEl.innerHTML = "";
+  states.forEach((active) => {
+    active.interfaces.forEach((iface) => {
+      const option = document.createElement("option");
+      option.value = iface.mac;
+      option.label = iface.display_name;
+      knownDevicesEl.appendChild(option);
+    });
+  });
+}
+
+addWatchedBtn.addEventListener("click", () => addWatchedRow());
+
+async function loadSettings() {
+  if (!invoke) {
+    statusEl.textContent = tr("ui.api_unavailable");
+    return;
+  }
+  let settings;
+  let routers;
+  try {
+    [settings, routers] = await Promise.all([
+      invoke("get_notification_settings"),
+      invoke("list_routers"),
+    ]);
+  } catch (err) {
+    statusEl.textContent = err;
+    return;
+  }
+  CATEGORIES.forEach((name) => {
+    form.elements[name].checked = !!settings[name];
+  });
+  SOUNDS.forEach((name) => {
+    form.elements[name].value = settings[name] || "";
+  });
+  form.quiet_enabled.checked = !!settings.quiet_hours;
+  if (settings.quiet_hours) {
+    form.quiet_start.value = toInput(settings.quiet_hours.start);
+    form.quiet_end.value = toInput(settings.quiet_hours.end);
+  }
+  renderRouters(routers, settings.muted_routers || []);
+  watchedListEl.innerHTML = "";
+  (settings.watched_devices || []).forEach((device) => addWatchedRow(device));
+  form.presence_debounce_minutes.value = settings.presence_debounce_minutes ?? 3;
+  loadKnownDevices().catch(() => {});
+  const telegram = settings.telegram || {};
+  form.telegram_enabled.checked = !!telegram.enabled;
+  form.telegram_chat_id.value = telegram.chat_id || "";
+  const push = settings.push || {};
+  form.push_service.value = push.service || "";
+  form.ntfy_server.value = push.ntfy_server || "https://ntfy.sh";
+  form.ntfy_topic.value = push.ntfy_topic || "";
+  form.pushover_user.value = push.pushover_user || "";
+  const pushCategories = push.categories || [];
+  pushCategoryInputs.forEach((input) => {
+    input.checked = pushCategories.includes(input.value);
+  });
+  showPushFields();
+  const email = settings.email || {};
+  form.email_enabled.checked = !!email.enabled;
+  form.smtp_host.value = email.host || "";
+  form.smtp_port.value = email.port || 587;
+  form.smtp_security.value = email.security || "start_tls";
+  form.smtp_username.value = email.username || "";
+  form.email_from.value = email.from || "";
+  form.email_to.value = email.to || "";
+  form.email_update.checked = email.update !== false;
+  form.email_repeated_outages.checked = email.repeated_outages !== false;
+  form.email_new_device.checked = !!email.new_device;
+}
+
+function emailSettings() {
+  return {
+    enabled: form.email_enabled.checked,
+    host: form.smtp_host.value.trim(),
+    port: Number(form.smtp_port.value) || 587,
+    security: form.smtp_security.value,
+    username: form.smtp_username.value.trim(),
+    from: form.email_from.value.trim(),
+    to: form.email_to.value.trim(),
+    update: form.email_update.checked,
+    repeated_outages: form.email_repeated_outages.checked,
+    new_device: form.email_new_device.checked,
+  };
+}
+
+// Only the fields of the chosen service are shown.
+function showPushFields() {
+  const service = form.push_service.value;
+  document.querySelectorAll("[data-push]").forEach((el) => {
+    el.hidden = !service || !el.dataset.push.split(" ").includes(service);
+  });
+}
+
+form.push_service.addEventListener("change", showPushFields);
+
+form.addEventListener("submit", async (event) => {
+  event.preventDefault();
+  if (!invoke) return;
+  const notifications = {
+    quiet_hours: form.quiet_enabled.checked
+      ? { start: fromInput(form.quiet_start.value), end: fromInput(form.quiet_end.value) }
+      : null,
+    muted_routers: Array.from(routerList.querySelectorAll("input:checked")).map(
+      (input) => input.value,
+    ),
+    watched_devices: readWatched(),
+    presence_debounce_minutes: Math.max(0, Number(form.presence_debounce_minutes.value) || 0),
+    telegram: {
+      enabled: form.telegram_enabled.checked,
+      chat_id: form.telegram_chat_id.value.trim(),
+    },
+    push: {
+      service: form.push_service.value || null,
+      ntfy_server: form.ntfy_server.value.trim() || "https://ntfy.sh",
+      ntfy_topic: form.ntfy_topic.value.trim(),
+      pushover_user: form.pushover_user.value.trim(),
+      categories: Array.from(pushCategoryInputs)
+        .filter((input) => input.checked)
+        .map((input) => input.value),
+    },
+    email: emailSettings(),
+  };
+  CATEGORIES.forEach((name) => {
+    notifications[name] = form.elements[name].checked;
+  });
+  SOUNDS.forEach((name) => {
+    notifications[name] = form.elements[name].value || null;
+  });
+  try {
+    await invoke("save_notification_settings", {
+      notifications,
+      telegramToken: form.telegram_token.value || null,
+      pushToken: form.push_token.value || null,
+      smtpPassword: form.smtp_password.value || null,
+    });
+    form.telegram_token.value = "";
+    form.push_token.value = "";
+    form.smtp_password.value = "";
+    statusEl.textContent = tr("ui.saved");
+  } catch (err) {
+    statusEl.textContent = err;
+  }
+});
+
+testSoundBtn.addEventListener("click", async () => {
+  if (!invoke) return;
+  const sound = form.outage_sound.value || form.new_device_sound.value || "alert";
+  try {
+    await invoke("play_sound", { sound });
+  } catch (err) {
+    statusEl.textContent = err;
+  }
+});
+
+// Tests what's in the form, saved or not.
+testEmailBtn.addEventListener("click", async () => {
+  if (!invoke) return;
+  testEmailBtn.disabled = true;
+  statusEl.textContent = tr("ui.sending_email");
+  try {
+    await invoke("send_test_email", {
+      email: emailSettings(),
+      password: form.smtp_password.value || null,
+    });
+    statusEl.textContent = tr("ui.test_email_sent");
+  } catch (err) {
+    statusEl.textContent = err;
+  } finally {
+    testEmailBtn.disabled = false;
+  }
+});
+
+loadTranslations().then(loadSettings);
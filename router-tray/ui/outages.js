@@ -0,0 +1,81 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+
+const refreshBtn = document.getElementById("refresh-btn");
+const clearBtn = document.getElementById("clear-btn");
+const statusEl = document.getElementById("status");
+const bodyEl = document.getElementById("outage-body");
+
+const LIMIT = 200;
+
+let translations = {};
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+}
+
+function formatDuration(seconds) {
+  const h = Math.floor(seconds / 3600);
+  const m = Math.floor(seconds / 60) % 60;
+  const s = seconds % 60;
+  if (h > 0) return `${h}h ${m}m`;
+  if (m > 0) return `${m}m ${s}s`;
+  return `${s}s`;
+}
+
+function formatTime(value) {
+  return new Date(value).toLocaleString();
+}
+
+async function loadOutages() {
+  if (!invoke) {
+    statusEl.textContent = tr("ui.api_unavailable");
+    return;
+  }
+  let outages;
+  try {
+    outages = await invoke("get_outages", { limit: LIMIT });
+  } catch (err) {
+    statusEl.textContent = err;
+    return;
+  }
+  statusEl.textContent = outages.length ? "" : tr("ui.no_outages");
+  bodyEl.innerHTML = "";
+  outages.forEach((outage) => {
+    const row = document.createElement("tr");
+    [
+      outage.router,
+      formatTime(outage.start),
+      outage.end ? formatTime(outage.end) : tr("ui.ongoing"),
+      formatDuration(outage.seconds),
+    ].forEach((text) => {
+      const cell = document.createElement("td");
+      cell.textContent = text;
+      row.appendChild(cell);
+    });
+    bodyEl.appendChild(row);
+  });
+}
+
+clearBtn.addEventListener("click", async () => {
+  if (!invoke || !window.confirm(tr("ui.clear_outages_confirm"))) return;
+  try {
+    await invoke("clear_outages");
+  } catch (err) {
+    statusEl.textContent = err;
+    return;
+  }
+  loadOutages();
+});
+
+refreshBtn.addEventListener("click", () => loadOutages());
+
+loadTranslations().then(loadOutages);
@@ -0,0 +1,158 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+const events = window.__TAURI__ && window.__TAURI__.event;
+const appWindow = window.__TAURI__ && window.__TAURI__.window.getCurrentWindow();
+
+const queryEl = document.getElementById("query");
+const resultsEl = document.getElementById("results");
+
+const MAX_RESULTS = 50;
+
+let translations = {};
+let items = [];
+let matches = [];
+let activeIndex = 0;
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+  document.querySelectorAll("[data-i18n-placeholder]").forEach((el) => {
+    el.placeholder = tr(el.dataset.i18nPlaceholder);
+  });
+  queryEl.setAttribute("aria-label", tr("ui.palette_title"));
+}
+
+const WORD_START_BONUS = 8;
+const CONSECUTIVE_BONUS = 5;
+const GAP_PENALTY = 1;
+const SEPARATOR = /[\s'’·\-_.:()]/;
+
+// The query's characters have to appear in the label in order but not
+// necessarily together, so "lptp" finds "Laptop" and "blo tab" finds "Block
+// Kid's Tablet". Of all the ways they can line up, the best one counts:
+// characters at the start of a word or right after the previous match score
+// more, and every jump between matches costs a little. Returns null when the
+// label doesn't match.
+function score(label, query) {
+  const text = label.toLowerCase();
+  const chars = [...query.replace(/\s+/g, "")];
+  if (!chars.length) return 0;
+  const bonus = [...text].map(
+    (_, j) => 1 + (j === 0 || SEPARATOR.test(text[j - 1]) ? WORD_START_BONUS : 0),
+  );
+  // best[j]: the highest score with the current query character at j.
+  let best = null;
+  for (const ch of chars) {
+    const next = new Array(text.length).fill(-Infinity);
+    let jumpFrom = -Infinity;
+    for (let j = 0; j < text.length; j++) {
+      if (best && j >= 2) jumpFrom = Math.max(jumpFrom, best[j - 2]);
+      if (text[j] !== ch) continue;
+      if (!best) {
+        next[j] = bonus[j];
+        continue;
+      }
+      const after = j >= 1 ? best[j - 1] + CONSECUTIVE_BONUS : -Infinity;
+      next[j] = bonus[j] + Math.max(after, jumpFrom - GAP_PENALTY);
+    }
+    best = next;
+  }
+  const top = Math.max(...best);
+  return top === -Infinity ? null : top;
+}
+
+function filter() {
+  const query = queryEl.value.toLowerCase();
+  matches = items
+    .map((item) => ({ item, score: score(item.label, query) }))
+    .filter((entry) => entry.score !== null)
+    .sort((a, b) => b.score - a.score)
+    .slice(0, MAX_RESULTS)
+    .map((entry) => entry.item);
+  activeIndex = 0;
+  render();
+}
+
+function render() {
+  resultsEl.innerHTML = "";
+  matches.forEach((item, index) => {
+    const li = document.createElement("li");
+    li.id = `result-${index}`;
+    li.setAttribute("role", "option");
+    li.setAttribute("aria-selected", index === activeIndex ? "true" : "false");
+    if (index === activeIndex) li.className = "active";
+    const label = document.createElement("span");
+    label.textContent = item.label;
+    li.appendChild(label);
+    if (item.detail) {
+      const detail = document.createElement("span");
+      detail.className = "palette-detail";
+      detail.textContent = item.detail;
+      li.appendChild(detail);
+    }
+    li.onclick = () => run(item);
+    resultsEl.appendChild(li);
+  });
+  const active = resultsEl.children[activeIndex];
+  if (active) {
+    active.scrollIntoView({ block: "nearest" });
+    queryEl.setAttribute("aria-activedescendant", active.id);
+  } else {
+    queryEl.removeAttribute("aria-activedescendant");
+  }
+}
+
+async function run(item) {
+  if (!invoke || !item) return;
+  try {
+    await invoke("run_palette_item", { item });
+  } catch (err) {
+    console.error(err);
+  }
+}
+
+function hide() {
+  if (appWindow) appWindow.hide();
+}
+
+async function open() {
+  if (!invoke) return;
+  await loadTranslations();
+  items = await invoke("palette_items");
+  queryEl.value = "";
+  filter();
+  queryEl.focus();
+}
+
+queryEl.addEventListener("input", () => filter());
+queryEl.addEventListener("keydown", (e) => {
+  if (e.key === "ArrowDown") {
+    e.preventDefault();
+    activeIndex = Math.min(activeIndex + 1, matches.length - 1);
+    render();
+  } else if (e.key === "ArrowUp") {
+    e.preventDefault();
+    activeIndex = Math.max(activeIndex - 1, 0);
+    render();
+  } else if (e.key === "Enter") {
+    e.preventDefault();
+    run(matches[activeIndex]);
+  } else if (e.key === "Escape") {
+    e.preventDefault();
+    hide();
+  }
+});
+window.addEventListener("blur", () => hide());
+
+if (events) {
+  events.listen("palette-opened", () => open());
+}
+
+open();
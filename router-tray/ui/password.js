@@ -0,0 +1,55 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+const events = window.__TAURI__ && window.__TAURI__.event;
+
+const form = document.getElementById("password-form");
+const statusEl = document.getElementById("status");
+
+let translations = {};
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+}
+
+async function loadPrompt() {
+  if (!invoke) return;
+  const name = await invoke("get_password_prompt");
+  if (name) form.name.value = name;
+}
+
+form.addEventListener("submit", async (e) => {
+  e.preventDefault();
+  if (!invoke) {
+    statusEl.textContent = tr("ui.api_unavailable");
+    return;
+  }
+  try {
+    await invoke("update_router_password", {
+      name: form.name.value,
+      password: form.password.value,
+    });
+    form.password.value = "";
+    statusEl.textContent = "";
+  } catch (err) {
+    statusEl.textContent = err;
+    statusEl.style.color = "#f05b5b";
+  }
+});
+
+if (events) {
+  events.listen("password-required", (event) => {
+    form.name.value = event.payload;
+    statusEl.textContent = "";
+  });
+}
+window.addEventListener("focus", () => loadPrompt());
+
+loadTranslations().then(loadPrompt);
@@ -0,0 +1,99 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+
+const periodEl = document.getElementById("period");
+const statusEl = document.getElementById("status");
+const deviceBody = document.getElementById("device-body");
+const dayBody = document.getElementById("day-body");
+
+let translations = {};
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+}
+
+function formatBytes(bytes) {
+  const units = ["B", "KB", "MB", "GB", "TB"];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1000 && unit < units.length - 1) {
+    value /= 1000;
+    unit += 1;
+  }
+  return unit === 0 ? `${bytes} B` : `${value.toFixed(1)} ${units[unit]}`;
+}
+
+function sumBy(rows, keyOf, labelOf) {
+  const totals = new Map();
+  rows.forEach((row) => {
+    const key = keyOf(row);
+    const entry = totals.get(key) || { label: labelOf(row), rx: 0, tx: 0 };
+    entry.label = labelOf(row);
+    entry.rx += row.rx_bytes;
+    entry.tx += row.tx_bytes;
+    totals.set(key, entry);
+  });
+  return totals;
+}
+
+function fillTable(body, rows) {
+  body.innerHTML = "";
+  rows.forEach((cells) => {
+    const tr = document.createElement("tr");
+    cells.forEach((text) => {
+      const td = document.createElement("td");
+      td.textContent = text;
+      tr.appendChild(td);
+    });
+    body.appendChild(tr);
+  });
+}
+
+async function loadReport() {
+  if (!invoke) {
+    statusEl.textContent = tr("ui.api_unavailable");
+    return;
+  }
+  let rows;
+  try {
+    rows = await invoke("get_usage_report", { days: Number(periodEl.value) });
+  } catch (err) {
+    statusEl.textContent = err;
+    return;
+  }
+  statusEl.textContent = rows.length ? "" : tr("ui.no_usage");
+
+  const devices = [...sumBy(rows, (r) => r.mac, (r) => r.name).entries()].sort(
+    ([, a], [, b]) => b.rx + b.tx - (a.rx + a.tx),
+  );
+  fillTable(
+    deviceBody,
+    devices.map(([mac, t]) => [
+      t.label,
+      mac,
+      formatBytes(t.rx),
+      formatBytes(t.tx),
+      formatBytes(t.rx + t.tx),
+    ]),
+  );
+
+  const days = [...sumBy(rows, (r) => r.day, (r) => r.day).values()].sort((a, b) =>
+    b.label.localeCompare(a.label),
+  );
+  fillTable(
+    dayBody,
+    days.map((t) => [t.label, formatBytes(t.rx), formatBytes(t.tx), formatBytes(t.rx + t.tx)]),
+  );
+}
+
+periodEl.addEventListener("change", () => loadReport());
+
+loadTranslations().then(loadReport);
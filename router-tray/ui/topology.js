@@ -0,0 +1,205 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+
+const routerSelect = document.getElementById("router-select");
+const statusEl = document.getElementById("status");
+const mapEl = document.getElementById("map");
+const actionsEl = document.getElementById("actions");
+
+const SVG = "http://www.w3.org/2000/svg";
+const ROW_HEIGHT = 110;
+const CLIENT_SPACING = 90;
+const RADIUS = 14;
+
+let translations = {};
+let view = null;
+let notes = {};
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+}
+
+function setStatus(text, isError = false) {
+  statusEl.textContent = text;
+  statusEl.style.color = isError ? "#f05b5b" : "#98a3b3";
+}
+
+function svg(tag, attrs) {
+  const el = document.createElementNS(SVG, tag);
+  Object.entries(attrs).forEach(([key, value]) => el.setAttribute(key, value));
+  return el;
+}
+
+function drawNode(x, y, label, className, onClick, note = "") {
+  const group = svg("g", { class: `map-node ${className}` });
+  if (note) {
+    const tooltip = svg("title", {});
+    tooltip.textContent = note;
+    group.appendChild(tooltip);
+  }
+  group.appendChild(svg("circle", { cx: x, cy: y, r: RADIUS }));
+  const text = svg("text", { x, y: y + RADIUS + 14, "text-anchor": "middle" });
+  text.textContent = label;
+  group.appendChild(text);
+  if (onClick) {
+    makeActivatable(group, label);
+    group.addEventListener("click", onClick);
+  }
+  mapEl.appendChild(group);
+}
+
+function drawEdge(from, to, wireless, label) {
+  mapEl.appendChild(
+    svg("line", {
+      x1: from.x,
+      y1: from.y,
+      x2: to.x,
+      y2: to.y,
+      class: wireless ? "map-edge wireless" : "map-edge",
+    }),
+  );
+  if (label) {
+    const text = svg("text", {
+      x: (from.x + to.x) / 2 + 4,
+      y: (from.y + to.y) / 2,
+    });
+    text.textContent = label;
+    mapEl.appendChild(text);
+  }
+}
+
+// Router on top, mesh nodes below it, and each parent's clients fanned out
+// in the row underneath.
+function render() {
+  mapEl.innerHTML = "";
+  actionsEl.hidden = true;
+  if (!view) return;
+  const { nodes, clients } = view.topology;
+  const groups = [{ id: null, label: view.router }].concat(
+    nodes.map((node) => ({ id: node.id, label: node.name || node.model || node.id, node })),
+  );
+  const childrenOf = (id) =>
+    clients.filter((c) =>
+      id === null ? !c.parent || !nodes.some((n) => n.id === c.parent) : c.parent === id,
+    );
+  const widths = groups.map((g) => Math.max(1, childrenOf(g.id).length) * CLIENT_SPACING);
+  const width = Math.max(widths.reduce((a, b) => a + b, 0), 600);
+  const height = ROW_HEIGHT * 3 + 40;
+  mapEl.setAttribute("width", width);
+  mapEl.setAttribute("height", height);
+
+  const root = { x: width / 2, y: 40 };
+  let offset = 0;
+  const positions = groups.map((group, index) => {
+    const center = offset + widths[index] / 2;
+    offset += widths[index];
+    return group.id === null ? root : { x: center, y: 40 + ROW_HEIGHT };
+  });
+
+  groups.forEach((group, index) => {
+    const position = positions[index];
+    if (group.id !== null) drawEdge(root, position, false);
+    const children = childrenOf(group.id);
+    const start = position.x - ((children.length - 1) * CLIENT_SPACING) / 2;
+    children.forEach((client, i) => {
+      const point = { x: start + i * CLIENT_SPACING, y: position.y + ROW_HEIGHT };
+      const edgeLabel = client.port ? tr("ui.port").replace("{port}", client.port) : "";
+      drawEdge(position, point, client.wireless, edgeLabel);
+      const classes = [client.online ? "" : "offline", client.deny ? "blocked" : ""];
+      drawNode(
+        point.x,
+        point.y,
+        client.name,
+        classes.join(" "),
+        () => showActions(client),
+        notes[client.mac],
+      );
+    });
+  });
+
+  groups.forEach((group, index) => {
+    const position = positions[index];
+    const offline = group.node && !group.node.online ? "offline" : "";
+    drawNode(position.x, position.y, group.label, offline);
+  });
+}
+
+function actionButton(label, action, client, policy = null) {
+  const button = document.createElement("button");
+  button.type = "button";
+  button.className = action === "blocked" ? "danger" : "secondary";
+  button.textContent = label;
+  button.addEventListener("click", async () => {
+    try {
+      await invoke("apply_policy_ui", {
+        mac: client.mac,
+        action,
+        policy,
+        router: view.router,
+      });
+      setStatus(tr("notify.policy_changed"));
+    } catch (err) {
+      setStatus(err, true);
+    }
+  });
+  return button;
+}
+
+function showActions(client) {
+  actionsEl.innerHTML = "";
+  const title = document.createElement("span");
+  title.textContent = `${client.name} · ${client.ip || client.mac}`;
+  if (notes[client.mac]) title.title = notes[client.mac];
+  actionsEl.appendChild(title);
+  actionsEl.appendChild(actionButton(tr("policy.default"), "default", client));
+  Object.entries(view.policies)
+    .sort(([a], [b]) => a.localeCompare(b))
+    .forEach(([name, info]) => {
+      actionsEl.appendChild(actionButton(info.description || name, "set", client, name));
+    });
+  actionsEl.appendChild(actionButton(tr("policy.blocked"), "blocked", client));
+  actionsEl.hidden = false;
+}
+
+async function loadRouterList() {
+  const routers = await invoke("list_routers");
+  routerSelect.innerHTML = "";
+  routers.forEach((router) => {
+    const option = document.createElement("option");
+    option.value = router.name;
+    option.textContent = router.name;
+    routerSelect.appendChild(option);
+  });
+}
+
+async function loadTopology() {
+  if (!invoke || !routerSelect.value) return;
+  setStatus(tr("ui.loading"));
+  try {
+    [view, notes] = await Promise.all([
+      invoke("get_topology", { router: routerSelect.value }),
+      invoke("get_device_notes"),
+    ]);
+    render();
+    setStatus("");
+  } catch (err) {
+    view = null;
+    render();
+    setStatus(err, true);
+  }
+}
+
+routerSelect.addEventListener("change", () => loadTopology());
+window.addEventListener("focus", () => loadTopology());
+
+loadTranslations()
+  .then(() => (invoke ? loadRouterList() : null))
+  .then(loadTopology);
@@ -0,0 +1,113 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+const events = window.__TAURI__ && window.__TAURI__.event;
+
+const deviceSelect = document.getElementById("device-select");
+const chartEl = document.getElementById("chart");
+const rxNowEl = document.getElementById("rx-now");
+const txNowEl = document.getElementById("tx-now");
+const statusEl = document.getElementById("status");
+
+const RX_COLOR = "#4f8cff";
+const TX_COLOR = "#f0b45b";
+
+let translations = {};
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+}
+
+function formatRate(bps) {
+  const units = ["bit/s", "kbit/s", "Mbit/s", "Gbit/s"];
+  let value = bps;
+  let unit = 0;
+  while (value >= 1000 && unit < units.length - 1) {
+    value /= 1000;
+    unit += 1;
+  }
+  return `${value.toFixed(value < 10 ? 1 : 0)} ${units[unit]}`;
+}
+
+function renderDevices(states) {
+  const current = deviceSelect.value;
+  deviceSelect.innerHTML = "";
+  states.forEach((active) => {
+    active.interfaces.forEach((iface) => {
+      const option = document.createElement("option");
+      option.value = iface.mac;
+      option.textContent = `${iface.display_name} (${active.router.name})`;
+      deviceSelect.appendChild(option);
+    });
+  });
+  if ([...deviceSelect.options].some((o) => o.value === current)) {
+    deviceSelect.value = current;
+  }
+}
+
+function drawSeries(ctx, points, key, color, max, width, height) {
+  ctx.strokeStyle = color;
+  ctx.lineWidth = 2;
+  ctx.beginPath();
+  points.forEach((point, i) => {
+    const x = points.length > 1 ? (i / (points.length - 1)) * width : 0;
+    const y = height - (point[key] / max) * (height - 10);
+    if (i === 0) ctx.moveTo(x, y);
+    else ctx.lineTo(x, y);
+  });
+  ctx.stroke();
+}
+
+function drawChart(points) {
+  const width = chartEl.clientWidth;
+  const height = chartEl.clientHeight;
+  chartEl.width = width * window.devicePixelRatio;
+  chartEl.height = height * window.devicePixelRatio;
+  const ctx = chartEl.getContext("2d");
+  ctx.scale(window.devicePixelRatio, window.devicePixelRatio);
+  ctx.clearRect(0, 0, width, height);
+  if (!points.length) {
+    statusEl.textContent = tr("ui.no_samples");
+    rxNowEl.textContent = "–";
+    txNowEl.textContent = "–";
+    return;
+  }
+  statusEl.textContent = "";
+  const max = Math.max(1, ...points.map((p) => Math.max(p.rx_bps, p.tx_bps)));
+  ctx.fillStyle = "#98a3b3";
+  ctx.font = "11px system-ui";
+  ctx.fillText(formatRate(max), 4, 12);
+  drawSeries(ctx, points, "rx_bps", RX_COLOR, max, width, height);
+  drawSeries(ctx, points, "tx_bps", TX_COLOR, max, width, height);
+  const last = points[points.length - 1];
+  rxNowEl.textContent = formatRate(last.rx_bps);
+  txNowEl.textContent = formatRate(last.tx_bps);
+}
+
+async function update() {
+  if (!invoke || !deviceSelect.value) return;
+  const points = await invoke("get_traffic_history", { mac: deviceSelect.value });
+  drawChart(points);
+}
+
+deviceSelect.addEventListener("change", () => update());
+window.addEventListener("resize", () => update());
+
+if (events) {
+  events.listen("traffic-updated", () => update());
+  events.listen("state-updated", (event) => renderDevices(event.payload));
+}
+
+loadTranslations()
+  .then(() => (invoke ? invoke("get_active_state") : []))
+  .then((states) => {
+    renderDevices(states);
+    return update();
+  });
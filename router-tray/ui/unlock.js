@@ -0,0 +1,37 @@
+const tauriApi = window.__TAURI__ && window.__TAURI__.core;
+const invoke = tauriApi ? tauriApi.invoke : null;
+
+const form = document.getElementById("unlock-form");
+const statusEl = document.getElementById("status");
+
+let translations = {};
+
+function tr(key) {
+  return translations[key] || key;
+}
+
+async function loadTranslations() {
+  if (!invoke) return;
+  translations = await invoke("get_translations");
+  document.querySelectorAll("[data-i18n]").forEach((el) => {
+    el.textContent = tr(el.dataset.i18n);
+  });
+}
+
+form.addEventListener("submit", async (e) => {
+  e.preventDefault();
+  if (!invoke) {
+    statusEl.textContent = tr("ui.api_unavailable");
+    return;
+  }
+  try {
+    await invoke("unlock_credentials", { password: form.password.value });
+    form.reset();
+    statusEl.textContent = "";
+  } catch (err) {
+    statusEl.textContent = err;
+    statusEl.style.color = "#f05b5b";
+  }
+});
+
+loadTranslations();
@@ -0,0 +1,207 @@
+//! The server side of RFC 6455 that the tray's WebSocket needs: the upgrade
+//! handshake, frames, and putting text messages together. Connections,
+//! broadcasting and what the messages mean are up to the caller.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const MAX_REQUEST: u64 = 16 * 1024;
+const MAX_HEADER_LINES: usize = 64;
+/// Control frames can't be fragmented and carry at most this much.
+const MAX_CONTROL: u64 = 125;
+
+pub const OP_CONTINUATION: u8 = 0x0;
+pub const OP_TEXT: u8 = 0x1;
+pub const OP_BINARY: u8 = 0x2;
+pub const OP_CLOSE: u8 = 0x8;
+pub const OP_PING: u8 = 0x9;
+pub const OP_PONG: u8 = 0xA;
+
+/// Close status codes.
+pub const CLOSE_NORMAL: u16 = 1000;
+pub const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+pub const CLOSE_POLICY_VIOLATION: u16 = 1008;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub fin: bool,
+    pub opcode: u8,
+    pub payload: Vec<u8>,
+}
+
+/// What `read_message` stopped at.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    /// The client closed, with its status code if it gave one. The close
+    /// has already been answered.
+    Close(Option<u16>),
+}
+
+/// Reads an HTTP upgrade request and returns its `Sec-WebSocket-Key`, or
+/// `None` when it has none.
+pub fn read_upgrade(stream: impl Read) -> io::Result<Option<String>> {
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut key = None;
+    for _ in 0..MAX_HEADER_LINES {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Sec-WebSocket-Key") {
+                key = Some(value.trim().to_string());
+            }
+        }
+    }
+    Ok(key)
+}
+
+/// The `101 Switching Protocols` answer to a request with `key`.
+pub fn upgrade_response(key: &str) -> String {
+    format!(
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )
+}
+
+pub fn accept_key(key: &str) -> String {
+    let mut sha = Sha1::new();
+    sha.update(key.as_bytes());
+    sha.update(HANDSHAKE_GUID.as_bytes());
+    STANDARD.encode(sha.finalize())
+}
+
+/// Reads one frame from a client, unmasking it. Clients must mask every
+/// frame, so an unmasked one is an error, as is a payload over `max_len`.
+pub fn read_frame(stream: &mut impl Read, max_len: u64) -> io::Result<Frame> {
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head)?;
+    let fin = head[0] & 0x80 != 0;
+    let opcode = head[0] & 0x0F;
+    if head[1] & 0x80 == 0 {
+        return Err(invalid("unmasked frame from a client"));
+    }
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut len = [0u8; 2];
+            stream.read_exact(&mut len)?;
+            u64::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0u8; 8];
+            stream.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+    if opcode >= OP_CLOSE && (!fin || len > MAX_CONTROL) {
+        return Err(invalid("fragmented or oversized control frame"));
+    }
+    if len > max_len {
+        return Err(invalid("frame too large"));
+    }
+    let mut mask = [0u8; 4];
+    stream.read_exact(&mut mask)?;
+    let mut payload = vec![0u8; len as usize];
+    stream.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok(Frame {
+        fin,
+        opcode,
+        payload,
+    })
+}
+
+/// Writes one unfragmented frame. Servers don't mask.
+pub fn write_frame(stream: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= usize::from(u16::MAX) => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame)
+}
+
+/// The payload of a close frame with `code` and a short `reason`.
+pub fn close_payload(code: u16, reason: &str) -> Vec<u8> {
+    let mut payload = code.to_be_bytes().to_vec();
+    payload.extend(reason.bytes().take(MAX_CONTROL as usize - 2));
+    payload
+}
+
+/// Reads frames until a whole text message or a close arrives, at most
+/// `max_len` bytes of message. Pings are answered and a close is echoed
+/// through `reply`, which gets the opcode and payload of the frame to send.
+/// Binary messages are skipped.
+pub fn read_message(
+    stream: &mut impl Read,
+    max_len: u64,
+    mut reply: impl FnMut(u8, &[u8]) -> io::Result<()>,
+) -> io::Result<Message> {
+    let mut message = Vec::new();
+    // The opcode of the message the continuations belong to.
+    let mut started = None;
+    loop {
+        let frame = read_frame(stream, max_len)?;
+        match frame.opcode {
+            OP_TEXT | OP_BINARY if started.is_some() => {
+                return Err(invalid("new message before the last one ended"));
+            }
+            OP_TEXT | OP_BINARY => started = Some(frame.opcode),
+            OP_CONTINUATION if started.is_none() => {
+                return Err(invalid("continuation without a message"));
+            }
+            OP_CONTINUATION => {}
+            OP_PING => {
+                reply(OP_PONG, &frame.payload)?;
+                continue;
+            }
+            OP_PONG => continue,
+            OP_CLOSE => {
+                let code = (frame.payload.len() >= 2)
+                    .then(|| u16::from_be_bytes([frame.payload[0], frame.payload[1]]));
+                let echo = code.map(|code| code.to_be_bytes().to_vec());
+                reply(OP_CLOSE, echo.as_deref().unwrap_or_default())?;
+                return Ok(Message::Close(code));
+            }
+            _ => return Err(invalid("unknown opcode")),
+        }
+        if started == Some(OP_TEXT) {
+            message.extend_from_slice(&frame.payload);
+            if message.len() as u64 > max_len {
+                return Err(invalid("message too large"));
+            }
+        }
+        if frame.fin {
+            if started == Some(OP_TEXT) {
+                return String::from_utf8(message)
+                    .map(Message::Text)
+                    .map_err(|_| invalid("text message isn't UTF-8"));
+            }
+            started = None;
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,151 @@
+//! Frames and messages as a client sends them, read from memory.
+
+use router_websocket::*;
+use std::io::Cursor;
+
+/// A frame as a client sends it, masked with `mask`.
+fn client_frame(fin: bool, opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+    let mut frame = vec![if fin { 0x80 } else { 0 } | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(0x80 | len as u8),
+        len => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    frame
+}
+
+const MASK: [u8; 4] = [0x37, 0xfa, 0x21, 0x3d];
+
+/// `read_message` over `bytes`, with the frames it sent back.
+fn read(bytes: Vec<u8>) -> (std::io::Result<Message>, Vec<(u8, Vec<u8>)>) {
+    let mut sent = Vec::new();
+    let message = read_message(&mut Cursor::new(bytes), 1024, |opcode, payload| {
+        sent.push((opcode, payload.to_vec()));
+        Ok(())
+    });
+    (message, sent)
+}
+
+#[test]
+fn accept_key_matches_the_rfc_example() {
+    // RFC 6455, section 1.3.
+    assert_eq!(
+        accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+}
+
+#[test]
+fn upgrade_request_gives_its_key() {
+    let request = "GET / HTTP/1.1\r\nHost: 127.0.0.1\r\nUpgrade: websocket\r\n\
+                   sec-websocket-key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+    let key = read_upgrade(request.as_bytes()).unwrap();
+    assert_eq!(key.as_deref(), Some("dGhlIHNhbXBsZSBub25jZQ=="));
+    assert!(read_upgrade("GET / HTTP/1.1\r\n\r\n".as_bytes())
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn masked_frame_is_unmasked() {
+    // RFC 6455, section 5.7: a masked "Hello".
+    let bytes = [
+        0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+    ];
+    let frame = read_frame(&mut Cursor::new(bytes), 1024).unwrap();
+    assert_eq!(
+        frame,
+        Frame {
+            fin: true,
+            opcode: OP_TEXT,
+            payload: b"Hello".to_vec(),
+        }
+    );
+}
+
+#[test]
+fn unmasked_frame_from_a_client_is_refused() {
+    let bytes = [0x81, 0x05, b'H', b'e', b'l', b'l', b'o'];
+    assert!(read_frame(&mut Cursor::new(bytes), 1024).is_err());
+}
+
+#[test]
+fn extended_lengths_round_trip() {
+    let payload = vec![b'x'; 300];
+    let frame = read_frame(
+        &mut Cursor::new(client_frame(true, OP_TEXT, &payload, MASK)),
+        1024,
+    )
+    .unwrap();
+    assert_eq!(frame.payload, payload);
+
+    let mut written = Vec::new();
+    write_frame(&mut written, OP_TEXT, &payload).unwrap();
+    assert_eq!(&written[..4], &[0x81, 126, 0x01, 0x2c]);
+    assert_eq!(&written[4..], &payload[..]);
+}
+
+#[test]
+fn oversized_frame_is_refused() {
+    let frame = client_frame(true, OP_TEXT, &[b'x'; 200], MASK);
+    assert!(read_frame(&mut Cursor::new(frame), 100).is_err());
+}
+
+#[test]
+fn fragments_are_put_together_around_a_ping() {
+    let mut bytes = client_frame(false, OP_TEXT, b"{\"type\":", MASK);
+    bytes.extend(client_frame(true, OP_PING, b"hi", MASK));
+    bytes.extend(client_frame(true, OP_CONTINUATION, b"\"get_state\"}", MASK));
+    let (message, sent) = read(bytes);
+    assert_eq!(
+        message.unwrap(),
+        Message::Text("{\"type\":\"get_state\"}".into())
+    );
+    assert_eq!(sent, vec![(OP_PONG, b"hi".to_vec())]);
+}
+
+#[test]
+fn binary_messages_are_skipped() {
+    let mut bytes = client_frame(false, OP_BINARY, &[1, 2], MASK);
+    bytes.extend(client_frame(true, OP_CONTINUATION, &[3], MASK));
+    bytes.extend(client_frame(true, OP_TEXT, b"next", MASK));
+    assert_eq!(read(bytes).0.unwrap(), Message::Text("next".into()));
+}
+
+#[test]
+fn continuation_without_a_message_is_refused() {
+    let bytes = client_frame(true, OP_CONTINUATION, b"x", MASK);
+    assert!(read(bytes).0.is_err());
+}
+
+#[test]
+fn close_is_echoed_with_its_code() {
+    let bytes = client_frame(true, OP_CLOSE, &close_payload(CLOSE_NORMAL, "bye"), MASK);
+    let (message, sent) = read(bytes);
+    assert_eq!(message.unwrap(), Message::Close(Some(CLOSE_NORMAL)));
+    assert_eq!(sent, vec![(OP_CLOSE, CLOSE_NORMAL.to_be_bytes().to_vec())]);
+}
+
+#[test]
+fn close_without_a_code_is_echoed_empty() {
+    let (message, sent) = read(client_frame(true, OP_CLOSE, &[], MASK));
+    assert_eq!(message.unwrap(), Message::Close(None));
+    assert_eq!(sent, vec![(OP_CLOSE, Vec::new())]);
+}
+
+#[test]
+fn fragmented_control_frame_is_refused() {
+    let bytes = client_frame(false, OP_PING, b"hi", MASK);
+    assert!(read(bytes).0.is_err());
+}
+
+#[test]
+fn close_reason_is_cut_to_fit_a_control_frame() {
+    let payload = close_payload(CLOSE_POLICY_VIOLATION, &"x".repeat(200));
+    assert_eq!(payload.len(), 125);
+    assert_eq!(&payload[..2], &CLOSE_POLICY_VIOLATION.to_be_bytes());
+}